@@ -1,3 +1,5 @@
+use bitflags::bitflags;
+
 use crate::{code::OpCode, source::ByteStream};
 
 use super::constant_pool::CpPool;
@@ -6,23 +8,23 @@ use super::constant_pool::CpPool;
 pub enum Attribute {
     Other(Vec<u8>),
     // critical for correct interpretation
-    ConstantValue,
+    ConstantValue(ConstantValueAttribute),
     Code(CodeAttribute),
     StackMapTable(StackMapTableAttribute),
-    BootstrapMethods,
-    NestHost,
-    NestMembers,
-    PermittedSubclasses,
+    BootstrapMethods(BootstrapMethodsAttribute),
+    NestHost(NestHostAttribute),
+    NestMembers(NestMembersAttribute),
+    PermittedSubclasses(PermittedSubclassesAttribute),
     // critical for correct interpretation of class libraries
-    Exceptions,
-    InnerClasses,
-    EnclosingMethod,
+    Exceptions(ExceptionsAttribute),
+    InnerClasses(InnerClassesAttribute),
+    EnclosingMethod(EnclosingMethodAttribute),
     Synthetic,
     Signature,
-    Record,
+    Record(RecordAttribute),
     SourceFile(SourceFileAttribute),
     LineNumberTable(LineNumberTableAttribute),
-    LocalVariableTable,
+    LocalVariableTable(LocalVariableTableAttribute),
     LocalVariableTypeTable,
     // non critical
     SourceDebugExtension,
@@ -35,9 +37,9 @@ pub enum Attribute {
     RuntimeInvisibleTypeAnnotations,
     AnnotationDefault,
     MethodParameters,
-    Module,
-    ModulePackages,
-    ModuleMainClass,
+    Module(ModuleAttribute),
+    ModulePackages(ModulePackagesAttribute),
+    ModuleMainClass(ModuleMainClassAttribute),
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +47,166 @@ pub struct StackMapTableAttribute {
     pub entries: Vec<StackMapFrame>,
 }
 
+#[derive(Debug, Clone)]
+pub struct BootstrapMethodsAttribute {
+    pub bootstrap_methods: Vec<BootstrapMethod>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BootstrapMethod {
+    pub bootstrap_method_ref: usize,
+    pub bootstrap_arguments: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InnerClassesAttribute {
+    pub classes: Vec<InnerClass>,
+}
+
+bitflags! {
+    /// access flags of a nested class as declared in the source, which the class file of
+    /// the class itself can not express
+    pub struct InnerClassAccessFlags: usize {
+        const PUBLIC	  = 0x0001;
+        const PRIVATE	  = 0x0002;
+        const PROTECTED	  = 0x0004;
+        const STATIC	  = 0x0008;
+        const FINAL	      = 0x0010;
+        const INTERFACE	  = 0x0200;
+        const ABSTRACT	  = 0x0400;
+        const SYNTHETIC	  = 0x1000;
+        const ANNOTATION  = 0x2000;
+        const ENUM	      = 0x4000;
+    }
+}
+
+/// a nested class, which is either the class itself, one nested in it or one it refers to
+#[derive(Debug, Clone)]
+pub struct InnerClass {
+    pub inner_class: String,
+    /// class declaring it as a member, `None` for local and anonymous classes
+    pub outer_class: Option<String>,
+    /// simple name, `None` for anonymous classes
+    pub inner_name: Option<String>,
+    pub inner_class_access_flags: InnerClassAccessFlags,
+}
+
+/// the innermost class, and method if any, a local or anonymous class is declared in
+#[derive(Debug, Clone)]
+pub struct EnclosingMethodAttribute {
+    pub class: String,
+    pub method_name: Option<String>,
+    pub method_descriptor: Option<String>,
+}
+
+/// the class whose nest this class belongs to
+#[derive(Debug, Clone)]
+pub struct NestHostAttribute {
+    pub host_class: String,
+}
+
+/// classes the nest host claims as the other members of its nest
+#[derive(Debug, Clone)]
+pub struct NestMembersAttribute {
+    pub classes: Vec<String>,
+}
+
+/// checked exceptions a method declares in its `throws` clause
+#[derive(Debug, Clone)]
+pub struct ExceptionsAttribute {
+    pub exceptions: Vec<String>,
+}
+
+/// classes which may directly extend or implement a sealed class or interface
+#[derive(Debug, Clone)]
+pub struct PermittedSubclassesAttribute {
+    pub classes: Vec<String>,
+}
+
+bitflags! {
+    /// flags of a module, its `requires`, `exports` and `opens` directives
+    pub struct ModuleFlags: usize {
+        const OPEN	      = 0x0020;
+        const SYNTHETIC	  = 0x1000;
+        const MANDATED	  = 0x8000;
+    }
+}
+
+bitflags! {
+    pub struct RequiresFlags: usize {
+        const TRANSITIVE	= 0x0020;
+        const STATIC_PHASE	= 0x0040;
+        const SYNTHETIC	    = 0x1000;
+        const MANDATED	    = 0x8000;
+    }
+}
+
+/// the declaration in `module-info.class`, packages are in internal form like `java/lang`
+#[derive(Debug, Clone)]
+pub struct ModuleAttribute {
+    pub name: String,
+    pub flags: ModuleFlags,
+    pub version: Option<String>,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Exports>,
+    /// services the module looks up with `ServiceLoader`
+    pub uses: Vec<String>,
+    pub provides: Vec<Provides>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Requires {
+    pub module: String,
+    pub flags: RequiresFlags,
+    pub version: Option<String>,
+}
+
+/// an `exports` or `opens` directive, qualified when `to` is not empty
+#[derive(Debug, Clone)]
+pub struct Exports {
+    pub package: String,
+    pub flags: ModuleFlags,
+    pub to: Vec<String>,
+}
+
+/// service implementations a module provides
+#[derive(Debug, Clone)]
+pub struct Provides {
+    pub service: String,
+    pub with: Vec<String>,
+}
+
+/// every package of a module, including those it does not export
+#[derive(Debug, Clone)]
+pub struct ModulePackagesAttribute {
+    pub packages: Vec<String>,
+}
+
+/// the class `java -m module` launches
+#[derive(Debug, Clone)]
+pub struct ModuleMainClassAttribute {
+    pub main_class: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordAttribute {
+    pub components: Vec<RecordComponent>,
+}
+
+/// component of a record class, which has a private field and an accessor of the same name
+#[derive(Debug, Clone)]
+pub struct RecordComponent {
+    pub name: String,
+    pub descriptor: String,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstantValueAttribute {
+    pub constantvalue_index: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct SourceFileAttribute {
     pub sourcefile_index: usize,
@@ -62,6 +224,21 @@ pub struct LineNumber {
     pub line_number: usize,
 }
 
+#[derive(Debug, Clone)]
+pub struct LocalVariableTableAttribute {
+    pub local_variable_table: Vec<LocalVariable>,
+}
+
+/// local variable `index` holds `name` for the bytecode offsets `start_pc..start_pc + length`
+#[derive(Debug, Clone)]
+pub struct LocalVariable {
+    pub start_pc: usize,
+    pub length: usize,
+    pub name: String,
+    pub descriptor: String,
+    pub index: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct CodeAttribute {
     pub max_stack: usize,
@@ -89,29 +266,60 @@ pub struct AttributeInfo {
     pub attribute: Attribute,
 }
 
+impl CodeAttribute {
+    /// entries of every `LineNumberTable`, empty without debug information
+    pub fn line_numbers(&self) -> impl Iterator<Item = &LineNumber> {
+        self.attributes
+            .iter()
+            .filter_map(|a| match &a.attribute {
+                Attribute::LineNumberTable(table) => Some(&table.line_number_table),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// source line of the instruction at bytecode offset `pc`
+    pub fn line_number(&self, pc: usize) -> Option<usize> {
+        self.line_numbers()
+            .filter(|line| line.start_pc <= pc)
+            .max_by_key(|line| line.start_pc)
+            .map(|line| line.line_number)
+    }
+
+    /// local variables in scope at bytecode offset `pc`, empty without debug information
+    pub fn local_variables(&self, pc: usize) -> impl Iterator<Item = &LocalVariable> {
+        self.attributes
+            .iter()
+            .filter_map(|a| match &a.attribute {
+                Attribute::LocalVariableTable(table) => Some(&table.local_variable_table),
+                _ => None,
+            })
+            .flatten()
+            .filter(move |var| (var.start_pc..var.start_pc + var.length).contains(&pc))
+    }
+}
+
 impl AttributeInfo {
     pub fn parse(f: &mut ByteStream, cp: &CpPool) -> Option<Self> {
         let attribute_name_index = f.next_u2()?;
         let attribute_name = cp.get_utf(attribute_name_index)?.bytes.clone();
 
-        let _attribute_length = f.next_u4()?;
+        let attribute_length = f.next_u4()?;
 
         let attribute = match attribute_name.as_str() {
             "Code" => {
                 let max_stack = f.next_u2()?;
                 let max_locals = f.next_u2()?;
                 let code_length = f.next_u4()?;
-                let mut code_raw = Vec::with_capacity(code_length);
-                for _ in 0..code_length {
-                    code_raw.push(f.next_u1()?);
-                }
+                let code_raw = f.next_bytes(code_length)?;
 
                 let mut code_stream = ByteStream::from(code_raw.clone());
                 let mut bytecode_to_op = vec![0; code_raw.len()];
                 let mut code = Vec::new();
                 let mut op_to_bytecode = Vec::new();
                 let mut start = 0;
-                while let Some(op) = OpCode::parse(&mut code_stream) {
+                while code_stream.i < code_raw.len() {
+                    let op = OpCode::parse(&mut code_stream)?;
                     bytecode_to_op[start] = code.len();
                     op_to_bytecode.push(start);
                     start = code_stream.i;
@@ -156,6 +364,29 @@ impl AttributeInfo {
                 }
                 Attribute::LineNumberTable(LineNumberTableAttribute { line_number_table })
             }
+            "LocalVariableTable" => {
+                let local_variable_table_length = f.next_u2()?;
+                let mut local_variable_table = Vec::with_capacity(local_variable_table_length);
+                for _ in 0..local_variable_table_length {
+                    let start_pc = f.next_u2()?;
+                    let length = f.next_u2()?;
+                    let name = cp.get_utf(f.next_u2()?)?.bytes.clone();
+                    let descriptor = cp.get_utf(f.next_u2()?)?.bytes.clone();
+                    local_variable_table.push(LocalVariable {
+                        start_pc,
+                        length,
+                        name,
+                        descriptor,
+                        index: f.next_u2()?,
+                    });
+                }
+                Attribute::LocalVariableTable(LocalVariableTableAttribute {
+                    local_variable_table,
+                })
+            }
+            "ConstantValue" => Attribute::ConstantValue(ConstantValueAttribute {
+                constantvalue_index: f.next_u2()?,
+            }),
             "SourceFile" => {
                 let sourcefile_index = f.next_u2()?;
                 let sourcefile = cp.get_utf(sourcefile_index)?.bytes.clone();
@@ -172,7 +403,175 @@ impl AttributeInfo {
                 }
                 Attribute::StackMapTable(StackMapTableAttribute { entries })
             }
-            name => todo!("parsing attribute: {name}"),
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = f.next_u2()?;
+                let mut bootstrap_methods = Vec::with_capacity(num_bootstrap_methods);
+                for _ in 0..num_bootstrap_methods {
+                    let bootstrap_method_ref = f.next_u2()?;
+                    let num_bootstrap_arguments = f.next_u2()?;
+                    let mut bootstrap_arguments = Vec::with_capacity(num_bootstrap_arguments);
+                    for _ in 0..num_bootstrap_arguments {
+                        bootstrap_arguments.push(f.next_u2()?);
+                    }
+                    bootstrap_methods.push(BootstrapMethod {
+                        bootstrap_method_ref,
+                        bootstrap_arguments,
+                    });
+                }
+                Attribute::BootstrapMethods(BootstrapMethodsAttribute { bootstrap_methods })
+            }
+            "InnerClasses" => {
+                let number_of_classes = f.next_u2()?;
+                let mut classes = Vec::with_capacity(number_of_classes);
+                for _ in 0..number_of_classes {
+                    let inner_class = cp.get_class_name(f.next_u2()?)?.to_string();
+                    let outer_class = match f.next_u2()? {
+                        0 => None,
+                        index => Some(cp.get_class_name(index)?.to_string()),
+                    };
+                    let inner_name = match f.next_u2()? {
+                        0 => None,
+                        index => Some(cp.get_utf(index)?.bytes.clone()),
+                    };
+                    classes.push(InnerClass {
+                        inner_class,
+                        outer_class,
+                        inner_name,
+                        inner_class_access_flags: InnerClassAccessFlags::from_bits_truncate(
+                            f.next_u2()?,
+                        ),
+                    });
+                }
+                Attribute::InnerClasses(InnerClassesAttribute { classes })
+            }
+            "EnclosingMethod" => {
+                let class = cp.get_class_name(f.next_u2()?)?.to_string();
+                let (method_name, method_descriptor) = match f.next_u2()? {
+                    0 => (None, None),
+                    index => {
+                        let name_and_type = cp.get_name_and_type(index)?;
+                        (
+                            Some(cp.get_utf(name_and_type.name_index)?.bytes.clone()),
+                            Some(cp.get_utf(name_and_type.descriptor_index)?.bytes.clone()),
+                        )
+                    }
+                };
+                Attribute::EnclosingMethod(EnclosingMethodAttribute {
+                    class,
+                    method_name,
+                    method_descriptor,
+                })
+            }
+            "NestHost" => Attribute::NestHost(NestHostAttribute {
+                host_class: cp.get_class_name(f.next_u2()?)?.to_string(),
+            }),
+            "NestMembers" => {
+                let number_of_classes = f.next_u2()?;
+                let mut classes = Vec::with_capacity(number_of_classes);
+                for _ in 0..number_of_classes {
+                    classes.push(cp.get_class_name(f.next_u2()?)?.to_string());
+                }
+                Attribute::NestMembers(NestMembersAttribute { classes })
+            }
+            "Exceptions" => {
+                let number_of_exceptions = f.next_u2()?;
+                let mut exceptions = Vec::with_capacity(number_of_exceptions);
+                for _ in 0..number_of_exceptions {
+                    exceptions.push(cp.get_class_name(f.next_u2()?)?.to_string());
+                }
+                Attribute::Exceptions(ExceptionsAttribute { exceptions })
+            }
+            "PermittedSubclasses" => {
+                let number_of_classes = f.next_u2()?;
+                let mut classes = Vec::with_capacity(number_of_classes);
+                for _ in 0..number_of_classes {
+                    classes.push(cp.get_class_name(f.next_u2()?)?.to_string());
+                }
+                Attribute::PermittedSubclasses(PermittedSubclassesAttribute { classes })
+            }
+            "Module" => {
+                let name = cp.get_module_name(f.next_u2()?)?.to_string();
+                let flags = ModuleFlags::from_bits_truncate(f.next_u2()?);
+                let version = match f.next_u2()? {
+                    0 => None,
+                    index => Some(cp.get_utf(index)?.bytes.clone()),
+                };
+                let requires_count = f.next_u2()?;
+                let mut requires = Vec::with_capacity(requires_count);
+                for _ in 0..requires_count {
+                    let module = cp.get_module_name(f.next_u2()?)?.to_string();
+                    let flags = RequiresFlags::from_bits_truncate(f.next_u2()?);
+                    let version = match f.next_u2()? {
+                        0 => None,
+                        index => Some(cp.get_utf(index)?.bytes.clone()),
+                    };
+                    requires.push(Requires {
+                        module,
+                        flags,
+                        version,
+                    });
+                }
+                let exports = Exports::parse_all(f, cp)?;
+                let opens = Exports::parse_all(f, cp)?;
+                let uses_count = f.next_u2()?;
+                let mut uses = Vec::with_capacity(uses_count);
+                for _ in 0..uses_count {
+                    uses.push(cp.get_class_name(f.next_u2()?)?.to_string());
+                }
+                let provides_count = f.next_u2()?;
+                let mut provides = Vec::with_capacity(provides_count);
+                for _ in 0..provides_count {
+                    let service = cp.get_class_name(f.next_u2()?)?.to_string();
+                    let with_count = f.next_u2()?;
+                    let mut with = Vec::with_capacity(with_count);
+                    for _ in 0..with_count {
+                        with.push(cp.get_class_name(f.next_u2()?)?.to_string());
+                    }
+                    provides.push(Provides { service, with });
+                }
+                Attribute::Module(ModuleAttribute {
+                    name,
+                    flags,
+                    version,
+                    requires,
+                    exports,
+                    opens,
+                    uses,
+                    provides,
+                })
+            }
+            "ModulePackages" => {
+                let package_count = f.next_u2()?;
+                let mut packages = Vec::with_capacity(package_count);
+                for _ in 0..package_count {
+                    packages.push(cp.get_package_name(f.next_u2()?)?.to_string());
+                }
+                Attribute::ModulePackages(ModulePackagesAttribute { packages })
+            }
+            "ModuleMainClass" => Attribute::ModuleMainClass(ModuleMainClassAttribute {
+                main_class: cp.get_class_name(f.next_u2()?)?.to_string(),
+            }),
+            "Record" => {
+                let components_count = f.next_u2()?;
+                let mut components = Vec::with_capacity(components_count);
+                for _ in 0..components_count {
+                    let name = cp.get_utf(f.next_u2()?)?.bytes.clone();
+                    let descriptor = cp.get_utf(f.next_u2()?)?.bytes.clone();
+                    let attributes_count = f.next_u2()?;
+                    let mut attributes = Vec::with_capacity(attributes_count);
+                    for _ in 0..attributes_count {
+                        attributes.push(AttributeInfo::parse(f, cp)?);
+                    }
+                    components.push(RecordComponent {
+                        name,
+                        descriptor,
+                        attributes,
+                    });
+                }
+                Attribute::Record(RecordAttribute { components })
+            }
+            // attributes we do not interpret yet are kept as raw bytes
+            _ => Attribute::Other(f.next_bytes(attribute_length)?),
         };
 
         Some(AttributeInfo {
@@ -183,24 +582,58 @@ impl AttributeInfo {
     }
 }
 
+impl Exports {
+    /// parses the `exports` or `opens` table of a `Module` attribute
+    fn parse_all(f: &mut ByteStream, cp: &CpPool) -> Option<Vec<Exports>> {
+        let count = f.next_u2()?;
+        let mut all = Vec::with_capacity(count);
+        for _ in 0..count {
+            let package = cp.get_package_name(f.next_u2()?)?.to_string();
+            let flags = ModuleFlags::from_bits_truncate(f.next_u2()?);
+            let to_count = f.next_u2()?;
+            let mut to = Vec::with_capacity(to_count);
+            for _ in 0..to_count {
+                to.push(cp.get_module_name(f.next_u2()?)?.to_string());
+            }
+            all.push(Exports { package, flags, to });
+        }
+        Some(all)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum StackMapFrame {
     SameFrame {
         frame_type: usize,
     },
-    SameLocals1StackItemFrame,
-    SameLocals1StackItemFrameExtended,
+    SameLocals1StackItemFrame {
+        frame_type: usize,
+        stack: VerificationTypeInfo,
+    },
+    SameLocals1StackItemFrameExtended {
+        frame_type: usize,
+        offset_delta: usize,
+        stack: VerificationTypeInfo,
+    },
     ChopFrame {
         frame_type: usize,
         offset_delta: usize,
     },
-    SameFrameExtended,
+    SameFrameExtended {
+        frame_type: usize,
+        offset_delta: usize,
+    },
     AppendFrame {
         frame_type: usize,
         offset_delta: usize,
         locals: Vec<VerificationTypeInfo>,
     },
-    FullFrame,
+    FullFrame {
+        frame_type: usize,
+        offset_delta: usize,
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
 }
 
 impl StackMapFrame {
@@ -208,6 +641,15 @@ impl StackMapFrame {
         let frame_type = f.next_u1()? as usize;
         match frame_type {
             0..=63 => Some(StackMapFrame::SameFrame { frame_type }),
+            64..=127 => Some(StackMapFrame::SameLocals1StackItemFrame {
+                frame_type,
+                stack: VerificationTypeInfo::parse(f)?,
+            }),
+            247 => Some(StackMapFrame::SameLocals1StackItemFrameExtended {
+                frame_type,
+                offset_delta: f.next_u2()?,
+                stack: VerificationTypeInfo::parse(f)?,
+            }),
             248..=250 => {
                 let offset_delta = f.next_u2()?;
                 Some(StackMapFrame::ChopFrame {
@@ -215,6 +657,10 @@ impl StackMapFrame {
                     offset_delta,
                 })
             }
+            251 => Some(StackMapFrame::SameFrameExtended {
+                frame_type,
+                offset_delta: f.next_u2()?,
+            }),
             252..=254 => {
                 let offset_delta = f.next_u2()?;
                 let capacity = frame_type - 251;
@@ -228,9 +674,27 @@ impl StackMapFrame {
                     locals,
                 })
             }
-            a => {
-                todo!("stack frame not implemented: {a}");
+            255 => {
+                let offset_delta = f.next_u2()?;
+                let number_of_locals = f.next_u2()?;
+                let mut locals = Vec::with_capacity(number_of_locals);
+                for _ in 0..number_of_locals {
+                    locals.push(VerificationTypeInfo::parse(f)?)
+                }
+                let number_of_stack_items = f.next_u2()?;
+                let mut stack = Vec::with_capacity(number_of_stack_items);
+                for _ in 0..number_of_stack_items {
+                    stack.push(VerificationTypeInfo::parse(f)?)
+                }
+                Some(StackMapFrame::FullFrame {
+                    frame_type,
+                    offset_delta,
+                    locals,
+                    stack,
+                })
             }
+            // 128-246 are reserved
+            _ => None,
         }
     }
 }
@@ -261,7 +725,7 @@ impl VerificationTypeInfo {
             6 => Self::UninitializedThis,
             7 => Self::Object(f.next_u2()?),
             8 => Self::Uninitialized(f.next_u2()?),
-            _ => unreachable!(),
+            _ => return None,
         };
         Some(res)
     }
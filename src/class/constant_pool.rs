@@ -1,6 +1,6 @@
 use std::ops::{Deref, DerefMut};
 
-use crate::ByteStream;
+use crate::source::ByteStream;
 
 #[derive(Debug, Clone)]
 pub struct ClassInfo {
@@ -39,6 +39,56 @@ pub struct StringInfo {
 pub struct IntegerInfo {
     pub val: usize
 }
+#[derive(Debug, Clone)]
+pub struct FloatInfo {
+    pub val: f32,
+}
+#[derive(Debug, Clone)]
+pub struct LongInfo {
+    pub val: i64,
+}
+#[derive(Debug, Clone)]
+pub struct DoubleInfo {
+    pub val: f64,
+}
+#[derive(Debug, Clone)]
+pub struct MethodHandleInfo {
+    pub reference_kind: usize,
+    pub reference_index: usize,
+}
+#[derive(Debug, Clone)]
+pub struct MethodTypeInfo {
+    pub descriptor_index: usize,
+}
+#[derive(Debug, Clone)]
+pub struct InvokeDynamicInfo {
+    pub bootstrap_method_attr_index: usize,
+    pub name_and_type_index: usize,
+}
+/// constant computed by a bootstrap method, like `invokedynamic` call sites are
+#[derive(Debug, Clone)]
+pub struct DynamicInfo {
+    pub bootstrap_method_attr_index: usize,
+    pub name_and_type_index: usize,
+}
+/// module named in `module-info.class`
+#[derive(Debug, Clone)]
+pub struct ModuleInfo {
+    pub name_index: usize,
+}
+/// package in internal form, like `java/lang`, named in `module-info.class`
+#[derive(Debug, Clone)]
+pub struct PackageInfo {
+    pub name_index: usize,
+}
+
+/// symbolic reference to a field or method with all the names resolved
+#[derive(Debug, Clone, Copy)]
+pub struct MemberRef<'a> {
+    pub class: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
 
 #[derive(Debug, Clone)]
 #[repr(u8)]
@@ -50,7 +100,45 @@ pub enum CpInfo {
     NameAndType(NameAndTypeInfo),
     Utf(UtfInfo),
     String(StringInfo),
-    Integer(IntegerInfo)
+    Integer(IntegerInfo),
+    Float(FloatInfo),
+    Long(LongInfo),
+    Double(DoubleInfo),
+    /// second slot taken by Long and Double entries
+    Empty,
+    MethodHandle(MethodHandleInfo),
+    MethodType(MethodTypeInfo),
+    Dynamic(DynamicInfo),
+    InvokeDynamic(InvokeDynamicInfo),
+    Module(ModuleInfo),
+    Package(PackageInfo),
+}
+
+/// decodes the modified UTF-8 of class files, which encodes `\0` in two bytes and
+/// characters outside the basic plane as two surrogates of three bytes each. Unpaired
+/// surrogates become U+FFFD
+fn modified_utf8(bytes: &[u8]) -> Option<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let continuation = |j: usize| {
+            let b = bytes.get(j).filter(|&&b| b & 0xC0 == 0x80)?;
+            Some((b & 0x3F) as u16)
+        };
+        let b = bytes[i] as u16;
+        let (unit, len) = match b {
+            0x01..=0x7F => (b, 1),
+            0xC0..=0xDF => ((b & 0x1F) << 6 | continuation(i + 1)?, 2),
+            0xE0..=0xEF => {
+                let unit = (b & 0x0F) << 12 | continuation(i + 1)? << 6 | continuation(i + 2)?;
+                (unit, 3)
+            }
+            _ => return None,
+        };
+        units.push(unit);
+        i += len;
+    }
+    Some(String::from_utf16_lossy(&units))
 }
 
 impl CpInfo {
@@ -75,7 +163,7 @@ impl CpInfo {
                     bytes.push(f.next_u1()?);
                 }
                 Some(CpInfo::Utf(UtfInfo {
-                    bytes: String::from_utf8(bytes).ok()?,
+                    bytes: modified_utf8(&bytes)?,
                 }))
             }
             9 => Some(CpInfo::Fieldref(FieldrefInfo {
@@ -86,53 +174,185 @@ impl CpInfo {
                 string_index: f.next_u2()?,
             })),
             3 => Some(CpInfo::Integer(IntegerInfo { val: f.next_u4()? })),
-            tag => {
-                panic!("unknown tag {tag}")
+            4 => Some(CpInfo::Float(FloatInfo {
+                val: f32::from_bits(f.next_u4()? as u32),
+            })),
+            5 => {
+                let high = f.next_u4()? as u64;
+                let low = f.next_u4()? as u64;
+                Some(CpInfo::Long(LongInfo {
+                    val: (high << 32 | low) as i64,
+                }))
             }
+            6 => {
+                let high = f.next_u4()? as u64;
+                let low = f.next_u4()? as u64;
+                Some(CpInfo::Double(DoubleInfo {
+                    val: f64::from_bits(high << 32 | low),
+                }))
+            }
+            11 => Some(CpInfo::InterfaceMethodref(InterfaceMethodrefInfo {
+                class_index: f.next_u2()?,
+                name_and_type_index: f.next_u2()?,
+            })),
+            15 => Some(CpInfo::MethodHandle(MethodHandleInfo {
+                reference_kind: f.next_u1()? as usize,
+                reference_index: f.next_u2()?,
+            })),
+            16 => Some(CpInfo::MethodType(MethodTypeInfo {
+                descriptor_index: f.next_u2()?,
+            })),
+            17 => Some(CpInfo::Dynamic(DynamicInfo {
+                bootstrap_method_attr_index: f.next_u2()?,
+                name_and_type_index: f.next_u2()?,
+            })),
+            18 => Some(CpInfo::InvokeDynamic(InvokeDynamicInfo {
+                bootstrap_method_attr_index: f.next_u2()?,
+                name_and_type_index: f.next_u2()?,
+            })),
+            19 => Some(CpInfo::Module(ModuleInfo {
+                name_index: f.next_u2()?,
+            })),
+            20 => Some(CpInfo::Package(PackageInfo {
+                name_index: f.next_u2()?,
+            })),
+            // unknown tags make the class file malformed
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// the entry if it has the variant `$pat`, `None` for an entry of another kind
+macro_rules! entry {
+    ($info: expr, $pat: path) => {
+        match $info {
+            $pat(info) => Some(info),
+            _ => None,
+        }
+    };
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct CpPool(Vec<CpInfo>);
 
 impl CpPool {
     pub fn parse(f: &mut ByteStream) -> Option<Self> {
-        let constant_pool_count = f.next_u2()?;
-        let mut constant_pool = Vec::with_capacity(constant_pool_count - 1);
-        for _ in 0..constant_pool_count - 1 {
-            constant_pool.push(CpInfo::parse(f)?);
+        let entries = f.next_u2()?.checked_sub(1)?;
+        let mut constant_pool = Vec::with_capacity(entries);
+        while constant_pool.len() < entries {
+            let info = CpInfo::parse(f)?;
+            let wide = matches!(info, CpInfo::Long(_) | CpInfo::Double(_));
+            constant_pool.push(info);
+            if wide {
+                constant_pool.push(CpInfo::Empty);
+            }
         }
-        Some(CpPool(constant_pool))
+        let pool = CpPool(constant_pool);
+        pool.is_valid().then_some(pool)
     }
+
+    /// whether every entry refers to entries of the right kind (JVMS §4.4), so the getters
+    /// only return `None` for indices coming from elsewhere in the class file
+    fn is_valid(&self) -> bool {
+        self.0.iter().all(|info| match info {
+            CpInfo::Class(ClassInfo { name_index })
+            | CpInfo::Module(ModuleInfo { name_index })
+            | CpInfo::Package(PackageInfo { name_index }) => self.get_utf(*name_index).is_some(),
+            CpInfo::String(s) => self.get_utf(s.string_index).is_some(),
+            CpInfo::Fieldref(FieldrefInfo { class_index, name_and_type_index })
+            | CpInfo::Methodref(MethodrefInfo { class_index, name_and_type_index })
+            | CpInfo::InterfaceMethodref(InterfaceMethodrefInfo {
+                class_index,
+                name_and_type_index,
+            }) => {
+                self.get_class(*class_index).is_some()
+                    && self.get_name_and_type(*name_and_type_index).is_some()
+            }
+            CpInfo::NameAndType(nt) => {
+                self.get_utf(nt.name_index).is_some() && self.get_utf(nt.descriptor_index).is_some()
+            }
+            CpInfo::MethodHandle(h) => matches!(
+                self.get(h.reference_index),
+                Some(CpInfo::Fieldref(_) | CpInfo::Methodref(_) | CpInfo::InterfaceMethodref(_))
+            ),
+            CpInfo::MethodType(t) => self.get_utf(t.descriptor_index).is_some(),
+            CpInfo::Dynamic(DynamicInfo { name_and_type_index, .. })
+            | CpInfo::InvokeDynamic(InvokeDynamicInfo { name_and_type_index, .. }) => {
+                self.get_name_and_type(*name_and_type_index).is_some()
+            }
+            _ => true,
+        })
+    }
+    /// entry at `index`, `None` for index 0 and beyond the end
     pub fn get(&self, index: usize) -> Option<&CpInfo> {
-        self.0.get(index - 1)
+        self.0.get(index.checked_sub(1)?)
     }
     pub fn get_utf(&self, index: usize) -> Option<&UtfInfo> {
-        self.get(index).map(|x| cast!(x, CpInfo::Utf))
+        entry!(self.get(index)?, CpInfo::Utf)
     }
     pub fn get_class(&self, index: usize) -> Option<&ClassInfo> {
-        self.get(index).map(|x| cast!(x, CpInfo::Class))
+        entry!(self.get(index)?, CpInfo::Class)
     }
     pub fn get_string(&self, index: usize) -> Option<&StringInfo> {
-        self.get(index).map(|x| cast!(x, CpInfo::String))
+        entry!(self.get(index)?, CpInfo::String)
     }
     pub fn get_fieldref(&self, index: usize) -> Option<&FieldrefInfo> {
-        self.get(index).map(|x| cast!(x, CpInfo::Fieldref))
+        entry!(self.get(index)?, CpInfo::Fieldref)
     }
     pub fn get_name_and_type(&self, index: usize) -> Option<&NameAndTypeInfo> {
-        self.get(index).map(|x| cast!(x, CpInfo::NameAndType))
+        entry!(self.get(index)?, CpInfo::NameAndType)
     }
     pub fn get_interface_methodref(&self, index: usize) -> Option<&InterfaceMethodrefInfo> {
-        self.get(index)
-            .map(|x| cast!(x, CpInfo::InterfaceMethodref))
+        entry!(self.get(index)?, CpInfo::InterfaceMethodref)
     }
     pub fn get_methodref(&self, index: usize) -> Option<&MethodrefInfo> {
-        self.get(index)
-            .map(|x| cast!(x, CpInfo::Methodref))
+        entry!(self.get(index)?, CpInfo::Methodref)
     }
     pub fn get_integer(&self, index: usize) -> Option<&IntegerInfo> {
-        self.get(index).map(|x| cast!(x, CpInfo::Integer))
+        entry!(self.get(index)?, CpInfo::Integer)
+    }
+    pub fn get_method_handle(&self, index: usize) -> Option<&MethodHandleInfo> {
+        entry!(self.get(index)?, CpInfo::MethodHandle)
+    }
+    pub fn get_method_type(&self, index: usize) -> Option<&MethodTypeInfo> {
+        entry!(self.get(index)?, CpInfo::MethodType)
+    }
+    pub fn get_invoke_dynamic(&self, index: usize) -> Option<&InvokeDynamicInfo> {
+        entry!(self.get(index)?, CpInfo::InvokeDynamic)
+    }
+    pub fn get_module(&self, index: usize) -> Option<&ModuleInfo> {
+        entry!(self.get(index)?, CpInfo::Module)
+    }
+    pub fn get_package(&self, index: usize) -> Option<&PackageInfo> {
+        entry!(self.get(index)?, CpInfo::Package)
+    }
+
+    pub fn get_class_name(&self, index: usize) -> Option<&str> {
+        Some(self.get_utf(self.get_class(index)?.name_index)?.bytes.as_str())
+    }
+
+    pub fn get_module_name(&self, index: usize) -> Option<&str> {
+        Some(self.get_utf(self.get_module(index)?.name_index)?.bytes.as_str())
+    }
+
+    pub fn get_package_name(&self, index: usize) -> Option<&str> {
+        Some(self.get_utf(self.get_package(index)?.name_index)?.bytes.as_str())
+    }
+
+    /// resolves Fieldref, Methodref and InterfaceMethodref entries into names
+    pub fn get_member_ref(&self, index: usize) -> Option<MemberRef<'_>> {
+        let (class_index, name_and_type_index) = match self.get(index)? {
+            CpInfo::Fieldref(r) => (r.class_index, r.name_and_type_index),
+            CpInfo::Methodref(r) => (r.class_index, r.name_and_type_index),
+            CpInfo::InterfaceMethodref(r) => (r.class_index, r.name_and_type_index),
+            _ => return None,
+        };
+        let name_and_type = self.get_name_and_type(name_and_type_index)?;
+        Some(MemberRef {
+            class: self.get_class_name(class_index)?,
+            name: &self.get_utf(name_and_type.name_index)?.bytes,
+            descriptor: &self.get_utf(name_and_type.descriptor_index)?.bytes,
+        })
     }
 }
 
@@ -0,0 +1,42 @@
+/// parsed method descriptor, e.g. `(ILjava/lang/String;)V`
+#[derive(Debug, Clone)]
+pub struct MethodDescriptor {
+    pub args: Vec<String>,
+    pub ret: String,
+}
+
+impl MethodDescriptor {
+    pub fn parse(descriptor: &str) -> Option<Self> {
+        let rest = descriptor.strip_prefix('(')?;
+        let (mut params, ret) = rest.split_once(')')?;
+        let mut args = Vec::new();
+        while !params.is_empty() {
+            let (arg, tail) = split_field_type(params)?;
+            args.push(arg.to_string());
+            params = tail;
+        }
+        Some(MethodDescriptor {
+            args,
+            ret: ret.to_string(),
+        })
+    }
+}
+
+/// splits the first field type off a sequence of field descriptors
+pub fn split_field_type(descriptor: &str) -> Option<(&str, &str)> {
+    let len = match descriptor.as_bytes().first()? {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b'V' => 1,
+        b'L' => descriptor.find(';')? + 1,
+        b'[' => 1 + split_field_type(&descriptor[1..])?.0.len(),
+        _ => return None,
+    };
+    Some(descriptor.split_at(len))
+}
+
+/// `Ljava/lang/String;` -> `java/lang/String`, array descriptors are kept as they are
+pub fn class_name(descriptor: &str) -> &str {
+    descriptor
+        .strip_prefix('L')
+        .and_then(|d| d.strip_suffix(';'))
+        .unwrap_or(descriptor)
+}
@@ -2,7 +2,10 @@ use bitflags::bitflags;
 
 use crate::source::ByteStream;
 
-use super::{attribute::AttributeInfo, constant_pool::CpPool};
+use super::{
+    attribute::{Attribute, AttributeInfo},
+    constant_pool::CpPool,
+};
 
 #[derive(Debug, Clone)]
 pub struct FieldInfo {
@@ -37,6 +40,14 @@ impl FieldInfo {
             attributes,
         })
     }
+
+    /// constant pool index of the initial value of a static final field
+    pub fn constant_value(&self) -> Option<usize> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::ConstantValue(value) => Some(value.constantvalue_index),
+            _ => None,
+        })
+    }
 }
 
 bitflags! {
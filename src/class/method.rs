@@ -2,7 +2,10 @@ use bitflags::bitflags;
 
 use crate::source::ByteStream;
 
-use super::{attribute::AttributeInfo, constant_pool::CpPool};
+use super::{
+    attribute::{Attribute, AttributeInfo, CodeAttribute},
+    constant_pool::CpPool,
+};
 
 #[derive(Debug, Clone)]
 pub struct MethodInfo {
@@ -37,6 +40,24 @@ impl MethodInfo {
             attributes,
         })
     }
+
+    pub fn code(&self) -> Option<&CodeAttribute> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::Code(code) => Some(code),
+            _ => None,
+        })
+    }
+
+    /// classes in the `throws` clause
+    pub fn exceptions(&self) -> &[String] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.attribute {
+                Attribute::Exceptions(e) => Some(e.exceptions.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
 }
 
 bitflags! {
@@ -3,10 +3,18 @@ use constant_pool::CpPool;
 
 use crate::source::ByteStream;
 
-use self::{attribute::AttributeInfo, field::FieldInfo, method::MethodInfo};
+use self::{
+    attribute::{
+        Attribute, AttributeInfo, BootstrapMethod, EnclosingMethodAttribute, InnerClass,
+        ModuleAttribute, RecordComponent,
+    },
+    field::FieldInfo,
+    method::MethodInfo,
+};
 
 pub mod attribute;
 pub mod constant_pool;
+pub mod descriptor;
 pub mod field;
 pub mod method;
 
@@ -59,10 +67,11 @@ impl Class {
             .bytes
             .clone();
 
-        let super_class_name = constant_pool
-            .get_utf(constant_pool.get_class(super_class)?.name_index)?
-            .bytes
-            .clone();
+        // only java/lang/Object has no superclass
+        let super_class_name = match super_class {
+            0 => String::new(),
+            _ => constant_pool.get_class_name(super_class)?.to_string(),
+        };
 
         let interfaces_count = f.next_u2()?;
         let mut interfaces = Vec::with_capacity(interfaces_count);
@@ -104,4 +113,159 @@ impl Class {
             attributes,
         })
     }
+
+    pub fn get_method(&self, name: &str, descriptor: &str) -> Option<&MethodInfo> {
+        self.methods
+            .iter()
+            .find(|m| m.name == name && m.descriptor == descriptor)
+    }
+
+    pub fn interface_names(&self) -> impl Iterator<Item = &str> {
+        self.interfaces
+            .iter()
+            .filter_map(|&i| self.cp.get_class_name(i))
+    }
+
+    pub fn source_file(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::SourceFile(s) => Some(s.sourcefile.as_str()),
+            _ => None,
+        })
+    }
+
+    /// nested classes the class is, declares or refers to
+    pub fn inner_classes(&self) -> &[InnerClass] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.attribute {
+                Attribute::InnerClasses(i) => Some(i.classes.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// the entry describing the class itself when it is nested
+    fn inner_class(&self) -> Option<&InnerClass> {
+        self.inner_classes()
+            .iter()
+            .find(|i| i.inner_class == self.this_class_name)
+    }
+
+    /// where a local or anonymous class is declared
+    pub fn enclosing_method(&self) -> Option<&EnclosingMethodAttribute> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::EnclosingMethod(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.inner_class().is_some_and(|i| i.inner_name.is_none())
+    }
+
+    /// whether the class is declared in a block, with a name
+    pub fn is_local(&self) -> bool {
+        self.enclosing_method().is_some() && !self.is_anonymous()
+    }
+
+    /// class the class is a member of, like `Class.getDeclaringClass`. `None` for top level,
+    /// local and anonymous classes
+    pub fn declaring_class(&self) -> Option<&str> {
+        self.inner_class()?.outer_class.as_deref()
+    }
+
+    /// class the class is declared in, including local and anonymous classes
+    pub fn enclosing_class(&self) -> Option<&str> {
+        match self.enclosing_method() {
+            Some(enclosing) => Some(&enclosing.class),
+            None => self.declaring_class(),
+        }
+    }
+
+    /// name in the source, empty for anonymous classes
+    pub fn simple_name(&self) -> &str {
+        match self.inner_class() {
+            Some(inner) => inner.inner_name.as_deref().unwrap_or_default(),
+            None => self.this_class_name.rsplit('/').next().unwrap(),
+        }
+    }
+
+    /// access flags as declared in the source, like `Class.getModifiers`. Nested classes
+    /// take them from their `InnerClasses` entry
+    pub fn modifiers(&self) -> usize {
+        let flags = match self.inner_class() {
+            Some(inner) => inner.inner_class_access_flags.bits(),
+            None => self.access_flags.bits(),
+        };
+        flags & !ClassAccessFlags::SUPER.bits()
+    }
+
+    /// host of the nest the class claims to belong to, `None` when it hosts its own nest
+    pub fn nest_host(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::NestHost(n) => Some(n.host_class.as_str()),
+            _ => None,
+        })
+    }
+
+    /// other members of the nest hosted by the class
+    pub fn nest_members(&self) -> &[String] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.attribute {
+                Attribute::NestMembers(n) => Some(n.classes.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// classes a sealed class or interface permits as direct subclasses, `None` when it is
+    /// not sealed
+    pub fn permitted_subclasses(&self) -> Option<&[String]> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::PermittedSubclasses(p) => Some(p.classes.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// components of a record class, `None` for other classes
+    pub fn record_components(&self) -> Option<&[RecordComponent]> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::Record(r) => Some(r.components.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// declaration of the module when the class is `module-info`
+    pub fn module(&self) -> Option<&ModuleAttribute> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::Module(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// packages of the module, which `jar` records but `javac` leaves out
+    pub fn module_packages(&self) -> Option<&[String]> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::ModulePackages(p) => Some(p.packages.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn module_main_class(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| match &a.attribute {
+            Attribute::ModuleMainClass(m) => Some(m.main_class.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethod] {
+        self.attributes
+            .iter()
+            .find_map(|a| match &a.attribute {
+                Attribute::BootstrapMethods(b) => Some(b.bootstrap_methods.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
 }
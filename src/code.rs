@@ -3,6 +3,8 @@ use crate::source::ByteStream;
 #[derive(Debug, Clone)]
 #[repr(u8)]
 pub enum OpCode {
+    Nop = 0x0,
+    AConstNull = 0x1, // push null onto stack
     IConstM1 = 0x2, // push -1 onto stack
     IConst0 = 0x3,  // push 0 onto stack
     IConst1 = 0x4,  // push 1 onto stack
@@ -10,49 +12,191 @@ pub enum OpCode {
     IConst3 = 0x6,  // push 3 onto stack
     IConst4 = 0x7,  // push 4 onto stack
     IConst5 = 0x8,  // push 5 onto stack
+    LConst0 = 0x9,  // push 0L onto stack
+    LConst1 = 0xa,  // push 1L onto stack
+    FConst0 = 0xb,  // push 0.0f onto stack
+    FConst1 = 0xc,  // push 1.0f onto stack
+    FConst2 = 0xd,  // push 2.0f onto stack
+    DConst0 = 0xe,  // push 0.0 onto stack
+    DConst1 = 0xf,  // push 1.0 onto stack
 
     BiPush(isize) = 0x10, // push byte
     SiPush(isize) = 0x11, // push short
     Ldc(usize) = 0x12, // push constant pool index onto stack
+    LdcW(usize) = 0x13, // push constant with wide index onto stack
+    Ldc2W(usize) = 0x14, // push long or double constant onto stack
+
+    ILoad(usize) = 0x15, // load int from local
+    LLoad(usize) = 0x16, // load long from local
+    FLoad(usize) = 0x17, // load float from local
+    DLoad(usize) = 0x18, // load double from local
+    ALoad(usize) = 0x19, // load reference from local
 
     ILoad0 = 0x1a, // load int from local
     ILoad1 = 0x1b, // load int from local
     ILoad2 = 0x1c, // load int from local
     ILoad3 = 0x1d, // load int from local
-
+    LLoad0 = 0x1e, // load long from local
+    LLoad1 = 0x1f, // load long from local
+    LLoad2 = 0x20, // load long from local
+    LLoad3 = 0x21, // load long from local
+    FLoad0 = 0x22, // load float from local
+    FLoad1 = 0x23, // load float from local
+    FLoad2 = 0x24, // load float from local
+    FLoad3 = 0x25, // load float from local
+    DLoad0 = 0x26, // load double from local
+    DLoad1 = 0x27, // load double from local
+    DLoad2 = 0x28, // load double from local
+    DLoad3 = 0x29, // load double from local
 
     ALoad0 = 0x2a, // load reference from local
     ALoad1 = 0x2b, // load reference from local
     ALoad2 = 0x2c, // load reference from local
     ALoad3 = 0x2d, // load reference from local
 
+    IALoad = 0x2e, // load int from array
+    LALoad = 0x2f, // load long from array
+    FALoad = 0x30, // load float from array
+    DALoad = 0x31, // load double from array
+    AALoad = 0x32, // load reference from array
+    BALoad = 0x33, // load byte or boolean from array
+    CALoad = 0x34, // load char from array
+    SALoad = 0x35, // load short from array
+
+    IStore(usize) = 0x36, // store int into local
+    LStore(usize) = 0x37, // store long into local
+    FStore(usize) = 0x38, // store float into local
+    DStore(usize) = 0x39, // store double into local
+    AStore(usize) = 0x3a, // store reference into local
+
     IStore0 = 0x3b, // store int into local
     IStore1 = 0x3c, // store int into local
     IStore2 = 0x3d, // store int into local
     IStore3 = 0x3e, // store int into local
-
+    LStore0 = 0x3f, // store long into local
+    LStore1 = 0x40, // store long into local
+    LStore2 = 0x41, // store long into local
+    LStore3 = 0x42, // store long into local
+    FStore0 = 0x43, // store float into local
+    FStore1 = 0x44, // store float into local
+    FStore2 = 0x45, // store float into local
+    FStore3 = 0x46, // store float into local
+    DStore0 = 0x47, // store double into local
+    DStore1 = 0x48, // store double into local
+    DStore2 = 0x49, // store double into local
+    DStore3 = 0x4a, // store double into local
 
     AStore0 = 0x4b, // load reference into local
     AStore1 = 0x4c, // load reference into local
     AStore2 = 0x4d, // load reference into local
     AStore3 = 0x4e, // load reference into local
 
+    IAStore = 0x4f, // store into int array
+    LAStore = 0x50, // store into long array
+    FAStore = 0x51, // store into float array
+    DAStore = 0x52, // store into double array
+    AAStore = 0x53, // store into reference array
+    BAStore = 0x54, // store into byte or boolean array
+    CAStore = 0x55, // store into char array
+    SAStore = 0x56, // store into short array
+
+    Pop = 0x57, // discard top of stack
+    Pop2 = 0x58, // discard two category 1 values or one category 2 value
     Dup = 0x59, // duplicate top of stack
+    DupX1 = 0x5a, // duplicate top of stack and insert it two values down
+    DupX2 = 0x5b, // duplicate top of stack and insert it three values down
+    Dup2 = 0x5c, // duplicate two category 1 values or one category 2 value
+    Dup2X1 = 0x5d,
+    Dup2X2 = 0x5e,
+    Swap = 0x5f, // swap two top values
 
     IAdd = 0x60,
+    LAdd = 0x61,
+    FAdd = 0x62,
+    DAdd = 0x63,
     ISub = 0x64,
+    LSub = 0x65,
+    FSub = 0x66,
+    DSub = 0x67,
     IMul = 0x68,
+    LMul = 0x69,
+    FMul = 0x6a,
+    DMul = 0x6b,
+    IDiv = 0x6c,
+    LDiv = 0x6d,
+    FDiv = 0x6e,
+    DDiv = 0x6f,
+    IRem = 0x70,
+    LRem = 0x71,
+    FRem = 0x72,
+    DRem = 0x73,
+    INeg = 0x74,
+    LNeg = 0x75,
+    FNeg = 0x76,
+    DNeg = 0x77,
+    IShl = 0x78,
+    LShl = 0x79,
+    IShr = 0x7a,
+    LShr = 0x7b,
+    IUShr = 0x7c,
+    LUShr = 0x7d,
+    IAnd = 0x7e,
+    LAnd = 0x7f,
+    IOr = 0x80,
+    LOr = 0x81,
+    IXor = 0x82,
+    LXor = 0x83,
 
     Iinc(usize, isize) = 0x84,
-     
+
+    I2L = 0x85,
+    I2F = 0x86,
+    I2D = 0x87,
+    L2I = 0x88,
+    L2F = 0x89,
+    L2D = 0x8a,
+    F2I = 0x8b,
+    F2L = 0x8c,
+    F2D = 0x8d,
+    D2I = 0x8e,
+    D2L = 0x8f,
+    D2F = 0x90,
+    I2B = 0x91,
+    I2C = 0x92,
+    I2S = 0x93,
+
+    LCmp = 0x94,
+    FCmpL = 0x95, // compare floats, -1 on NaN
+    FCmpG = 0x96, // compare floats, 1 on NaN
+    DCmpL = 0x97, // compare doubles, -1 on NaN
+    DCmpG = 0x98, // compare doubles, 1 on NaN
+
     IReturn = 0xac,
+    LReturn = 0xad,
+    FReturn = 0xae,
+    DReturn = 0xaf,
+    AReturn = 0xb0,
 
     Return = 0xb1, // return void
     GetStatic(usize) = 0xb2,
+    PutStatic(usize) = 0xb3,
+    GetField(usize) = 0xb4,
+    PutField(usize) = 0xb5,
     InvokeVirtual(usize) = 0xb6,
     InvokeSpecial(usize) = 0xb7,
     InvokeStatic(usize) = 0xb8,
+    InvokeInterface(usize, usize) = 0xb9, // index and argument count
+    InvokeDynamic(usize) = 0xba,
     New(usize) = 0xbb, // create new object
+    NewArray(usize) = 0xbc, // create new array of primitive type
+    ANewArray(usize) = 0xbd, // create new array of references
+    ArrayLength = 0xbe,
+    AThrow = 0xbf, // throw exception or error
+    MultiANewArray(usize, usize) = 0xc5, // class index and number of dimensions
+    CheckCast(usize) = 0xc0, // check that reference is of class
+    InstanceOf(usize) = 0xc1, // push whether reference is of class
+    MonitorEnter = 0xc2,
+    MonitorExit = 0xc3,
 
     IfEq(isize) = 0x99,
     IfNe(isize) = 0x9a,
@@ -66,14 +210,25 @@ pub enum OpCode {
     IfICmpGe(isize) = 0xa2,
     IfICmpGt(isize) = 0xa3,
     IfICmpLe(isize) = 0xa4,
-
-    Goto(isize) = 0xa7
-
+    IfACmpEq(isize) = 0xa5,
+    IfACmpNe(isize) = 0xa6,
+    IfNull(isize) = 0xc6,
+    IfNonNull(isize) = 0xc7,
+
+    Goto(isize) = 0xa7,
+    GotoW(isize) = 0xc8,
+    Jsr(isize) = 0xa8, // jump to subroutine, only in class files before version 51
+    JsrW(isize) = 0xc9,
+    Ret(usize) = 0xa9, // return from subroutine to address in local
+    TableSwitch(isize, i32, Vec<isize>) = 0xaa, // default, lowest key and offsets
+    LookupSwitch(isize, Vec<(i32, isize)>) = 0xab, // default and (key, offset) pairs
 }
 
 impl OpCode {
     pub fn parse(c: &mut ByteStream) -> Option<OpCode> {
         let opcode = match c.next()? {
+            0x0 => OpCode::Nop,
+            0x1 => OpCode::AConstNull,
             0x2 => OpCode::IConstM1,
             0x3 => OpCode::IConst0,
             0x4 => OpCode::IConst1,
@@ -81,49 +236,217 @@ impl OpCode {
             0x6 => OpCode::IConst3,
             0x7 => OpCode::IConst4,
             0x8 => OpCode::IConst5,
+            0x9 => OpCode::LConst0,
+            0xa => OpCode::LConst1,
+            0xb => OpCode::FConst0,
+            0xc => OpCode::FConst1,
+            0xd => OpCode::FConst2,
+            0xe => OpCode::DConst0,
+            0xf => OpCode::DConst1,
 
             0x10 => OpCode::BiPush(c.next_u1()? as i8 as isize),
             0x11 => OpCode::SiPush(c.next_u2()? as i16 as isize),
             0x12 => OpCode::Ldc(c.next_u1()? as usize),
+            0x13 => OpCode::LdcW(c.next_u2()?),
+            0x14 => OpCode::Ldc2W(c.next_u2()?),
+
+            0x15 => OpCode::ILoad(c.next_u1()? as usize),
+            0x16 => OpCode::LLoad(c.next_u1()? as usize),
+            0x17 => OpCode::FLoad(c.next_u1()? as usize),
+            0x18 => OpCode::DLoad(c.next_u1()? as usize),
+            0x19 => OpCode::ALoad(c.next_u1()? as usize),
 
             0x1a => OpCode::ILoad0,
             0x1b => OpCode::ILoad1,
             0x1c => OpCode::ILoad2,
             0x1d => OpCode::ILoad3,
-
+            0x1e => OpCode::LLoad0,
+            0x1f => OpCode::LLoad1,
+            0x20 => OpCode::LLoad2,
+            0x21 => OpCode::LLoad3,
+            0x22 => OpCode::FLoad0,
+            0x23 => OpCode::FLoad1,
+            0x24 => OpCode::FLoad2,
+            0x25 => OpCode::FLoad3,
+            0x26 => OpCode::DLoad0,
+            0x27 => OpCode::DLoad1,
+            0x28 => OpCode::DLoad2,
+            0x29 => OpCode::DLoad3,
 
             0x2a => OpCode::ALoad0,
             0x2b => OpCode::ALoad1,
             0x2c => OpCode::ALoad2,
             0x2d => OpCode::ALoad3,
 
+            0x2e => OpCode::IALoad,
+            0x2f => OpCode::LALoad,
+            0x30 => OpCode::FALoad,
+            0x31 => OpCode::DALoad,
+            0x32 => OpCode::AALoad,
+            0x33 => OpCode::BALoad,
+            0x34 => OpCode::CALoad,
+            0x35 => OpCode::SALoad,
+
+            0x36 => OpCode::IStore(c.next_u1()? as usize),
+            0x37 => OpCode::LStore(c.next_u1()? as usize),
+            0x38 => OpCode::FStore(c.next_u1()? as usize),
+            0x39 => OpCode::DStore(c.next_u1()? as usize),
+            0x3a => OpCode::AStore(c.next_u1()? as usize),
+
             0x3b => OpCode::IStore0,
             0x3c => OpCode::IStore1,
             0x3d => OpCode::IStore2,
             0x3e => OpCode::IStore3,
+            0x3f => OpCode::LStore0,
+            0x40 => OpCode::LStore1,
+            0x41 => OpCode::LStore2,
+            0x42 => OpCode::LStore3,
+            0x43 => OpCode::FStore0,
+            0x44 => OpCode::FStore1,
+            0x45 => OpCode::FStore2,
+            0x46 => OpCode::FStore3,
+            0x47 => OpCode::DStore0,
+            0x48 => OpCode::DStore1,
+            0x49 => OpCode::DStore2,
+            0x4a => OpCode::DStore3,
 
             0x4b => OpCode::AStore0,
-            0x4c => OpCode::AStore0,
-            0x4d => OpCode::AStore0,
-            0x4e => OpCode::AStore0,
-
+            0x4c => OpCode::AStore1,
+            0x4d => OpCode::AStore2,
+            0x4e => OpCode::AStore3,
+
+            0x4f => OpCode::IAStore,
+            0x50 => OpCode::LAStore,
+            0x51 => OpCode::FAStore,
+            0x52 => OpCode::DAStore,
+            0x53 => OpCode::AAStore,
+            0x54 => OpCode::BAStore,
+            0x55 => OpCode::CAStore,
+            0x56 => OpCode::SAStore,
+
+            0x57 => OpCode::Pop,
+            0x58 => OpCode::Pop2,
             0x59 => OpCode::Dup,
+            0x5a => OpCode::DupX1,
+            0x5b => OpCode::DupX2,
+            0x5c => OpCode::Dup2,
+            0x5d => OpCode::Dup2X1,
+            0x5e => OpCode::Dup2X2,
+            0x5f => OpCode::Swap,
 
             0x60 => OpCode::IAdd,
+            0x61 => OpCode::LAdd,
+            0x62 => OpCode::FAdd,
+            0x63 => OpCode::DAdd,
             0x64 => OpCode::ISub,
+            0x65 => OpCode::LSub,
+            0x66 => OpCode::FSub,
+            0x67 => OpCode::DSub,
             0x68 => OpCode::IMul,
+            0x69 => OpCode::LMul,
+            0x6a => OpCode::FMul,
+            0x6b => OpCode::DMul,
+            0x6c => OpCode::IDiv,
+            0x6d => OpCode::LDiv,
+            0x6e => OpCode::FDiv,
+            0x6f => OpCode::DDiv,
+            0x70 => OpCode::IRem,
+            0x71 => OpCode::LRem,
+            0x72 => OpCode::FRem,
+            0x73 => OpCode::DRem,
+            0x74 => OpCode::INeg,
+            0x75 => OpCode::LNeg,
+            0x76 => OpCode::FNeg,
+            0x77 => OpCode::DNeg,
+            0x78 => OpCode::IShl,
+            0x79 => OpCode::LShl,
+            0x7a => OpCode::IShr,
+            0x7b => OpCode::LShr,
+            0x7c => OpCode::IUShr,
+            0x7d => OpCode::LUShr,
+            0x7e => OpCode::IAnd,
+            0x7f => OpCode::LAnd,
+            0x80 => OpCode::IOr,
+            0x81 => OpCode::LOr,
+            0x82 => OpCode::IXor,
+            0x83 => OpCode::LXor,
 
             0x84 => OpCode::Iinc(c.next_u1()? as usize, c.next_u1()? as i8 as isize),
 
-            0xac => OpCode::IReturn,
+            0x85 => OpCode::I2L,
+            0x86 => OpCode::I2F,
+            0x87 => OpCode::I2D,
+            0x88 => OpCode::L2I,
+            0x89 => OpCode::L2F,
+            0x8a => OpCode::L2D,
+            0x8b => OpCode::F2I,
+            0x8c => OpCode::F2L,
+            0x8d => OpCode::F2D,
+            0x8e => OpCode::D2I,
+            0x8f => OpCode::D2L,
+            0x90 => OpCode::D2F,
+            0x91 => OpCode::I2B,
+            0x92 => OpCode::I2C,
+            0x93 => OpCode::I2S,
+
+            0x94 => OpCode::LCmp,
+            0x95 => OpCode::FCmpL,
+            0x96 => OpCode::FCmpG,
+            0x97 => OpCode::DCmpL,
+            0x98 => OpCode::DCmpG,
 
+            0xac => OpCode::IReturn,
+            0xad => OpCode::LReturn,
+            0xae => OpCode::FReturn,
+            0xaf => OpCode::DReturn,
+            0xb0 => OpCode::AReturn,
 
             0xb1 => OpCode::Return,
             0xb2 => OpCode::GetStatic(c.next_u2()?),
+            0xb3 => OpCode::PutStatic(c.next_u2()?),
+            0xb4 => OpCode::GetField(c.next_u2()?),
+            0xb5 => OpCode::PutField(c.next_u2()?),
             0xb6 => OpCode::InvokeVirtual(c.next_u2()?),
             0xb7 => OpCode::InvokeSpecial(c.next_u2()?),
             0xb8 => OpCode::InvokeStatic(c.next_u2()?),
+            0xb9 => {
+                let index = c.next_u2()?;
+                let count = c.next_u1()? as usize;
+                c.next_u1()?; // always zero
+                OpCode::InvokeInterface(index, count)
+            }
+            0xba => {
+                let index = c.next_u2()?;
+                c.next_u2()?; // always zero
+                OpCode::InvokeDynamic(index)
+            }
             0xbb => OpCode::New(c.next_u2()?),
+            0xbc => OpCode::NewArray(c.next_u1()? as usize),
+            0xbd => OpCode::ANewArray(c.next_u2()?),
+            0xbe => OpCode::ArrayLength,
+            0xbf => OpCode::AThrow,
+            0xc4 => match c.next()? {
+                // wide: same instructions with two byte local indexes
+                0x15 => OpCode::ILoad(c.next_u2()?),
+                0x16 => OpCode::LLoad(c.next_u2()?),
+                0x17 => OpCode::FLoad(c.next_u2()?),
+                0x18 => OpCode::DLoad(c.next_u2()?),
+                0x19 => OpCode::ALoad(c.next_u2()?),
+                0x36 => OpCode::IStore(c.next_u2()?),
+                0x37 => OpCode::LStore(c.next_u2()?),
+                0x38 => OpCode::FStore(c.next_u2()?),
+                0x39 => OpCode::DStore(c.next_u2()?),
+                0x3a => OpCode::AStore(c.next_u2()?),
+                0x84 => OpCode::Iinc(c.next_u2()?, c.next_u2()? as i16 as isize),
+                0xa9 => OpCode::Ret(c.next_u2()?),
+                // other instructions can't be widened, the class file is malformed
+                _ => return None,
+            },
+            0xc5 => OpCode::MultiANewArray(c.next_u2()?, c.next_u1()? as usize),
+            0xc0 => OpCode::CheckCast(c.next_u2()?),
+            0xc1 => OpCode::InstanceOf(c.next_u2()?),
+            0xc2 => OpCode::MonitorEnter,
+            0xc3 => OpCode::MonitorExit,
 
             // 0x99 => OpCode::IfEq(c.next_u1()? as usize, c.next_u1()? as usize),
             0x99 => OpCode::IfEq((c.next_u2()?) as i16 as isize),
@@ -141,9 +464,50 @@ impl OpCode {
             0xa3 => OpCode::IfICmpGt((c.next_u2()?) as i16 as isize),
             0xa4 => OpCode::IfICmpLe((c.next_u2()?) as i16 as isize),
 
-            0xa7 => OpCode::Goto((c.next_u2()?) as i16 as isize),
+            0xa5 => OpCode::IfACmpEq((c.next_u2()?) as i16 as isize),
+            0xa6 => OpCode::IfACmpNe((c.next_u2()?) as i16 as isize),
+            0xc6 => OpCode::IfNull((c.next_u2()?) as i16 as isize),
+            0xc7 => OpCode::IfNonNull((c.next_u2()?) as i16 as isize),
 
-            op => todo!("opcode not implemented: 0x{op:0X}"),
+            0xa7 => OpCode::Goto((c.next_u2()?) as i16 as isize),
+            0xc8 => OpCode::GotoW((c.next_u4()?) as i32 as isize),
+            0xa8 => OpCode::Jsr((c.next_u2()?) as i16 as isize),
+            0xc9 => OpCode::JsrW((c.next_u4()?) as i32 as isize),
+            0xa9 => OpCode::Ret(c.next_u1()? as usize),
+
+            // switches are padded so that their operands start at a multiple of 4
+            0xaa => {
+                c.i += (4 - c.i % 4) % 4;
+                let default = c.next_u4()? as i32 as isize;
+                let low = c.next_u4()? as i32;
+                let high = c.next_u4()? as i32;
+                // a jump offset for every value from low to high, which the code has to hold
+                let count = (high as i64 - low as i64 + 1).max(0) as usize;
+                if low > high || count > c.remaining() / 4 {
+                    return None;
+                }
+                let mut offsets = Vec::with_capacity(count);
+                for _ in low..=high {
+                    offsets.push(c.next_u4()? as i32 as isize);
+                }
+                OpCode::TableSwitch(default, low, offsets)
+            }
+            0xab => {
+                c.i += (4 - c.i % 4) % 4;
+                let default = c.next_u4()? as i32 as isize;
+                let count = c.next_u4()?;
+                if count > c.remaining() / 8 {
+                    return None;
+                }
+                let mut pairs = Vec::with_capacity(count);
+                for _ in 0..count {
+                    pairs.push((c.next_u4()? as i32, c.next_u4()? as i32 as isize));
+                }
+                OpCode::LookupSwitch(default, pairs)
+            }
+
+            // undefined and reserved opcodes
+            _ => return None,
         };
         Some(opcode)
     }
@@ -0,0 +1,61 @@
+//! Java virtual machine which runs class files, on a built-in subset of the java library or
+//! on the class library of a JDK. It can be embedded to run java code in process:
+//!
+//! ```no_run
+//! use jrust::{Throw, Value, Vm};
+//!
+//! let mut vm = Vm::builder().classpath("classes").build()?;
+//! vm.load_class("Main")?.expect("Main is on the classpath");
+//! let result = vm.invoke_static("Main", "fact", "(I)I", &[Value::Int(6)])?;
+//! assert_eq!(result, Some(Value::Int(720)));
+//!
+//! let name = vm.new_string("world".to_string());
+//! match vm.invoke_static("Main", "greet", "(Ljava/lang/String;)Ljava/lang/String;", &[name]) {
+//!     Ok(Some(greeting)) => println!("{}", vm.string_value(greeting)?),
+//!     Ok(None) => unreachable!(),
+//!     // java exceptions come back as a `Throw` error
+//!     Err(err) => match err.downcast_ref::<Throw>() {
+//!         Some(&Throw(exception)) => println!("{}", vm.describe_exception(exception)),
+//!         None => return Err(err),
+//!     },
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! The garbage collector only keeps objects alive which java code can reach. Objects held
+//! on to between calls have to be pushed to [`Vm::roots`]. Untrusted code can be run within
+//! [`Limits`] given to [`VmBuilder::limits`], which fail the call with a [`LimitExceeded`]
+//! error whichever java thread exceeds them. Java code recurses on the rust stack of the
+//! thread calling into the vm, which [`VmBuilder::host_stack`] tells the size of when it has
+//! less than the 2 MiB of threads spawned by the standard library.
+//!
+//! Java threads run on OS threads of their own, taking turns with the caller under a global
+//! lock, and use the vm while they run. A call into the vm therefore only returns once every
+//! thread the java code started ended: threads run to their end, while daemon threads are
+//! stopped. No java thread outlives the call, so the vm can be moved or dropped in between
+
+#![allow(dead_code)]
+
+macro_rules! cast {
+    ($target: expr, $pat: path) => {{
+        if let $pat(a) = $target {
+            a
+        } else {
+            panic!("mismatch variant when cast to {}", stringify!($pat)); // #2
+        }
+    }};
+}
+
+pub mod class;
+pub mod code;
+pub mod source;
+pub mod vm;
+
+pub use vm::{
+    builder::VmBuilder,
+    exception::Throw,
+    limits::{LimitExceeded, Limits},
+    thread::Exit,
+    value::Value,
+    Vm,
+};
@@ -1,48 +1,280 @@
-#![feature(iter_advance_by)]
-#![allow(dead_code)]
-
-use class::attribute::CodeAttribute;
-use code::OpCode;
-use source::ByteStream;
-use std::{collections::HashMap, env, fs::File, io::Read, process::exit};
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    panic,
+    path::{Path, PathBuf},
+    process::exit,
+    thread,
+    time::Duration,
+};
 
 use anyhow::Context;
 
-use crate::class::{
-    attribute::Attribute,
-    constant_pool::{CpInfo, IntegerInfo, StringInfo},
-    Class,
+use jrust::{
+    class::{method::MethodAccessFlags, Class},
+    source::ByteStream,
+    vm::{
+        classpath::Archive,
+        debug::Debugger,
+        limits::{set_thread_stack, MAX_STACK_DEPTH},
+        module::FindException,
+        profile::Profiler,
+        trace::Tracer,
+    },
+    Exit, Limits, Throw, Vm,
 };
 
-macro_rules! cast {
-    ($target: expr, $pat: path) => {{
-        if let $pat(a) = $target {
-            a
-        } else {
-            panic!("mismatch variant when cast to {}", stringify!($pat)); // #2
-        }
-    }};
+const USAGE: &str = "\
+Usage: jrust [options] <mainclass> [args...]
+           (to execute a class)
+   or  jrust [options] -jar <jarfile> [args...]
+           (to execute a jar file)
+   or  jrust [options] -m <module>[/<mainclass>] [args...]
+       jrust [options] --module <module>[/<mainclass>] [args...]
+           (to execute the main class in a module)
+   or  jrust debug [options] <mainclass> [args...]
+           (to execute a class in the debugger)
+
+ where options include:
+    -cp <class search path of directories and zip/jar files>
+    -classpath <class search path of directories and zip/jar files>
+    --class-path <class search path of directories and zip/jar files>
+                  A : separated list of directories, JAR archives,
+                  and ZIP archives to search for class files.
+    -p <module path>
+    --module-path <module path>
+                  A : separated list of modular or plain JAR files,
+                  exploded modules and directories of them.
+    -D<name>=<value>
+                  set a system property
+    -verbose:class
+                  log every class loaded from the class path
+    -Xss<size>    set java thread stack size
+    -Xmx<size>    set maximum java heap size
+    -version      print product version to the error stream and exit
+    --version     print product version to the output stream and exit
+    -? -h -help --help
+                  print this help message to the output stream
+    --jdk <java home>
+                  run on the class library of a JDK instead of the built-in one
+    --trace[=<pattern>,...]
+                  log the instructions of the methods matching the patterns
+    --trace-file <file>
+                  write the trace to a file instead of stderr
+    --profile[=<file>]
+                  write collapsed stacks to a file, profile.folded by default,
+                  and a summary of the hottest methods to stderr
+    -agentlib:jdwp=<options>
+                  let a debugger attach over a socket
+    --dump        print the constants, fields and methods of the main class
+    --fuel <instructions>
+                  stop the program after running that many instructions
+    --timeout <seconds>
+                  stop the program after running for that long
+    --max-classes <count>
+                  stop the program once it loads more classes from the class path
+    --allow-natives=<pattern>,...
+                  only let java code call the native methods matching the patterns,
+                  which with --jdk have to name java.io.UnixFileSystem to see the
+                  files outside of the JDK
+    --allow-classes=<pattern>,...
+                  only load the classes matching the patterns from the class path";
+
+/// stack HotSpot takes for a small frame, which makes the `-Xss` default of 1m about the
+/// default stack depth
+const FRAME_SIZE: usize = 256;
+/// smallest `-Xss` HotSpot takes
+const MIN_THREAD_STACK: usize = 136 << 10;
+
+/// `-Xmx` style size: a number of bytes with an optional `k`, `m` or `g` suffix
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.char_indices().last()? {
+        (i, 'k' | 'K') => (&size[..i], 1 << 10),
+        (i, 'm' | 'M') => (&size[..i], 1 << 20),
+        (i, 'g' | 'G') => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(unit)
 }
 
-mod class;
-mod code;
-mod source;
+/// reports a bad option the way `java` does, which does not start the program
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!("Error: Could not create the Java Virtual Machine.");
+    eprintln!("Error: A fatal exception has occurred. Program will exit.");
+    exit(1);
+}
 
-struct Method {}
+/// what to run
+enum Launch {
+    /// a class on the classpath, by its binary name
+    Class(String),
+    /// the `Main-Class` of a jar, which is the classpath
+    Jar(PathBuf),
+    /// a class file, whose directory is the classpath unless one is given
+    File(PathBuf),
+    /// `module` or `module/class`, with the module on the module path
+    Module(String),
+}
 
-fn main() -> anyhow::Result<()> {
-    let args: Vec<_> = env::args().collect();
-    if args.len() != 2 {
-        println!("usage: {} file", &args[0]);
-        exit(-1);
+#[derive(Default)]
+struct Options {
+    debug: bool,
+    classpath: Option<String>,
+    module_path: Option<String>,
+    properties: Vec<(String, String)>,
+    jdk: Option<PathBuf>,
+    max_heap: Option<usize>,
+    limits: Limits,
+    verbose_class: bool,
+    trace: Option<Vec<String>>,
+    trace_file: Option<PathBuf>,
+    profile: Option<PathBuf>,
+    agent: Option<String>,
+    dump: bool,
+}
+
+/// argument of `option`, which is the next one
+fn value(option: &str, args: &mut impl Iterator<Item = String>) -> String {
+    match args.next() {
+        Some(value) => value,
+        None => fail(&format!("Error: {option} requires an argument")),
     }
+}
 
-    let mut file = File::open(args[1].clone()).context("failed to open file")?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let mut f = ByteStream::from(buffer);
+/// options up to the main class or jar like `java` takes them, then the main class or jar
+/// and the arguments of the program
+fn parse_args(mut args: impl Iterator<Item = String>) -> (Options, Launch, Vec<String>) {
+    let mut options = Options::default();
+    let mut first = true;
+    let launch = loop {
+        let Some(arg) = args.next() else {
+            eprintln!("{USAGE}");
+            exit(1);
+        };
+        // `jrust debug <mainclass>` runs the program in the debugger
+        if first && arg == "debug" {
+            options.debug = true;
+            continue;
+        }
+        first = false;
+        match arg.as_str() {
+            "-cp" | "-classpath" | "--class-path" => {
+                options.classpath = Some(value(&arg, &mut args));
+            }
+            "-jar" => break Launch::Jar(PathBuf::from(value(&arg, &mut args))),
+            "-p" | "--module-path" => options.module_path = Some(value(&arg, &mut args)),
+            "-m" | "--module" => break Launch::Module(value(&arg, &mut args)),
+            "-verbose" | "-verbose:class" => options.verbose_class = true,
+            "--version" => {
+                println!("jrust {}", env!("CARGO_PKG_VERSION"));
+                exit(0);
+            }
+            "-version" => {
+                eprintln!("jrust {}", env!("CARGO_PKG_VERSION"));
+                exit(0);
+            }
+            "-?" | "-h" | "-help" | "--help" => {
+                println!("{USAGE}");
+                exit(0);
+            }
+            "--jdk" => options.jdk = Some(PathBuf::from(value(&arg, &mut args))),
+            "--trace" => options.trace = Some(vec![]),
+            "--trace-file" => options.trace_file = Some(PathBuf::from(value(&arg, &mut args))),
+            "--profile" => options.profile = Some(PathBuf::from("profile.folded")),
+            "--dump" => options.dump = true,
+            "--fuel" => match value(&arg, &mut args).parse() {
+                Ok(fuel) => options.limits.fuel = Some(fuel),
+                Err(_) => fail(&format!("Invalid fuel: {arg}")),
+            },
+            "--timeout" => {
+                let seconds = value(&arg, &mut args).parse().ok();
+                match seconds.and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()) {
+                    Some(timeout) => options.limits.timeout = Some(timeout),
+                    None => fail(&format!("Invalid timeout: {arg}")),
+                }
+            }
+            "--max-classes" => match value(&arg, &mut args).parse() {
+                Ok(max) => options.limits.max_classes = Some(max),
+                Err(_) => fail(&format!("Invalid number of classes: {arg}")),
+            },
+            _ => {
+                if let Some(property) = arg.strip_prefix("-D") {
+                    let (key, value) = property.split_once('=').unwrap_or((property, ""));
+                    options
+                        .properties
+                        .push((key.to_string(), value.to_string()));
+                } else if let Some(size) = arg.strip_prefix("-Xmx") {
+                    match parse_size(size) {
+                        Some(size) => options.max_heap = Some(size),
+                        None => fail(&format!("Invalid maximum heap size: {arg}")),
+                    }
+                } else if let Some(size) = arg.strip_prefix("-Xss") {
+                    match parse_size(size) {
+                        Some(size) if size < MIN_THREAD_STACK => fail(
+                            "\nThe Java thread stack size specified is too small. Specify at \
+                             least 136k",
+                        ),
+                        Some(size) => {
+                            options.limits.max_stack_depth =
+                                (size / FRAME_SIZE).min(MAX_STACK_DEPTH)
+                        }
+                        None => fail(&format!("Invalid thread stack size: {arg}")),
+                    }
+                } else if let Some(patterns) = arg.strip_prefix("--trace=") {
+                    options.trace = Some(patterns.split(',').map(str::to_string).collect());
+                } else if let Some(patterns) = arg.strip_prefix("--allow-natives=") {
+                    let patterns = patterns.split(',').map(str::to_string).collect();
+                    options.limits.natives = Some(patterns);
+                } else if let Some(patterns) = arg.strip_prefix("--allow-classes=") {
+                    let patterns = patterns.split(',').map(str::to_string).collect();
+                    options.limits.classes = Some(patterns);
+                } else if let Some(file) = arg.strip_prefix("--profile=") {
+                    options.profile = Some(PathBuf::from(file));
+                } else if let Some(agent) = arg.strip_prefix("-agentlib:jdwp=") {
+                    options.agent = Some(agent.to_string());
+                } else if arg.starts_with('-') {
+                    fail(&format!("Unrecognized option: {arg}"));
+                } else if arg.ends_with(".class") && Path::new(&arg).is_file() {
+                    break Launch::File(PathBuf::from(arg));
+                } else {
+                    break Launch::Class(arg);
+                }
+            }
+        }
+    };
+    (options, launch, args.collect())
+}
 
-    let class = Class::parse(&mut f).context("could not parse class file")?;
+/// entries of a `-cp` list, where `dir/*` stands for the jars in `dir`. Like `java` it
+/// leaves out those which do not exist
+fn classpath_entries(classpath: &str) -> Vec<PathBuf> {
+    let mut entries = Vec::new();
+    for entry in classpath.split(':') {
+        let entry = if entry.is_empty() { "." } else { entry };
+        if let Some(dir) = entry.strip_suffix('*') {
+            let dir = if dir.is_empty() { "." } else { dir };
+            let mut jars: Vec<_> = fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext == "jar" || ext == "JAR")
+                })
+                .collect();
+            jars.sort();
+            entries.extend(jars);
+        } else if Path::new(entry).exists() {
+            entries.push(PathBuf::from(entry));
+        }
+    }
+    entries
+}
+
+fn dump(class: &Class) {
     println!("----CONSTANTS----");
     for (index, attribute) in class.cp.iter().enumerate() {
         println!("{}:\t{attribute:?}", index + 1);
@@ -59,257 +291,265 @@ fn main() -> anyhow::Result<()> {
 
     println!();
 
-    let mut functions = HashMap::new();
-
     println!("----METHODS----");
     for method in &class.methods {
         println!(
             "{:?} {:?} {:?}",
             method.access_flags, method.descriptor, method.name,
         );
-        let code = method
-            .attributes
-            .iter()
-            .find(|a| a.attribute_name == "Code")
-            .unwrap();
-        let code = cast!(&code.attribute, Attribute::Code);
-        functions.insert((method.name.clone(), method.descriptor.clone()), code);
-        println!(
-            "stack: {:?}, locals: {:?}\n",
-            code.max_stack, code.max_locals
-        );
+        if let Some(code) = method.code() {
+            println!(
+                "stack: {:?}, locals: {:?}\n",
+                code.max_stack, code.max_locals
+            );
+        }
     }
 
     println!("----EXECUTION----");
     println!();
-
-    let entry = functions
-        .get(&(String::from("main"), String::from("([Ljava/lang/String;)V")))
-        .context("no main function found")?;
-    let locals = vec![0; entry.max_locals];
-    let stack = Vec::with_capacity(entry.max_stack);
-    exec(&class, &functions, entry, locals, &mut vec![], stack)?;
-
-    Ok(())
 }
 
-fn exec(
-    c: &Class,
-    f: &HashMap<(String, String), &CodeAttribute>,
-    code: &CodeAttribute,
-    mut l: Vec<isize>,
-    s0: &mut Vec<isize>,
-    mut s: Vec<isize>,
-) -> anyhow::Result<()> {
-    let mut i = 0;
-    loop {
-        let op = &code.code[i];
-        match op {
-            OpCode::GetStatic(_index) => {
-                // here we initialize class or interface
-                // TODO: figure out what it means
-            }
-            OpCode::IConstM1 => s.push(-1),
-            OpCode::IConst0 => s.push(0),
-            OpCode::IConst1 => s.push(1),
-            OpCode::IConst2 => s.push(2),
-            OpCode::IConst3 => s.push(3),
-            OpCode::IConst4 => s.push(4),
-            OpCode::IConst5 => s.push(5),
-
-            &OpCode::BiPush(val) => s.push(val as i8 as isize),
-            &OpCode::SiPush(val) => s.push(val as i16 as isize),
-
-            &OpCode::InvokeVirtual(index) => {
-                let methodref = c.cp.get_methodref(index).unwrap();
-                let class = c.cp.get_class(methodref.class_index).unwrap();
-                let _class_name = &c.cp.get_utf(class.name_index).unwrap().bytes;
-
-                let name_type =
-                    c.cp.get_name_and_type(methodref.name_and_type_index)
-                        .unwrap();
-                let fn_name = c.cp.get_utf(name_type.name_index).unwrap().bytes.as_str();
-                let fn_type =
-                    c.cp.get_utf(name_type.descriptor_index)
-                        .unwrap()
-                        .bytes
-                        .as_str();
-                match (fn_name, fn_type) {
-                    ("println", "(I)V") => println!("{}", s.pop().unwrap()),
-                    ("println", "(Ljava/lang/String;)V") => {
-                        println!("{}", c.cp.get_utf(s.pop().unwrap() as usize).unwrap().bytes)
-                    }
+fn main() -> anyhow::Result<()> {
+    let (options, launch, args) = parse_args(env::args().skip(1));
+    let limits = options.limits.clone();
+    // java code recurses on the rust stack, the one of the main thread is too small
+    let stack = limits.stack_size();
+    let main = thread::Builder::new()
+        .name("main".to_string())
+        .stack_size(stack)
+        .spawn(move || {
+            set_thread_stack(stack);
+            run(options, limits, launch, args)
+        })?;
+    let status = main
+        .join()
+        .unwrap_or_else(|panic| panic::resume_unwind(panic))?;
+    io::stdout().flush()?;
+    exit(status)
+}
 
-                    (n, t) => todo!("not implemented {n} {t}"),
+/// runs the program, returning the exit status
+fn run(options: Options, limits: Limits, launch: Launch, args: Vec<String>) -> anyhow::Result<i32> {
+    // the class path comes from `-cp`, the jar or the `CLASSPATH` variable, with `.` as
+    // the default
+    let classpath = match &launch {
+        Launch::Jar(jar) => {
+            let archive = match Archive::open(jar) {
+                Ok(archive) => archive,
+                Err(_) => {
+                    eprintln!("Error: Unable to access jarfile {}", jar.display());
+                    return Ok(1);
                 }
-            }
-            &OpCode::InvokeStatic(index) => {
-                let methodref = c.cp.get_methodref(index).unwrap();
-                let class = c.cp.get_class(methodref.class_index).unwrap();
-                let _class_name = c.cp.get_utf(class.name_index).unwrap().bytes.as_str();
-
-                let name_type =
-                    c.cp.get_name_and_type(methodref.name_and_type_index)
-                        .unwrap();
-                let fn_name = c.cp.get_utf(name_type.name_index).unwrap().bytes.clone();
-                let fn_type =
-                    c.cp.get_utf(name_type.descriptor_index)
-                        .unwrap()
-                        .bytes
-                        .clone();
-
-                let func = f.get(&(fn_name, fn_type)).unwrap();
-
-                let mut locals = Vec::with_capacity(func.max_locals);
-                for _ in 0..func.max_locals {
-                    locals.push(s.pop().unwrap());
+            };
+            let manifest = archive.manifest()?;
+            let Some(main_class) = manifest.get("Main-Class") else {
+                eprintln!("no main manifest attribute, in {}", jar.display());
+                return Ok(1);
+            };
+            let main_class = main_class.clone();
+            // `Class-Path` lists further jars relative to the jar
+            let dir = jar.parent().unwrap_or(Path::new(""));
+            let mut classpath = jar.display().to_string();
+            for entry in manifest
+                .get("Class-Path")
+                .into_iter()
+                .flat_map(|c| c.split(' '))
+            {
+                if !entry.is_empty() {
+                    classpath += &format!(":{}", dir.join(entry).display());
                 }
-                let stack = Vec::with_capacity(func.max_stack);
-                exec(c, f, func, locals, &mut s, stack).unwrap();
             }
+            let launch = Launch::Class(main_class);
+            return start(options, limits, launch, classpath, args);
+        }
+        Launch::File(file) => match &options.classpath {
+            Some(classpath) => classpath.clone(),
+            None => match file.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.display().to_string(),
+                _ => ".".to_string(),
+            },
+        },
+        Launch::Class(_) | Launch::Module(_) => match &options.classpath {
+            Some(classpath) => classpath.clone(),
+            None => env::var("CLASSPATH").unwrap_or(".".to_string()),
+        },
+    };
+    start(options, limits, launch, classpath, args)
+}
 
-            OpCode::ILoad0 => s.push(l[0]),
-            OpCode::ILoad1 => s.push(l[1]),
-            OpCode::ILoad2 => s.push(l[2]),
-            OpCode::ILoad3 => s.push(l[3]),
-
-            OpCode::ALoad0 => s.push(l[0]),
-            OpCode::ALoad1 => s.push(l[1]),
-            OpCode::ALoad2 => s.push(l[2]),
-            OpCode::ALoad3 => s.push(l[3]),
-
-            OpCode::IStore0 => l[0] = s.pop().unwrap(),
-            OpCode::IStore1 => l[1] = s.pop().unwrap(),
-            OpCode::IStore2 => l[2] = s.pop().unwrap(),
-            OpCode::IStore3 => l[3] = s.pop().unwrap(),
-            OpCode::AStore0 => l[0] = s.pop().unwrap(),
-            OpCode::AStore1 => l[1] = s.pop().unwrap(),
-            OpCode::AStore2 => l[2] = s.pop().unwrap(),
-            OpCode::AStore3 => l[3] = s.pop().unwrap(),
-
-            OpCode::IfEq(offset) => {
-                if s.pop().unwrap() == 0 {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfNe(offset) => {
-                if s.pop().unwrap() != 0 {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfLt(offset) => {
-                if s.pop().unwrap() < 0 {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfGe(offset) => {
-                if s.pop().unwrap() >= 0 {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfGt(offset) => {
-                if s.pop().unwrap() > 0 {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfLe(offset) => {
-                if s.pop().unwrap() <= 0 {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfICmpEq(offset) => {
-                if s.pop().unwrap() == s.pop().unwrap() {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
+fn start(
+    options: Options,
+    limits: Limits,
+    launch: Launch,
+    classpath: String,
+    args: Vec<String>,
+) -> anyhow::Result<i32> {
+    let mut builder = Vm::builder()
+        .property("java.class.path", &classpath)
+        .verbose_class(options.verbose_class)
+        .limits(limits);
+    for entry in classpath_entries(&classpath) {
+        builder = builder.classpath(entry);
+    }
+    if let Launch::Module(spec) = &launch {
+        let module = spec
+            .split_once('/')
+            .map_or(spec.as_str(), |(module, _)| module);
+        let module_path = options.module_path.clone().unwrap_or_default();
+        builder = builder
+            .property("jdk.module.path", &module_path)
+            .property("jdk.module.main", module)
+            .main_module(module);
+        for entry in module_path.split(':').filter(|e| Path::new(e).exists()) {
+            builder = builder.module_path(entry);
+        }
+    }
+    for (key, value) in options.properties {
+        builder = builder.property(key, value);
+    }
+    if let Some(home) = options.jdk {
+        builder = builder.jdk(home);
+    }
+    if let Some(max_heap) = options.max_heap {
+        builder = builder.max_heap(max_heap);
+    }
+    if let Some(patterns) = options.trace {
+        let out: Box<dyn Write + Send> = match options.trace_file {
+            Some(file) => {
+                let file = File::create(&file)
+                    .with_context(|| format!("failed to create {}", file.display()))?;
+                Box::new(BufWriter::new(file))
             }
-
-            OpCode::IfICmpNe(offset) => {
-                if s.pop().unwrap() != s.pop().unwrap() {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
+            None => Box::new(BufWriter::new(io::stderr())),
+        };
+        builder = builder.tracer(Tracer::new(patterns, out));
+    }
+    if let Some(file) = options.profile {
+        let stacks =
+            File::create(&file).with_context(|| format!("failed to create {}", file.display()))?;
+        builder = builder.profiler(Profiler::new(
+            Box::new(BufWriter::new(stacks)),
+            Box::new(io::stderr()),
+        ));
+    }
+    // the JDK boots without stopping in the debugger
+    if options.debug {
+        let input = Box::new(BufReader::new(io::stdin()));
+        builder = builder.debugger(Debugger::new(input));
+    }
+    let mut vm = match builder.build() {
+        Ok(vm) => vm,
+        Err(err) => match err.downcast_ref::<FindException>() {
+            Some(find) => {
+                eprintln!("Error occurred during initialization of boot layer\n{find}");
+                return Ok(1);
             }
+            None => return Err(err),
+        },
+    };
+    if let Some(agent) = &options.agent {
+        vm.jdwp_start(agent)
+            .context("failed to start the JDWP agent")?;
+    }
 
-            OpCode::IfICmpLt(offset) => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                if b < a {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfICmpGe(offset) => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                if b >= a {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
+    let class = match launch {
+        Launch::File(file) => {
+            let mut buffer = Vec::new();
+            File::open(&file)
+                .with_context(|| format!("failed to open {}", file.display()))?
+                .read_to_end(&mut buffer)?;
+            let class = Class::parse(&mut ByteStream::from(buffer))
+                .context("could not parse class file")?;
+            if options.dump {
+                dump(&class);
             }
-            OpCode::IfICmpGt(offset) => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                if b > a {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
-            }
-            OpCode::IfICmpLe(offset) => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                if b <= a {
-                    i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                    continue;
-                }
+            vm.define_class(class).map(Some)
+        }
+        Launch::Class(name) => {
+            let class = vm.load_class(&name.replace('.', "/"));
+            if let Ok(None) = class {
+                eprintln!("Error: Could not find or load main class {name}");
+                eprintln!("Caused by: java.lang.ClassNotFoundException: {name}");
+                vm.shutdown()?;
+                return Ok(1);
             }
-
-            OpCode::Goto(offset) => {
-                i = code.bytecode_to_op[(code.op_to_bytecode[i] as isize + offset) as usize];
-                continue;
+            if let (true, Ok(Some(class))) = (options.dump, &class) {
+                dump(class);
             }
-
-            OpCode::IAdd => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                s.push(a + b);
+            class
+        }
+        Launch::Module(spec) => {
+            let (module, name) = match spec.split_once('/') {
+                Some((module, class)) => (module, class.to_string()),
+                None => match vm.modules().get(&spec).and_then(|m| m.main_class.as_ref()) {
+                    Some(class) => (spec.as_str(), class.replace('/', ".")),
+                    None => {
+                        eprintln!(
+                            "module {spec} does not have a ModuleMainClass attribute, use -m \
+                             <module>/<main-class>"
+                        );
+                        vm.shutdown()?;
+                        return Ok(1);
+                    }
+                },
+            };
+            let class_name = name.replace('.', "/");
+            // the main class has to be in the module
+            let in_module = vm
+                .modules()
+                .of(&class_name)
+                .is_some_and(|m| m.name == module);
+            let class = match in_module {
+                true => vm.load_class(&class_name),
+                false => Ok(None),
+            };
+            if let Ok(None) = class {
+                eprintln!("Error: Could not find or load main class {name} in module {module}");
+                vm.shutdown()?;
+                return Ok(1);
             }
-
-            OpCode::ISub => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                s.push(b - a);
+            if let (true, Ok(Some(class))) = (options.dump, &class) {
+                dump(class);
             }
-
-            OpCode::IMul => {
-                let a = s.pop().unwrap();
-                let b = s.pop().unwrap();
-                s.push(a * b);
+            class
+        }
+        Launch::Jar(_) => unreachable!("jars are launched by their main class"),
+    };
+    let result = class.and_then(|class| {
+        let class = class.unwrap();
+        let entry = class.get_method("main", "([Ljava/lang/String;)V");
+        let public_static = MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC;
+        let Some(entry) = entry.filter(|m| m.access_flags.contains(public_static)) else {
+            let name = class.this_class_name.replace('/', ".");
+            eprintln!(
+                "Error: Main method not found in class {name}, please define the main method \
+                 as:\n   public static void main(String[] args)\nor a JavaFX application \
+                 class must extend javafx.application.Application"
+            );
+            return Err(Exit(1).into());
+        };
+        let args = vm.new_string_array(args.into_iter().map(Some).collect());
+        vm.roots.push(args);
+        vm.init_class(&class.this_class_name)?;
+        let args = vm.roots.pop().unwrap();
+        vm.invoke(&class, entry, vec![args])
+    });
+    let status = match result {
+        Ok(_) => 0,
+        Err(err) => match (err.downcast_ref::<Throw>(), err.downcast_ref::<Exit>()) {
+            (Some(&Throw(exception)), _) => {
+                vm.print_uncaught("main", exception);
+                1
             }
-
-            &OpCode::Iinc(index, incr) => l[index] += incr,
-
-            &OpCode::Ldc(index) => match c.cp.get(index).unwrap() {
-                &CpInfo::Integer(IntegerInfo { val }) => s.push(val as i32 as isize),
-                &CpInfo::String(StringInfo { string_index }) => s.push(string_index as isize),
-
-                a => todo!("not implemented {:?}", a),
-            },
-            OpCode::IReturn => {
-                s0.push(s.pop().unwrap());
-                break;
+            (_, Some(&Exit(status))) => status,
+            _ => {
+                vm.shutdown()?;
+                return Err(err);
             }
-            OpCode::Return => break,
-
-            op => todo!("opcode not implemented: {op:?}"),
-        }
-        i += 1;
-    }
-    Ok(())
+        },
+    };
+    // the call returned once the last thread which is not a daemon terminated
+    let status = vm.exit_status().unwrap_or(status);
+    vm.shutdown()?;
+    Ok(status)
 }
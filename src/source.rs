@@ -37,6 +37,16 @@ impl ByteStream {
             None
         }
     }
+    /// bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.v.len().saturating_sub(self.i)
+    }
+    /// the next `len` bytes, `None` when fewer are left
+    pub fn next_bytes(&mut self, len: usize) -> Option<Vec<u8>> {
+        let bytes = self.v.get(self.i..self.i.checked_add(len)?)?.to_vec();
+        self.i += len;
+        Some(bytes)
+    }
     pub fn next_u1(&mut self) -> Option<u8> {
         self.next()
     }
@@ -0,0 +1,282 @@
+//! access control of JVMS §5.4.4, checked the first time a class runs a field or method
+//! instruction. Private members are accessible within a nest: a top level class and the
+//! classes nested in it, as told by their `NestHost` and `NestMembers` attributes.
+//! Public classes of a named module are accessible from the modules which read it and
+//! which it exports their package to, see [`module`](super::module).
+//! Violations throw `IllegalAccessError` with the message of HotSpot
+
+use crate::class::{descriptor::MethodDescriptor, Class, ClassAccessFlags};
+
+use super::{
+    link::{FieldSite, MethodSite},
+    module::module_name,
+    Vm,
+};
+
+const PUBLIC: usize = 0x0001;
+const PRIVATE: usize = 0x0002;
+const PROTECTED: usize = 0x0004;
+
+pub(super) fn package(class: &str) -> &str {
+    class.rsplit_once('/').map_or("", |(package, _)| package)
+}
+
+/// `[Ljava/lang/String;` -> `java.lang.String[]`
+pub(super) fn type_name(descriptor: &str) -> String {
+    match descriptor.strip_prefix('[') {
+        Some(element) => format!("{}[]", type_name(element)),
+        None => match descriptor {
+            "Z" => "boolean".to_string(),
+            "B" => "byte".to_string(),
+            "C" => "char".to_string(),
+            "S" => "short".to_string(),
+            "I" => "int".to_string(),
+            "J" => "long".to_string(),
+            "F" => "float".to_string(),
+            "D" => "double".to_string(),
+            "V" => "void".to_string(),
+            _ => descriptor[1..descriptor.len() - 1].replace('/', "."),
+        },
+    }
+}
+
+fn visibility(flags: usize) -> &'static str {
+    match flags {
+        _ if flags & PRIVATE != 0 => "private ",
+        _ if flags & PROTECTED != 0 => "protected ",
+        _ => "",
+    }
+}
+
+/// element class of an array class, the class itself otherwise
+fn element(class: &str) -> &str {
+    let element = class.trim_start_matches('[');
+    match element.strip_prefix('L') {
+        Some(name) if class.starts_with('[') => name.trim_end_matches(';'),
+        _ => element,
+    }
+}
+
+/// whether a class comes with the JVM, in module `java.base`: classes of the java library
+/// and arrays of them or of primitives
+pub(super) fn is_boot(class: &str) -> bool {
+    let element = element(class);
+    let primitive = class.starts_with('[') && element.len() == 1;
+    primitive
+        || ["java/", "javax/", "jdk/", "sun/"]
+            .iter()
+            .any(|p| element.starts_with(p))
+}
+
+impl Vm {
+    /// where HotSpot tells a class comes from
+    pub(super) fn class_location(&self, class: &str) -> String {
+        if is_boot(class) {
+            return "module java.base of loader 'bootstrap'".to_string();
+        }
+        match self.modules.of(element(class)) {
+            Some(module) => match &module.version {
+                Some(version) => format!("module {}@{version} of loader 'app'", module.name),
+                None => format!("module {} of loader 'app'", module.name),
+            },
+            None => "unnamed module of loader 'app'".to_string(),
+        }
+    }
+
+    /// `(A and B are in unnamed module of loader 'app')`, which HotSpot appends to the
+    /// messages of errors involving two classes
+    pub(super) fn locations(&self, a: &str, b: &str) -> String {
+        let (a_location, b_location) = (self.class_location(a), self.class_location(b));
+        let (a, b) = (a.replace('/', "."), b.replace('/', "."));
+        match a_location == b_location {
+            true => format!("({a} and {b} are in {a_location})"),
+            false => format!("({a} is in {a_location}; {b} is in {b_location})"),
+        }
+    }
+
+    /// why code in class `from` may not use public class `class` of another module:
+    /// `module app does not read module lib` or `module lib does not export b to module
+    /// app`. Reflection does not need the module of `from` to read it
+    pub(super) fn module_denial(
+        &self,
+        from: &str,
+        class: &str,
+        reflective: bool,
+    ) -> Option<String> {
+        let to = self.modules.of(class)?;
+        let module = self.modules.of(from);
+        if module.is_some_and(|m| m.name == to.name) {
+            return None;
+        }
+        if !reflective && module.is_some_and(|m| !m.reads(to)) {
+            return Some(format!(
+                "{} does not read {}",
+                module_name(module),
+                module_name(Some(to))
+            ));
+        }
+        let package = package(class);
+        match to.exports(package, module) {
+            true => None,
+            false => Some(format!(
+                "{} does not export {} to {}",
+                module_name(Some(to)),
+                package.replace('/', "."),
+                module_name(module)
+            )),
+        }
+    }
+
+    /// ` (in module lib)` for classes of named modules, as reflection names them
+    pub(super) fn in_module(&self, class: &str) -> String {
+        match self.modules.of(class) {
+            Some(module) => format!(" (in module {})", module.name),
+            None => String::new(),
+        }
+    }
+
+    /// host of the nest of `class`. A class claiming a host which does not list it as a
+    /// member hosts its own nest, like in HotSpot
+    pub(super) fn nest_host(&mut self, class: &Class) -> anyhow::Result<String> {
+        let name = &class.this_class_name;
+        if let Some(host) = class.nest_host() {
+            let member = match self.load_class(host)? {
+                Some(h) => h.nest_members().contains(name) && package(host) == package(name),
+                None => false,
+            };
+            if member {
+                return Ok(host.to_string());
+            }
+        }
+        Ok(name.clone())
+    }
+
+    /// whether code in `from` may access a member of `declaring` with access `flags`
+    fn can_access(
+        &mut self,
+        from: &Class,
+        declaring: &Class,
+        flags: usize,
+    ) -> anyhow::Result<bool> {
+        let (from_name, declaring_name) = (&from.this_class_name, &declaring.this_class_name);
+        let same_package = package(from_name) == package(declaring_name);
+        Ok(match flags {
+            _ if flags & PUBLIC != 0 => true,
+            _ if flags & PRIVATE != 0 => {
+                from_name == declaring_name || self.nest_host(from)? == self.nest_host(declaring)?
+            }
+            _ if flags & PROTECTED != 0 => {
+                same_package || self.is_subclass(from_name, declaring_name)?
+            }
+            _ => same_package,
+        })
+    }
+
+    /// whether the method running a reflective call may use a member of `declaring` with
+    /// access `flags`, like `Reflection.verifyMemberAccess`
+    pub(super) fn can_reflect(&mut self, declaring: &Class, flags: usize) -> anyhow::Result<bool> {
+        let Some(from) = self.frames.last().map(|frame| frame.class.clone()) else {
+            return Ok(true);
+        };
+        let (from_name, declaring_name) = (&from.this_class_name, &declaring.this_class_name);
+        let class_access = declaring.access_flags.contains(ClassAccessFlags::PUBLIC)
+            && self
+                .module_denial(from_name, declaring_name, true)
+                .is_none()
+            || package(from_name) == package(declaring_name);
+        Ok(from_name == declaring_name
+            || class_access && self.can_access(&from, declaring, flags)?)
+    }
+
+    /// throws `IllegalAccessError` unless `class` is in the package of `from`, or public
+    /// and in a module the module of `from` reads and which exports it
+    fn check_class_access(&mut self, from: &Class, class: &str) -> anyhow::Result<()> {
+        if class.starts_with('[') {
+            return Ok(());
+        }
+        let Some(c) = self.load_class(class)? else {
+            return Ok(());
+        };
+        let from = &from.this_class_name;
+        if package(from) == package(class) {
+            return Ok(());
+        }
+        let message = match c.access_flags.contains(ClassAccessFlags::PUBLIC) {
+            true => match self.module_denial(from, class, false) {
+                None => return Ok(()),
+                Some(reason) => format!(
+                    "class {} (in {}) cannot access class {} (in {}) because {reason}",
+                    from.replace('/', "."),
+                    module_name(self.modules.of(from)),
+                    class.replace('/', "."),
+                    module_name(self.modules.of(class)),
+                ),
+            },
+            false => format!(
+                "failed to access class {} from class {} {}",
+                class.replace('/', "."),
+                from.replace('/', "."),
+                self.locations(class, from)
+            ),
+        };
+        Err(self.exception("java/lang/IllegalAccessError", &message))
+    }
+
+    /// checks that `from` may access the method a call site refers to. Methods which do
+    /// not resolve are left to the call
+    pub(super) fn check_method_access(
+        &mut self,
+        from: &Class,
+        site: &MethodSite,
+    ) -> anyhow::Result<()> {
+        self.check_class_access(from, &site.class)?;
+        if let Some((declaring, i)) =
+            self.resolve_method(&site.class, &site.name, &site.descriptor)?
+        {
+            let flags = declaring.methods[i].access_flags.bits();
+            if !self.can_access(from, &declaring, flags)? {
+                let descriptor = MethodDescriptor::parse(&site.descriptor).unwrap();
+                let params: Vec<_> = descriptor.args.iter().map(|a| type_name(a)).collect();
+                let message = format!(
+                    "class {} tried to access {}method '{} {}.{}({})' {}",
+                    from.this_class_name.replace('/', "."),
+                    visibility(flags),
+                    type_name(&descriptor.ret),
+                    declaring.this_class_name.replace('/', "."),
+                    site.name,
+                    params.join(", "),
+                    self.locations(&from.this_class_name, &declaring.this_class_name)
+                );
+                return Err(self.exception("java/lang/IllegalAccessError", &message));
+            }
+        }
+        site.checked.set(true);
+        Ok(())
+    }
+
+    /// checks that `from` may access the field a field instruction refers to. Fields which
+    /// do not resolve are left to the instruction
+    pub(super) fn check_field_access(
+        &mut self,
+        from: &Class,
+        site: &FieldSite,
+    ) -> anyhow::Result<()> {
+        self.check_class_access(from, &site.class)?;
+        if let Some((declaring, i)) = self.resolve_field(&site.class, &site.name)? {
+            let flags = declaring.fields[i].access_flags.bits();
+            if !self.can_access(from, &declaring, flags)? {
+                let message = format!(
+                    "class {} tried to access {}field {}.{} {}",
+                    from.this_class_name.replace('/', "."),
+                    visibility(flags),
+                    declaring.this_class_name.replace('/', "."),
+                    site.name,
+                    self.locations(&from.this_class_name, &declaring.this_class_name)
+                );
+                return Err(self.exception("java/lang/IllegalAccessError", &message));
+            }
+        }
+        site.checked.set(true);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+
+use super::{
+    classpath::ClassSource, debug::Debugger, exception::Throw, limits::Limits, profile::Profiler,
+    trace::Tracer, Vm,
+};
+
+/// sets up a [`Vm`], see [`Vm::builder`]
+#[derive(Default)]
+pub struct VmBuilder {
+    classpath: Vec<PathBuf>,
+    module_path: Vec<PathBuf>,
+    main_module: Option<String>,
+    jdk: Option<PathBuf>,
+    max_heap: Option<usize>,
+    tracer: Option<Tracer>,
+    profiler: Option<Profiler>,
+    debugger: Option<Debugger>,
+    limits: Option<Limits>,
+    host_stack: Option<usize>,
+    properties: Vec<(String, String)>,
+    verbose_class: bool,
+}
+
+impl VmBuilder {
+    /// adds a directory, archive or JDK to look for classes in, searched in the order added
+    pub fn classpath(mut self, path: impl Into<PathBuf>) -> Self {
+        self.classpath.push(path.into());
+        self
+    }
+
+    /// adds a modular jar, a plain jar taken as an automatic module, or a directory of
+    /// them to look for modules in, see [`module`](super::module)
+    pub fn module_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.module_path.push(path.into());
+        self
+    }
+
+    /// resolves module `name` from the module path and the modules it requires, failing
+    /// the build with a [`FindException`](super::module::FindException) if one is missing
+    pub fn main_module(mut self, name: impl Into<String>) -> Self {
+        self.main_module = Some(name.into());
+        self
+    }
+
+    /// runs on the class library of the JDK at `home` instead of the built-in one
+    pub fn jdk(mut self, home: impl Into<PathBuf>) -> Self {
+        self.jdk = Some(home.into());
+        self
+    }
+
+    /// limits the heap to about `bytes`, beyond which allocations throw `OutOfMemoryError`
+    pub fn max_heap(mut self, bytes: usize) -> Self {
+        self.max_heap = Some(bytes);
+        self
+    }
+
+    pub fn tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    pub fn profiler(mut self, profiler: Profiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// stops the program in `debugger` once the JDK booted
+    pub fn debugger(mut self, debugger: Debugger) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    /// sets a system property which `System.getProperty` returns
+    pub fn property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+
+    /// logs every class loaded from the classpath to stdout
+    pub fn verbose_class(mut self, verbose: bool) -> Self {
+        self.verbose_class = verbose;
+        self
+    }
+
+    /// runs java code within `limits`, which apply once the JDK booted
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// rust stack the threads of the host calling into the vm have left, from where they
+    /// call. Java code throws `StackOverflowError` before it runs out. The default is
+    /// [`HOST_STACK`](super::limits::HOST_STACK), the stack of threads the standard library
+    /// spawns
+    pub fn host_stack(mut self, bytes: usize) -> Self {
+        self.host_stack = Some(bytes);
+        self
+    }
+
+    /// opens the classpath and the modules, and with a JDK initializes its class library
+    pub fn build(self) -> anyhow::Result<Vm> {
+        let classpath = self
+            .classpath
+            .iter()
+            .map(|path| {
+                ClassSource::open(path).with_context(|| format!("bad classpath {}", path.display()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let mut vm = match &self.jdk {
+            Some(home) => Vm::with_jdk(home, classpath)?,
+            None => Vm::new(classpath),
+        };
+        if let Some(module) = &self.main_module {
+            vm.resolve_modules(&self.module_path, module)?;
+        }
+        if let Some(host_stack) = self.host_stack {
+            vm.host_stack.1 = host_stack;
+        }
+        if let Some(max_heap) = self.max_heap {
+            vm.heap.set_max(max_heap);
+        }
+        vm.properties = self.properties;
+        vm.verbose_class = self.verbose_class;
+        vm.tracer = self.tracer;
+        vm.profiler = self.profiler;
+        if self.jdk.is_some() {
+            if let Err(err) = vm.boot() {
+                return Err(match err.downcast_ref::<Throw>() {
+                    Some(&Throw(exception)) => anyhow!(
+                        "exception while booting the JDK: {}",
+                        vm.describe_exception(exception)
+                    ),
+                    None => err.context("failed to boot the JDK"),
+                });
+            }
+        }
+        vm.debugger = self.debugger;
+        if let Some(limits) = self.limits {
+            vm.set_limits(limits);
+        }
+        Ok(vm)
+    }
+}
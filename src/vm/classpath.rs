@@ -0,0 +1,319 @@
+//! Places class files are loaded from: directories, jar and jmod archives
+//! and the `lib/modules` image of a JDK
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+
+/// each with the path it was opened from
+pub enum ClassSource {
+    Dir(PathBuf),
+    Archive(PathBuf, Archive),
+    Image(PathBuf, Image),
+}
+
+impl ClassSource {
+    /// picks the kind of source from the path: directories, `.jar`/`.zip`/`.jmod` files,
+    /// a jimage file or the home directory of a JDK
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if path.is_dir() {
+            let modules = path.join("lib").join("modules");
+            if modules.is_file() {
+                return Ok(ClassSource::Image(modules.clone(), Image::open(&modules)?));
+            }
+            let java_base = path.join("jmods").join("java.base.jmod");
+            if java_base.is_file() {
+                let archive = Archive::open(&java_base)?;
+                return Ok(ClassSource::Archive(java_base, archive));
+            }
+            return Ok(ClassSource::Dir(path.to_path_buf()));
+        }
+        let data = fs::read(path).with_context(|| format!("failed to open {}", path.display()))?;
+        if data.starts_with(&IMAGE_MAGIC.to_le_bytes()) {
+            return Ok(ClassSource::Image(path.to_path_buf(), Image::parse(data)?));
+        }
+        let archive = Archive::parse(data)
+            .with_context(|| format!("failed to read archive {}", path.display()))?;
+        Ok(ClassSource::Archive(path.to_path_buf(), archive))
+    }
+
+    /// where classes come from as `-verbose:class` tells
+    pub fn location(&self) -> String {
+        let absolute = |path: &Path| fs::canonicalize(path).unwrap_or(path.to_path_buf());
+        match self {
+            ClassSource::Dir(dir) => format!("file:{}/", absolute(dir).display()),
+            ClassSource::Archive(path, _) => format!("file:{}", absolute(path).display()),
+            ClassSource::Image(..) => "jrt:/".to_string(),
+        }
+    }
+
+    /// contents of the class file for a binary name like `java/lang/Object`
+    pub fn read(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match self {
+            ClassSource::Dir(dir) => {
+                let path = dir.join(format!("{name}.class"));
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                let data = fs::read(&path)
+                    .with_context(|| format!("failed to open {}", path.display()))?;
+                Ok(Some(data))
+            }
+            ClassSource::Archive(_, archive) => archive.read(&format!("{name}.class")),
+            ClassSource::Image(_, image) => Ok(image.read(&format!("{name}.class"))),
+        }
+    }
+
+    /// packages with classes in them, like `b/internal`, which a module without a
+    /// `ModulePackages` attribute consists of
+    pub fn packages(&self) -> anyhow::Result<HashSet<String>> {
+        let mut packages = HashSet::new();
+        let mut add = |name: &str| {
+            if let Some((package, _)) = name.strip_suffix(".class").and_then(|n| n.rsplit_once('/'))
+            {
+                if !package.starts_with("META-INF") {
+                    packages.insert(package.to_string());
+                }
+            }
+        };
+        match self {
+            ClassSource::Dir(dir) => {
+                let mut dirs = vec![dir.clone()];
+                while let Some(at) = dirs.pop() {
+                    let entries = fs::read_dir(&at)
+                        .with_context(|| format!("failed to read {}", at.display()))?;
+                    for entry in entries {
+                        let path = entry?.path();
+                        if path.is_dir() {
+                            dirs.push(path);
+                        } else if let Ok(name) = path.strip_prefix(dir) {
+                            add(&name.to_string_lossy());
+                        }
+                    }
+                }
+            }
+            ClassSource::Archive(_, archive) => archive.entries.keys().for_each(|name| add(name)),
+            ClassSource::Image(..) => {}
+        }
+        Ok(packages)
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().unwrap()) as usize)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().unwrap()) as usize)
+}
+
+struct Entry {
+    offset: usize,
+    method: usize,
+    compressed_size: usize,
+    size: usize,
+}
+
+/// zip file: a jar, or a jmod which is a zip with a 4 byte header and classes under `classes/`
+pub struct Archive {
+    data: Vec<u8>,
+    entries: HashMap<String, Entry>,
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read(path).with_context(|| format!("failed to open {}", path.display()))?;
+        Archive::parse(data).with_context(|| format!("failed to read archive {}", path.display()))
+    }
+
+    pub fn parse(data: Vec<u8>) -> anyhow::Result<Self> {
+        const END_OF_DIRECTORY: &[u8] = b"PK\x05\x06";
+        const DIRECTORY_ENTRY: &[u8] = b"PK\x01\x02";
+        let end = data
+            .windows(4)
+            .rposition(|w| w == END_OF_DIRECTORY)
+            .context("not a zip file")?;
+        let count = u16_at(&data, end + 10).context("truncated zip file")?;
+        let directory_size = u32_at(&data, end + 12).context("truncated zip file")?;
+        let directory_offset = u32_at(&data, end + 16).context("truncated zip file")?;
+        // offsets are relative to the start of the zip, which need not be the start of the file
+        let base = end
+            .checked_sub(directory_offset + directory_size)
+            .context("invalid zip directory")?;
+        let jmod = data.starts_with(b"JM");
+
+        let mut entries = HashMap::with_capacity(count);
+        let mut at = base + directory_offset;
+        for _ in 0..count {
+            if data.get(at..at + 4) != Some(DIRECTORY_ENTRY) {
+                bail!("invalid zip directory entry");
+            }
+            let field = |offset| u16_at(&data, at + offset).context("truncated zip file");
+            let (name_length, extra_length, comment_length) = (field(28)?, field(30)?, field(32)?);
+            let name = data
+                .get(at + 46..at + 46 + name_length)
+                .context("truncated zip file")?;
+            let name = String::from_utf8_lossy(name);
+            let name = match jmod {
+                true => name.strip_prefix("classes/").map(str::to_string),
+                false => Some(name.into_owned()),
+            };
+            if let Some(name) = name {
+                let entry = Entry {
+                    method: field(10)?,
+                    compressed_size: u32_at(&data, at + 20).context("truncated zip file")?,
+                    size: u32_at(&data, at + 24).context("truncated zip file")?,
+                    offset: base + u32_at(&data, at + 42).context("truncated zip file")?,
+                };
+                entries.insert(name, entry);
+            }
+            at += 46 + name_length + extra_length + comment_length;
+        }
+        Ok(Archive { data, entries })
+    }
+
+    /// main attributes of the manifest of a jar, like `Main-Class`
+    pub fn manifest(&self) -> anyhow::Result<HashMap<String, String>> {
+        let Some(manifest) = self.read("META-INF/MANIFEST.MF")? else {
+            return Ok(HashMap::new());
+        };
+        let manifest = String::from_utf8_lossy(&manifest);
+        // lines starting with a space continue the one before
+        let mut lines: Vec<String> = Vec::new();
+        for line in manifest.lines() {
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(rest), Some(last)) => last.push_str(rest),
+                _ if line.is_empty() => break,
+                _ => lines.push(line.to_string()),
+            }
+        }
+        Ok(lines
+            .iter()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+            .collect())
+    }
+
+    pub fn read(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(entry) = self.entries.get(name) else {
+            return Ok(None);
+        };
+        // the local header repeats name and extra field, with lengths that may differ
+        let header = entry.offset;
+        let name_length = u16_at(&self.data, header + 26).context("truncated zip file")?;
+        let extra_length = u16_at(&self.data, header + 28).context("truncated zip file")?;
+        let start = header + 30 + name_length + extra_length;
+        let raw = self
+            .data
+            .get(start..start + entry.compressed_size)
+            .context("truncated zip file")?;
+        let data = match entry.method {
+            0 => raw.to_vec(),
+            8 => miniz_oxide::inflate::decompress_to_vec_with_limit(raw, entry.size)
+                .map_err(|err| anyhow::anyhow!("failed to inflate {name}: {err}"))?,
+            method => bail!("unsupported compression method {method} for {name}"),
+        };
+        Ok(Some(data))
+    }
+}
+
+const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+
+/// location attribute kinds of a jimage
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: u8 = 1;
+const ATTRIBUTE_PARENT: u8 = 2;
+const ATTRIBUTE_BASE: u8 = 3;
+const ATTRIBUTE_EXTENSION: u8 = 4;
+const ATTRIBUTE_OFFSET: u8 = 5;
+const ATTRIBUTE_COMPRESSED: u8 = 6;
+const ATTRIBUTE_UNCOMPRESSED: u8 = 7;
+
+/// the jimage format of `lib/modules`. Every resource is indexed by its name without the module,
+/// since class names do not say which module they belong to
+pub struct Image {
+    data: Vec<u8>,
+    /// name -> (offset, size) of the uncompressed resources
+    resources: HashMap<String, (usize, usize)>,
+}
+
+impl Image {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read(path).with_context(|| format!("failed to open {}", path.display()))?;
+        Image::parse(data).with_context(|| format!("failed to read image {}", path.display()))
+    }
+
+    pub fn parse(data: Vec<u8>) -> anyhow::Result<Self> {
+        let header = |i: usize| u32_at(&data, i * 4).context("truncated image header");
+        if header(0)? != IMAGE_MAGIC as usize {
+            bail!("not a jimage file");
+        }
+        let (table_length, locations_size, strings_size) = (header(4)?, header(5)?, header(6)?);
+        let offsets = 28 + table_length * 4;
+        let locations = offsets + table_length * 4;
+        let strings = locations + locations_size;
+        let index_size = strings + strings_size;
+        if data.len() < index_size {
+            bail!("truncated image index");
+        }
+        let string = |offset: u64| -> anyhow::Result<&str> {
+            let start = strings + offset as usize;
+            let len = data[start..index_size]
+                .iter()
+                .position(|&b| b == 0)
+                .context("unterminated image string")?;
+            Ok(std::str::from_utf8(&data[start..start + len])?)
+        };
+
+        let mut resources = HashMap::with_capacity(table_length);
+        for i in 0..table_length {
+            let mut at = locations + u32_at(&data, offsets + i * 4).unwrap();
+            let mut attributes = [0u64; 8];
+            loop {
+                let byte = *data.get(at).context("truncated image location")?;
+                let kind = byte >> 3;
+                if kind == ATTRIBUTE_END {
+                    break;
+                }
+                let length = (byte & 7) as usize + 1;
+                let value = data
+                    .get(at + 1..at + 1 + length)
+                    .context("truncated image location")?;
+                if let Some(attribute) = attributes.get_mut(kind as usize) {
+                    *attribute = value.iter().fold(0, |v, &b| v << 8 | b as u64);
+                }
+                at += 1 + length;
+            }
+            if attributes[ATTRIBUTE_COMPRESSED as usize] != 0 {
+                continue;
+            }
+            let mut name = String::new();
+            if attributes[ATTRIBUTE_PARENT as usize] != 0 {
+                name.push_str(string(attributes[ATTRIBUTE_PARENT as usize])?);
+                name.push('/');
+            }
+            name.push_str(string(attributes[ATTRIBUTE_BASE as usize])?);
+            if attributes[ATTRIBUTE_EXTENSION as usize] != 0 {
+                name.push('.');
+                name.push_str(string(attributes[ATTRIBUTE_EXTENSION as usize])?);
+            }
+            // module-info.class exists in every module, the resource name alone is ambiguous
+            if attributes[ATTRIBUTE_MODULE as usize] == 0 || name == "module-info.class" {
+                continue;
+            }
+            let offset = index_size + attributes[ATTRIBUTE_OFFSET as usize] as usize;
+            let size = attributes[ATTRIBUTE_UNCOMPRESSED as usize] as usize;
+            resources.insert(name, (offset, size));
+        }
+        Ok(Image { data, resources })
+    }
+
+    pub fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let &(offset, size) = self.resources.get(name)?;
+        self.data.get(offset..offset + size).map(<[u8]>::to_vec)
+    }
+}
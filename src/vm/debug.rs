@@ -0,0 +1,440 @@
+//! `jrust debug`: a command line debugger. The interpreter calls `Vm::debug_hook` before
+//! every instruction of a debugged run, which stops at breakpoints and after steps and then
+//! reads commands until one of them resumes the program
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+use crate::class::Class;
+
+use super::{heap::Object, link::Linked, trace::describe_instruction, value::Value, Vm};
+
+const HELP: &str = "\
+break <Class.method>[@pc] | <File.java:line> | <line>   set a breakpoint
+delete <n>          remove breakpoint n
+breakpoints         list breakpoints
+step                run one instruction
+next                run one instruction, stepping over calls
+finish              run until the current method returns
+continue            run until the next breakpoint
+backtrace           show the call stack
+locals              show the local variables
+stack               show the operand stack
+print <local | @object>   show a local variable or a heap object
+quit                exit the program";
+
+enum Breakpoint {
+    /// first instruction at bytecode offset `pc` of a method, the class with dots
+    Method {
+        class: String,
+        method: String,
+        pc: usize,
+    },
+    /// first instruction of a source line, in any file when `file` is `None`
+    Line { file: Option<String>, line: usize },
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Method { class, method, pc } => write!(f, "{class}.{method}@{pc}"),
+            Breakpoint::Line {
+                file: Some(file),
+                line,
+            } => write!(f, "{file}:{line}"),
+            Breakpoint::Line { file: None, line } => write!(f, "line {line}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Step {
+    /// runs until a breakpoint
+    Continue,
+    /// stops at the next instruction
+    Into,
+    /// stops at the next instruction of `thread` in a frame at most `depth` deep
+    Over { thread: usize, depth: usize },
+    /// stops at the next instruction of `thread` in a frame less than `depth` deep
+    Out { thread: usize, depth: usize },
+}
+
+/// instruction a frame is at
+#[derive(Clone)]
+pub(super) struct Position {
+    pub class: Rc<Class>,
+    pub method: usize,
+    pub pc: usize,
+}
+
+impl Position {
+    pub fn line(&self) -> Option<usize> {
+        self.class.methods[self.method].code()?.line_number(self.pc)
+    }
+}
+
+/// position of every frame of every thread, innermost last. Frames are only known from
+/// their first instruction on
+pub(super) type Positions = HashMap<usize, Vec<Option<Position>>>;
+
+/// records that the innermost frame of `thread`, `depth` deep, is at instruction `i`
+pub(super) fn record_position(
+    positions: &mut Positions,
+    thread: usize,
+    depth: usize,
+    class: &Rc<Class>,
+    code: &Linked,
+    i: usize,
+) {
+    let frames = positions.entry(thread).or_default();
+    frames.resize(depth, None);
+    frames[depth - 1] = Some(Position {
+        class: class.clone(),
+        method: code.method,
+        pc: code.pcs[i],
+    });
+}
+
+pub struct Debugger {
+    breakpoints: Vec<Option<Breakpoint>>,
+    step: Step,
+    positions: Positions,
+    input: Box<dyn BufRead + Send>,
+}
+
+impl Debugger {
+    /// debugger reading commands from `input`, which stops at the first instruction
+    pub fn new(input: Box<dyn BufRead + Send>) -> Self {
+        Debugger {
+            breakpoints: Vec::new(),
+            step: Step::Into,
+            positions: HashMap::new(),
+            input,
+        }
+    }
+
+    fn stops(&self, thread: usize, depth: usize) -> bool {
+        match self.step {
+            Step::Continue => false,
+            Step::Into => true,
+            Step::Over {
+                thread: t,
+                depth: d,
+            } => t == thread && depth <= d,
+            Step::Out {
+                thread: t,
+                depth: d,
+            } => t == thread && depth < d,
+        }
+    }
+
+    /// number of the first breakpoint at instruction `i` of `code`
+    fn breakpoint(&self, class: &Class, code: &Linked, i: usize) -> Option<usize> {
+        let pc = code.pcs[i];
+        let method = &class.methods[code.method];
+        self.breakpoints.iter().position(|b| match b {
+            Some(Breakpoint::Method {
+                class: name,
+                method: method_name,
+                pc: at,
+            }) => {
+                *at == pc
+                    && *method_name == method.name
+                    && class.this_class_name.replace('/', ".") == *name
+            }
+            Some(Breakpoint::Line { file, line }) => {
+                let starts_line = method.code().is_some_and(|code| {
+                    code.line_numbers()
+                        .any(|l| l.start_pc == pc && l.line_number == *line)
+                });
+                starts_line
+                    && file
+                        .as_ref()
+                        .is_none_or(|file| class.source_file() == Some(file.as_str()))
+            }
+            None => false,
+        })
+    }
+
+    /// `Class.method[@pc]`, `File.java:line` or `line`
+    fn parse_breakpoint(spec: &str) -> Option<Breakpoint> {
+        if let Ok(line) = spec.parse() {
+            return Some(Breakpoint::Line { file: None, line });
+        }
+        if let Some((file, line)) = spec.split_once(':') {
+            return Some(Breakpoint::Line {
+                file: Some(file.to_string()),
+                line: line.parse().ok()?,
+            });
+        }
+        let (method, pc) = match spec.split_once('@') {
+            Some((method, pc)) => (method, pc.parse().ok()?),
+            None => (spec, 0),
+        };
+        let (class, method) = method.rsplit_once('.')?;
+        Some(Breakpoint::Method {
+            class: class.replace('/', "."),
+            method: method.to_string(),
+            pc,
+        })
+    }
+}
+
+impl Vm {
+    /// called before instruction `i` of `code` runs in the innermost frame
+    pub(crate) fn debug_hook(
+        &mut self,
+        class: &Rc<Class>,
+        code: &Linked,
+        i: usize,
+    ) -> anyhow::Result<()> {
+        let Some(mut debugger) = self.debugger.take() else {
+            return Ok(());
+        };
+        let (thread, depth) = (self.threads.current, self.frames.len());
+        record_position(&mut debugger.positions, thread, depth, class, code, i);
+
+        let hit = debugger.breakpoint(class, code, i);
+        if hit.is_some() || debugger.stops(thread, depth) {
+            if let Some(n) = hit {
+                println!(
+                    "Breakpoint {}, {}",
+                    n + 1,
+                    debugger.breakpoints[n].as_ref().unwrap()
+                );
+            }
+            self.show_position(&debugger, class, code, i);
+            self.debug_prompt(&mut debugger)?;
+        }
+        self.debugger = Some(debugger);
+        Ok(())
+    }
+
+    fn show_position(&mut self, debugger: &Debugger, class: &Class, code: &Linked, i: usize) {
+        let thread = self.threads.current;
+        let position = debugger.positions[&thread].last().cloned().flatten();
+        let line = match (position.and_then(|p| p.line()), class.source_file()) {
+            (Some(line), Some(file)) => format!(" ({file}:{line})"),
+            (Some(line), None) => format!(" (line {line})"),
+            _ => String::new(),
+        };
+        println!("{}{line}", describe_instruction(class, code, i));
+    }
+
+    /// reads commands until one resumes the program, at the end of the input it runs on
+    /// without stopping
+    fn debug_prompt(&mut self, debugger: &mut Debugger) -> anyhow::Result<()> {
+        let thread = self.threads.current;
+        let depth = self.frames.len();
+        loop {
+            print!("(jrust) ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            if debugger.input.read_line(&mut line)? == 0 {
+                println!();
+                debugger.breakpoints.clear();
+                debugger.step = Step::Continue;
+                return Ok(());
+            }
+            let mut words = line.split_whitespace();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            let argument = words.next();
+            match (command, argument) {
+                ("s" | "step", _) => debugger.step = Step::Into,
+                ("n" | "next", _) => debugger.step = Step::Over { thread, depth },
+                ("f" | "finish", _) => debugger.step = Step::Out { thread, depth },
+                ("c" | "continue", _) => debugger.step = Step::Continue,
+                ("b" | "break", Some(spec)) => match Debugger::parse_breakpoint(spec) {
+                    Some(breakpoint) => {
+                        println!(
+                            "Breakpoint {} at {breakpoint}",
+                            debugger.breakpoints.len() + 1
+                        );
+                        debugger.breakpoints.push(Some(breakpoint));
+                        continue;
+                    }
+                    None => {
+                        println!("invalid breakpoint {spec}");
+                        continue;
+                    }
+                },
+                ("d" | "delete", Some(n)) => {
+                    match n
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| debugger.breakpoints.get_mut(n.wrapping_sub(1)))
+                    {
+                        Some(breakpoint @ Some(_)) => *breakpoint = None,
+                        _ => println!("no breakpoint {n}"),
+                    }
+                    continue;
+                }
+                ("breakpoints" | "info", _) => {
+                    for (n, breakpoint) in debugger.breakpoints.iter().enumerate() {
+                        if let Some(breakpoint) = breakpoint {
+                            println!("{}: {breakpoint}", n + 1);
+                        }
+                    }
+                    continue;
+                }
+                ("bt" | "backtrace", _) => {
+                    self.show_backtrace(debugger);
+                    continue;
+                }
+                ("l" | "locals", _) => {
+                    self.show_locals(debugger);
+                    continue;
+                }
+                ("stack", _) => {
+                    let stack = self.frames.last().unwrap().stack.clone();
+                    for (n, value) in stack.into_iter().enumerate().rev() {
+                        println!("  {n}: {}", self.show_value(value));
+                    }
+                    continue;
+                }
+                ("p" | "print", Some(what)) => {
+                    self.show(debugger, what);
+                    continue;
+                }
+                ("q" | "quit", _) => std::process::exit(0),
+                ("h" | "help", _) => {
+                    println!("{HELP}");
+                    continue;
+                }
+                _ => {
+                    println!("unknown command {}, try help", line.trim());
+                    continue;
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    fn show_backtrace(&self, debugger: &Debugger) {
+        let positions = &debugger.positions[&self.threads.current];
+        for (n, position) in positions.iter().rev().enumerate() {
+            let Some(position) = position else {
+                println!("#{n} <unknown>");
+                continue;
+            };
+            let method = &position.class.methods[position.method];
+            let line = match (position.line(), position.class.source_file()) {
+                (Some(line), Some(file)) => format!(" ({file}:{line})"),
+                _ => String::new(),
+            };
+            println!(
+                "#{n} {}.{}{} @{}{line}",
+                position.class.this_class_name.replace('/', "."),
+                method.name,
+                method.descriptor,
+                position.pc
+            );
+        }
+    }
+
+    /// name of every local variable slot of the innermost frame, by `LocalVariableTable`
+    fn local_names(&self, debugger: &Debugger) -> HashMap<usize, String> {
+        let positions = &debugger.positions[&self.threads.current];
+        let Some(Some(position)) = positions.last() else {
+            return HashMap::new();
+        };
+        let Some(code) = position.class.methods[position.method].code() else {
+            return HashMap::new();
+        };
+        code.local_variables(position.pc)
+            .map(|var| (var.index, var.name.clone()))
+            .collect()
+    }
+
+    fn show_locals(&mut self, debugger: &Debugger) {
+        let names = self.local_names(debugger);
+        let locals = self.frames.last().unwrap().locals.clone();
+        let mut slot = 0;
+        while slot < locals.len() {
+            let value = locals[slot];
+            let name = match names.get(&slot) {
+                Some(name) => name.clone(),
+                None => format!("local {slot}"),
+            };
+            // without debug information unused slots cannot be told from ints
+            if names.is_empty() || names.contains_key(&slot) {
+                println!("  {name} = {}", self.show_value(value));
+            }
+            slot += if value.is_wide() { 2 } else { 1 };
+        }
+    }
+
+    /// `print` of a local by name or slot, or of a heap object as `@ref`
+    fn show(&mut self, debugger: &Debugger, what: &str) {
+        let value = match what.strip_prefix('@') {
+            Some(r) => match r.parse() {
+                Ok(r) => Value::Ref(r),
+                Err(_) => {
+                    println!("invalid object {what}");
+                    return;
+                }
+            },
+            None => {
+                let names = self.local_names(debugger);
+                let slot = match what.parse::<usize>() {
+                    Ok(slot) => Some(slot),
+                    Err(_) => names
+                        .iter()
+                        .find(|(_, name)| *name == what)
+                        .map(|(&slot, _)| slot),
+                };
+                let locals = &self.frames.last().unwrap().locals;
+                match slot.and_then(|slot| locals.get(slot)) {
+                    Some(&value) => value,
+                    None => {
+                        println!("no local variable {what}");
+                        return;
+                    }
+                }
+            }
+        };
+        let Value::Ref(r) = value else {
+            println!("{what} = {}", self.show_value(value));
+            return;
+        };
+        let Some(object) = self.heap.try_get(r) else {
+            println!("no object @{r}");
+            return;
+        };
+        let fields: Vec<(String, Value)> = match object {
+            Object::Instance(instance) if !self.is_string(r) => {
+                let mut fields: Vec<_> = instance
+                    .layout
+                    .fields
+                    .iter()
+                    .zip(&instance.fields)
+                    .map(|(field, &value)| (field.name.clone(), value))
+                    .collect();
+                fields.sort_by(|a, b| a.0.cmp(&b.0));
+                fields
+            }
+            Object::Array(array) => array
+                .elements
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| (format!("[{i}]"), value))
+                .collect(),
+            Object::Lambda(lambda) => lambda
+                .captured
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| (format!("captured {i}"), value))
+                .collect(),
+            _ => vec![],
+        };
+        println!("{what} = {}", self.show_value(value));
+        for (name, value) in fields {
+            println!("  {name} = {}", self.show_value(value));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::class::Class;
+
+use super::{access::is_boot, heap::Object, link::Linked, value::Value, Vm};
+
+/// most frames a stack trace keeps, as with HotSpot's `MaxJavaStackTraceDepth`
+const MAX_STACK_TRACE_DEPTH: usize = 1024;
+
+/// java exception unwinding the rust stack until a frame with a matching handler catches it
+#[derive(Debug, Clone, Copy)]
+pub struct Throw(pub Value);
+
+impl Display for Throw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "java exception {:?}", self.0)
+    }
+}
+
+impl std::error::Error for Throw {}
+
+impl Vm {
+    /// creates an exception of the given class, to be returned as an error.
+    /// An empty message leaves `detailMessage` null
+    pub fn exception(&mut self, class: &str, message: &str) -> anyhow::Error {
+        let exception = match self.new_object(class) {
+            Ok(exception) => exception,
+            Err(err) => return err,
+        };
+        // the trace is left empty where it cannot be taken, as while the JDK boots
+        let _ = self.fill_in_stack_trace(exception);
+        if message.is_empty() {
+            return Throw(exception).into();
+        }
+        let message = self.intern(message);
+        let instance = cast!(
+            self.heap.get_mut(exception.as_ref().unwrap()),
+            Object::Instance
+        );
+        instance.set("detailMessage", message);
+        Throw(exception).into()
+    }
+
+    pub fn null_pointer(&mut self) -> anyhow::Error {
+        self.exception("java/lang/NullPointerException", "")
+    }
+
+    /// whether `class` is `of` or one of its subclasses or subinterfaces
+    pub fn is_subclass(&mut self, class: &str, of: &str) -> anyhow::Result<bool> {
+        if class == of {
+            return Ok(true);
+        }
+        let Some(c) = self.load_class(class)? else {
+            return Ok(false);
+        };
+        for interface in c.interface_names() {
+            if self.is_subclass(interface, of)? {
+                return Ok(true);
+            }
+        }
+        if c.super_class == 0 {
+            return Ok(false);
+        }
+        self.is_subclass(&c.super_class_name, of)
+    }
+
+    /// whether a value of `class` can be assigned to `to`, which follows superclasses and
+    /// interfaces, and for arrays their element types
+    pub fn is_assignable(&mut self, class: &str, to: &str) -> anyhow::Result<bool> {
+        if class == to {
+            return Ok(true);
+        }
+        let Some(element) = class.strip_prefix('[') else {
+            return match to.starts_with('[') {
+                true => Ok(false),
+                false => self.is_subclass(class, to),
+            };
+        };
+        match to.strip_prefix('[') {
+            // elements of primitive arrays have to be the same, which they are not
+            Some(to) if element.len() == 1 || to.len() == 1 => Ok(false),
+            Some(to) => {
+                let name = |descriptor: &'_ str| match descriptor.strip_prefix('L') {
+                    Some(name) => name.trim_end_matches(';').to_string(),
+                    None => descriptor.to_string(),
+                };
+                self.is_assignable(&name(element), &name(to))
+            }
+            None => Ok(matches!(
+                to,
+                "java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable"
+            )),
+        }
+    }
+
+    /// captures the java frames of the running thread into the stack trace of `exception`,
+    /// as `Throwable.fillInStackTrace` does
+    pub(crate) fn fill_in_stack_trace(&mut self, exception: Value) -> anyhow::Result<()> {
+        self.roots.push(exception);
+        let trace = self.stack_trace(exception);
+        self.roots.pop();
+        let trace = trace?;
+        let depth = self.heap.get_array(trace.as_ref().unwrap()).elements.len();
+        let instance = self.heap.get_instance_mut(exception.as_ref().unwrap());
+        match self.jdk {
+            // StackTraceElement.of copies the elements out of the backtrace once asked for
+            true => {
+                instance.set("backtrace", trace);
+                instance.set("depth", Value::Int(depth as i32));
+            }
+            false => instance.set("stackTrace", trace),
+        }
+        Ok(())
+    }
+
+    /// `StackTraceElement`s of the java frames of the running thread, innermost first.
+    /// Like HotSpot, it leaves out the frames creating `exception`: those of
+    /// `fillInStackTrace` and of the constructors of its class and superclasses
+    fn stack_trace(&mut self, exception: Value) -> anyhow::Result<Value> {
+        let class = self
+            .heap
+            .class_name(exception.as_ref().unwrap())
+            .to_string();
+        let frames: Vec<_> = self
+            .frames
+            .iter()
+            .rev()
+            .map(|frame| (frame.class.clone(), frame.code.clone(), frame.i))
+            .collect();
+        let runs = |(c, code, _): &(Rc<Class>, Rc<Linked>, usize), name: &str| {
+            c.methods[code.method].name == name
+        };
+        let mut skip = frames
+            .iter()
+            .take_while(|frame| runs(frame, "fillInStackTrace"))
+            .count();
+        while let Some(frame) = frames.get(skip) {
+            if !runs(frame, "<init>") || !self.is_subclass(&class, &frame.0.this_class_name)? {
+                break;
+            }
+            skip += 1;
+        }
+        // no java code runs after this, so the elements need no roots
+        self.init_class("java/lang/StackTraceElement")?;
+        let mut elements = Vec::new();
+        for (c, code, i) in frames.iter().skip(skip).take(MAX_STACK_TRACE_DEPTH) {
+            elements.push(self.stack_trace_element(c, code, *i)?);
+        }
+        let trace = self.heap.new_array("[Ljava/lang/StackTraceElement;", 0);
+        self.heap.get_array_mut(trace.as_ref().unwrap()).elements = elements;
+        Ok(trace)
+    }
+
+    /// `StackTraceElement` of the instruction at `i` of `code`
+    fn stack_trace_element(&mut self, c: &Class, code: &Linked, i: usize) -> anyhow::Result<Value> {
+        let class = &c.this_class_name;
+        let method = &c.methods[code.method];
+        let line = method
+            .code()
+            .and_then(|attribute| attribute.line_number(code.pcs[i]));
+        // the version of JDK modules is left out, as StackTraceElement.toString does
+        let (module, version) = match self.modules.of(class) {
+            _ if is_boot(class) => (Some("java.base".to_string()), None),
+            Some(module) => (Some(module.name.clone()), module.version.clone()),
+            None => (None, None),
+        };
+        let mut string = |s: Option<&str>| s.map_or(Value::Null, |s| self.intern(s));
+        let fields = [
+            ("declaringClass", string(Some(&class.replace('/', ".")))),
+            ("methodName", string(Some(&method.name))),
+            ("fileName", string(c.source_file())),
+            ("moduleName", string(module.as_deref())),
+            ("moduleVersion", string(version.as_deref())),
+            (
+                "lineNumber",
+                Value::Int(line.map_or(-1, |line| line as i32)),
+            ),
+        ];
+        let element = self.new_object("java/lang/StackTraceElement")?;
+        if self.jdk {
+            let mirror = self.class_object(class)?;
+            let instance = self.heap.get_instance_mut(element.as_ref().unwrap());
+            instance.set("declaringClassObject", mirror);
+        }
+        let instance = self.heap.get_instance_mut(element.as_ref().unwrap());
+        for (name, value) in fields {
+            instance.set(name, value);
+        }
+        Ok(element)
+    }
+
+    /// prints an exception no handler caught like the default handler of threads does:
+    /// `Exception in thread "main" ` and its stack trace on `System.err`
+    pub fn print_uncaught(&mut self, thread: &str, exception: Value) {
+        eprint!("Exception in thread \"{thread}\" ");
+        self.roots.push(exception);
+        let printed = self.invoke_virtual(
+            "java/lang/Throwable",
+            "printStackTrace",
+            "()V",
+            vec![exception],
+        );
+        if printed.is_err() {
+            eprintln!("{}", self.describe_exception(exception));
+        }
+        self.roots.pop();
+    }
+
+    /// `java.lang.IllegalStateException: message`, the first line of a stack trace
+    pub fn describe_exception(&mut self, exception: Value) -> String {
+        self.roots.push(exception);
+        let description = match self.java_string(exception, "Ljava/lang/Throwable;") {
+            Ok(description) => description,
+            // toString may not run, as while the JDK boots, so the fields are read directly
+            Err(err) => match exception.as_ref() {
+                Some(r) => {
+                    let name = self.heap.class_name(r).replace('/', ".");
+                    let message = match self.heap.get(r) {
+                        Object::Instance(instance) => instance.get("detailMessage"),
+                        _ => None,
+                    };
+                    match message.filter(|message| *message != Value::Null) {
+                        Some(message) => {
+                            let message = self.string_value(message).unwrap_or_default();
+                            format!("{name}: {message}")
+                        }
+                        None => name,
+                    }
+                }
+                None => format!("{err:#}"),
+            },
+        };
+        self.roots.pop();
+        description
+    }
+}
@@ -0,0 +1,950 @@
+use std::{mem::size_of, rc::Rc};
+
+use anyhow::{anyhow, Context};
+
+use crate::class::{descriptor::class_name, Class};
+
+use super::{
+    exception::Throw,
+    heap::{ObjRef, Object},
+    invokedynamic::invoke_dynamic,
+    limits::LimitExceeded,
+    link::{FieldSite, Instr, Linked, MethodSite, Target},
+    thread::{Exit, Lock, Stopped},
+    value::Value,
+    Vm,
+};
+
+/// instruction index of the handler in `code` catching `exception` thrown at instruction `i`
+fn find_handler(
+    vm: &mut Vm,
+    code: &Linked,
+    i: usize,
+    exception: Value,
+) -> anyhow::Result<Option<usize>> {
+    let class = vm.heap.class_name(exception.as_ref().unwrap()).to_string();
+    for handler in &code.handlers {
+        if i < handler.start || i >= handler.end {
+            continue;
+        }
+        match &handler.catch {
+            Some(catch) if !vm.is_subclass(&class, catch)? => {}
+            _ => return Ok(Some(handler.handler)),
+        }
+    }
+    Ok(None)
+}
+
+/// target of a static or special call site. `None` when the method is not in a loaded
+/// class, those calls go through [`Vm::invoke_static`] and [`Vm::invoke_special`]
+fn resolve_site(vm: &mut Vm, site: &MethodSite) -> anyhow::Result<Option<Target>> {
+    if let Some(target) = site.cached("") {
+        return Ok(Some(target));
+    }
+    let Some((class, i)) = vm.resolve_method(&site.class, &site.name, &site.descriptor)? else {
+        return Ok(None);
+    };
+    let target = vm.target(&class, &class.methods[i])?;
+    site.remember("", &target);
+    Ok(Some(target))
+}
+
+/// target of a virtual call on `receiver`, cached for its class. `None` for lambdas and
+/// methods not in a loaded class, which go through [`Vm::invoke_virtual`]
+fn resolve_virtual(
+    vm: &mut Vm,
+    site: &MethodSite,
+    receiver: ObjRef,
+) -> anyhow::Result<Option<Target>> {
+    if let Some(target) = site.cached(vm.heap.class_name(receiver)) {
+        return Ok(Some(target));
+    }
+    if let Object::Lambda(_) = vm.heap.get(receiver) {
+        return Ok(None);
+    }
+    let class = vm.heap.class_name(receiver).to_string();
+    let runtime_class = match class.starts_with('[') {
+        true => "java/lang/Object",
+        false => &class,
+    };
+    let Some((c, i)) =
+        vm.select_method(&site.class, runtime_class, &site.name, &site.descriptor)?
+    else {
+        return Ok(None);
+    };
+    let target = vm.target(&c, &c.methods[i])?;
+    site.remember(&class, &target);
+    Ok(Some(target))
+}
+
+/// the operand stack of the running method
+fn stack(vm: &mut Vm) -> &mut Vec<Value> {
+    &mut vm.frames.last_mut().unwrap().stack
+}
+
+/// checks the array reference and index of an array load or store
+fn array_index(vm: &mut Vm, array: Value, index: Value) -> anyhow::Result<(ObjRef, usize)> {
+    let index = index.as_int();
+    let Some(array) = array.as_ref() else {
+        return Err(vm.null_pointer());
+    };
+    let length = vm.heap.get_array(array).elements.len();
+    if index < 0 || index as usize >= length {
+        let message = format!("Index {index} out of bounds for length {length}");
+        return Err(vm.exception("java/lang/ArrayIndexOutOfBoundsException", &message));
+    }
+    Ok((array, index as usize))
+}
+
+/// slot of the field of `getfield` and `putfield` in the objects, resolved on first use
+fn instance_slot(vm: &mut Vm, field: &FieldSite) -> anyhow::Result<usize> {
+    if let Some(slot) = field.slot.get() {
+        return Ok(slot);
+    }
+    let slot = vm.instance_slot(&field.class, &field.name)?;
+    field.slot.set(Some(slot));
+    Ok(slot)
+}
+
+/// allocates a possibly nested array, `counts` holds the length of each dimension
+fn new_array(vm: &mut Vm, class: &str, counts: &[i32]) -> anyhow::Result<Value> {
+    if let Some(&count) = counts.iter().find(|&&count| count < 0) {
+        return Err(vm.exception("java/lang/NegativeArraySizeException", &count.to_string()));
+    }
+    if counts[0] > i32::MAX - 2 {
+        let message = "Requested array size exceeds VM limit";
+        return Err(vm.exception("java/lang/OutOfMemoryError", message));
+    }
+    let (mut arrays, mut bytes) = (1usize, 0usize);
+    for &count in counts {
+        bytes = bytes.saturating_add(arrays.saturating_mul(size_of::<Object>()));
+        arrays = arrays.saturating_mul(count as usize);
+        bytes = bytes.saturating_add(arrays.saturating_mul(size_of::<Value>()));
+    }
+    vm.reserve(bytes)?;
+    Ok(new_multi_array(vm, class, counts))
+}
+
+fn new_multi_array(vm: &mut Vm, class: &str, counts: &[i32]) -> Value {
+    let array = vm.heap.new_array(class, counts[0] as usize);
+    if counts.len() > 1 {
+        for i in 0..counts[0] as usize {
+            let element = new_multi_array(vm, &class[1..], &counts[1..]);
+            vm.heap.get_array_mut(array.as_ref().unwrap()).elements[i] = element;
+        }
+    }
+    array
+}
+
+fn int_op(s: &mut Vec<Value>, f: fn(i32, i32) -> i32) {
+    let a = s.pop().unwrap().as_int();
+    let b = s.pop().unwrap().as_int();
+    s.push(Value::Int(f(b, a)));
+}
+
+fn long_op(s: &mut Vec<Value>, f: fn(i64, i64) -> i64) {
+    let a = s.pop().unwrap().as_long();
+    let b = s.pop().unwrap().as_long();
+    s.push(Value::Long(f(b, a)));
+}
+
+fn float_op(s: &mut Vec<Value>, f: fn(f32, f32) -> f32) {
+    let a = s.pop().unwrap().as_float();
+    let b = s.pop().unwrap().as_float();
+    s.push(Value::Float(f(b, a)));
+}
+
+fn double_op(s: &mut Vec<Value>, f: fn(f64, f64) -> f64) {
+    let a = s.pop().unwrap().as_double();
+    let b = s.pop().unwrap().as_double();
+    s.push(Value::Double(f(b, a)));
+}
+
+/// fcmpl/fcmpg and dcmpl/dcmpg, `nan` is pushed when either value is NaN
+fn compare<T: PartialOrd>(b: T, a: T, nan: i32) -> Value {
+    Value::Int(match b.partial_cmp(&a) {
+        Some(std::cmp::Ordering::Less) => -1,
+        Some(std::cmp::Ordering::Equal) => 0,
+        Some(std::cmp::Ordering::Greater) => 1,
+        None => nan,
+    })
+}
+
+/// element type descriptor of `newarray` type codes
+pub(super) fn primitive_array_class(atype: usize) -> &'static str {
+    match atype {
+        4 => "[Z",
+        5 => "[C",
+        6 => "[F",
+        7 => "[D",
+        8 => "[B",
+        9 => "[S",
+        10 => "[I",
+        11 => "[J",
+        t => panic!("invalid array type {t}"),
+    }
+}
+
+fn division_by_zero(vm: &mut Vm) -> anyhow::Error {
+    vm.exception("java/lang/ArithmeticException", "/ by zero")
+}
+
+fn is_instance(vm: &mut Vm, object: ObjRef, class: &str) -> anyhow::Result<bool> {
+    let of = vm.heap.class_name(object);
+    if of == class {
+        return Ok(true);
+    }
+    let of = of.to_string();
+    vm.is_assignable(&of, class)
+}
+
+/// `class A cannot be cast to class B (A and B are in unnamed module of loader 'app')`
+pub(super) fn class_cast_message(vm: &Vm, class: &str, to: &str) -> String {
+    let locations = vm.locations(class, to);
+    let (class, to) = (class.replace('/', "."), to.replace('/', "."));
+    format!("class {class} cannot be cast to class {to} {locations}")
+}
+
+/// lets other threads run and the garbage collector free memory, called where every value
+/// of the running method is on its operand stack or in its locals
+fn safepoint(vm: &mut Vm) -> anyhow::Result<()> {
+    vm.tick();
+    vm.check_limits(false)?;
+    vm.reserve(0)
+}
+
+/// locals and operand stack of a method being interpreted
+pub struct Frame {
+    pub locals: Vec<Value>,
+    pub stack: Vec<Value>,
+    /// class of the method, which reflection checks access from
+    pub class: Rc<Class>,
+    pub code: Rc<Linked>,
+    /// instruction which last called out of the interpreter or threw, which stack traces
+    /// take the line number of. Only such instructions record it, as recording every one
+    /// halves the speed of the interpreter
+    pub i: usize,
+}
+
+/// tools watching the instructions of a method, looked up once per call
+#[derive(Clone, Copy)]
+struct Hooks {
+    tracing: bool,
+    debugging: bool,
+    attached: bool,
+    profiling: bool,
+    fueled: bool,
+}
+
+pub fn exec(
+    vm: &mut Vm,
+    c: &Rc<Class>,
+    code: &Rc<Linked>,
+    locals: Vec<Value>,
+) -> anyhow::Result<Option<Value>> {
+    let lock = match code.synchronized {
+        false => None,
+        true if code.is_static => Some(Lock::Class(c.this_class_name.clone())),
+        true => Some(Lock::Object(locals[0].as_ref().unwrap())),
+    };
+    let hooks = Hooks {
+        tracing: vm.tracing(c, code),
+        debugging: vm.debugger.is_some(),
+        attached: vm.jdwp.is_some(),
+        profiling: vm.profiler.is_some(),
+        fueled: vm.is_fueled(),
+    };
+    vm.check_stack_depth()?;
+    vm.frames.push(Frame {
+        locals,
+        stack: Vec::with_capacity(code.max_stack),
+        class: c.clone(),
+        code: code.clone(),
+        i: 0,
+    });
+    if hooks.profiling {
+        vm.profile_enter(c, code);
+    }
+    let mut result = match &lock {
+        Some(lock) => vm.monitor_enter(lock.clone()).and_then(|()| {
+            match run(vm, c, code, hooks) {
+                // the thread may have given up the monitor waiting on it
+                Err(err)
+                    if err.is::<LimitExceeded>() || err.is::<Exit>() || err.is::<Stopped>() =>
+                {
+                    Err(err)
+                }
+                result => vm.monitor_exit(lock).and(result),
+            }
+        }),
+        None => run(vm, c, code, hooks),
+    };
+    if hooks.tracing {
+        result = vm.trace_exit(&result).and(result);
+    }
+    if hooks.profiling {
+        vm.profile_exit();
+    }
+    vm.frames.pop();
+    result
+}
+
+/// interprets `code` in the innermost frame. Values stay on the operand stack while
+/// instructions call out of the interpreter, where the garbage collector may run
+fn run(vm: &mut Vm, c: &Rc<Class>, code: &Linked, hooks: Hooks) -> anyhow::Result<Option<Value>> {
+    let mut i = 0;
+    // whether the frame after the last traced instruction is still to be logged, which
+    // happens here as branches continue the loop
+    let mut traced = false;
+    loop {
+        if hooks.tracing {
+            if traced {
+                vm.trace_state()?;
+            }
+            vm.trace_instruction(c, code, i)?;
+            traced = true;
+        }
+        if hooks.debugging {
+            vm.debug_hook(c, code, i)?;
+        }
+        if hooks.attached {
+            vm.jdwp_hook(c, code, i);
+        }
+        if hooks.profiling {
+            vm.profile_instruction(i);
+        }
+        if hooks.fueled {
+            vm.burn_fuel()?;
+        }
+        let err = match step(vm, c, code, i) {
+            Ok(Step::Next) => {
+                i += 1;
+                continue;
+            }
+            Ok(Step::Jump(target)) => {
+                i = target;
+                continue;
+            }
+            Ok(Step::Return) => return Ok(None),
+            Ok(Step::ReturnValue) => return Ok(stack(vm).pop()),
+            Err(err) => err,
+        };
+        let Some(&Throw(exception)) = err.downcast_ref::<Throw>() else {
+            return Err(err);
+        };
+        match find_handler(vm, code, i, exception)? {
+            Some(handler) => {
+                if hooks.tracing {
+                    vm.trace_catch(exception, code.pcs[handler])?;
+                    traced = false;
+                }
+                let s = stack(vm);
+                s.clear();
+                s.push(exception);
+                i = handler;
+            }
+            None => return Err(err),
+        }
+    }
+}
+
+/// what the interpreter does after an instruction
+enum Step {
+    Next,
+    Jump(usize),
+    Return,
+    /// return with the value on top of the stack
+    ReturnValue,
+}
+
+/// runs the instruction at `i` of `code` in the innermost frame. Inlined into the loop
+/// of [`run`], which is twice as slow with a call per instruction
+#[inline(always)]
+fn step(vm: &mut Vm, c: &Rc<Class>, code: &Linked, i: usize) -> anyhow::Result<Step> {
+    let frame = vm.frames.last_mut().unwrap();
+    let (s, l) = (&mut frame.stack, &mut frame.locals);
+    match &code.code[i] {
+        Instr::GetStatic(field) => {
+            frame.i = i;
+            let slot = match field.slot.get() {
+                Some(slot) => slot,
+                None => {
+                    vm.check_field_access(c, field)?;
+                    let slot = vm.static_slot(&field.class, &field.name, &field.descriptor)?;
+                    field.slot.set(Some(slot));
+                    slot
+                }
+            };
+            let value = vm.statics[slot];
+            stack(vm).push(value);
+        }
+        Instr::PutStatic(field) => {
+            frame.i = i;
+            let slot = match field.slot.get() {
+                Some(slot) => slot,
+                None => {
+                    vm.check_field_access(c, field)?;
+                    let slot = vm.field_slot(&field.class, &field.name)?.with_context(|| {
+                        format!("no static field {}.{}", field.class, field.name)
+                    })?;
+                    field.slot.set(Some(slot));
+                    slot
+                }
+            };
+            vm.statics[slot] = stack(vm).pop().unwrap();
+        }
+        Instr::GetField(field) => {
+            frame.i = i;
+            if !field.checked.get() {
+                vm.check_field_access(c, field)?;
+            }
+            let s = stack(vm);
+            let Some(object) = s.pop().unwrap().as_ref() else {
+                Err(vm.null_pointer())?
+            };
+            let slot = instance_slot(vm, field)?;
+            let instance = cast!(vm.heap.get(object), Object::Instance);
+            let value = instance.fields[slot];
+            stack(vm).push(value);
+        }
+        Instr::PutField(field) => {
+            frame.i = i;
+            if !field.checked.get() {
+                vm.check_field_access(c, field)?;
+            }
+            let s = stack(vm);
+            let value = s.pop().unwrap();
+            let Some(object) = s.pop().unwrap().as_ref() else {
+                Err(vm.null_pointer())?
+            };
+            let slot = instance_slot(vm, field)?;
+            let instance = cast!(vm.heap.get_mut(object), Object::Instance);
+            instance.fields[slot] = value;
+        }
+        Instr::New(class) => {
+            frame.i = i;
+            vm.reserve(size_of::<Object>())?;
+            let object = vm.new_object(class)?;
+            stack(vm).push(object);
+        }
+
+        // arguments stay on the stack until the call returns
+        Instr::InvokeVirtual(method) => {
+            frame.i = i;
+            safepoint(vm)?;
+            if !method.checked.get() {
+                vm.check_method_access(c, method)?;
+            }
+            let s = stack(vm);
+            let base = s.len() - method.args - 1;
+            let args = s[base..].to_vec();
+            let Some(receiver) = args[0].as_ref() else {
+                Err(vm.null_pointer())?
+            };
+            let ret = match resolve_virtual(vm, method, receiver)? {
+                Some(target) => vm.call(&target, args)?,
+                None => vm.invoke_virtual(&method.class, &method.name, &method.descriptor, args)?,
+            };
+            let s = stack(vm);
+            s.truncate(base);
+            s.extend(ret);
+        }
+        Instr::InvokeSpecial(method) => {
+            frame.i = i;
+            safepoint(vm)?;
+            if !method.checked.get() {
+                vm.check_method_access(c, method)?;
+            }
+            let s = stack(vm);
+            let base = s.len() - method.args - 1;
+            let args = s[base..].to_vec();
+            let ret = match resolve_site(vm, method)? {
+                Some(target) => vm.call(&target, args)?,
+                None => vm.invoke_special(&method.class, &method.name, &method.descriptor, args)?,
+            };
+            let s = stack(vm);
+            s.truncate(base);
+            s.extend(ret);
+        }
+        Instr::InvokeStatic(method) => {
+            frame.i = i;
+            safepoint(vm)?;
+            if !method.checked.get() {
+                vm.check_method_access(c, method)?;
+            }
+            if method.cached("").is_none() {
+                vm.init_class(&method.class)?;
+            }
+            let s = stack(vm);
+            let base = s.len() - method.args;
+            let args = s[base..].to_vec();
+            let ret = match resolve_site(vm, method)? {
+                Some(target) => vm.call(&target, args)?,
+                None => vm.invoke_static(&method.class, &method.name, &method.descriptor, args)?,
+            };
+            let s = stack(vm);
+            s.truncate(base);
+            s.extend(ret);
+        }
+        Instr::InvokeDynamic(site) => {
+            frame.i = i;
+            safepoint(vm)?;
+            let s = stack(vm);
+            let base = s.len() - site.args;
+            let args = s[base..].to_vec();
+            let ret = invoke_dynamic(vm, c, site, args)?;
+            let s = stack(vm);
+            s.truncate(base);
+            s.push(ret);
+        }
+
+        Instr::Nop => {}
+        &Instr::Const(value) => s.push(value),
+        Instr::Ldc(constant) => match constant.value.get() {
+            Some(value) => s.push(value),
+            None => {
+                frame.i = i;
+                let value = vm.constant(c, constant.index)?;
+                constant.value.set(Some(value));
+                stack(vm).push(value);
+            }
+        },
+        &Instr::Load(index) => s.push(l[index]),
+        &Instr::Store(index) => l[index] = s.pop().unwrap(),
+
+        Instr::ArrayLoad => {
+            frame.i = i;
+            let index = s.pop().unwrap();
+            let array = s.pop().unwrap();
+            let (array, index) = array_index(vm, array, index)?;
+            let value = vm.heap.get_array(array).elements[index];
+            stack(vm).push(value);
+        }
+        &Instr::ArrayStore(narrow) => {
+            frame.i = i;
+            let value = narrow(s.pop().unwrap());
+            let index = s.pop().unwrap();
+            let array = s.pop().unwrap();
+            let (array, index) = array_index(vm, array, index)?;
+            vm.heap.get_array_mut(array).elements[index] = value;
+        }
+        Instr::AAStore => {
+            frame.i = i;
+            let value = s.pop().unwrap();
+            let index = s.pop().unwrap();
+            let array = s.pop().unwrap();
+            let (array, index) = array_index(vm, array, index)?;
+            if let Some(r) = value.as_ref() {
+                let component = vm.heap.get_array(array).component();
+                let element = class_name(component).to_string();
+                if element != "java/lang/Object" && !is_instance(vm, r, &element)? {
+                    let class = vm.heap.class_name(r).replace('/', ".");
+                    return Err(vm.exception("java/lang/ArrayStoreException", &class));
+                }
+            }
+            vm.heap.get_array_mut(array).elements[index] = value;
+        }
+        Instr::NewArray(class) => {
+            frame.i = i;
+            let count = s.pop().unwrap().as_int();
+            let array = new_array(vm, class, &[count])?;
+            stack(vm).push(array);
+        }
+        Instr::MultiANewArray(class, dimensions) => {
+            frame.i = i;
+            let counts: Vec<i32> = s
+                .split_off(s.len() - dimensions)
+                .into_iter()
+                .map(Value::as_int)
+                .collect();
+            let array = new_array(vm, class, &counts)?;
+            stack(vm).push(array);
+        }
+        Instr::ArrayLength => {
+            frame.i = i;
+            let Some(array) = s.pop().unwrap().as_ref() else {
+                Err(vm.null_pointer())?
+            };
+            let length = vm.heap.get_array(array).elements.len();
+            s.push(Value::Int(length as i32));
+        }
+        Instr::CheckCast(to) => {
+            frame.i = i;
+            if let Some(object) = s.last().unwrap().as_ref() {
+                if !is_instance(vm, object, to)? {
+                    let class = vm.heap.class_name(object).to_string();
+                    let message = class_cast_message(vm, &class, to);
+                    Err(vm.exception("java/lang/ClassCastException", &message))?;
+                }
+            }
+        }
+        Instr::InstanceOf(to) => {
+            frame.i = i;
+            let instance = match s.pop().unwrap().as_ref() {
+                Some(object) => is_instance(vm, object, to)?,
+                None => false,
+            };
+            stack(vm).push(Value::Int(instance as i32));
+        }
+
+        Instr::Pop => {
+            s.pop().unwrap();
+        }
+        Instr::Pop2 => {
+            if !s.pop().unwrap().is_wide() {
+                s.pop().unwrap();
+            }
+        }
+        Instr::Dup => s.push(*s.last().unwrap()),
+        Instr::DupX1 => s.insert(s.len() - 2, *s.last().unwrap()),
+        Instr::DupX2 => {
+            let depth = if s[s.len() - 2].is_wide() { 2 } else { 3 };
+            s.insert(s.len() - depth, *s.last().unwrap());
+        }
+        Instr::Dup2 => {
+            let count = if s.last().unwrap().is_wide() { 1 } else { 2 };
+            s.extend_from_within(s.len() - count..);
+        }
+        Instr::Dup2X1 => {
+            let count = if s.last().unwrap().is_wide() { 1 } else { 2 };
+            let top = s[s.len() - count..].to_vec();
+            let at = s.len() - count - 1;
+            s.splice(at..at, top);
+        }
+        Instr::Dup2X2 => {
+            let count = if s.last().unwrap().is_wide() { 1 } else { 2 };
+            let below = if s[s.len() - count - 1].is_wide() {
+                1
+            } else {
+                2
+            };
+            let top = s[s.len() - count..].to_vec();
+            let at = s.len() - count - below;
+            s.splice(at..at, top);
+        }
+        Instr::Swap => {
+            let len = s.len();
+            s.swap(len - 1, len - 2);
+        }
+
+        &Instr::IfEq(target) => {
+            if s.pop().unwrap().as_int() == 0 {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfNe(target) => {
+            if s.pop().unwrap().as_int() != 0 {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfLt(target) => {
+            if s.pop().unwrap().as_int() < 0 {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfGe(target) => {
+            if s.pop().unwrap().as_int() >= 0 {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfGt(target) => {
+            if s.pop().unwrap().as_int() > 0 {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfLe(target) => {
+            if s.pop().unwrap().as_int() <= 0 {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfICmpEq(target) => {
+            if s.pop().unwrap().as_int() == s.pop().unwrap().as_int() {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfICmpNe(target) => {
+            if s.pop().unwrap().as_int() != s.pop().unwrap().as_int() {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfICmpLt(target) => {
+            let a = s.pop().unwrap().as_int();
+            let b = s.pop().unwrap().as_int();
+            if b < a {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfICmpGe(target) => {
+            let a = s.pop().unwrap().as_int();
+            let b = s.pop().unwrap().as_int();
+            if b >= a {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfICmpGt(target) => {
+            let a = s.pop().unwrap().as_int();
+            let b = s.pop().unwrap().as_int();
+            if b > a {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfICmpLe(target) => {
+            let a = s.pop().unwrap().as_int();
+            let b = s.pop().unwrap().as_int();
+            if b <= a {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfACmpEq(target) => {
+            if s.pop().unwrap() == s.pop().unwrap() {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfACmpNe(target) => {
+            if s.pop().unwrap() != s.pop().unwrap() {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfNull(target) => {
+            if s.pop().unwrap() == Value::Null {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::IfNonNull(target) => {
+            if s.pop().unwrap() != Value::Null {
+                return Ok(Step::Jump(target));
+            }
+        }
+        &Instr::Goto(target) => {
+            if target <= i {
+                safepoint(vm)?;
+            }
+            return Ok(Step::Jump(target));
+        }
+        Instr::TableSwitch(low, targets, default) => {
+            let key = s.pop().unwrap().as_int();
+            let index = (key as i64 - *low as i64) as usize;
+            let target = targets.get(index).copied().unwrap_or(*default);
+            if target <= i {
+                safepoint(vm)?;
+            }
+            return Ok(Step::Jump(target));
+        }
+        Instr::LookupSwitch(targets, default) => {
+            let key = s.pop().unwrap().as_int();
+            let target = match targets.binary_search_by_key(&key, |&(key, _)| key) {
+                Ok(found) => targets[found].1,
+                Err(_) => *default,
+            };
+            if target <= i {
+                safepoint(vm)?;
+            }
+            return Ok(Step::Jump(target));
+        }
+
+        Instr::IAdd => int_op(s, i32::wrapping_add),
+        Instr::LAdd => long_op(s, i64::wrapping_add),
+        Instr::FAdd => float_op(s, |b, a| b + a),
+        Instr::DAdd => double_op(s, |b, a| b + a),
+        Instr::ISub => int_op(s, i32::wrapping_sub),
+        Instr::LSub => long_op(s, i64::wrapping_sub),
+        Instr::FSub => float_op(s, |b, a| b - a),
+        Instr::DSub => double_op(s, |b, a| b - a),
+        Instr::IMul => int_op(s, i32::wrapping_mul),
+        Instr::LMul => long_op(s, i64::wrapping_mul),
+        Instr::FMul => float_op(s, |b, a| b * a),
+        Instr::DMul => double_op(s, |b, a| b * a),
+        Instr::IDiv => match *s.last().unwrap() == Value::Int(0) {
+            true => {
+                frame.i = i;
+                Err(division_by_zero(vm))?
+            }
+            false => int_op(s, i32::wrapping_div),
+        },
+        Instr::IRem => match *s.last().unwrap() == Value::Int(0) {
+            true => {
+                frame.i = i;
+                Err(division_by_zero(vm))?
+            }
+            false => int_op(s, i32::wrapping_rem),
+        },
+        Instr::LDiv => match *s.last().unwrap() == Value::Long(0) {
+            true => {
+                frame.i = i;
+                Err(division_by_zero(vm))?
+            }
+            false => long_op(s, i64::wrapping_div),
+        },
+        Instr::LRem => match *s.last().unwrap() == Value::Long(0) {
+            true => {
+                frame.i = i;
+                Err(division_by_zero(vm))?
+            }
+            false => long_op(s, i64::wrapping_rem),
+        },
+        Instr::FDiv => float_op(s, |b, a| b / a),
+        Instr::DDiv => double_op(s, |b, a| b / a),
+        Instr::FRem => float_op(s, |b, a| b % a),
+        Instr::DRem => double_op(s, |b, a| b % a),
+        Instr::INeg => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Int(a.wrapping_neg()));
+        }
+        Instr::LNeg => {
+            let a = s.pop().unwrap().as_long();
+            s.push(Value::Long(a.wrapping_neg()));
+        }
+        Instr::FNeg => {
+            let a = s.pop().unwrap().as_float();
+            s.push(Value::Float(-a));
+        }
+        Instr::DNeg => {
+            let a = s.pop().unwrap().as_double();
+            s.push(Value::Double(-a));
+        }
+        Instr::IShl => int_op(s, |b, a| b.wrapping_shl(a as u32)),
+        Instr::IShr => int_op(s, |b, a| b.wrapping_shr(a as u32)),
+        Instr::IUShr => int_op(s, |b, a| (b as u32).wrapping_shr(a as u32) as i32),
+        Instr::IAnd => int_op(s, |b, a| b & a),
+        Instr::IOr => int_op(s, |b, a| b | a),
+        Instr::IXor => int_op(s, |b, a| b ^ a),
+        // shift distance is an int
+        Instr::LShl => {
+            let a = s.pop().unwrap().as_int() as u32;
+            let b = s.pop().unwrap().as_long();
+            s.push(Value::Long(b.wrapping_shl(a)));
+        }
+        Instr::LShr => {
+            let a = s.pop().unwrap().as_int() as u32;
+            let b = s.pop().unwrap().as_long();
+            s.push(Value::Long(b.wrapping_shr(a)));
+        }
+        Instr::LUShr => {
+            let a = s.pop().unwrap().as_int() as u32;
+            let b = s.pop().unwrap().as_long();
+            s.push(Value::Long((b as u64).wrapping_shr(a) as i64));
+        }
+        Instr::LAnd => long_op(s, |b, a| b & a),
+        Instr::LOr => long_op(s, |b, a| b | a),
+        Instr::LXor => long_op(s, |b, a| b ^ a),
+
+        &Instr::Iinc(index, incr) => l[index] = Value::Int(l[index].as_int().wrapping_add(incr)),
+
+        // `as` casts saturate and map NaN to 0, as the conversions require
+        Instr::I2L => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Long(a as i64));
+        }
+        Instr::I2F => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Float(a as f32));
+        }
+        Instr::I2D => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Double(a as f64));
+        }
+        Instr::L2I => {
+            let a = s.pop().unwrap().as_long();
+            s.push(Value::Int(a as i32));
+        }
+        Instr::L2F => {
+            let a = s.pop().unwrap().as_long();
+            s.push(Value::Float(a as f32));
+        }
+        Instr::L2D => {
+            let a = s.pop().unwrap().as_long();
+            s.push(Value::Double(a as f64));
+        }
+        Instr::F2I => {
+            let a = s.pop().unwrap().as_float();
+            s.push(Value::Int(a as i32));
+        }
+        Instr::F2L => {
+            let a = s.pop().unwrap().as_float();
+            s.push(Value::Long(a as i64));
+        }
+        Instr::F2D => {
+            let a = s.pop().unwrap().as_float();
+            s.push(Value::Double(a as f64));
+        }
+        Instr::D2I => {
+            let a = s.pop().unwrap().as_double();
+            s.push(Value::Int(a as i32));
+        }
+        Instr::D2L => {
+            let a = s.pop().unwrap().as_double();
+            s.push(Value::Long(a as i64));
+        }
+        Instr::D2F => {
+            let a = s.pop().unwrap().as_double();
+            s.push(Value::Float(a as f32));
+        }
+        Instr::I2B => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Int(a as i8 as i32));
+        }
+        Instr::I2C => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Int(a as u16 as i32));
+        }
+        Instr::I2S => {
+            let a = s.pop().unwrap().as_int();
+            s.push(Value::Int(a as i16 as i32));
+        }
+
+        Instr::LCmp => {
+            let a = s.pop().unwrap().as_long();
+            let b = s.pop().unwrap().as_long();
+            s.push(compare(b, a, 0));
+        }
+        Instr::FCmpL | Instr::FCmpG => {
+            let nan = if let Instr::FCmpL = code.code[i] {
+                -1
+            } else {
+                1
+            };
+            let a = s.pop().unwrap().as_float();
+            let b = s.pop().unwrap().as_float();
+            s.push(compare(b, a, nan));
+        }
+        Instr::DCmpL | Instr::DCmpG => {
+            let nan = if let Instr::DCmpL = code.code[i] {
+                -1
+            } else {
+                1
+            };
+            let a = s.pop().unwrap().as_double();
+            let b = s.pop().unwrap().as_double();
+            s.push(compare(b, a, nan));
+        }
+
+        Instr::ReturnValue => return Ok(Step::ReturnValue),
+        Instr::Return => return Ok(Step::Return),
+
+        Instr::AThrow => {
+            frame.i = i;
+            let exception = s.pop().unwrap();
+            if exception == Value::Null {
+                return Err(vm.null_pointer());
+            }
+            return Err(Throw(exception).into());
+        }
+
+        // the object stays on the stack while waiting for its monitor
+        Instr::MonitorEnter => {
+            frame.i = i;
+            let object = *s.last().unwrap();
+            let lock = vm.object_lock(object)?;
+            vm.monitor_enter(lock)?;
+            stack(vm).pop();
+        }
+        Instr::MonitorExit => {
+            frame.i = i;
+            let object = s.pop().unwrap();
+            let lock = vm.object_lock(object)?;
+            vm.monitor_exit(&lock)?;
+        }
+        Instr::Unsupported(op) => Err(anyhow!("unsupported instruction {op:?}"))?,
+    }
+    Ok(Step::Next)
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, mem::size_of, rc::Rc};
+
+use super::{invokedynamic::MethodHandle, value::Value};
+
+pub type ObjRef = usize;
+
+#[derive(Debug, Clone)]
+pub enum Object {
+    Instance(Instance),
+    Array(Array),
+    String(String),
+    StringBuilder(String),
+    Lambda(Lambda),
+}
+
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub class: String,
+    pub layout: Rc<Layout>,
+    /// values of the fields, in the order of [`Layout::fields`]
+    pub fields: Vec<Value>,
+}
+
+impl Instance {
+    /// value of the field `name` as the class of the object sees it, which is the one
+    /// declared furthest down the hierarchy when a subclass hides a field
+    pub fn get(&self, name: &str) -> Option<Value> {
+        Some(self.fields[self.layout.slot(name)?])
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        let slot = self.layout.slot(name).unwrap_or_else(|| {
+            panic!("no field {name} in {}", self.class);
+        });
+        self.fields[slot] = value;
+    }
+}
+
+/// instance field of a [`Layout`]
+#[derive(Debug, Clone)]
+pub struct LayoutField {
+    /// class declaring the field
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// slots of the instance fields of a class. Those of the superclass come first, so a
+/// field has the same slot in every subclass, and a hidden field keeps its own slot
+#[derive(Debug, Default)]
+pub struct Layout {
+    pub fields: Vec<LayoutField>,
+}
+
+impl Layout {
+    /// slot of the field `name` the class sees, its own or the nearest inherited one
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.fields.iter().rposition(|f| f.name == name)
+    }
+
+    /// slot of the field `name` declared by `class`
+    pub fn declared_slot(&self, class: &str, name: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .rposition(|f| f.class == class && f.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Array {
+    /// array type descriptor, e.g. `[I` or `[Ljava/lang/String;`
+    pub class: String,
+    pub elements: Vec<Value>,
+}
+
+impl Array {
+    pub fn component(&self) -> &str {
+        &self.class[1..]
+    }
+}
+
+/// functional interface instance created by `LambdaMetafactory`
+#[derive(Debug, Clone)]
+pub struct Lambda {
+    pub interface: String,
+    pub name: String,
+    /// erased descriptor of the interface method
+    pub descriptor: String,
+    pub target: MethodHandle,
+    pub captured: Vec<Value>,
+}
+
+impl Object {
+    /// approximate number of bytes the object takes
+    fn size(&self) -> usize {
+        size_of::<Object>()
+            + match self {
+                Object::Instance(i) => i.fields.len() * size_of::<Value>(),
+                Object::Array(a) => a.elements.len() * size_of::<Value>(),
+                Object::String(s) | Object::StringBuilder(s) => s.len(),
+                Object::Lambda(l) => l.captured.len() * size_of::<Value>(),
+            }
+    }
+
+    /// references held by the object
+    fn references(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+        match self {
+            Object::Instance(i) => Box::new(i.fields.iter()),
+            Object::Array(a) => Box::new(a.elements.iter()),
+            Object::Lambda(l) => Box::new(l.captured.iter()),
+            Object::String(_) | Object::StringBuilder(_) => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+/// maximum heap size when none is given, in bytes
+pub const DEFAULT_MAX_HEAP: usize = 1 << 30;
+/// heap size below which no collection runs
+const MIN_COLLECTION: usize = 8 << 20;
+
+/// objects with a mark-sweep collector. Slots of collected objects are reused
+#[derive(Debug)]
+pub struct Heap {
+    objects: Vec<Option<Object>>,
+    free: Vec<ObjRef>,
+    strings: HashMap<String, ObjRef>,
+    /// approximate bytes taken by objects, including garbage not collected yet
+    pub used: usize,
+    /// [`Heap::used`] at which the next collection runs
+    pub next_collection: usize,
+    /// bytes the heap may grow to, see [`Vm::reserve`](super::Vm::reserve)
+    pub max: usize,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap {
+            objects: Vec::new(),
+            free: Vec::new(),
+            strings: HashMap::new(),
+            used: 0,
+            next_collection: MIN_COLLECTION,
+            max: DEFAULT_MAX_HEAP,
+        }
+    }
+}
+
+impl Heap {
+    pub fn alloc(&mut self, object: Object) -> Value {
+        self.used += object.size();
+        match self.free.pop() {
+            Some(r) => {
+                self.objects[r] = Some(object);
+                Value::Ref(r)
+            }
+            None => {
+                self.objects.push(Some(object));
+                Value::Ref(self.objects.len() - 1)
+            }
+        }
+    }
+
+    pub fn get(&self, r: ObjRef) -> &Object {
+        self.objects[r]
+            .as_ref()
+            .expect("reference to a collected object")
+    }
+
+    /// `None` for references to collected or never allocated objects
+    pub fn try_get(&self, r: ObjRef) -> Option<&Object> {
+        self.objects.get(r)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, r: ObjRef) -> &mut Object {
+        self.objects[r]
+            .as_mut()
+            .expect("reference to a collected object")
+    }
+
+    /// frees every object not reachable from `roots` or the interned strings,
+    /// returns the number of freed objects
+    pub fn collect(&mut self, roots: impl IntoIterator<Item = Value>) -> usize {
+        let mut marked = vec![false; self.objects.len()];
+        let mut pending: Vec<ObjRef> = roots
+            .into_iter()
+            .filter_map(|root| match root {
+                Value::Ref(r) => Some(r),
+                _ => None,
+            })
+            .chain(self.strings.values().copied())
+            .collect();
+        while let Some(r) = pending.pop() {
+            if std::mem::replace(&mut marked[r], true) {
+                continue;
+            }
+            for &value in self.get(r).references() {
+                if let Value::Ref(r) = value {
+                    if !marked[r] {
+                        pending.push(r);
+                    }
+                }
+            }
+        }
+
+        let mut freed = 0;
+        self.used = 0;
+        for (r, object) in self.objects.iter_mut().enumerate() {
+            match object {
+                Some(_) if !marked[r] => {
+                    *object = None;
+                    self.free.push(r);
+                    freed += 1;
+                }
+                Some(object) => self.used += object.size(),
+                None => {}
+            }
+        }
+        self.next_collection = (self.used * 2).max(MIN_COLLECTION).min(self.max);
+        freed
+    }
+
+    pub fn set_max(&mut self, max: usize) {
+        self.max = max;
+        self.next_collection = MIN_COLLECTION.min(max);
+    }
+
+    /// string object registered for the contents with [`Heap::add_interned`]
+    pub fn interned(&self, s: &str) -> Option<Value> {
+        self.strings.get(s).map(|&r| Value::Ref(r))
+    }
+
+    pub fn add_interned(&mut self, s: &str, value: Value) {
+        self.strings.insert(s.to_string(), value.as_ref().unwrap());
+    }
+
+    pub fn class_name(&self, r: ObjRef) -> &str {
+        match self.get(r) {
+            Object::Instance(i) => &i.class,
+            Object::Array(a) => &a.class,
+            Object::String(_) => "java/lang/String",
+            Object::StringBuilder(_) => "java/lang/StringBuilder",
+            Object::Lambda(l) => &l.interface,
+        }
+    }
+
+    pub fn get_string(&self, r: ObjRef) -> &str {
+        cast!(self.get(r), Object::String)
+    }
+
+    pub fn get_array(&self, r: ObjRef) -> &Array {
+        cast!(self.get(r), Object::Array)
+    }
+
+    pub fn get_array_mut(&mut self, r: ObjRef) -> &mut Array {
+        cast!(self.get_mut(r), Object::Array)
+    }
+
+    pub fn get_instance(&self, r: ObjRef) -> &Instance {
+        cast!(self.get(r), Object::Instance)
+    }
+
+    pub fn get_instance_mut(&mut self, r: ObjRef) -> &mut Instance {
+        cast!(self.get_mut(r), Object::Instance)
+    }
+
+    /// array of the given type with every element set to its default
+    pub fn new_array(&mut self, class: &str, length: usize) -> Value {
+        let elements = vec![Value::default_for(&class[1..]); length];
+        self.alloc(Object::Array(Array {
+            class: class.to_string(),
+            elements,
+        }))
+    }
+}
@@ -0,0 +1,408 @@
+use anyhow::{bail, Context};
+
+use crate::class::{
+    constant_pool::{CpInfo, CpPool},
+    descriptor::{class_name, MethodDescriptor},
+    Class,
+};
+
+use super::{
+    heap::{Lambda, Object},
+    link::DynamicSite,
+    value::Value,
+    Vm,
+};
+
+const REF_INVOKE_VIRTUAL: usize = 5;
+pub(super) const REF_INVOKE_STATIC: usize = 6;
+const REF_INVOKE_SPECIAL: usize = 7;
+const REF_NEW_INVOKE_SPECIAL: usize = 8;
+const REF_INVOKE_INTERFACE: usize = 9;
+
+/// resolved `CONSTANT_MethodHandle` pointing to a method
+#[derive(Debug, Clone)]
+pub struct MethodHandle {
+    pub kind: usize,
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl MethodHandle {
+    pub fn resolve(cp: &CpPool, index: usize) -> Option<Self> {
+        let handle = cp.get_method_handle(index)?;
+        let member = cp.get_member_ref(handle.reference_index)?;
+        Some(MethodHandle {
+            kind: handle.reference_kind,
+            class: member.class.to_string(),
+            name: member.name.to_string(),
+            descriptor: member.descriptor.to_string(),
+        })
+    }
+
+    pub fn invoke(&self, vm: &mut Vm, mut args: Vec<Value>) -> anyhow::Result<Option<Value>> {
+        let (class, name, descriptor) = (&self.class, &self.name, &self.descriptor);
+        match self.kind {
+            REF_INVOKE_VIRTUAL | REF_INVOKE_INTERFACE => {
+                vm.invoke_virtual(class, name, descriptor, args)
+            }
+            REF_INVOKE_STATIC => vm.invoke_static(class, name, descriptor, args),
+            REF_INVOKE_SPECIAL => vm.invoke_special(class, name, descriptor, args),
+            REF_NEW_INVOKE_SPECIAL => {
+                let object = vm.new_object(class)?;
+                args.insert(0, object);
+                vm.invoke_special(class, name, descriptor, args)?;
+                Ok(Some(object))
+            }
+            kind => bail!("method handle kind {kind} cannot be invoked"),
+        }
+    }
+}
+
+impl Lambda {
+    /// calls the implementation method with the captured values followed by `args`,
+    /// boxing and unboxing where the erased interface method and the implementation differ
+    pub fn invoke(&self, vm: &mut Vm, args: Vec<Value>) -> anyhow::Result<Option<Value>> {
+        let erased = MethodDescriptor::parse(&self.descriptor).context("invalid descriptor")?;
+        let target =
+            MethodDescriptor::parse(&self.target.descriptor).context("invalid descriptor")?;
+        let mut params = target.args;
+        if matches!(self.target.kind, REF_INVOKE_VIRTUAL | REF_INVOKE_INTERFACE) {
+            params.insert(0, format!("L{};", self.target.class));
+        }
+        // adapted arguments are roots while boxing the next ones may run java code
+        let base = vm.roots.len();
+        vm.roots.extend_from_slice(&self.captured);
+        let adapted = args
+            .into_iter()
+            .zip(&erased.args)
+            .try_for_each(|(arg, from)| {
+                let to = params.get(vm.roots.len() - base).unwrap_or(from);
+                let value = vm.adapt(arg, from, to)?;
+                vm.roots.push(value);
+                anyhow::Ok(())
+            });
+        let target_args = vm.roots.split_off(base);
+        adapted?;
+        let ret = match self.target.kind {
+            REF_NEW_INVOKE_SPECIAL => format!("L{};", self.target.class),
+            _ => target.ret,
+        };
+        match self.target.invoke(vm, target_args)? {
+            Some(_) if erased.ret == "V" => Ok(None),
+            Some(value) => Ok(Some(vm.adapt(value, &ret, &erased.ret)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// piece of the recipe of a string concatenation
+#[derive(Debug)]
+pub enum Piece {
+    Text(String),
+    /// next argument, with its type descriptor
+    Arg(String),
+}
+
+/// case label of `SwitchBootstraps.typeSwitch` and `enumSwitch`
+#[derive(Debug)]
+pub enum Label {
+    /// matches instances of the class
+    Class(String),
+    /// matches equal strings and enum constants of that name
+    String(String),
+    /// matches the enum constant of that class and name
+    Enum { class: String, name: String },
+    /// matches boxed ints and chars
+    Integer(i32),
+}
+
+/// what the bootstrap method of an `invokedynamic` links it to, run with the arguments
+/// taken from the stack on every execution
+#[derive(Debug)]
+pub enum CallSite {
+    /// `LambdaMetafactory`: creates a lambda capturing the arguments
+    Lambda {
+        interface: String,
+        name: String,
+        /// erased descriptor of the interface method
+        descriptor: String,
+        target: MethodHandle,
+    },
+    /// `StringConcatFactory.makeConcatWithConstants`
+    Concat(Vec<Piece>),
+    /// `ObjectMethods.bootstrap`: `toString`, `equals` or `hashCode` of a record
+    Record { class: String, name: String },
+    /// `SwitchBootstraps.typeSwitch` and `enumSwitch`
+    Switch(Vec<Label>),
+}
+
+/// pieces of a `makeConcatWithConstants` recipe, where `\1` stands for an argument and
+/// `\2` for the next constant
+fn concat_pieces(
+    cp: &CpPool,
+    recipe: &str,
+    descriptor: &str,
+    constants: &[usize],
+) -> anyhow::Result<Vec<Piece>> {
+    let desc = MethodDescriptor::parse(descriptor).context("invalid descriptor")?;
+    let mut args = desc.args.into_iter();
+    let mut constants = constants.iter();
+    let mut pieces = vec![];
+    let mut text = String::new();
+    for ch in recipe.chars() {
+        match ch {
+            '\u{1}' => {
+                let ty = args.next().context("not enough concat arguments")?;
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Arg(ty));
+            }
+            '\u{2}' => {
+                let constant = constants.next().context("not enough concat constants")?;
+                match cp.get(*constant) {
+                    Some(CpInfo::String(s)) => {
+                        text.push_str(&cp.get_utf(s.string_index).unwrap().bytes)
+                    }
+                    Some(CpInfo::Integer(i)) => text.push_str(&(i.val as i32).to_string()),
+                    Some(CpInfo::Long(l)) => text.push_str(&l.val.to_string()),
+                    constant => bail!("unsupported concat constant {constant:?}"),
+                }
+            }
+            ch => text.push(ch),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+fn string_constant(cp: &CpPool, index: usize) -> Option<&str> {
+    match cp.get(index)? {
+        CpInfo::String(s) => Some(&cp.get_utf(s.string_index)?.bytes),
+        _ => None,
+    }
+}
+
+fn switch_label(c: &Class, label: usize) -> anyhow::Result<Label> {
+    let cp = &c.cp;
+    Ok(match cp.get(label).context("invalid switch label")? {
+        CpInfo::Class(_) => Label::Class(cp.get_class_name(label).unwrap().to_string()),
+        CpInfo::String(s) => Label::String(cp.get_utf(s.string_index).unwrap().bytes.clone()),
+        CpInfo::Integer(n) => Label::Integer(n.val as i32),
+        CpInfo::Dynamic(info) => {
+            // javac describes qualified enum constants as `EnumDesc.of(ClassDesc.of(class), name)`
+            // computed by `ConstantBootstraps.invoke`
+            let bootstrap = c
+                .bootstrap_methods()
+                .get(info.bootstrap_method_attr_index)
+                .context("missing bootstrap method")?;
+            let handle = MethodHandle::resolve(cp, bootstrap.bootstrap_method_ref)
+                .context("invalid bootstrap method handle")?;
+            let arguments = &bootstrap.bootstrap_arguments;
+            let factory = match (handle.class.as_str(), handle.name.as_str()) {
+                ("java/lang/invoke/ConstantBootstraps", "invoke") => arguments
+                    .first()
+                    .and_then(|&i| MethodHandle::resolve(cp, i))
+                    .context("invalid constant factory handle")?,
+                (class, method) => bail!("unsupported constant bootstrap method {class}.{method}"),
+            };
+            let argument = |i: usize| {
+                arguments
+                    .get(i)
+                    .copied()
+                    .context("missing constant argument")
+            };
+            match (factory.class.as_str(), factory.name.as_str()) {
+                ("java/lang/Enum$EnumDesc", "of") => {
+                    let Label::Class(class) = switch_label(c, argument(1)?)? else {
+                        bail!("invalid enum class description");
+                    };
+                    let name = string_constant(cp, argument(2)?).context("invalid enum name")?;
+                    Label::Enum {
+                        class,
+                        name: name.to_string(),
+                    }
+                }
+                ("java/lang/constant/ClassDesc", "of") => {
+                    let name = string_constant(cp, argument(1)?).context("invalid class name")?;
+                    Label::Class(name.replace('.', "/"))
+                }
+                (class, method) => bail!("unsupported switch label {class}.{method}"),
+            }
+        }
+        constant => bail!("unsupported switch label {constant:?}"),
+    })
+}
+
+impl CallSite {
+    /// links the `invokedynamic` call site at `index` by what its bootstrap method does
+    pub fn link(c: &Class, index: usize) -> anyhow::Result<CallSite> {
+        let indy =
+            c.cp.get_invoke_dynamic(index)
+                .context("invalid invokedynamic")?;
+        let name_and_type = c.cp.get_name_and_type(indy.name_and_type_index).unwrap();
+        let name = &c.cp.get_utf(name_and_type.name_index).unwrap().bytes;
+        let descriptor = &c.cp.get_utf(name_and_type.descriptor_index).unwrap().bytes;
+        let bootstrap = c
+            .bootstrap_methods()
+            .get(indy.bootstrap_method_attr_index)
+            .context("missing bootstrap method")?;
+        let bootstrap_handle = MethodHandle::resolve(&c.cp, bootstrap.bootstrap_method_ref)
+            .context("invalid bootstrap method handle")?;
+        let arguments = &bootstrap.bootstrap_arguments;
+
+        Ok(
+            match (
+                bootstrap_handle.class.as_str(),
+                bootstrap_handle.name.as_str(),
+            ) {
+                ("java/lang/invoke/LambdaMetafactory", "metafactory" | "altMetafactory") => {
+                    // arguments are: erased interface method type, implementation, instantiated type
+                    let target = arguments
+                        .get(1)
+                        .and_then(|&i| MethodHandle::resolve(&c.cp, i))
+                        .context("invalid lambda implementation handle")?;
+                    let erased = arguments
+                        .first()
+                        .and_then(|&i| c.cp.get_method_type(i))
+                        .and_then(|t| c.cp.get_utf(t.descriptor_index))
+                        .context("invalid lambda method type")?;
+                    let desc = MethodDescriptor::parse(descriptor).context("invalid descriptor")?;
+                    CallSite::Lambda {
+                        interface: class_name(&desc.ret).to_string(),
+                        name: name.clone(),
+                        descriptor: erased.bytes.clone(),
+                        target,
+                    }
+                }
+                ("java/lang/invoke/StringConcatFactory", "makeConcatWithConstants") => {
+                    let recipe = arguments
+                        .first()
+                        .and_then(|&i| c.cp.get_string(i))
+                        .and_then(|s| c.cp.get_utf(s.string_index))
+                        .context("invalid concat recipe")?;
+                    CallSite::Concat(concat_pieces(
+                        &c.cp,
+                        &recipe.bytes,
+                        descriptor,
+                        &arguments[1..],
+                    )?)
+                }
+                ("java/lang/runtime/ObjectMethods", "bootstrap") => {
+                    // arguments are: record class, component names, accessor handles
+                    let record = arguments
+                        .first()
+                        .and_then(|&i| c.cp.get_class_name(i))
+                        .context("invalid record class")?;
+                    CallSite::Record {
+                        class: record.to_string(),
+                        name: name.clone(),
+                    }
+                }
+                ("java/lang/runtime/SwitchBootstraps", "typeSwitch" | "enumSwitch") => {
+                    let labels = arguments.iter().map(|&label| switch_label(c, label));
+                    CallSite::Switch(labels.collect::<anyhow::Result<_>>()?)
+                }
+                (class, method) => bail!("unsupported bootstrap method {class}.{method}"),
+            },
+        )
+    }
+
+    pub fn invoke(&self, vm: &mut Vm, args: Vec<Value>) -> anyhow::Result<Value> {
+        match self {
+            CallSite::Lambda {
+                interface,
+                name,
+                descriptor,
+                target,
+            } => Ok(vm.heap.alloc(Object::Lambda(Lambda {
+                interface: interface.clone(),
+                name: name.clone(),
+                descriptor: descriptor.clone(),
+                target: target.clone(),
+                captured: args,
+            }))),
+            CallSite::Concat(pieces) => {
+                let mut args = args.into_iter();
+                let mut result = String::new();
+                for piece in pieces {
+                    match piece {
+                        Piece::Text(text) => result.push_str(text),
+                        Piece::Arg(ty) => {
+                            let value = args.next().context("not enough concat arguments")?;
+                            result.push_str(&vm.java_string(value, ty)?);
+                        }
+                    }
+                }
+                Ok(vm.new_string(result))
+            }
+            CallSite::Record { class, name } => vm.record_method(class, name, &args),
+            CallSite::Switch(labels) => Ok(Value::Int(switch_index(vm, labels, args[0], args[1])?)),
+        }
+    }
+}
+
+/// `SwitchBootstraps.typeSwitch` and `enumSwitch`: index of the first case label from
+/// `restart` on which matches `target`, -1 for `null` and the number of labels for none
+fn switch_index(
+    vm: &mut Vm,
+    labels: &[Label],
+    target: Value,
+    restart: Value,
+) -> anyhow::Result<i32> {
+    let Some(r) = target.as_ref() else {
+        return Ok(-1);
+    };
+    let class = vm.heap.class_name(r).to_string();
+    let restart = restart.as_int().max(0) as usize;
+    for (i, label) in labels.iter().enumerate().skip(restart) {
+        let matches = match label {
+            Label::Class(label) => vm.is_assignable(&class, label)?,
+            Label::String(label) => {
+                let string = match vm.is_string(r) {
+                    true => target,
+                    false if vm.is_subclass(&class, "java/lang/Enum")? => vm
+                        .invoke_virtual("java/lang/Enum", "name", "()Ljava/lang/String;", [target])?
+                        .unwrap(),
+                    false => continue,
+                };
+                vm.string_value(string)? == *label
+            }
+            Label::Enum { class: owner, name } => {
+                if !vm.is_assignable(&class, owner)? {
+                    continue;
+                }
+                let string = vm
+                    .invoke_virtual("java/lang/Enum", "name", "()Ljava/lang/String;", [target])?
+                    .unwrap();
+                vm.string_value(string)? == *name
+            }
+            &Label::Integer(n) => vm.unbox(target) == Some(Value::Int(n)),
+        };
+        if matches {
+            return Ok(i as i32);
+        }
+    }
+    Ok(labels.len() as i32)
+}
+
+/// runs the `invokedynamic` of `site` with `args` taken from the stack, linking it on
+/// first use
+pub fn invoke_dynamic(
+    vm: &mut Vm,
+    c: &Class,
+    site: &DynamicSite,
+    args: Vec<Value>,
+) -> anyhow::Result<Value> {
+    let call_site = match site.call_site.get() {
+        Some(call_site) => call_site,
+        None => {
+            let call_site = CallSite::link(c, site.index)?;
+            site.call_site.get_or_init(|| call_site)
+        }
+    };
+    call_site.invoke(vm, args)
+}
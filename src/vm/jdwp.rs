@@ -0,0 +1,1420 @@
+//! JDWP agent, so that jdb or an IDE can debug a program over a socket. It is started with
+//! `-agentlib:jdwp=transport=dt_socket,server=y,address=<port>` like on HotSpot. As for
+//! `jrust debug`, the interpreter calls [`Vm::jdwp_hook`] before every instruction. A thread
+//! stopped at an event keeps the global interpreter lock, so every other thread is
+//! suspended as well, and suspending or resuming a single thread acts on all of them.
+//!
+//! All ids are 8 bytes. Object ids are heap references plus one, type ids number the types
+//! the debugger has seen and method and field ids hold their type id above their index
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use anyhow::{bail, Context};
+
+use crate::class::{Class, ClassAccessFlags};
+
+use super::{
+    classpath::ClassSource,
+    debug::{record_position, Position, Positions},
+    exception::Throw,
+    heap::Object,
+    link::Linked,
+    trace::glob,
+    value::Value,
+    Vm,
+};
+
+const HANDSHAKE: &[u8] = b"JDWP-Handshake";
+const REPLY: u8 = 0x80;
+
+// error codes
+const INVALID_THREAD: u16 = 10;
+const INVALID_OBJECT: u16 = 20;
+const INVALID_CLASS: u16 = 21;
+const INVALID_METHODID: u16 = 23;
+const INVALID_FIELDID: u16 = 25;
+const INVALID_FRAMEID: u16 = 30;
+const INVALID_SLOT: u16 = 35;
+const TYPE_MISMATCH: u16 = 34;
+const NOT_IMPLEMENTED: u16 = 99;
+const ABSENT_INFORMATION: u16 = 101;
+const ILLEGAL_ARGUMENT: u16 = 103;
+const INTERNAL: u16 = 113;
+
+// invoke options
+const INVOKE_NONVIRTUAL: u32 = 2;
+
+// event kinds
+const SINGLE_STEP: u8 = 1;
+const BREAKPOINT: u8 = 2;
+const THREAD_START: u8 = 6;
+const THREAD_DEATH: u8 = 7;
+const CLASS_PREPARE: u8 = 8;
+const VM_START: u8 = 90;
+const VM_DEATH: u8 = 99;
+
+// suspend policies
+const SUSPEND_NONE: u8 = 0;
+const SUSPEND_ALL: u8 = 2;
+
+// type tags
+const CLASS: u8 = 1;
+const INTERFACE: u8 = 2;
+const ARRAY: u8 = 3;
+
+/// id of the single thread group, which is not a heap object
+const THREAD_GROUP: u64 = 1 << 48;
+
+struct Packet {
+    id: u32,
+    flags: u8,
+    command_set: u8,
+    command: u8,
+    data: Vec<u8>,
+}
+
+fn read_packet(stream: &mut TcpStream) -> std::io::Result<Packet> {
+    let mut header = [0; 11];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let mut data = vec![0; length.saturating_sub(header.len())];
+    stream.read_exact(&mut data)?;
+    Ok(Packet {
+        id: u32::from_be_bytes(header[4..8].try_into().unwrap()),
+        flags: header[8],
+        command_set: header[9],
+        command: header[10],
+        data,
+    })
+}
+
+/// code location, `index` is the bytecode offset
+#[derive(Clone, Copy, PartialEq)]
+struct Location {
+    tag: u8,
+    class: u64,
+    method: u64,
+    index: u64,
+}
+
+/// command data being decoded, a truncated packet is an illegal argument
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes(&mut self, n: usize) -> Result<&[u8], u16> {
+        if self.0.len() < n {
+            return Err(ILLEGAL_ARGUMENT);
+        }
+        let (bytes, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, u16> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, u16> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, u16> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn u64(&mut self) -> Result<u64, u16> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, u16> {
+        let length = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+
+    /// tag and bits of a tagged value
+    fn value(&mut self) -> Result<(u8, u64), u16> {
+        let tag = self.u8()?;
+        let bits = match tag {
+            b'V' => 0,
+            b'Z' | b'B' => self.u8()? as u64,
+            b'C' | b'S' => u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()) as u64,
+            b'I' | b'F' => self.u32()? as u64,
+            _ => self.u64()?,
+        };
+        Ok((tag, bits))
+    }
+
+    fn location(&mut self) -> Result<Location, u16> {
+        Ok(Location {
+            tag: self.u8()?,
+            class: self.u64()?,
+            method: self.u64()?,
+            index: self.u64()?,
+        })
+    }
+}
+
+/// reply or event data
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) -> &mut Self {
+        self.0.push(value);
+        self
+    }
+
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn u64(&mut self, value: u64) -> &mut Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn bool(&mut self, value: bool) -> &mut Self {
+        self.u8(value as u8)
+    }
+
+    fn string(&mut self, value: &str) -> &mut Self {
+        self.u32(value.len() as u32);
+        self.0.extend(value.as_bytes());
+        self
+    }
+
+    fn location(&mut self, location: Location) -> &mut Self {
+        self.u8(location.tag)
+            .u64(location.class)
+            .u64(location.method)
+            .u64(location.index)
+    }
+}
+
+enum Modifier {
+    /// reports only the event after this many more occurrences
+    Count(u32),
+    ThreadOnly(u64),
+    ClassOnly(u64),
+    ClassMatch(String),
+    ClassExclude(String),
+    LocationOnly(Location),
+    /// `from` is the depth and line of the thread when the step started or last stopped
+    Step {
+        thread: u64,
+        size: u32,
+        depth: u32,
+        from: (usize, Option<usize>),
+    },
+    SourceNameMatch(String),
+    /// restricts events which are never sent
+    Ignored,
+}
+
+struct Request {
+    id: u32,
+    kind: u8,
+    suspend: u8,
+    modifiers: Vec<Modifier>,
+    /// a request whose count ran out is removed after reporting its event
+    expired: bool,
+}
+
+/// where an event happens, thread events have no class
+struct Site<'a> {
+    thread: u64,
+    class: Option<&'a Class>,
+    type_id: u64,
+    location: Option<Location>,
+    depth: usize,
+    line: Option<usize>,
+}
+
+impl Request {
+    fn matches(&mut self, site: &Site) -> bool {
+        let class = site
+            .class
+            .map(|class| class.this_class_name.replace('/', "."));
+        for modifier in &self.modifiers {
+            let matches = match modifier {
+                Modifier::Count(_) | Modifier::Ignored => true,
+                Modifier::ThreadOnly(thread) => *thread == site.thread,
+                Modifier::ClassOnly(type_id) => *type_id == site.type_id,
+                Modifier::ClassMatch(pattern) => class.as_ref().is_some_and(|c| glob(pattern, c)),
+                Modifier::ClassExclude(pattern) => {
+                    !class.as_ref().is_some_and(|c| glob(pattern, c))
+                }
+                Modifier::LocationOnly(location) => Some(*location) == site.location,
+                Modifier::SourceNameMatch(pattern) => site
+                    .class
+                    .and_then(Class::source_file)
+                    .is_some_and(|file| glob(pattern, file)),
+                &Modifier::Step {
+                    thread,
+                    size,
+                    depth,
+                    from: (from_depth, from_line),
+                } => {
+                    // steps by line become steps by instruction without line numbers
+                    let moved = match size {
+                        1 if site.line.is_some() => site.line != from_line,
+                        _ => true,
+                    };
+                    thread == site.thread
+                        && match depth {
+                            // into
+                            0 => site.depth != from_depth || moved,
+                            // over
+                            1 => site.depth < from_depth || (site.depth == from_depth && moved),
+                            // out
+                            _ => site.depth < from_depth,
+                        }
+                }
+            };
+            if !matches {
+                return false;
+            }
+        }
+        for modifier in &mut self.modifiers {
+            match modifier {
+                Modifier::Count(count) => {
+                    *count -= 1;
+                    if *count > 0 {
+                        return false;
+                    }
+                    self.expired = true;
+                }
+                Modifier::Step { from, .. } => *from = (site.depth, site.line),
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+pub struct Jdwp {
+    stream: TcpStream,
+    packets: Receiver<Packet>,
+    next_packet: u32,
+    next_request: u32,
+    requests: Vec<Request>,
+    /// class names by type id minus one
+    types: Vec<String>,
+    type_ids: HashMap<String, u64>,
+    positions: Positions,
+    /// number of suspends not resumed yet, the program stops while it is not 0
+    suspended: u32,
+}
+
+impl Jdwp {
+    fn type_id(&mut self, name: &str) -> u64 {
+        if let Some(&id) = self.type_ids.get(name) {
+            return id;
+        }
+        self.types.push(name.to_string());
+        let id = self.types.len() as u64;
+        self.type_ids.insert(name.to_string(), id);
+        id
+    }
+
+    fn type_name(&self, id: u64) -> Result<&str, u16> {
+        let name = (id as usize).checked_sub(1).and_then(|i| self.types.get(i));
+        name.map(String::as_str).ok_or(INVALID_CLASS)
+    }
+
+    fn send(&mut self, command_set: u8, command: u8, data: &[u8]) -> std::io::Result<()> {
+        self.next_packet += 1;
+        let mut packet = Writer::default();
+        packet
+            .u32(11 + data.len() as u32)
+            .u32(self.next_packet)
+            .u8(0)
+            .u8(command_set)
+            .u8(command);
+        packet.0.extend(data);
+        self.stream.write_all(&packet.0)
+    }
+
+    fn reply(&mut self, id: u32, reply: Result<Writer, u16>) -> std::io::Result<()> {
+        let (error, data) = match reply {
+            Ok(data) => (0, data.0),
+            Err(error) => (error, vec![]),
+        };
+        let mut packet = Writer::default();
+        packet
+            .u32(11 + data.len() as u32)
+            .u32(id)
+            .u8(REPLY)
+            .u16(error);
+        packet.0.extend(data);
+        self.stream.write_all(&packet.0)
+    }
+
+    /// sends a composite event packet, stopping the program unless the policy is none
+    fn send_events(&mut self, suspend: u8, events: Vec<(u8, u32, Writer)>) -> std::io::Result<()> {
+        let mut data = Writer::default();
+        data.u8(suspend).u32(events.len() as u32);
+        for (kind, request, body) in events {
+            data.u8(kind).u32(request);
+            data.0.extend(body.0);
+        }
+        if suspend != SUSPEND_NONE {
+            self.suspended += 1;
+        }
+        self.send(64, 100, &data.0)
+    }
+
+    /// events for the requests of one of `kinds` matching `site`, and their suspend policy
+    fn matching(&mut self, kinds: &[u8], site: &Site) -> (u8, Vec<(u8, u32)>) {
+        let mut suspend = SUSPEND_NONE;
+        let mut events = vec![];
+        for request in &mut self.requests {
+            if kinds.contains(&request.kind) && request.matches(site) {
+                suspend = suspend.max(request.suspend);
+                events.push((request.kind, request.id));
+            }
+        }
+        self.requests.retain(|request| !request.expired);
+        // steps are reported before breakpoints at the same location
+        events.sort_by_key(|&(kind, _)| kind);
+        (suspend, events)
+    }
+}
+
+fn object_id(value: Value) -> u64 {
+    match value {
+        Value::Ref(r) => r as u64 + 1,
+        _ => 0,
+    }
+}
+
+fn method_id(type_id: u64, index: usize) -> u64 {
+    (type_id << 32) | (index as u64 + 1)
+}
+
+fn signature(name: &str) -> String {
+    match name.starts_with('[') {
+        true => name.to_string(),
+        false => format!("L{name};"),
+    }
+}
+
+type Reply = Result<Writer, u16>;
+
+impl Vm {
+    /// starts the agent for the options of `-agentlib:jdwp=`, waiting for the debugger to
+    /// connect and, with `suspend=y`, to resume the program
+    pub fn jdwp_start(&mut self, options: &str) -> anyhow::Result<()> {
+        let options: HashMap<_, _> = options
+            .split(',')
+            .filter_map(|option| option.split_once('='))
+            .collect();
+        match options.get("transport") {
+            Some(&"dt_socket") => {}
+            Some(transport) => bail!("unsupported transport {transport}"),
+            None => bail!("no transport given"),
+        }
+        let address = options.get("address").context("no address given")?;
+        let address = match address.split_once(':') {
+            Some(("*", port)) => format!("0.0.0.0:{port}"),
+            Some(_) => address.to_string(),
+            None => format!("127.0.0.1:{address}"),
+        };
+        let mut stream = match options.get("server") {
+            Some(&"y") => {
+                let listener = TcpListener::bind(&address)
+                    .with_context(|| format!("failed to listen at {address}"))?;
+                let port = listener.local_addr()?.port();
+                println!("Listening for transport dt_socket at address: {port}");
+                std::io::stdout().flush()?;
+                listener.accept()?.0
+            }
+            _ => TcpStream::connect(&address)
+                .with_context(|| format!("failed to connect to {address}"))?,
+        };
+
+        let mut handshake = [0; HANDSHAKE.len()];
+        stream.read_exact(&mut handshake)?;
+        if handshake != HANDSHAKE {
+            bail!("JDWP handshake failed");
+        }
+        stream.write_all(HANDSHAKE)?;
+
+        let mut reader = stream.try_clone()?;
+        let (sender, packets) = mpsc::channel();
+        thread::Builder::new()
+            .name("jdwp".to_string())
+            .spawn(move || {
+                while let Ok(packet) = read_packet(&mut reader) {
+                    if sender.send(packet).is_err() {
+                        break;
+                    }
+                }
+            })?;
+        let mut agent = Jdwp {
+            stream,
+            packets,
+            next_packet: 0,
+            next_request: 0,
+            requests: vec![],
+            types: vec![],
+            type_ids: HashMap::new(),
+            positions: Positions::new(),
+            suspended: 0,
+        };
+
+        let suspend = match options.get("suspend") {
+            Some(&"n") => SUSPEND_NONE,
+            _ => SUSPEND_ALL,
+        };
+        let mut body = Writer::default();
+        body.u64(object_id(self.thread));
+        agent.send_events(suspend, vec![(VM_START, 0, body)])?;
+        if self.jdwp_serve(&mut agent) {
+            self.jdwp = Some(agent);
+        }
+        Ok(())
+    }
+
+    /// tells the debugger that the program ended
+    pub(crate) fn jdwp_exit(&mut self) {
+        let Some(mut agent) = self.jdwp.take() else {
+            return;
+        };
+        let mut events: Vec<_> = agent
+            .requests
+            .iter()
+            .filter(|request| request.kind == VM_DEATH)
+            .map(|request| (VM_DEATH, request.id, Writer::default()))
+            .collect();
+        if events.is_empty() {
+            events.push((VM_DEATH, 0, Writer::default()));
+        }
+        // the program ends anyway when the debugger is gone
+        let _ = agent.send_events(SUSPEND_NONE, events);
+        let _ = agent.stream.shutdown(std::net::Shutdown::Both);
+    }
+
+    /// called before instruction `i` of `code` runs in the innermost frame
+    pub(crate) fn jdwp_hook(&mut self, class: &Rc<Class>, code: &Linked, i: usize) {
+        let Some(mut agent) = self.jdwp.take() else {
+            return;
+        };
+        let (thread, depth) = (self.threads.current, self.frames.len());
+        record_position(&mut agent.positions, thread, depth, class, code, i);
+        // answers commands sent while the program runs
+        if !self.jdwp_serve(&mut agent) {
+            return;
+        }
+
+        let position = Position {
+            class: class.clone(),
+            method: code.method,
+            pc: code.pcs[i],
+        };
+        let location = self.jdwp_location(&mut agent, &position);
+        let site = Site {
+            thread: object_id(self.thread),
+            class: Some(class),
+            type_id: location.class,
+            location: Some(location),
+            depth,
+            line: position.line(),
+        };
+        let (suspend, events) = agent.matching(&[SINGLE_STEP, BREAKPOINT], &site);
+        if !events.is_empty() {
+            let events = events
+                .into_iter()
+                .map(|(kind, request)| {
+                    let mut body = Writer::default();
+                    body.u64(site.thread).location(location);
+                    (kind, request, body)
+                })
+                .collect();
+            if agent.send_events(suspend, events).is_err() || !self.jdwp_serve(&mut agent) {
+                return;
+            }
+        }
+        self.jdwp = Some(agent);
+    }
+
+    /// reports a newly loaded class
+    pub(crate) fn jdwp_class_prepared(&mut self, class: &Rc<Class>) {
+        let Some(mut agent) = self.jdwp.take() else {
+            return;
+        };
+        let type_id = agent.type_id(&class.this_class_name);
+        let site = Site {
+            thread: object_id(self.thread),
+            class: Some(class),
+            type_id,
+            location: None,
+            depth: self.frames.len(),
+            line: None,
+        };
+        let (suspend, events) = agent.matching(&[CLASS_PREPARE], &site);
+        if !events.is_empty() {
+            let tag = self.jdwp_type_tag(&class.this_class_name);
+            let status = self.jdwp_class_status(&class.this_class_name);
+            let events = events
+                .into_iter()
+                .map(|(kind, request)| {
+                    let mut body = Writer::default();
+                    body.u64(site.thread)
+                        .u8(tag)
+                        .u64(type_id)
+                        .string(&signature(&class.this_class_name))
+                        .u32(status);
+                    (kind, request, body)
+                })
+                .collect();
+            if agent.send_events(suspend, events).is_err() || !self.jdwp_serve(&mut agent) {
+                return;
+            }
+        }
+        self.jdwp = Some(agent);
+    }
+
+    /// reports that the running thread starts, or ends when not `started`
+    pub(crate) fn jdwp_thread_event(&mut self, started: bool) {
+        let Some(mut agent) = self.jdwp.take() else {
+            return;
+        };
+        let kind = match started {
+            true => THREAD_START,
+            false => THREAD_DEATH,
+        };
+        let site = Site {
+            thread: object_id(self.thread),
+            class: None,
+            type_id: 0,
+            location: None,
+            depth: self.frames.len(),
+            line: None,
+        };
+        let (suspend, events) = agent.matching(&[kind], &site);
+        if !events.is_empty() {
+            let events = events
+                .into_iter()
+                .map(|(kind, request)| {
+                    let mut body = Writer::default();
+                    body.u64(site.thread);
+                    (kind, request, body)
+                })
+                .collect();
+            if agent.send_events(suspend, events).is_err() || !self.jdwp_serve(&mut agent) {
+                return;
+            }
+        }
+        self.jdwp = Some(agent);
+    }
+
+    /// answers the commands which arrived, and waits for more while the program is
+    /// suspended. Returns false once the debugger is gone
+    fn jdwp_serve(&mut self, agent: &mut Jdwp) -> bool {
+        loop {
+            let packet = match agent.suspended {
+                0 => match agent.packets.try_recv() {
+                    Ok(packet) => packet,
+                    Err(TryRecvError::Empty) => return true,
+                    Err(TryRecvError::Disconnected) => return false,
+                },
+                _ => match agent.packets.recv() {
+                    Ok(packet) => packet,
+                    Err(_) => return false,
+                },
+            };
+            if packet.flags & REPLY != 0 {
+                continue;
+            }
+            let (command_set, command) = (packet.command_set, packet.command);
+            let reply = self.jdwp_command(agent, &packet);
+            if agent.reply(packet.id, reply).is_err() {
+                return false;
+            }
+            match (command_set, command) {
+                // VirtualMachine.Dispose
+                (1, 6) => return false,
+                // VirtualMachine.Exit
+                (1, 10) => {
+                    let code = Reader(&packet.data).i32().unwrap_or(0);
+                    std::process::exit(code);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn jdwp_class(&self, agent: &Jdwp, type_id: u64) -> Result<Rc<Class>, u16> {
+        let name = agent.type_name(type_id)?;
+        self.classes.get(name).cloned().ok_or(INVALID_CLASS)
+    }
+
+    /// declaring class and index of the method or field with `id`, `error` when there is none
+    fn jdwp_member(&self, agent: &Jdwp, id: u64, error: u16) -> Result<(Rc<Class>, usize), u16> {
+        let class = self.jdwp_class(agent, id >> 32)?;
+        let index = ((id & 0xFFFF_FFFF) as usize).checked_sub(1).ok_or(error)?;
+        Ok((class, index))
+    }
+
+    fn jdwp_type_tag(&self, name: &str) -> u8 {
+        match self.classes.get(name) {
+            _ if name.starts_with('[') => ARRAY,
+            Some(class) if class.access_flags.contains(ClassAccessFlags::INTERFACE) => INTERFACE,
+            _ => CLASS,
+        }
+    }
+
+    /// verified and prepared, and initialized once its static initializer ran
+    fn jdwp_class_status(&self, name: &str) -> u32 {
+        match self.initialized.contains(name) {
+            true => 7,
+            false => 3,
+        }
+    }
+
+    fn jdwp_location(&self, agent: &mut Jdwp, position: &Position) -> Location {
+        let name = &position.class.this_class_name;
+        let class = agent.type_id(name);
+        Location {
+            tag: self.jdwp_type_tag(name),
+            class,
+            method: method_id(class, position.method),
+            index: position.pc as u64,
+        }
+    }
+
+    fn jdwp_object(&self, id: u64) -> Result<Value, u16> {
+        match id {
+            0 => Ok(Value::Null),
+            id => match self.heap.try_get(id as usize - 1) {
+                Some(_) => Ok(Value::Ref(id as usize - 1)),
+                None => Err(INVALID_OBJECT),
+            },
+        }
+    }
+
+    /// internal id of a live thread by its object id
+    fn jdwp_thread(&self, id: u64) -> Result<(Value, usize), u16> {
+        let thread = self.jdwp_object(id).map_err(|_| INVALID_THREAD)?;
+        if thread == Value::Null {
+            return Err(INVALID_THREAD);
+        }
+        let alive = self.live_threads().into_iter().find(|&(_, t)| t == thread);
+        match alive {
+            Some((id, _)) => Ok((thread, id)),
+            None => Ok((thread, 0)),
+        }
+    }
+
+    /// frames of a thread whose position is known, innermost first, with their depth
+    fn jdwp_frames(&self, agent: &Jdwp, thread: usize) -> Vec<(usize, Position)> {
+        let count = self.thread_frames(thread).map_or(0, <[_]>::len);
+        let positions = agent.positions.get(&thread);
+        (1..=count)
+            .rev()
+            .filter_map(|depth| {
+                let position = positions?.get(depth - 1)?.clone()?;
+                Some((depth, position))
+            })
+            .collect()
+    }
+
+    fn jdwp_object_tag(&mut self, value: Value) -> u8 {
+        let Value::Ref(r) = value else {
+            return b'L';
+        };
+        match self.heap.get(r) {
+            Object::Array(_) => b'[',
+            Object::String(_) => b's',
+            _ if self.is_string(r) => b's',
+            Object::Instance(instance) => {
+                let class = instance.class.clone();
+                match self.is_subclass(&class, "java/lang/Thread") {
+                    Ok(true) => b't',
+                    _ => b'L',
+                }
+            }
+            _ => b'L',
+        }
+    }
+
+    /// writes `value` of type `descriptor`, with a tag byte first when `tagged`
+    fn jdwp_value(&mut self, w: &mut Writer, value: Value, descriptor: u8, tagged: bool) {
+        if matches!(descriptor, b'L' | b'[') {
+            if tagged {
+                let tag = match value {
+                    Value::Null => descriptor,
+                    value => self.jdwp_object_tag(value),
+                };
+                w.u8(tag);
+            }
+            w.u64(object_id(value));
+            return;
+        }
+        if tagged {
+            w.u8(descriptor);
+        }
+        let int = match value {
+            Value::Int(i) => i,
+            _ => 0,
+        };
+        match descriptor {
+            b'Z' | b'B' => {
+                w.u8(int as u8);
+            }
+            b'C' | b'S' => {
+                w.u16(int as u16);
+            }
+            b'J' => {
+                w.u64(match value {
+                    Value::Long(l) => l as u64,
+                    _ => 0,
+                });
+            }
+            b'F' => {
+                w.u32(match value {
+                    Value::Float(f) => f.to_bits(),
+                    _ => 0,
+                });
+            }
+            b'D' => {
+                w.u64(match value {
+                    Value::Double(d) => d.to_bits(),
+                    _ => 0,
+                });
+            }
+            _ => {
+                w.u32(int as u32);
+            }
+        }
+    }
+
+    fn jdwp_command(&mut self, agent: &mut Jdwp, packet: &Packet) -> Reply {
+        let mut r = Reader(&packet.data);
+        let mut w = Writer::default();
+        match (packet.command_set, packet.command) {
+            // VirtualMachine
+            (1, 1) => {
+                w.string("jrust")
+                    .u32(17)
+                    .u32(0)
+                    .string("17")
+                    .string("jrust");
+            }
+            (1, 2) => {
+                let signature = r.string()?;
+                let name = match signature.strip_prefix('L') {
+                    Some(name) => name.trim_end_matches(';'),
+                    None => &signature,
+                };
+                let names = match self.classes.contains_key(name) {
+                    true => vec![name.to_string()],
+                    false => vec![],
+                };
+                w = Writer::default();
+                w.u32(names.len() as u32);
+                for name in names {
+                    let id = agent.type_id(&name);
+                    w.u8(self.jdwp_type_tag(&name))
+                        .u64(id)
+                        .u32(self.jdwp_class_status(&name));
+                }
+            }
+            (1, 3) | (1, 20) => {
+                let generic = packet.command == 20;
+                let mut names: Vec<_> = self.classes.keys().cloned().collect();
+                names.sort();
+                w.u32(names.len() as u32);
+                for name in names {
+                    let id = agent.type_id(&name);
+                    w.u8(self.jdwp_type_tag(&name))
+                        .u64(id)
+                        .string(&signature(&name));
+                    if generic {
+                        w.string("");
+                    }
+                    w.u32(self.jdwp_class_status(&name));
+                }
+            }
+            (1, 4) => {
+                let threads = self.live_threads();
+                w.u32(threads.len() as u32);
+                for (_, thread) in threads {
+                    w.u64(object_id(thread));
+                }
+            }
+            (1, 5) => {
+                w.u32(1).u64(THREAD_GROUP);
+            }
+            (1, 6) => {}
+            (1, 7) => {
+                for _ in 0..5 {
+                    w.u32(8);
+                }
+            }
+            (1, 8) => agent.suspended += 1,
+            (1, 9) => agent.suspended = agent.suspended.saturating_sub(1),
+            (1, 10) => {}
+            (1, 11) => {
+                let string = r.string()?;
+                let string = self.new_string(string);
+                w.u64(object_id(string));
+            }
+            (1, 12) => {
+                for _ in 0..7 {
+                    w.bool(false);
+                }
+            }
+            (1, 13) => {
+                // debuggers look for sources in the directories of the classpath
+                let dir = std::env::current_dir().unwrap_or_default();
+                let classpath: Vec<_> = self
+                    .classpath
+                    .iter()
+                    .filter_map(|source| match source {
+                        ClassSource::Dir(path) => Some(dir.join(path)),
+                        _ => None,
+                    })
+                    .collect();
+                w.string(&dir.display().to_string())
+                    .u32(classpath.len() as u32);
+                for path in classpath {
+                    w.string(&path.display().to_string());
+                }
+                w.u32(0);
+            }
+            (1, 14..=16) => {}
+            (1, 17) => {
+                for _ in 0..32 {
+                    w.bool(false);
+                }
+            }
+
+            // ReferenceType
+            (2, _) => {
+                let type_id = r.u64()?;
+                let name = agent.type_name(type_id)?.to_string();
+                match packet.command {
+                    1 => {
+                        w.string(&signature(&name));
+                    }
+                    13 => {
+                        w.string(&signature(&name)).string("");
+                    }
+                    2 => {
+                        w.u64(0);
+                    }
+                    9 => {
+                        w.u32(self.jdwp_class_status(&name));
+                    }
+                    _ if name.starts_with('[') => match packet.command {
+                        3 => {
+                            w.u32(0x11);
+                        }
+                        4 | 5 | 10 | 14 | 15 => {
+                            w.u32(0);
+                        }
+                        _ => return Err(ABSENT_INFORMATION),
+                    },
+                    command => {
+                        let class = self.jdwp_class(agent, type_id)?;
+                        self.jdwp_reference_type(agent, &mut r, &mut w, command, type_id, &class)?;
+                    }
+                }
+            }
+            // ClassType.InvokeMethod
+            (3, 3) => {
+                r.u64()?;
+                r.u64()?;
+                let (class, index) = self.jdwp_member(agent, r.u64()?, INVALID_METHODID)?;
+                let args = self.jdwp_arguments(&mut r)?;
+                r.u32()?;
+                self.jdwp_invoke(&mut w, &class, index, args, false)?;
+            }
+            // ClassType.Superclass
+            (3, 1) => {
+                let class = self.jdwp_class(agent, r.u64()?)?;
+                match class.super_class {
+                    0 => w.u64(0),
+                    _ => w.u64(agent.type_id(&class.super_class_name)),
+                };
+            }
+            // Method
+            (6, command @ 1..=5) => {
+                r.u64()?;
+                let (class, index) = self.jdwp_member(agent, r.u64()?, INVALID_METHODID)?;
+                let method = class.methods.get(index).ok_or(INVALID_METHODID)?;
+                if command == 4 {
+                    w.bool(false);
+                    return Ok(w);
+                }
+                let code = method.code().ok_or(ABSENT_INFORMATION)?;
+                match command {
+                    1 => {
+                        let lines: Vec<_> = code.line_numbers().collect();
+                        w.u64(0)
+                            .u64(code.code_raw.len().saturating_sub(1) as u64)
+                            .u32(lines.len() as u32);
+                        for line in lines {
+                            w.u64(line.start_pc as u64).u32(line.line_number as u32);
+                        }
+                    }
+                    3 => {
+                        w.u32(code.code_raw.len() as u32);
+                        w.0.extend(&code.code_raw);
+                    }
+                    _ => {
+                        let variables: Vec<_> = code
+                            .attributes
+                            .iter()
+                            .filter_map(|a| match &a.attribute {
+                                crate::class::attribute::Attribute::LocalVariableTable(t) => {
+                                    Some(&t.local_variable_table)
+                                }
+                                _ => None,
+                            })
+                            .flatten()
+                            .collect();
+                        if variables.is_empty() {
+                            return Err(ABSENT_INFORMATION);
+                        }
+                        let descriptor =
+                            crate::class::descriptor::MethodDescriptor::parse(&method.descriptor)
+                                .ok_or(ILLEGAL_ARGUMENT)?;
+                        let is_static = method
+                            .access_flags
+                            .contains(crate::class::method::MethodAccessFlags::STATIC);
+                        let arguments: usize = descriptor
+                            .args
+                            .iter()
+                            .map(|p| match p.as_str() {
+                                "J" | "D" => 2,
+                                _ => 1,
+                            })
+                            .sum::<usize>()
+                            + !is_static as usize;
+                        w.u32(arguments as u32).u32(variables.len() as u32);
+                        for var in variables {
+                            w.u64(var.start_pc as u64)
+                                .string(&var.name)
+                                .string(&var.descriptor);
+                            if command == 5 {
+                                w.string("");
+                            }
+                            w.u32(var.length as u32).u32(var.index as u32);
+                        }
+                    }
+                }
+            }
+
+            // ObjectReference
+            (9, 1) => {
+                let object = self.jdwp_object(r.u64()?)?;
+                let Value::Ref(reference) = object else {
+                    return Err(INVALID_OBJECT);
+                };
+                let name = self.heap.class_name(reference).to_string();
+                let id = agent.type_id(&name);
+                w.u8(self.jdwp_type_tag(&name)).u64(id);
+            }
+            (9, 2) => {
+                let object = self.jdwp_object(r.u64()?)?;
+                let Value::Ref(reference) = object else {
+                    return Err(INVALID_OBJECT);
+                };
+                let count = r.u32()?;
+                w.u32(count);
+                for _ in 0..count {
+                    let (class, index) = self.jdwp_member(agent, r.u64()?, INVALID_FIELDID)?;
+                    let field = class.fields.get(index).ok_or(INVALID_FIELDID)?;
+                    let value = match self.heap.get(reference) {
+                        Object::Instance(instance) => instance
+                            .layout
+                            .declared_slot(&class.this_class_name, &field.name)
+                            .map(|slot| instance.fields[slot]),
+                        _ => None,
+                    };
+                    let value = value.unwrap_or(Value::default_for(&field.descriptor));
+                    self.jdwp_value(&mut w, value, field.descriptor.as_bytes()[0], true);
+                }
+            }
+            (9, 6) => {
+                let object = self.jdwp_object(r.u64()?)?;
+                r.u64()?;
+                r.u64()?;
+                let (class, index) = self.jdwp_member(agent, r.u64()?, INVALID_METHODID)?;
+                let mut args = vec![object];
+                args.extend(self.jdwp_arguments(&mut r)?);
+                let virtual_call = r.u32()? & INVOKE_NONVIRTUAL == 0;
+                self.jdwp_invoke(&mut w, &class, index, args, virtual_call)?;
+            }
+            (9, 7 | 8) => {}
+            (9, 9) => {
+                let id = r.u64()?;
+                w.bool(id != 0 && self.jdwp_object(id).is_err());
+            }
+            // StringReference.Value
+            (10, 1) => {
+                let string = self.jdwp_object(r.u64()?)?;
+                let string = self.string_value(string).map_err(|_| INVALID_OBJECT)?;
+                w.string(&string);
+            }
+
+            // ThreadReference
+            (11, command) => {
+                let (thread, id) = self.jdwp_thread(r.u64()?)?;
+                match command {
+                    1 => {
+                        let name = self.thread_field(thread, "name");
+                        let name = self.string_value(name).unwrap_or_default();
+                        w.string(&name);
+                    }
+                    2 => agent.suspended += 1,
+                    3 => agent.suspended = agent.suspended.saturating_sub(1),
+                    4 => {
+                        // running, or a zombie once terminated
+                        let running = (id != 0) as u32;
+                        w.u32(running).u32((agent.suspended > 0) as u32);
+                    }
+                    5 => {
+                        w.u64(THREAD_GROUP);
+                    }
+                    6 => {
+                        let start = r.i32()?.max(0) as usize;
+                        let length = r.i32()?;
+                        let frames = self.jdwp_frames(agent, id);
+                        let end = match length {
+                            -1 => frames.len(),
+                            length => (start + length.max(0) as usize).min(frames.len()),
+                        };
+                        let frames = frames.get(start..end).ok_or(ILLEGAL_ARGUMENT)?;
+                        w.u32(frames.len() as u32);
+                        for (depth, position) in frames {
+                            let location = self.jdwp_location(agent, position);
+                            w.u64(((id as u64) << 32) | *depth as u64)
+                                .location(location);
+                        }
+                    }
+                    7 => {
+                        w.u32(self.jdwp_frames(agent, id).len() as u32);
+                    }
+                    11 => self.interrupt(thread),
+                    12 => {
+                        w.u32(agent.suspended);
+                    }
+                    _ => return Err(NOT_IMPLEMENTED),
+                }
+            }
+            // ThreadGroupReference
+            (12, command) => {
+                if r.u64()? != THREAD_GROUP {
+                    return Err(INVALID_OBJECT);
+                }
+                match command {
+                    1 => {
+                        w.string("main");
+                    }
+                    2 => {
+                        w.u64(0);
+                    }
+                    3 => {
+                        let threads = self.live_threads();
+                        w.u32(threads.len() as u32);
+                        for (_, thread) in threads {
+                            w.u64(object_id(thread));
+                        }
+                        w.u32(0);
+                    }
+                    _ => return Err(NOT_IMPLEMENTED),
+                }
+            }
+            // ArrayReference
+            (13, command @ (1 | 2)) => {
+                let array = self.jdwp_object(r.u64()?)?;
+                let Some(Object::Array(array)) = array.as_ref().map(|a| self.heap.get(a)) else {
+                    return Err(INVALID_OBJECT);
+                };
+                let descriptor = array.component().as_bytes()[0];
+                let elements = array.elements.clone();
+                if command == 1 {
+                    w.u32(elements.len() as u32);
+                    return Ok(w);
+                }
+                let first = r.i32()?.max(0) as usize;
+                let length = r.i32()?.max(0) as usize;
+                let elements = elements
+                    .get(first..first + length)
+                    .ok_or(ILLEGAL_ARGUMENT)?;
+                let tagged = matches!(descriptor, b'L' | b'[');
+                w.u8(descriptor).u32(elements.len() as u32);
+                for &element in elements {
+                    self.jdwp_value(&mut w, element, descriptor, tagged);
+                }
+            }
+
+            // EventRequest
+            (15, 1) => {
+                let kind = r.u8()?;
+                let suspend = r.u8()?;
+                let count = r.u32()?;
+                let mut modifiers = vec![];
+                for _ in 0..count {
+                    modifiers.push(match r.u8()? {
+                        1 => Modifier::Count(r.u32()?),
+                        2 => {
+                            r.u32()?;
+                            Modifier::Ignored
+                        }
+                        3 => Modifier::ThreadOnly(r.u64()?),
+                        4 => Modifier::ClassOnly(r.u64()?),
+                        5 => Modifier::ClassMatch(r.string()?),
+                        6 => Modifier::ClassExclude(r.string()?),
+                        7 => Modifier::LocationOnly(r.location()?),
+                        8 => {
+                            r.u64()?;
+                            r.bytes(2)?;
+                            Modifier::Ignored
+                        }
+                        9 => {
+                            r.u64()?;
+                            r.u64()?;
+                            Modifier::Ignored
+                        }
+                        10 => {
+                            let thread = r.u64()?;
+                            let size = r.u32()?;
+                            let depth = r.u32()?;
+                            let (_, id) = self.jdwp_thread(thread)?;
+                            let frames = self.jdwp_frames(agent, id);
+                            let from = match frames.first() {
+                                Some((depth, position)) => (*depth, position.line()),
+                                None => (0, None),
+                            };
+                            Modifier::Step {
+                                thread,
+                                size,
+                                depth,
+                                from,
+                            }
+                        }
+                        11 => {
+                            r.u64()?;
+                            Modifier::Ignored
+                        }
+                        12 => Modifier::SourceNameMatch(r.string()?),
+                        _ => return Err(ILLEGAL_ARGUMENT),
+                    });
+                }
+                agent.next_request += 1;
+                let id = agent.next_request;
+                agent.requests.push(Request {
+                    id,
+                    kind,
+                    suspend,
+                    modifiers,
+                    expired: false,
+                });
+                w.u32(id);
+            }
+            (15, 2) => {
+                let kind = r.u8()?;
+                let id = r.u32()?;
+                agent
+                    .requests
+                    .retain(|request| request.kind != kind || request.id != id);
+            }
+            (15, 3) => agent.requests.retain(|request| request.kind != BREAKPOINT),
+
+            // StackFrame
+            (16, command @ (1 | 3)) => {
+                let (_, thread) = self.jdwp_thread(r.u64()?)?;
+                let frame = r.u64()?;
+                let depth = (frame & 0xFFFF_FFFF) as usize;
+                if frame >> 32 != thread as u64 {
+                    return Err(INVALID_FRAMEID);
+                }
+                let frames = self.thread_frames(thread).ok_or(INVALID_THREAD)?;
+                let locals = frames
+                    .get(depth.wrapping_sub(1))
+                    .ok_or(INVALID_FRAMEID)?
+                    .locals
+                    .clone();
+                if command == 3 {
+                    let position = agent
+                        .positions
+                        .get(&thread)
+                        .and_then(|positions| positions.get(depth - 1)?.clone())
+                        .ok_or(INVALID_FRAMEID)?;
+                    let method = &position.class.methods[position.method];
+                    let is_static = method
+                        .access_flags
+                        .contains(crate::class::method::MethodAccessFlags::STATIC);
+                    let this = match is_static {
+                        true => Value::Null,
+                        false => locals[0],
+                    };
+                    self.jdwp_value(&mut w, this, b'L', true);
+                    return Ok(w);
+                }
+                let count = r.u32()?;
+                w.u32(count);
+                for _ in 0..count {
+                    let slot = r.u32()? as usize;
+                    let descriptor = r.u8()?;
+                    let value = *locals.get(slot).ok_or(INVALID_SLOT)?;
+                    self.jdwp_value(&mut w, value, descriptor, true);
+                }
+            }
+            _ => return Err(NOT_IMPLEMENTED),
+        }
+        Ok(w)
+    }
+
+    /// arguments of an invoke command
+    fn jdwp_arguments(&self, r: &mut Reader) -> Result<Vec<Value>, u16> {
+        let count = r.u32()?;
+        let mut args = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            args.push(match r.value()? {
+                (b'Z' | b'B' | b'I', bits) => Value::Int(bits as i32),
+                (b'C', bits) => Value::Int(bits as u16 as i32),
+                (b'S', bits) => Value::Int(bits as u16 as i16 as i32),
+                (b'J', bits) => Value::Long(bits as i64),
+                (b'F', bits) => Value::Float(f32::from_bits(bits as u32)),
+                (b'D', bits) => Value::Double(f64::from_bits(bits)),
+                (b'V', _) => return Err(TYPE_MISMATCH),
+                (_, id) => self.jdwp_object(id)?,
+            });
+        }
+        Ok(args)
+    }
+
+    /// runs method `index` of `class` for the debugger, which gets the result or the exception
+    /// thrown. Events are not reported while it runs
+    fn jdwp_invoke(
+        &mut self,
+        w: &mut Writer,
+        class: &Rc<Class>,
+        index: usize,
+        args: Vec<Value>,
+        virtual_call: bool,
+    ) -> Result<(), u16> {
+        let method = class.methods.get(index).ok_or(INVALID_METHODID)?;
+        let result = match virtual_call {
+            true => self.invoke_virtual(
+                &class.this_class_name,
+                &method.name,
+                &method.descriptor,
+                args,
+            ),
+            false => self.invoke(class, method, args),
+        };
+        let returns = method
+            .descriptor
+            .rsplit(')')
+            .next()
+            .unwrap_or("V")
+            .as_bytes()[0];
+        match result {
+            Ok(value) => {
+                match value {
+                    Some(value) => self.jdwp_value(w, value, returns, true),
+                    None => {
+                        w.u8(b'V');
+                    }
+                }
+                w.u8(b'L').u64(0);
+            }
+            Err(err) => match err.downcast_ref::<Throw>() {
+                Some(&Throw(exception)) => {
+                    w.u8(b'V').u8(b'L').u64(object_id(exception));
+                }
+                None => return Err(INTERNAL),
+            },
+        }
+        Ok(())
+    }
+
+    /// commands of the ReferenceType set which need the class
+    fn jdwp_reference_type(
+        &mut self,
+        agent: &mut Jdwp,
+        r: &mut Reader,
+        w: &mut Writer,
+        command: u8,
+        type_id: u64,
+        class: &Class,
+    ) -> Result<(), u16> {
+        match command {
+            3 => {
+                w.u32(class.access_flags.bits() as u32);
+            }
+            4 | 14 => {
+                w.u32(class.fields.len() as u32);
+                for (i, field) in class.fields.iter().enumerate() {
+                    w.u64(method_id(type_id, i))
+                        .string(&field.name)
+                        .string(&field.descriptor);
+                    if command == 14 {
+                        w.string("");
+                    }
+                    w.u32(field.access_flags.bits() as u32);
+                }
+            }
+            5 | 15 => {
+                w.u32(class.methods.len() as u32);
+                for (i, method) in class.methods.iter().enumerate() {
+                    w.u64(method_id(type_id, i))
+                        .string(&method.name)
+                        .string(&method.descriptor);
+                    if command == 15 {
+                        w.string("");
+                    }
+                    w.u32(method.access_flags.bits() as u32);
+                }
+            }
+            6 => {
+                let count = r.u32()?;
+                w.u32(count);
+                for _ in 0..count {
+                    // the field may be declared in a superclass
+                    let (class, index) = self.jdwp_member(agent, r.u64()?, INVALID_FIELDID)?;
+                    let field = class.fields.get(index).ok_or(INVALID_FIELDID)?;
+                    let key = (class.this_class_name.clone(), field.name.clone());
+                    let value = match self.static_slots.get(&key) {
+                        Some(&slot) => self.statics[slot],
+                        None => Value::default_for(&field.descriptor),
+                    };
+                    self.jdwp_value(w, value, field.descriptor.as_bytes()[0], true);
+                }
+            }
+            7 => {
+                w.string(class.source_file().ok_or(ABSENT_INFORMATION)?);
+            }
+            10 => {
+                let interfaces: Vec<_> = class.interface_names().map(str::to_string).collect();
+                w.u32(interfaces.len() as u32);
+                for interface in interfaces {
+                    w.u64(agent.type_id(&interface));
+                }
+            }
+            12 => return Err(ABSENT_INFORMATION),
+            _ => return Err(NOT_IMPLEMENTED),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,711 @@
+use std::cmp::Ordering;
+
+use crate::{
+    class::field::FieldAccessFlags,
+    vm::{heap::Object, value::Value, Vm},
+};
+
+use super::ClassBuilder;
+
+/// `(primitive descriptor, wrapper class)`
+const WRAPPERS: &[(&str, &str)] = &[
+    ("Z", "java/lang/Boolean"),
+    ("C", "java/lang/Character"),
+    ("B", "java/lang/Byte"),
+    ("S", "java/lang/Short"),
+    ("I", "java/lang/Integer"),
+    ("J", "java/lang/Long"),
+    ("F", "java/lang/Float"),
+    ("D", "java/lang/Double"),
+];
+
+pub fn wrapper_class(primitive: &str) -> Option<&'static str> {
+    WRAPPERS
+        .iter()
+        .find(|(p, _)| *p == primitive)
+        .map(|(_, class)| *class)
+}
+
+/// `java/lang/Integer` -> `I`, `None` for classes other than the wrappers
+pub(super) fn wrapped_primitive(class: &str) -> Option<&'static str> {
+    WRAPPERS
+        .iter()
+        .find(|(_, c)| *c == class)
+        .map(|(primitive, _)| *primitive)
+}
+
+/// primitive conversion as done by the `x2y` instructions, `B`, `S` and `C` are stored as ints
+fn convert(value: Value, to: &str) -> Value {
+    let (int, long, float, double) = match value {
+        Value::Int(v) => (v, v as i64, v as f32, v as f64),
+        Value::Long(v) => (v as i32, v, v as f32, v as f64),
+        Value::Float(v) => (v as i32, v as i64, v, v as f64),
+        Value::Double(v) => (v as i32, v as i64, v as f32, v),
+        v => panic!("expected primitive, got {v:?}"),
+    };
+    match to {
+        "J" => Value::Long(long),
+        "F" => Value::Float(float),
+        "D" => Value::Double(double),
+        "B" => Value::Int(int as i8 as i32),
+        "S" => Value::Int(int as i16 as i32),
+        "C" => Value::Int(int as u16 as i32),
+        _ => Value::Int(int),
+    }
+}
+
+impl Vm {
+    /// `Integer.valueOf` and friends. Values in the range java caches are returned
+    /// as the same object each time
+    pub(crate) fn box_value(&mut self, value: Value, primitive: &str) -> anyhow::Result<Value> {
+        let class = wrapper_class(primitive).unwrap();
+        if self.jdk {
+            // the JDK's wrapper classes keep their own caches
+            let descriptor = format!("({primitive})L{class};");
+            let boxed = self.invoke_static(class, "valueOf", &descriptor, [value])?;
+            return Ok(boxed.unwrap());
+        }
+        let cached = match (primitive, value) {
+            ("Z" | "B", Value::Int(v)) => Some(v + 128),
+            ("S" | "I", Value::Int(v)) if (-128..128).contains(&v) => Some(v + 128),
+            ("C", Value::Int(v)) if v < 128 => Some(v),
+            ("J", Value::Long(v)) if (-128..128).contains(&v) => Some(v as i32 + 128),
+            _ => None,
+        };
+        let Some(index) = cached else {
+            return self.new_box(class, value);
+        };
+        let cache = match self.get_static(class, "cache", "[Ljava/lang/Object;")? {
+            Value::Null => {
+                let cache = self.heap.new_array("[Ljava/lang/Object;", 256);
+                self.put_static(class, "cache", cache)?;
+                cache
+            }
+            cache => cache,
+        };
+        let cache = cache.as_ref().unwrap();
+        match self.heap.get_array(cache).elements[index as usize] {
+            Value::Null => {
+                let boxed = self.new_box(class, value)?;
+                self.heap.get_array_mut(cache).elements[index as usize] = boxed;
+                Ok(boxed)
+            }
+            boxed => Ok(boxed),
+        }
+    }
+
+    fn new_box(&mut self, class: &str, value: Value) -> anyhow::Result<Value> {
+        let boxed = self.new_object(class)?;
+        let instance = self.heap.get_instance_mut(boxed.as_ref().unwrap());
+        instance.set("value", value);
+        Ok(boxed)
+    }
+
+    /// primitive inside a wrapper object, `None` for anything else
+    pub(crate) fn unbox(&self, value: Value) -> Option<Value> {
+        match self.heap.get(value.as_ref()?) {
+            Object::Instance(i) if WRAPPERS.iter().any(|(_, c)| *c == i.class) => i.get("value"),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_boolean(&self, value: Value) -> bool {
+        value
+            .as_ref()
+            .is_some_and(|r| self.heap.class_name(r) == "java/lang/Boolean")
+    }
+
+    /// converts between the primitive and reference form of a value, as needed
+    /// when a method reference is called through an erased interface method
+    pub(crate) fn adapt(&mut self, value: Value, from: &str, to: &str) -> anyhow::Result<Value> {
+        let is_primitive = |d: &str| !d.starts_with(['L', '[']);
+        match (is_primitive(from), is_primitive(to)) {
+            (true, false) => self.box_value(value, from),
+            (false, true) => match self.unbox(value) {
+                Some(primitive) => Ok(convert(primitive, to)),
+                None if value == Value::Null => Err(self.null_pointer()),
+                None => Ok(value),
+            },
+            (true, true) if from != to => Ok(convert(value, to)),
+            _ => Ok(value),
+        }
+    }
+}
+
+fn value(vm: &Vm, this: Value) -> Value {
+    vm.heap
+        .get_instance(this.as_ref().unwrap())
+        .get("value")
+        .unwrap()
+}
+
+pub(super) fn hash_code(value: Value, primitive: &str) -> i32 {
+    match (value, primitive) {
+        (Value::Int(v), "Z") => [1237, 1231][v as usize],
+        (Value::Int(v), _) => v,
+        (Value::Long(v), _) => (v ^ (v >> 32)) as i32,
+        (Value::Float(v), _) => canonical_float(v) as i32,
+        (Value::Double(v), _) => {
+            let bits = canonical_double(v);
+            (bits ^ (bits >> 32)) as i32
+        }
+        (v, _) => panic!("expected primitive, got {v:?}"),
+    }
+}
+
+/// `Float.floatToIntBits`, which collapses all NaNs into one
+fn canonical_float(v: f32) -> u32 {
+    if v.is_nan() {
+        0x7fc0_0000
+    } else {
+        v.to_bits()
+    }
+}
+
+fn canonical_double(v: f64) -> u64 {
+    if v.is_nan() {
+        0x7ff8_0000_0000_0000
+    } else {
+        v.to_bits()
+    }
+}
+
+/// `Integer.compare`, `Double.compare`, ...: NaN is greater than everything and -0.0 < 0.0
+pub(super) fn compare(a: Value, b: Value) -> i32 {
+    let ordering = match (a, b) {
+        (Value::Int(a), Value::Int(b)) => a.cmp(&b),
+        (Value::Long(a), Value::Long(b)) => a.cmp(&b),
+        (Value::Float(a), Value::Float(b)) => {
+            f32::from_bits(canonical_float(a)).total_cmp(&f32::from_bits(canonical_float(b)))
+        }
+        (Value::Double(a), Value::Double(b)) => {
+            f64::from_bits(canonical_double(a)).total_cmp(&f64::from_bits(canonical_double(b)))
+        }
+        (a, b) => panic!("cannot compare {a:?} and {b:?}"),
+    };
+    match ordering {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+fn number_format(vm: &mut Vm, input: &str, radix: u32) -> anyhow::Error {
+    let message = match radix {
+        10 => format!("For input string: \"{input}\""),
+        _ => format!("For input string: \"{input}\" under radix {radix}"),
+    };
+    vm.exception("java/lang/NumberFormatException", &message)
+}
+
+/// `parseInt`, `parseLong`, ... for the given primitive type
+fn parse(vm: &mut Vm, s: Value, primitive: &str, radix: u32) -> anyhow::Result<Value> {
+    if s == Value::Null && primitive == "Z" {
+        return Ok(Value::Int(0));
+    }
+    if s == Value::Null {
+        let message = "Cannot parse null string: null";
+        return Err(vm.exception("java/lang/NumberFormatException", message));
+    }
+    let input = vm.string_value(s)?;
+    let parsed = match primitive {
+        "Z" => Some(Value::Int(input.eq_ignore_ascii_case("true") as i32)),
+        "B" => i8::from_str_radix(&input, radix)
+            .ok()
+            .map(|v| Value::Int(v as i32)),
+        "S" => i16::from_str_radix(&input, radix)
+            .ok()
+            .map(|v| Value::Int(v as i32)),
+        "I" => i32::from_str_radix(&input, radix).ok().map(Value::Int),
+        "J" => i64::from_str_radix(&input, radix).ok().map(Value::Long),
+        "F" => parse_float(&input).map(|v| Value::Float(v as f32)),
+        "D" => parse_float(&input).map(Value::Double),
+        _ => None,
+    };
+    match parsed {
+        Some(value) => Ok(value),
+        None if matches!(primitive, "F" | "D") => {
+            let message = match input.trim() {
+                "" => "empty String".to_string(),
+                _ => format!("For input string: \"{input}\""),
+            };
+            Err(vm.exception("java/lang/NumberFormatException", &message))
+        }
+        None => Err(number_format(vm, &input, radix)),
+    }
+}
+
+/// `Double.parseDouble` accepts surrounding whitespace, `Infinity`, `NaN` and a type suffix
+fn parse_float(input: &str) -> Option<f64> {
+    let s = input.trim();
+    let unsigned = s.trim_start_matches(['+', '-']);
+    if unsigned.eq_ignore_ascii_case("inf") || unsigned.eq_ignore_ascii_case("infinity") {
+        return (unsigned == "Infinity").then(|| s.parse().ok())?;
+    }
+    let s = s.strip_suffix(['d', 'D', 'f', 'F']).unwrap_or(s);
+    s.parse().ok()
+}
+
+fn radix_string(mut v: u64, radix: u64) -> String {
+    if v == 0 {
+        return "0".to_string();
+    }
+    let mut digits = Vec::new();
+    while v > 0 {
+        digits.push(std::char::from_digit((v % radix) as u32, radix as u32).unwrap());
+        v /= radix;
+    }
+    digits.iter().rev().collect()
+}
+
+fn to_char(c: Value) -> char {
+    char::from_u32(c.as_int() as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn bool_value(b: bool) -> Option<Value> {
+    Some(Value::Int(b as i32))
+}
+
+/// constructor, `valueOf`, `xxxValue`, `toString`, `equals`, `hashCode` and comparisons,
+/// common to all wrapper classes
+fn wrapper(primitive: &'static str, class: &'static str, super_class: &str) -> ClassBuilder {
+    let object = format!("L{class};");
+    let mut builder = ClassBuilder::new(class, super_class)
+        .implements("java/lang/Comparable")
+        .field(
+            "value",
+            primitive,
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL,
+        )
+        .field(
+            "cache",
+            "[Ljava/lang/Object;",
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC,
+        )
+        .field("TYPE", "Ljava/lang/Class;", TYPE_FLAGS)
+        .method("<init>", &format!("({primitive})V"), |vm, a| {
+            let instance = vm.heap.get_instance_mut(a[0].as_ref().unwrap());
+            instance.set("value", a[1]);
+            Ok(None)
+        })
+        .static_method(
+            "valueOf",
+            &format!("({primitive}){object}"),
+            move |vm, a| Ok(Some(vm.box_value(a[0], primitive)?)),
+        )
+        .method(
+            &format!("{}Value", primitive_name(primitive)),
+            &format!("(){primitive}"),
+            |vm, a| Ok(Some(value(vm, a[0]))),
+        )
+        .method("toString", "()Ljava/lang/String;", move |vm, a| {
+            let s = vm.java_string(value(vm, a[0]), primitive)?;
+            Ok(Some(vm.new_string(s)))
+        })
+        .static_method(
+            "toString",
+            &format!("({primitive})Ljava/lang/String;"),
+            move |vm, a| {
+                let s = vm.java_string(a[0], primitive)?;
+                Ok(Some(vm.new_string(s)))
+            },
+        )
+        .method("hashCode", "()I", move |vm, a| {
+            Ok(Some(Value::Int(hash_code(value(vm, a[0]), primitive))))
+        })
+        .static_method("hashCode", &format!("({primitive})I"), move |_, a| {
+            Ok(Some(Value::Int(hash_code(a[0], primitive))))
+        })
+        .method("equals", "(Ljava/lang/Object;)Z", move |vm, a| {
+            let equal = match a[1].as_ref() {
+                Some(r) if vm.heap.class_name(r) == class => {
+                    compare(value(vm, a[0]), value(vm, a[1])) == 0
+                }
+                _ => false,
+            };
+            Ok(bool_value(equal))
+        })
+        .static_method("compare", &format!("({primitive}{primitive})I"), |_, a| {
+            Ok(Some(Value::Int(compare(a[0], a[1]))))
+        });
+    // comparing through `Comparable` uses the erased descriptor
+    for descriptor in [format!("({object})I"), "(Ljava/lang/Object;)I".to_string()] {
+        builder = builder.method("compareTo", &descriptor, |vm, a| {
+            if a[1] == Value::Null {
+                return Err(vm.null_pointer());
+            }
+            Ok(Some(Value::Int(compare(value(vm, a[0]), value(vm, a[1])))))
+        });
+    }
+    if primitive != "C" {
+        builder = builder
+            .static_method(
+                &format!("parse{}", wrapper_name(class)),
+                &format!("(Ljava/lang/String;){primitive}"),
+                move |vm, a| Ok(Some(parse(vm, a[0], primitive, 10)?)),
+            )
+            .static_method(
+                "valueOf",
+                &format!("(Ljava/lang/String;){object}"),
+                move |vm, a| {
+                    let value = parse(vm, a[0], primitive, 10)?;
+                    Ok(Some(vm.box_value(value, primitive)?))
+                },
+            );
+    }
+    if matches!(primitive, "I" | "J") {
+        builder = builder.static_method(
+            &format!("parse{}", wrapper_name(class)),
+            &format!("(Ljava/lang/String;I){primitive}"),
+            move |vm, a| Ok(Some(parse(vm, a[0], primitive, a[1].as_int() as u32)?)),
+        );
+    }
+    builder
+}
+
+/// `I` -> `int`
+pub(super) fn primitive_name(primitive: &str) -> &'static str {
+    match primitive {
+        "Z" => "boolean",
+        "C" => "char",
+        "B" => "byte",
+        "S" => "short",
+        "I" => "int",
+        "J" => "long",
+        "F" => "float",
+        "V" => "void",
+        _ => "double",
+    }
+}
+
+/// `java/lang/Integer` -> `Int`, as used by `parseInt`
+fn wrapper_name(class: &str) -> &str {
+    match class {
+        "java/lang/Integer" => "Int",
+        _ => class.strip_prefix("java/lang/").unwrap(),
+    }
+}
+
+/// the `TYPE` field of the wrappers and `Void`, holding the `Class` of the primitive type
+const TYPE_FLAGS: FieldAccessFlags = FieldAccessFlags::PUBLIC
+    .union(FieldAccessFlags::STATIC)
+    .union(FieldAccessFlags::FINAL);
+
+fn set_type(vm: &mut Vm, class: &str, primitive: &str) -> anyhow::Result<()> {
+    let mirror = vm.class_object(primitive_name(primitive))?;
+    vm.put_static(class, "TYPE", mirror)
+}
+
+fn constants(
+    builder: ClassBuilder,
+    class: &'static str,
+    descriptor: &'static str,
+    min: Value,
+    max: Value,
+) -> ClassBuilder {
+    let flags = FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL;
+    builder
+        .field("MIN_VALUE", descriptor, flags)
+        .field("MAX_VALUE", descriptor, flags)
+        .static_method("<clinit>", "()V", move |vm, _| {
+            vm.put_static(class, "MIN_VALUE", min)?;
+            vm.put_static(class, "MAX_VALUE", max)?;
+            set_type(vm, class, descriptor)?;
+            Ok(None)
+        })
+}
+
+fn numbers(vm: &mut Vm) {
+    let mut number = ClassBuilder::new("java/lang/Number", "java/lang/Object")
+        .implements("java/io/Serializable");
+    // every wrapper of a number converts to all numeric types
+    for primitive in ["B", "S", "I", "J", "F", "D"] {
+        number = number.method(
+            &format!("{}Value", primitive_name(primitive)),
+            &format!("(){primitive}"),
+            move |vm, a| Ok(Some(convert(value(vm, a[0]), primitive))),
+        );
+    }
+    number.install(vm);
+
+    let byte = wrapper("B", "java/lang/Byte", "java/lang/Number");
+    constants(
+        byte,
+        "java/lang/Byte",
+        "B",
+        Value::Int(-128),
+        Value::Int(127),
+    )
+    .install(vm);
+    let short = wrapper("S", "java/lang/Short", "java/lang/Number");
+    let (min, max) = (Value::Int(i16::MIN as i32), Value::Int(i16::MAX as i32));
+    constants(short, "java/lang/Short", "S", min, max).install(vm);
+
+    let integer = wrapper("I", "java/lang/Integer", "java/lang/Number")
+        .static_method("toString", "(II)Ljava/lang/String;", |vm, a| {
+            let (v, radix) = (a[0].as_int(), a[1].as_int());
+            let radix = if (2..=36).contains(&radix) { radix } else { 10 };
+            let digits = radix_string(v.unsigned_abs() as u64, radix as u64);
+            let s = if v < 0 { format!("-{digits}") } else { digits };
+            Ok(Some(vm.new_string(s)))
+        })
+        .static_method("sum", "(II)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().wrapping_add(a[1].as_int()))))
+        })
+        .static_method("max", "(II)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().max(a[1].as_int()))))
+        })
+        .static_method("min", "(II)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().min(a[1].as_int()))))
+        })
+        .static_method("signum", "(I)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().signum())))
+        })
+        .static_method("bitCount", "(I)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().count_ones() as i32)))
+        })
+        .static_method("reverse", "(I)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().reverse_bits())))
+        })
+        .static_method("highestOneBit", "(I)I", |_, a| {
+            let v = a[0].as_int() as u32;
+            Ok(Some(Value::Int(if v == 0 {
+                0
+            } else {
+                (1u32 << (31 - v.leading_zeros())) as i32
+            })))
+        })
+        .static_method("numberOfLeadingZeros", "(I)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().leading_zeros() as i32)))
+        })
+        .static_method("numberOfTrailingZeros", "(I)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().trailing_zeros() as i32)))
+        });
+    let mut integer = constants(
+        integer,
+        "java/lang/Integer",
+        "I",
+        Value::Int(i32::MIN),
+        Value::Int(i32::MAX),
+    );
+    for (name, radix) in [
+        ("toBinaryString", 2),
+        ("toOctalString", 8),
+        ("toHexString", 16),
+    ] {
+        integer = integer.static_method(name, "(I)Ljava/lang/String;", move |vm, a| {
+            let s = radix_string(a[0].as_int() as u32 as u64, radix);
+            Ok(Some(vm.new_string(s)))
+        });
+    }
+    integer.install(vm);
+
+    let long = wrapper("J", "java/lang/Long", "java/lang/Number")
+        .static_method("sum", "(JJ)J", |_, a| {
+            Ok(Some(Value::Long(
+                a[0].as_long().wrapping_add(a[1].as_long()),
+            )))
+        })
+        .static_method("max", "(JJ)J", |_, a| {
+            Ok(Some(Value::Long(a[0].as_long().max(a[1].as_long()))))
+        })
+        .static_method("min", "(JJ)J", |_, a| {
+            Ok(Some(Value::Long(a[0].as_long().min(a[1].as_long()))))
+        })
+        .static_method("signum", "(J)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_long().signum() as i32)))
+        })
+        .static_method("bitCount", "(J)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_long().count_ones() as i32)))
+        })
+        .static_method("numberOfLeadingZeros", "(J)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_long().leading_zeros() as i32)))
+        })
+        .static_method("numberOfTrailingZeros", "(J)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_long().trailing_zeros() as i32)))
+        });
+    let mut long = constants(
+        long,
+        "java/lang/Long",
+        "J",
+        Value::Long(i64::MIN),
+        Value::Long(i64::MAX),
+    );
+    for (name, radix) in [
+        ("toBinaryString", 2),
+        ("toOctalString", 8),
+        ("toHexString", 16),
+    ] {
+        long = long.static_method(name, "(J)Ljava/lang/String;", move |vm, a| {
+            let s = radix_string(a[0].as_long() as u64, radix);
+            Ok(Some(vm.new_string(s)))
+        });
+    }
+    long.install(vm);
+
+    let float = wrapper("F", "java/lang/Float", "java/lang/Number")
+        .static_method("isNaN", "(F)Z", |_, a| {
+            Ok(bool_value(a[0].as_float().is_nan()))
+        })
+        .static_method("isInfinite", "(F)Z", |_, a| {
+            Ok(bool_value(a[0].as_float().is_infinite()))
+        })
+        .method("isNaN", "()Z", |vm, a| {
+            Ok(bool_value(value(vm, a[0]).as_float().is_nan()))
+        })
+        .static_method("floatToIntBits", "(F)I", |_, a| {
+            Ok(Some(Value::Int(canonical_float(a[0].as_float()) as i32)))
+        })
+        .static_method("intBitsToFloat", "(I)F", |_, a| {
+            Ok(Some(Value::Float(f32::from_bits(a[0].as_int() as u32))))
+        })
+        .static_method("sum", "(FF)F", |_, a| {
+            Ok(Some(Value::Float(a[0].as_float() + a[1].as_float())))
+        });
+    constants(
+        float,
+        "java/lang/Float",
+        "F",
+        Value::Float(f32::from_bits(1)),
+        Value::Float(f32::MAX),
+    )
+    .install(vm);
+
+    let double = wrapper("D", "java/lang/Double", "java/lang/Number")
+        .static_method("isNaN", "(D)Z", |_, a| {
+            Ok(bool_value(a[0].as_double().is_nan()))
+        })
+        .static_method("isInfinite", "(D)Z", |_, a| {
+            Ok(bool_value(a[0].as_double().is_infinite()))
+        })
+        .static_method("isFinite", "(D)Z", |_, a| {
+            Ok(bool_value(a[0].as_double().is_finite()))
+        })
+        .method("isNaN", "()Z", |vm, a| {
+            Ok(bool_value(value(vm, a[0]).as_double().is_nan()))
+        })
+        .static_method("doubleToLongBits", "(D)J", |_, a| {
+            Ok(Some(Value::Long(canonical_double(a[0].as_double()) as i64)))
+        })
+        .static_method("longBitsToDouble", "(J)D", |_, a| {
+            Ok(Some(Value::Double(f64::from_bits(a[0].as_long() as u64))))
+        })
+        .static_method("sum", "(DD)D", |_, a| {
+            Ok(Some(Value::Double(a[0].as_double() + a[1].as_double())))
+        })
+        .static_method("max", "(DD)D", |_, a| {
+            Ok(Some(Value::Double(a[0].as_double().max(a[1].as_double()))))
+        });
+    constants(
+        double,
+        "java/lang/Double",
+        "D",
+        Value::Double(f64::from_bits(1)),
+        Value::Double(f64::MAX),
+    )
+    .install(vm);
+}
+
+type CharPredicate = fn(char) -> bool;
+type CharMapping = fn(char) -> char;
+
+fn character(vm: &mut Vm) {
+    let mut character = wrapper("C", "java/lang/Character", "java/lang/Object").static_method(
+        "<clinit>",
+        "()V",
+        |vm, _| {
+            set_type(vm, "java/lang/Character", "C")?;
+            Ok(None)
+        },
+    );
+    let predicates: [(&str, CharPredicate); 8] = [
+        ("isDigit", |c| c.is_numeric()),
+        ("isLetter", char::is_alphabetic),
+        ("isLetterOrDigit", char::is_alphanumeric),
+        ("isAlphabetic", char::is_alphabetic),
+        ("isWhitespace", |c| c.is_whitespace() && c != '\u{a0}'),
+        ("isSpaceChar", |c| c == ' ' || c == '\u{a0}'),
+        ("isUpperCase", char::is_uppercase),
+        ("isLowerCase", char::is_lowercase),
+    ];
+    for (name, predicate) in predicates {
+        let descriptor = if name == "isAlphabetic" {
+            "(I)Z"
+        } else {
+            "(C)Z"
+        };
+        character = character.static_method(name, descriptor, move |_, a| {
+            Ok(bool_value(predicate(to_char(a[0]))))
+        });
+    }
+    let mappings: [(&str, CharMapping); 2] = [
+        ("toUpperCase", |c| c.to_uppercase().next().unwrap()),
+        ("toLowerCase", |c| c.to_lowercase().next().unwrap()),
+    ];
+    for (name, mapping) in mappings {
+        character = character.static_method(name, "(C)C", move |_, a| {
+            let c = mapping(to_char(a[0]));
+            Ok(Some(Value::Int(if (c as u32) < 0x10000 {
+                c as i32
+            } else {
+                a[0].as_int()
+            })))
+        });
+    }
+    character
+        .static_method("getNumericValue", "(C)I", |_, a| {
+            let digit = to_char(a[0]).to_digit(36);
+            Ok(Some(Value::Int(digit.map_or(-1, |d| d as i32))))
+        })
+        .static_method("digit", "(CI)I", |_, a| {
+            let radix = a[1].as_int();
+            let digit = (2..=36)
+                .contains(&radix)
+                .then(|| to_char(a[0]).to_digit(radix as u32))
+                .flatten();
+            Ok(Some(Value::Int(digit.map_or(-1, |d| d as i32))))
+        })
+        .static_method("forDigit", "(II)C", |_, a| {
+            let digit = std::char::from_digit(a[0].as_int() as u32, a[1].as_int() as u32);
+            Ok(Some(Value::Int(digit.map_or(0, |d| d as i32))))
+        })
+        .install(vm);
+}
+
+fn boolean(vm: &mut Vm) {
+    let flags = FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL;
+    wrapper("Z", "java/lang/Boolean", "java/lang/Object")
+        .field("TRUE", "Ljava/lang/Boolean;", flags)
+        .field("FALSE", "Ljava/lang/Boolean;", flags)
+        .static_method("<clinit>", "()V", |vm, _| {
+            for (name, value) in [("FALSE", 0), ("TRUE", 1)] {
+                let boxed = vm.box_value(Value::Int(value), "Z")?;
+                vm.put_static("java/lang/Boolean", name, boxed)?;
+            }
+            set_type(vm, "java/lang/Boolean", "Z")?;
+            Ok(None)
+        })
+        .static_method("logicalAnd", "(ZZ)Z", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int() & a[1].as_int())))
+        })
+        .static_method("logicalOr", "(ZZ)Z", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int() | a[1].as_int())))
+        })
+        .static_method("logicalXor", "(ZZ)Z", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int() ^ a[1].as_int())))
+        })
+        .install(vm);
+}
+
+pub fn install(vm: &mut Vm) {
+    numbers(vm);
+    character(vm);
+    boolean(vm);
+    ClassBuilder::new("java/lang/Void", "java/lang/Object")
+        .field("TYPE", "Ljava/lang/Class;", TYPE_FLAGS)
+        .static_method("<clinit>", "()V", |vm, _| {
+            set_type(vm, "java/lang/Void", "V")?;
+            Ok(None)
+        })
+        .install(vm);
+}
@@ -0,0 +1,72 @@
+use std::io::Write;
+
+use crate::{
+    class::field::FieldAccessFlags,
+    vm::{value::Value, Vm},
+};
+
+use super::ClassBuilder;
+
+/// writes to stdout or stderr depending on the `fd` field of the stream
+fn write(vm: &mut Vm, this: Value, text: &str) -> anyhow::Result<()> {
+    let instance = vm.heap.get_instance(this.as_ref().unwrap());
+    match instance.get("fd") {
+        Some(Value::Int(2)) => std::io::stderr().write_all(text.as_bytes())?,
+        _ => std::io::stdout().write_all(text.as_bytes())?,
+    }
+    Ok(())
+}
+
+pub fn install(vm: &mut Vm) {
+    let mut stream = ClassBuilder::new("java/io/PrintStream", "java/lang/Object")
+        .implements("java/lang/AutoCloseable")
+        .field("fd", "I", FieldAccessFlags::PRIVATE)
+        .method("println", "()V", |vm, a| {
+            write(vm, a[0], "\n")?;
+            Ok(None)
+        })
+        .method("flush", "()V", |_, _| {
+            std::io::stdout().flush()?;
+            Ok(None)
+        })
+        .method("write", "(I)V", |vm, a| {
+            let byte = [a[1].as_int() as u8];
+            write(vm, a[0], &String::from_utf8_lossy(&byte))?;
+            Ok(None)
+        })
+        .method(
+            "printf",
+            "(Ljava/lang/String;[Ljava/lang/Object;)Ljava/io/PrintStream;",
+            |vm, a| {
+                let format = vm.string_value(a[1])?;
+                let args = vm.heap.get_array(a[2].as_ref().unwrap()).elements.clone();
+                let text = vm.format(&format, &args)?;
+                write(vm, a[0], &text)?;
+                Ok(Some(a[0]))
+            },
+        );
+    for descriptor in [
+        "Z",
+        "C",
+        "I",
+        "J",
+        "F",
+        "D",
+        "[C",
+        "Ljava/lang/String;",
+        "Ljava/lang/Object;",
+    ] {
+        stream = stream
+            .method("print", &format!("({descriptor})V"), move |vm, a| {
+                let text = vm.java_string(a[1], descriptor)?;
+                write(vm, a[0], &text)?;
+                Ok(None)
+            })
+            .method("println", &format!("({descriptor})V"), move |vm, a| {
+                let text = vm.java_string(a[1], descriptor)? + "\n";
+                write(vm, a[0], &text)?;
+                Ok(None)
+            });
+    }
+    stream.install(vm);
+}
@@ -0,0 +1,821 @@
+//! Natives needed by the class library of a real JDK, which is loaded from its `lib/modules`
+//! image, its jmods or an extracted `java.base`. Most are stubs which answer the way a
+//! freshly started single threaded vm without a security manager would
+
+use std::{
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+
+use crate::{
+    class::ClassAccessFlags,
+    vm::{value::Value, Vm},
+};
+
+use super::{
+    lang::{array_copy, clone, get_class, identity_hash, notify, wait},
+    reflect::{
+        bool, class_modifiers, component, construct, declared_fields, declared_methods, for_name,
+        has_flag, invoke, super_class, PRIMITIVES,
+    },
+};
+
+type StrictMathFn = fn(f64) -> f64;
+
+fn field(vm: &Vm, this: Value, name: &str) -> Value {
+    let instance = vm.heap.get_instance(this.as_ref().unwrap());
+    instance
+        .get(name)
+        .unwrap_or_else(|| panic!("no field {name} in {}", instance.class))
+}
+
+fn set_field(vm: &mut Vm, this: Value, name: &str, value: Value) {
+    let instance = vm.heap.get_instance_mut(this.as_ref().unwrap());
+    instance.set(name, value);
+}
+
+/// the path of a `java.io.File`
+fn file_path(vm: &mut Vm, file: Value) -> anyhow::Result<String> {
+    if file == Value::Null {
+        return Err(vm.null_pointer());
+    }
+    let path = field(vm, file, "path");
+    vm.string_value(path)
+}
+
+/// values of `jdk.internal.util.SystemProps$Raw.platformProperties`, by the name of the
+/// static field holding their index
+pub(super) fn platform_properties() -> Vec<(&'static str, String)> {
+    let env = |name: &str, default: &str| std::env::var(name).unwrap_or(default.to_string());
+    let user_dir = std::env::current_dir()
+        .map(|d| d.display().to_string())
+        .unwrap_or_default();
+    vec![
+        ("_file_encoding_NDX", "UTF-8".to_string()),
+        ("_file_separator_NDX", "/".to_string()),
+        ("_java_io_tmpdir_NDX", "/tmp".to_string()),
+        ("_line_separator_NDX", "\n".to_string()),
+        ("_os_arch_NDX", std::env::consts::ARCH.to_string()),
+        ("_os_name_NDX", "Linux".to_string()),
+        ("_os_version_NDX", String::new()),
+        ("_path_separator_NDX", ":".to_string()),
+        ("_sun_arch_data_model_NDX", "64".to_string()),
+        ("_sun_cpu_endian_NDX", "little".to_string()),
+        ("_sun_io_unicode_encoding_NDX", "UnicodeLittle".to_string()),
+        ("_sun_jnu_encoding_NDX", "UTF-8".to_string()),
+        ("_user_dir_NDX", user_dir),
+        ("_user_home_NDX", env("HOME", "?")),
+        ("_user_name_NDX", env("USER", "?")),
+    ]
+}
+
+/// host of the nest the class `name` belongs to; arrays and primitives host their own
+fn nest_host(vm: &mut Vm, name: &str) -> anyhow::Result<String> {
+    match vm.load_class(name)? {
+        Some(class) if !name.starts_with('[') => vm.nest_host(&class),
+        _ => Ok(name.to_string()),
+    }
+}
+
+pub fn install(vm: &mut Vm, home: &Path) {
+    for class in [
+        "java/lang/Object",
+        "java/lang/System",
+        "java/lang/Class",
+        "java/lang/ClassLoader",
+        "java/lang/Thread",
+        "java/lang/invoke/MethodHandleNatives",
+        "jdk/internal/misc/Unsafe",
+        "jdk/internal/misc/ScopedMemoryAccess",
+    ] {
+        vm.register_native(class, "registerNatives", "()V", |_, _| Ok(None));
+    }
+    for class in [
+        "java/io/FileDescriptor",
+        "java/io/FileInputStream",
+        "java/io/FileOutputStream",
+        "java/io/RandomAccessFile",
+        "java/io/UnixFileSystem",
+    ] {
+        vm.register_native(class, "initIDs", "()V", |_, _| Ok(None));
+    }
+
+    super::misc::install(vm);
+
+    vm.register_native("java/lang/Object", "hashCode", "()I", |_, a| {
+        Ok(Some(Value::Int(identity_hash(a[0].as_ref().unwrap()))))
+    });
+    vm.register_native(
+        "java/lang/Object",
+        "clone",
+        "()Ljava/lang/Object;",
+        |vm, a| Ok(Some(clone(vm, a[0].as_ref().unwrap())?)),
+    );
+    vm.register_native(
+        "java/lang/Object",
+        "getClass",
+        "()Ljava/lang/Class;",
+        get_class,
+    );
+    vm.register_native("java/lang/Object", "wait", "(J)V", |vm, a| {
+        wait(vm, a[0], a[1].as_long())
+    });
+    vm.register_native("java/lang/Object", "notify", "()V", |vm, a| {
+        notify(vm, a[0], false)
+    });
+    vm.register_native("java/lang/Object", "notifyAll", "()V", |vm, a| {
+        notify(vm, a[0], true)
+    });
+
+    vm.register_native(
+        "java/lang/System",
+        "arraycopy",
+        "(Ljava/lang/Object;ILjava/lang/Object;II)V",
+        array_copy,
+    );
+    vm.register_native("java/lang/System", "currentTimeMillis", "()J", |_, _| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        Ok(Some(Value::Long(now.as_millis() as i64)))
+    });
+    vm.register_native("java/lang/System", "nanoTime", "()J", |_, _| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        Ok(Some(Value::Long(now.as_nanos() as i64)))
+    });
+    vm.register_native(
+        "java/lang/System",
+        "identityHashCode",
+        "(Ljava/lang/Object;)I",
+        |_, a| Ok(Some(Value::Int(a[0].as_ref().map_or(0, identity_hash)))),
+    );
+    for (name, field, descriptor) in [
+        ("setIn0", "in", "(Ljava/io/InputStream;)V"),
+        ("setOut0", "out", "(Ljava/io/PrintStream;)V"),
+        ("setErr0", "err", "(Ljava/io/PrintStream;)V"),
+    ] {
+        vm.register_native("java/lang/System", name, descriptor, move |vm, a| {
+            vm.put_static("java/lang/System", field, a[0])?;
+            Ok(None)
+        });
+    }
+
+    vm.register_native(
+        "java/lang/Thread",
+        "currentThread",
+        "()Ljava/lang/Thread;",
+        |vm, _| Ok(Some(vm.thread)),
+    );
+    vm.register_native("java/lang/Thread", "setPriority0", "(I)V", |_, _| Ok(None));
+    vm.register_native("java/lang/Thread", "isAlive", "()Z", |vm, a| {
+        Ok(Some(Value::Int(vm.is_alive(a[0]) as i32)))
+    });
+    vm.register_native(
+        "java/lang/Thread",
+        "holdsLock",
+        "(Ljava/lang/Object;)Z",
+        |vm, a| {
+            let lock = vm.object_lock(a[0])?;
+            Ok(Some(Value::Int(vm.holds_lock(&lock) as i32)))
+        },
+    );
+    vm.register_native("java/lang/Thread", "start0", "()V", |vm, a| {
+        vm.start_thread(a[0])?;
+        Ok(None)
+    });
+    vm.register_native("java/lang/Thread", "sleep", "(J)V", |vm, a| {
+        vm.sleep(a[0].as_long())?;
+        Ok(None)
+    });
+    vm.register_native("java/lang/Thread", "yield", "()V", |vm, _| {
+        vm.yield_now();
+        Ok(None)
+    });
+    // `Thread.interrupt` sets the field itself and only has to wake the thread up
+    vm.register_native("java/lang/Thread", "interrupt0", "()V", |vm, a| {
+        vm.interrupt(a[0]);
+        Ok(None)
+    });
+    vm.register_native(
+        "java/lang/Thread",
+        "setNativeName",
+        "(Ljava/lang/String;)V",
+        |_, _| Ok(None),
+    );
+    // references are strong, the collector does not clear or enqueue them
+    for class in ["java/lang/ref/Reference", "java/lang/ref/PhantomReference"] {
+        vm.register_native(class, "refersTo0", "(Ljava/lang/Object;)Z", |vm, a| {
+            bool(field(vm, a[0], "referent") == a[1])
+        });
+    }
+    vm.register_native("java/lang/ref/Reference", "clear0", "()V", |vm, a| {
+        set_field(vm, a[0], "referent", Value::Null);
+        Ok(None)
+    });
+    vm.register_native(
+        "java/lang/ref/Reference",
+        "hasReferencePendingList",
+        "()Z",
+        |_, _| bool(false),
+    );
+    vm.register_native(
+        "java/lang/ref/Reference",
+        "getAndClearReferencePendingList",
+        "()Ljava/lang/ref/Reference;",
+        |_, _| Ok(Some(Value::Null)),
+    );
+    vm.register_native(
+        "java/lang/ref/Reference",
+        "waitForReferencePendingList",
+        "()V",
+        |vm, _| loop {
+            vm.park_thread(false, 0)?;
+        },
+    );
+
+    vm.register_native(
+        "java/lang/Class",
+        "getPrimitiveClass",
+        "(Ljava/lang/String;)Ljava/lang/Class;",
+        |vm, a| {
+            let name = vm.string_value(a[0])?;
+            Ok(Some(vm.class_object(&name)?))
+        },
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "forName0",
+        "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
+        |vm, a| for_name(vm, a[0], a[1].as_int() != 0),
+    );
+    vm.register_native("java/lang/Class", "isPrimitive", "()Z", |vm, a| {
+        let class = vm.mirrored_class(a[0])?;
+        bool(PRIMITIVES.contains(&class.as_str()))
+    });
+    vm.register_native("java/lang/Class", "isArray", "()Z", |vm, a| {
+        bool(vm.mirrored_class(a[0])?.starts_with('['))
+    });
+    vm.register_native("java/lang/Class", "isInterface", "()Z", |vm, a| {
+        let class = vm.mirrored_class(a[0])?;
+        bool(has_flag(vm, &class, ClassAccessFlags::INTERFACE)?)
+    });
+    vm.register_native("java/lang/Class", "isHidden", "()Z", |_, _| bool(false));
+    vm.register_native("java/lang/Class", "isRecord0", "()Z", |vm, a| {
+        let class = vm.mirrored_class(a[0])?;
+        let c = vm.load_class(&class)?;
+        bool(c.is_some_and(|c| c.super_class_name == "java/lang/Record"))
+    });
+    vm.register_native(
+        "java/lang/Class",
+        "isInstance",
+        "(Ljava/lang/Object;)Z",
+        |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let Some(object) = a[1].as_ref() else {
+                return bool(false);
+            };
+            let object_class = vm.heap.class_name(object).to_string();
+            bool(vm.is_assignable(&object_class, &class)?)
+        },
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "isAssignableFrom",
+        "(Ljava/lang/Class;)Z",
+        |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let from = vm.mirrored_class(a[1])?;
+            bool(vm.is_assignable(&from, &class)?)
+        },
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "initClassName",
+        "()Ljava/lang/String;",
+        |vm, a| Ok(Some(field(vm, a[0], "name"))),
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "getSuperclass",
+        "()Ljava/lang/Class;",
+        |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            match super_class(vm, &class)? {
+                Some(super_class) => Ok(Some(vm.class_object(&super_class)?)),
+                None => Ok(Some(Value::Null)),
+            }
+        },
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "getInterfaces0",
+        "()[Ljava/lang/Class;",
+        |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let interfaces = match component(&class) {
+                Some(_) => vec!["java/lang/Cloneable".into(), "java/io/Serializable".into()],
+                None => match vm.load_class(&class)? {
+                    Some(c) => c.interface_names().map(str::to_string).collect(),
+                    None => vec![],
+                },
+            };
+            let mut mirrors = Vec::new();
+            for interface in interfaces {
+                mirrors.push(vm.class_object(&interface)?);
+            }
+            let array = vm.heap.new_array("[Ljava/lang/Class;", 0);
+            vm.heap.get_array_mut(array.as_ref().unwrap()).elements = mirrors;
+            Ok(Some(array))
+        },
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "getDeclaredMethods0",
+        "(Z)[Ljava/lang/reflect/Method;",
+        |vm, a| declared_methods(vm, a[0], a[1].as_int() != 0, false),
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "getDeclaredConstructors0",
+        "(Z)[Ljava/lang/reflect/Constructor;",
+        |vm, a| declared_methods(vm, a[0], a[1].as_int() != 0, true),
+    );
+    vm.register_native(
+        "java/lang/Class",
+        "getDeclaredFields0",
+        "(Z)[Ljava/lang/reflect/Field;",
+        |vm, a| declared_fields(vm, a[0], a[1].as_int() != 0),
+    );
+    vm.register_native(
+        "jdk/internal/reflect/NativeMethodAccessorImpl",
+        "invoke0",
+        "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+        invoke,
+    );
+    vm.register_native(
+        "jdk/internal/reflect/NativeConstructorAccessorImpl",
+        "newInstance0",
+        "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;",
+        construct,
+    );
+    // calls through reflection stay native, the JDK would generate accessor classes after
+    // a few calls
+    vm.register_native(
+        "jdk/internal/reflect/ReflectionFactory",
+        "inflationThreshold",
+        "()I",
+        |_, _| Ok(Some(Value::Int(i32::MAX))),
+    );
+    vm.register_native("java/lang/Class", "getModifiers", "()I", |vm, a| {
+        let class = vm.mirrored_class(a[0])?;
+        Ok(Some(Value::Int(class_modifiers(vm, &class)?)))
+    });
+    vm.register_native(
+        "java/lang/Class",
+        "getNestHost0",
+        "()Ljava/lang/Class;",
+        |vm, a| {
+            let name = vm.mirrored_class(a[0])?;
+            let host = nest_host(vm, &name)?;
+            Ok(Some(vm.class_object(&host)?))
+        },
+    );
+    for (name, descriptor) in [
+        ("getDeclaringClass0", "()Ljava/lang/Class;"),
+        ("getSimpleBinaryName0", "()Ljava/lang/String;"),
+        ("getEnclosingMethod0", "()[Ljava/lang/Object;"),
+        ("getGenericSignature0", "()Ljava/lang/String;"),
+        ("getRawAnnotations", "()[B"),
+        ("getRawTypeAnnotations", "()[B"),
+        ("getConstantPool", "()Ljdk/internal/reflect/ConstantPool;"),
+        ("getProtectionDomain0", "()Ljava/security/ProtectionDomain;"),
+    ] {
+        vm.register_native("java/lang/Class", name, descriptor, |_, _| {
+            Ok(Some(Value::Null))
+        });
+    }
+    vm.register_native(
+        "java/lang/Class",
+        "desiredAssertionStatus0",
+        "(Ljava/lang/Class;)Z",
+        |_, _| Ok(Some(Value::Int(0))),
+    );
+    vm.register_native(
+        "java/lang/Throwable",
+        "fillInStackTrace",
+        "(I)Ljava/lang/Throwable;",
+        |vm, a| {
+            vm.fill_in_stack_trace(a[0])?;
+            Ok(Some(a[0]))
+        },
+    );
+    // fills the elements StackTraceElement.of allocated from those of the backtrace
+    vm.register_native(
+        "java/lang/StackTraceElement",
+        "initStackTraceElements",
+        "([Ljava/lang/StackTraceElement;Ljava/lang/Throwable;)V",
+        |vm, a| {
+            let instance = vm.heap.get_instance(a[1].as_ref().unwrap());
+            let Some(Value::Ref(backtrace)) = instance.get("backtrace") else {
+                return Ok(None);
+            };
+            let sources = vm.heap.get_array(backtrace).elements.clone();
+            let elements = vm.heap.get_array(a[0].as_ref().unwrap()).elements.clone();
+            for (element, source) in elements.into_iter().zip(sources) {
+                let fields = vm
+                    .heap
+                    .get_instance(source.as_ref().unwrap())
+                    .fields
+                    .clone();
+                vm.heap.get_instance_mut(element.as_ref().unwrap()).fields = fields;
+            }
+            Ok(None)
+        },
+    );
+    vm.register_native(
+        "java/lang/String",
+        "intern",
+        "()Ljava/lang/String;",
+        |vm, a| {
+            let s = vm.string_value(a[0])?;
+            Ok(Some(vm.intern(&s)))
+        },
+    );
+    vm.register_native("java/lang/StringUTF16", "isBigEndian", "()Z", |_, _| {
+        Ok(Some(Value::Int(0)))
+    });
+    vm.register_native("java/lang/Float", "floatToRawIntBits", "(F)I", |_, a| {
+        Ok(Some(Value::Int(a[0].as_float().to_bits() as i32)))
+    });
+    vm.register_native("java/lang/Float", "intBitsToFloat", "(I)F", |_, a| {
+        Ok(Some(Value::Float(f32::from_bits(a[0].as_int() as u32))))
+    });
+    vm.register_native("java/lang/Double", "doubleToRawLongBits", "(D)J", |_, a| {
+        Ok(Some(Value::Long(a[0].as_double().to_bits() as i64)))
+    });
+    vm.register_native("java/lang/Double", "longBitsToDouble", "(J)D", |_, a| {
+        Ok(Some(Value::Double(f64::from_bits(a[0].as_long() as u64))))
+    });
+    let strict_math: [(&str, StrictMathFn); 11] = [
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+        ("exp", f64::exp),
+        ("log", f64::ln),
+        ("log10", f64::log10),
+        ("sqrt", f64::sqrt),
+        ("cbrt", f64::cbrt),
+    ];
+    for (name, f) in strict_math {
+        vm.register_native("java/lang/StrictMath", name, "(D)D", move |_, a| {
+            Ok(Some(Value::Double(f(a[0].as_double()))))
+        });
+    }
+
+    vm.register_native("java/lang/Runtime", "availableProcessors", "()I", |_, _| {
+        Ok(Some(Value::Int(1)))
+    });
+    for name in ["freeMemory", "totalMemory", "maxMemory"] {
+        vm.register_native("java/lang/Runtime", name, "()J", |_, _| {
+            Ok(Some(Value::Long(256 << 20)))
+        });
+    }
+    vm.register_native("java/lang/Runtime", "gc", "()V", |_, _| Ok(None));
+    vm.register_native("java/lang/Shutdown", "beforeHalt", "()V", |_, _| Ok(None));
+    vm.register_native("java/lang/Shutdown", "halt0", "(I)V", |vm, a| {
+        Err(vm.exit(a[0].as_int()))
+    });
+
+    for (name, descriptor) in [
+        ("isDumpingClassList0", "()Z"),
+        ("isDumpingArchive0", "()Z"),
+        ("isSharingEnabled0", "()Z"),
+    ] {
+        vm.register_native("jdk/internal/misc/CDS", name, descriptor, |_, _| {
+            Ok(Some(Value::Int(0)))
+        });
+    }
+    vm.register_native(
+        "jdk/internal/misc/CDS",
+        "getRandomSeedForDumping",
+        "()J",
+        |_, _| Ok(Some(Value::Long(0))),
+    );
+    vm.register_native(
+        "jdk/internal/misc/CDS",
+        "initializeFromArchive",
+        "(Ljava/lang/Class;)V",
+        |_, _| Ok(None),
+    );
+    vm.register_native("jdk/internal/misc/VM", "initialize", "()V", |_, _| Ok(None));
+    vm.register_native(
+        "jdk/internal/misc/VM",
+        "latestUserDefinedLoader0",
+        "()Ljava/lang/ClassLoader;",
+        |_, _| Ok(Some(Value::Null)),
+    );
+    // the class calling the method which asks, passing over the frames of reflection
+    vm.register_native(
+        "jdk/internal/reflect/Reflection",
+        "getCallerClass",
+        "()Ljava/lang/Class;",
+        |vm, _| {
+            let caller = vm.frames.iter().rev().skip(1).find(|frame| {
+                let class = &frame.class.this_class_name;
+                class != "java/lang/reflect/Method" && !class.starts_with("jdk/internal/reflect/")
+            });
+            match caller.map(|frame| frame.class.this_class_name.clone()) {
+                Some(class) => Ok(Some(vm.class_object(&class)?)),
+                None => Ok(Some(Value::Null)),
+            }
+        },
+    );
+    // every class is loaded by the vm itself, which the class loaders of the JDK find
+    for (class, name) in [
+        ("java/lang/ClassLoader", "findBootstrapClass"),
+        ("java/lang/ClassLoader", "findLoadedClass0"),
+    ] {
+        vm.register_native(
+            class,
+            name,
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            |vm, a| {
+                let name = vm.string_value(*a.last().unwrap())?.replace('.', "/");
+                match vm.load_class(&name)? {
+                    Some(_) => Ok(Some(vm.class_object(&name)?)),
+                    None => Ok(Some(Value::Null)),
+                }
+            },
+        );
+    }
+    vm.register_native(
+        "jdk/internal/loader/BootLoader",
+        "setBootLoaderUnnamedModule0",
+        "(Ljava/lang/Module;)V",
+        |vm, a| {
+            vm.set_unnamed_module(a[0]);
+            Ok(None)
+        },
+    );
+    vm.register_native(
+        "jdk/internal/reflect/Reflection",
+        "getClassAccessFlags",
+        "(Ljava/lang/Class;)I",
+        |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let c = vm.load_class(&class)?;
+            Ok(Some(Value::Int(
+                c.map_or(0, |c| c.access_flags.bits() as i32),
+            )))
+        },
+    );
+    vm.register_native(
+        "jdk/internal/reflect/Reflection",
+        "areNestMates",
+        "(Ljava/lang/Class;Ljava/lang/Class;)Z",
+        |vm, a| {
+            let (class, other) = (vm.mirrored_class(a[0])?, vm.mirrored_class(a[1])?);
+            bool(nest_host(vm, &class)? == nest_host(vm, &other)?)
+        },
+    );
+    for name in ["storeFence", "loadFence", "fullFence"] {
+        vm.register_native("jdk/internal/misc/Unsafe", name, "()V", |_, _| Ok(None));
+    }
+    vm.register_native(
+        "java/security/AccessController",
+        "getStackAccessControlContext",
+        "()Ljava/security/AccessControlContext;",
+        |_, _| Ok(Some(Value::Null)),
+    );
+    vm.register_native(
+        "jdk/internal/misc/Signal",
+        "findSignal0",
+        "(Ljava/lang/String;)I",
+        |_, _| Ok(Some(Value::Int(-1))),
+    );
+    vm.register_native("jdk/internal/misc/Signal", "handle0", "(IJ)J", |_, _| {
+        Ok(Some(Value::Long(0)))
+    });
+    vm.register_native("java/io/FileDescriptor", "getHandle", "(I)J", |_, _| {
+        Ok(Some(Value::Long(-1)))
+    });
+    vm.register_native("java/io/FileDescriptor", "getAppend", "(I)Z", |_, _| {
+        Ok(Some(Value::Int(0)))
+    });
+    vm.register_native(
+        "java/io/FileOutputStream",
+        "writeBytes",
+        "([BIIZ)V",
+        |vm, a| {
+            let fd = field(vm, a[0], "fd");
+            let fd = field(vm, fd, "fd").as_int();
+            let (offset, length) = (a[2].as_int() as usize, a[3].as_int() as usize);
+            let bytes: Vec<u8> = vm.heap.get_array(a[1].as_ref().unwrap()).elements
+                [offset..offset + length]
+                .iter()
+                .map(|b| b.as_int() as u8)
+                .collect();
+            match fd {
+                2 => std::io::stderr().write_all(&bytes)?,
+                _ => std::io::stdout().write_all(&bytes)?,
+            }
+            Ok(None)
+        },
+    );
+
+    // see `limits::FILE_NATIVES`
+    const FILE_SYSTEM: &str = "java/io/UnixFileSystem";
+    let jdk = std::fs::canonicalize(home).unwrap_or(home.to_path_buf());
+    let visible = move |vm: &Vm, name: &str, path: &Path| {
+        vm.grants_native(FILE_SYSTEM, name) || path.starts_with(&jdk)
+    };
+    let visible_file = visible.clone();
+    vm.register_native(
+        FILE_SYSTEM,
+        "canonicalize0",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        move |vm, a| {
+            let path = vm.string_value(a[1])?;
+            // paths which do not exist yet or are out of sight stay as they are
+            let canonical = match std::fs::canonicalize(&path) {
+                Ok(canonical) if visible(vm, "canonicalize0", &canonical) => {
+                    canonical.display().to_string()
+                }
+                _ => path,
+            };
+            Ok(Some(vm.new_string(canonical)))
+        },
+    );
+    vm.register_native(
+        FILE_SYSTEM,
+        "getBooleanAttributes0",
+        "(Ljava/io/File;)I",
+        move |vm, a| {
+            const EXISTS: i32 = 0x01;
+            const REGULAR: i32 = 0x02;
+            const DIRECTORY: i32 = 0x04;
+            let path = file_path(vm, a[1])?;
+            // files out of sight do not exist
+            let path = match std::fs::canonicalize(path) {
+                Ok(path) if visible_file(vm, "getBooleanAttributes0", &path) => path,
+                _ => return Ok(Some(Value::Int(0))),
+            };
+            let attributes = match std::fs::metadata(path) {
+                Ok(metadata) if metadata.is_dir() => EXISTS | DIRECTORY,
+                Ok(metadata) if metadata.is_file() => EXISTS | REGULAR,
+                Ok(_) => EXISTS,
+                Err(_) => 0,
+            };
+            Ok(Some(Value::Int(attributes)))
+        },
+    );
+    vm.register_native(FILE_SYSTEM, "checkAccess", "(Ljava/io/File;I)Z", |vm, a| {
+        let path = file_path(vm, a[1])?;
+        bool(std::fs::metadata(path).is_ok())
+    });
+    vm.register_native(FILE_SYSTEM, "getLength", "(Ljava/io/File;)J", |vm, a| {
+        let path = file_path(vm, a[1])?;
+        let length = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+        Ok(Some(Value::Long(length as i64)))
+    });
+    vm.register_native(
+        FILE_SYSTEM,
+        "getLastModifiedTime",
+        "(Ljava/io/File;)J",
+        |vm, a| {
+            let path = file_path(vm, a[1])?;
+            let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified());
+            let millis = modified.map_or(0, |modified| {
+                let since = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+                since.as_millis() as i64
+            });
+            Ok(Some(Value::Long(millis)))
+        },
+    );
+    vm.register_native(
+        FILE_SYSTEM,
+        "list",
+        "(Ljava/io/File;)[Ljava/lang/String;",
+        |vm, a| {
+            let path = file_path(vm, a[1])?;
+            let Ok(entries) = std::fs::read_dir(path) else {
+                return Ok(Some(Value::Null));
+            };
+            let names = entries
+                .filter_map(|entry| Some(entry.ok()?.file_name().to_string_lossy().into_owned()))
+                .map(Some)
+                .collect();
+            Ok(Some(vm.new_string_array(names)))
+        },
+    );
+
+    let java_home = home.display().to_string();
+    vm.register_native(
+        "jdk/internal/util/SystemProps$Raw",
+        "vmProperties",
+        "()[Ljava/lang/String;",
+        move |vm, _| {
+            let properties = [
+                ("java.home", java_home.as_str()),
+                ("java.vm.name", "jrust"),
+                ("java.vm.vendor", "jrust"),
+                ("java.vm.version", env!("CARGO_PKG_VERSION")),
+                ("java.vm.info", "interpreted mode"),
+                (
+                    "java.vm.specification.name",
+                    "Java Virtual Machine Specification",
+                ),
+                ("java.vm.specification.vendor", "Oracle Corporation"),
+                ("java.vm.specification.version", "17"),
+            ];
+            // those set by the host come last, overriding the others
+            let set = vm.properties.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+            let values = properties
+                .into_iter()
+                .chain(set)
+                .flat_map(|(k, v)| [Some(k.to_string()), Some(v.to_string())])
+                .collect();
+            Ok(Some(vm.new_string_array(values)))
+        },
+    );
+    vm.register_native(
+        "jdk/internal/util/SystemProps$Raw",
+        "platformProperties",
+        "()[Ljava/lang/String;",
+        |vm, _| {
+            let raw = "jdk/internal/util/SystemProps$Raw";
+            let length = vm.get_static(raw, "FIXED_LENGTH", "I")?.as_int();
+            let mut values = vec![None; length as usize];
+            for (index, value) in platform_properties() {
+                let index = vm.get_static(raw, index, "I")?.as_int();
+                if let Some(slot) = values.get_mut(index as usize) {
+                    *slot = Some(value);
+                }
+            }
+            Ok(Some(vm.new_string_array(values)))
+        },
+    );
+}
+
+impl Vm {
+    /// initializes the JDK's core classes in the order HotSpot does, creates the objects of
+    /// the main thread and its thread group and runs `System.initPhase1`
+    pub(crate) fn boot(&mut self) -> anyhow::Result<()> {
+        for class in [
+            "java/lang/String",
+            "java/lang/System",
+            "java/lang/Class",
+            "java/lang/ThreadGroup",
+            "java/lang/Thread",
+        ] {
+            self.init_class(class)
+                .with_context(|| format!("failed to initialize {class}"))?;
+        }
+
+        let system = self.new_object("java/lang/ThreadGroup")?;
+        self.invoke_special("java/lang/ThreadGroup", "<init>", "()V", vec![system])
+            .context("failed to create the system thread group")?;
+        let group = self.new_object("java/lang/ThreadGroup")?;
+        let name = self.intern("main");
+        self.invoke_special(
+            "java/lang/ThreadGroup",
+            "<init>",
+            "(Ljava/lang/ThreadGroup;Ljava/lang/String;)V",
+            vec![group, system, name],
+        )
+        .context("failed to create the main thread group")?;
+        // the constructor asks for the current thread, which is the one being constructed
+        let thread = self.new_object("java/lang/Thread")?;
+        set_field(self, thread, "priority", Value::Int(5));
+        self.attach_main_thread(thread);
+        self.invoke_special(
+            "java/lang/Thread",
+            "<init>",
+            "(Ljava/lang/ThreadGroup;Ljava/lang/String;)V",
+            vec![thread, group, name],
+        )
+        .context("failed to create the main thread")?;
+
+        let constants = "jdk/internal/misc/UnsafeConstants";
+        self.init_class(constants)?;
+        for (name, value) in [
+            ("ADDRESS_SIZE0", 8),
+            ("PAGE_SIZE", 4096),
+            ("BIG_ENDIAN", 0),
+            ("UNALIGNED_ACCESS", 1),
+        ] {
+            self.put_static(constants, name, Value::Int(value))?;
+        }
+        // sets up the access reflection goes through
+        self.init_class("java/lang/reflect/Method")?;
+        self.create_unnamed_module()?;
+
+        self.invoke_static("java/lang/System", "initPhase1", "()V", vec![])
+            .context("System.initPhase1 failed")?;
+        Ok(())
+    }
+}
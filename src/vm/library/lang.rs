@@ -0,0 +1,1102 @@
+use std::{
+    cell::Cell,
+    sync::OnceLock,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    class::field::FieldAccessFlags,
+    vm::{
+        exception::Throw,
+        heap::{ObjRef, Object},
+        value::Value,
+        Vm,
+    },
+};
+
+use super::{jdk::platform_properties, reflect::canonical_name, string, ClassBuilder};
+
+/// what `Object.hashCode` returns for objects which do not override it
+pub fn identity_hash(r: ObjRef) -> i32 {
+    ((r as u32).wrapping_add(1).wrapping_mul(0x9E37_79B1) >> 1) as i32
+}
+
+/// `Object.toString` of classes which do not override it
+fn object_to_string(vm: &mut Vm, this: Value) -> anyhow::Result<String> {
+    let r = this.as_ref().unwrap();
+    let class = vm.heap.class_name(r).replace('/', ".");
+    let hash = vm
+        .invoke_virtual("java/lang/Object", "hashCode", "()I", vec![this])?
+        .unwrap()
+        .as_int();
+    Ok(format!("{class}@{hash:x}"))
+}
+
+pub(super) fn get_class(vm: &mut Vm, args: &[Value]) -> anyhow::Result<Option<Value>> {
+    let class = vm.heap.class_name(args[0].as_ref().unwrap()).to_string();
+    Ok(Some(vm.class_object(&class)?))
+}
+
+pub(super) fn clone(vm: &mut Vm, this: ObjRef) -> anyhow::Result<Value> {
+    let class = vm.heap.class_name(this).to_string();
+    let cloneable = match vm.heap.get(this) {
+        Object::Array(_) => true,
+        Object::Instance(_) => vm.is_subclass(&class, "java/lang/Cloneable")?,
+        _ => false,
+    };
+    let copy = match cloneable {
+        true => vm.heap.get(this).clone(),
+        false => {
+            let message = class.replace('/', ".");
+            return Err(vm.exception("java/lang/CloneNotSupportedException", &message));
+        }
+    };
+    Ok(vm.heap.alloc(copy))
+}
+
+fn random() -> f64 {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64 | 1,
+        );
+    }
+    STATE.with(|state| {
+        // xorshift64*
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+pub(super) fn array_copy(vm: &mut Vm, args: &[Value]) -> anyhow::Result<Option<Value>> {
+    let (Some(src), Some(dest)) = (args[0].as_ref(), args[2].as_ref()) else {
+        return Err(vm.null_pointer());
+    };
+    let (src_pos, dest_pos, length) = (args[1].as_int(), args[3].as_int(), args[4].as_int());
+    for r in [src, dest] {
+        if !matches!(vm.heap.get(r), Object::Array(_)) {
+            let class = vm.heap.class_name(r).replace('/', ".");
+            let message = format!("arraycopy: destination type {class} is not an array");
+            return Err(vm.exception("java/lang/ArrayStoreException", &message));
+        }
+    }
+    let src_len = vm.heap.get_array(src).elements.len() as i64;
+    let dest_len = vm.heap.get_array(dest).elements.len() as i64;
+    if src_pos < 0
+        || dest_pos < 0
+        || length < 0
+        || src_pos as i64 + length as i64 > src_len
+        || dest_pos as i64 + length as i64 > dest_len
+    {
+        let message = format!(
+            "arraycopy: last source index {} out of bounds for length {src_len}",
+            src_pos as i64 + length as i64
+        );
+        return Err(vm.exception("java/lang/ArrayIndexOutOfBoundsException", &message));
+    }
+    let (src_pos, dest_pos, length) = (src_pos as usize, dest_pos as usize, length as usize);
+    let elements = vm.heap.get_array(src).elements[src_pos..src_pos + length].to_vec();
+    vm.heap.get_array_mut(dest).elements[dest_pos..dest_pos + length].copy_from_slice(&elements);
+    Ok(None)
+}
+
+type UnaryOp = fn(f64) -> f64;
+
+fn math(vm: &mut Vm) {
+    let mut math = ClassBuilder::new("java/lang/Math", "java/lang/Object")
+        .static_method("abs", "(I)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().wrapping_abs())))
+        })
+        .static_method("abs", "(J)J", |_, a| {
+            Ok(Some(Value::Long(a[0].as_long().wrapping_abs())))
+        })
+        .static_method("abs", "(F)F", |_, a| {
+            Ok(Some(Value::Float(a[0].as_float().abs())))
+        })
+        .static_method("abs", "(D)D", |_, a| {
+            Ok(Some(Value::Double(a[0].as_double().abs())))
+        })
+        .static_method("max", "(II)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().max(a[1].as_int()))))
+        })
+        .static_method("min", "(II)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_int().min(a[1].as_int()))))
+        })
+        .static_method("max", "(JJ)J", |_, a| {
+            Ok(Some(Value::Long(a[0].as_long().max(a[1].as_long()))))
+        })
+        .static_method("min", "(JJ)J", |_, a| {
+            Ok(Some(Value::Long(a[0].as_long().min(a[1].as_long()))))
+        })
+        // f64::max ignores NaN, java propagates it
+        .static_method("max", "(FF)F", |_, a| {
+            let (x, y) = (a[0].as_float(), a[1].as_float());
+            Ok(Some(Value::Float(if x.is_nan() { x } else { x.max(y) })))
+        })
+        .static_method("min", "(FF)F", |_, a| {
+            let (x, y) = (a[0].as_float(), a[1].as_float());
+            Ok(Some(Value::Float(if x.is_nan() { x } else { x.min(y) })))
+        })
+        .static_method("max", "(DD)D", |_, a| {
+            let (x, y) = (a[0].as_double(), a[1].as_double());
+            Ok(Some(Value::Double(if x.is_nan() { x } else { x.max(y) })))
+        })
+        .static_method("min", "(DD)D", |_, a| {
+            let (x, y) = (a[0].as_double(), a[1].as_double());
+            Ok(Some(Value::Double(if x.is_nan() { x } else { x.min(y) })))
+        })
+        .static_method("pow", "(DD)D", |_, a| {
+            Ok(Some(Value::Double(a[0].as_double().powf(a[1].as_double()))))
+        })
+        .static_method("atan2", "(DD)D", |_, a| {
+            Ok(Some(Value::Double(
+                a[0].as_double().atan2(a[1].as_double()),
+            )))
+        })
+        .static_method("hypot", "(DD)D", |_, a| {
+            Ok(Some(Value::Double(
+                a[0].as_double().hypot(a[1].as_double()),
+            )))
+        })
+        .static_method("round", "(D)J", |_, a| {
+            Ok(Some(Value::Long((a[0].as_double() + 0.5).floor() as i64)))
+        })
+        .static_method("round", "(F)I", |_, a| {
+            Ok(Some(Value::Int((a[0].as_float() + 0.5).floor() as i32)))
+        })
+        .static_method("random", "()D", |_, _| Ok(Some(Value::Double(random()))))
+        .static_method("floorDiv", "(II)I", |vm, a| {
+            let (x, y) = (a[0].as_int(), a[1].as_int());
+            if y == 0 {
+                return Err(vm.exception("java/lang/ArithmeticException", "/ by zero"));
+            }
+            let q = x.wrapping_div(y);
+            let adjust = (x % y != 0) && ((x < 0) != (y < 0));
+            Ok(Some(Value::Int(if adjust { q - 1 } else { q })))
+        })
+        .static_method("floorMod", "(II)I", |vm, a| {
+            let (x, y) = (a[0].as_int(), a[1].as_int());
+            if y == 0 {
+                return Err(vm.exception("java/lang/ArithmeticException", "/ by zero"));
+            }
+            let m = x.wrapping_rem(y);
+            let adjust = m != 0 && ((m < 0) != (y < 0));
+            Ok(Some(Value::Int(if adjust { m + y } else { m })))
+        })
+        .static_method("addExact", "(II)I", |vm, a| {
+            match a[0].as_int().checked_add(a[1].as_int()) {
+                Some(v) => Ok(Some(Value::Int(v))),
+                None => Err(vm.exception("java/lang/ArithmeticException", "integer overflow")),
+            }
+        })
+        .static_method("multiplyExact", "(II)I", |vm, a| {
+            match a[0].as_int().checked_mul(a[1].as_int()) {
+                Some(v) => Ok(Some(Value::Int(v))),
+                None => Err(vm.exception("java/lang/ArithmeticException", "integer overflow")),
+            }
+        })
+        .static_method("addExact", "(JJ)J", |vm, a| {
+            match a[0].as_long().checked_add(a[1].as_long()) {
+                Some(v) => Ok(Some(Value::Long(v))),
+                None => Err(vm.exception("java/lang/ArithmeticException", "long overflow")),
+            }
+        });
+    let unary: [(&str, UnaryOp); 16] = [
+        ("sqrt", f64::sqrt),
+        ("cbrt", f64::cbrt),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("rint", f64::round_ties_even),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+        ("exp", f64::exp),
+        ("log", f64::ln),
+        ("log10", f64::log10),
+        ("toRadians", f64::to_radians),
+        ("toDegrees", f64::to_degrees),
+    ];
+    for (name, f) in unary {
+        math = math.static_method(name, "(D)D", move |_, a| {
+            Ok(Some(Value::Double(f(a[0].as_double()))))
+        });
+    }
+    math.static_method("signum", "(D)D", |_, a| {
+        let x = a[0].as_double();
+        Ok(Some(Value::Double(if x == 0.0 || x.is_nan() {
+            x
+        } else {
+            x.signum()
+        })))
+    })
+    .install(vm);
+}
+
+/// `(superclass, class)` pairs of the built-in throwables, superclasses first
+const THROWABLES: &[(&str, &str)] = &[
+    ("java/lang/Throwable", "java/lang/Exception"),
+    ("java/lang/Throwable", "java/lang/Error"),
+    ("java/lang/Exception", "java/lang/RuntimeException"),
+    (
+        "java/lang/Exception",
+        "java/lang/CloneNotSupportedException",
+    ),
+    ("java/lang/Exception", "java/lang/InterruptedException"),
+    (
+        "java/lang/Exception",
+        "java/lang/ReflectiveOperationException",
+    ),
+    (
+        "java/lang/ReflectiveOperationException",
+        "java/lang/ClassNotFoundException",
+    ),
+    (
+        "java/lang/ReflectiveOperationException",
+        "java/lang/IllegalAccessException",
+    ),
+    (
+        "java/lang/ReflectiveOperationException",
+        "java/lang/InstantiationException",
+    ),
+    (
+        "java/lang/ReflectiveOperationException",
+        "java/lang/NoSuchFieldException",
+    ),
+    (
+        "java/lang/ReflectiveOperationException",
+        "java/lang/NoSuchMethodException",
+    ),
+    ("java/lang/Exception", "java/io/IOException"),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/ArithmeticException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/ArrayStoreException",
+    ),
+    ("java/lang/RuntimeException", "java/lang/ClassCastException"),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/IllegalArgumentException",
+    ),
+    (
+        "java/lang/IllegalArgumentException",
+        "java/lang/NumberFormatException",
+    ),
+    (
+        "java/lang/IllegalArgumentException",
+        "java/lang/IllegalThreadStateException",
+    ),
+    (
+        "java/lang/IllegalArgumentException",
+        "java/util/IllegalFormatException",
+    ),
+    (
+        "java/util/IllegalFormatException",
+        "java/util/IllegalFormatConversionException",
+    ),
+    (
+        "java/util/IllegalFormatException",
+        "java/util/MissingFormatArgumentException",
+    ),
+    (
+        "java/util/IllegalFormatException",
+        "java/util/UnknownFormatConversionException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/IllegalMonitorStateException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/IllegalStateException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/reflect/InaccessibleObjectException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/IndexOutOfBoundsException",
+    ),
+    (
+        "java/lang/IndexOutOfBoundsException",
+        "java/lang/ArrayIndexOutOfBoundsException",
+    ),
+    (
+        "java/lang/IndexOutOfBoundsException",
+        "java/lang/StringIndexOutOfBoundsException",
+    ),
+    ("java/lang/RuntimeException", "java/lang/MatchException"),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/NegativeArraySizeException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/NullPointerException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/lang/UnsupportedOperationException",
+    ),
+    ("java/lang/RuntimeException", "java/lang/SecurityException"),
+    (
+        "java/lang/RuntimeException",
+        "java/util/NoSuchElementException",
+    ),
+    (
+        "java/lang/RuntimeException",
+        "java/util/ConcurrentModificationException",
+    ),
+    ("java/lang/Error", "java/lang/AssertionError"),
+    ("java/lang/Error", "java/lang/LinkageError"),
+    ("java/lang/LinkageError", "java/lang/ClassFormatError"),
+    (
+        "java/lang/LinkageError",
+        "java/lang/ExceptionInInitializerError",
+    ),
+    ("java/lang/LinkageError", "java/lang/NoClassDefFoundError"),
+    ("java/lang/LinkageError", "java/lang/UnsatisfiedLinkError"),
+    ("java/lang/LinkageError", "java/lang/VerifyError"),
+    (
+        "java/lang/LinkageError",
+        "java/lang/IncompatibleClassChangeError",
+    ),
+    (
+        "java/lang/IncompatibleClassChangeError",
+        "java/lang/AbstractMethodError",
+    ),
+    (
+        "java/lang/IncompatibleClassChangeError",
+        "java/lang/IllegalAccessError",
+    ),
+    (
+        "java/lang/IncompatibleClassChangeError",
+        "java/lang/NoSuchFieldError",
+    ),
+    (
+        "java/lang/IncompatibleClassChangeError",
+        "java/lang/NoSuchMethodError",
+    ),
+    ("java/lang/Error", "java/lang/VirtualMachineError"),
+    ("java/lang/VirtualMachineError", "java/lang/InternalError"),
+    (
+        "java/lang/VirtualMachineError",
+        "java/lang/OutOfMemoryError",
+    ),
+    (
+        "java/lang/VirtualMachineError",
+        "java/lang/StackOverflowError",
+    ),
+];
+
+pub(super) fn wait(vm: &mut Vm, this: Value, millis: i64) -> anyhow::Result<Option<Value>> {
+    let lock = vm.object_lock(this)?;
+    vm.wait(lock, millis)?;
+    Ok(None)
+}
+
+pub(super) fn notify(vm: &mut Vm, this: Value, all: bool) -> anyhow::Result<Option<Value>> {
+    let lock = vm.object_lock(this)?;
+    vm.notify(&lock, all)?;
+    Ok(None)
+}
+
+/// `System.getProperty`: a property the host set, or one the JDK would have
+fn property(vm: &mut Vm, key: Value, default: Value) -> anyhow::Result<Option<Value>> {
+    let key = match key {
+        Value::Null => {
+            return Err(vm.exception("java/lang/NullPointerException", "key can't be null"))
+        }
+        key => vm.string_value(key)?,
+    };
+    if key.is_empty() {
+        let message = "key can't be empty";
+        return Err(vm.exception("java/lang/IllegalArgumentException", message));
+    }
+    let set = vm.properties.iter().rev().find(|(k, _)| *k == key);
+    let value = match set {
+        Some((_, value)) => Some(value.clone()),
+        None => default_properties()
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value),
+    };
+    Ok(Some(value.map_or(default, |value| vm.new_string(value))))
+}
+
+/// properties of the built-in library, named like those of the JDK
+fn default_properties() -> Vec<(String, String)> {
+    let vm = [
+        ("java.version", "17"),
+        ("java.specification.version", "17"),
+        ("java.vm.name", "jrust"),
+        ("java.vm.vendor", "jrust"),
+        ("java.vm.version", env!("CARGO_PKG_VERSION")),
+    ];
+    // `_java_io_tmpdir_NDX` is `java.io.tmpdir`
+    let platform = platform_properties().into_iter().map(|(index, value)| {
+        let name = index.trim_start_matches('_').trim_end_matches("_NDX");
+        (name.replace('_', "."), value)
+    });
+    vm.into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .chain(platform)
+        .collect()
+}
+
+pub(super) fn set_field(vm: &mut Vm, this: Value, name: &str, value: Value) {
+    let instance = vm.heap.get_instance_mut(this.as_ref().unwrap());
+    instance.set(name, value);
+}
+
+pub(super) fn get_field(vm: &Vm, this: Value, name: &str) -> Value {
+    let instance = vm.heap.get_instance(this.as_ref().unwrap());
+    instance.get(name).unwrap_or(Value::Null)
+}
+
+/// `Enum.getDeclaringClass`: constants with a body are instances of a subclass
+fn enum_class(vm: &mut Vm, this: Value) -> anyhow::Result<String> {
+    let class = vm.heap.class_name(this.as_ref().unwrap()).to_string();
+    let c = vm.load_class(&class)?.unwrap();
+    Ok(match c.super_class_name.as_str() {
+        "java/lang/Enum" => class,
+        super_class => super_class.to_string(),
+    })
+}
+
+fn enum_compare(vm: &mut Vm, a: &[Value]) -> anyhow::Result<Option<Value>> {
+    let Some(other) = a[1].as_ref() else {
+        return Err(vm.null_pointer());
+    };
+    if !vm.is_subclass(
+        vm.heap.class_name(other).to_string().as_str(),
+        "java/lang/Enum",
+    )? || enum_class(vm, a[0])? != enum_class(vm, a[1])?
+    {
+        return Err(vm.exception("java/lang/ClassCastException", ""));
+    }
+    let ordinal = |this| get_field(vm, this, "ordinal").as_int();
+    Ok(Some(Value::Int(ordinal(a[0]) - ordinal(a[1]))))
+}
+
+/// `Enum.valueOf`, looking through the `values()` of the enum class
+fn enum_value_of(vm: &mut Vm, a: &[Value]) -> anyhow::Result<Option<Value>> {
+    let class = vm.mirrored_class(a[0])?;
+    if a[1] == Value::Null {
+        return Err(vm.exception("java/lang/NullPointerException", "Name is null"));
+    }
+    let name = vm.string_value(a[1])?;
+    let descriptor = format!("()[L{class};");
+    let is_enum = vm.is_subclass(&class, "java/lang/Enum")? && class != "java/lang/Enum";
+    let values = match is_enum {
+        true => vm.invoke_static(&class, "values", &descriptor, vec![])?,
+        false => None,
+    };
+    let Some(values) = values.and_then(|values| values.as_ref()) else {
+        let message = format!("{} is not an enum class", class.replace('/', "."));
+        return Err(vm.exception("java/lang/IllegalArgumentException", &message));
+    };
+    for constant in vm.heap.get_array(values).elements.clone() {
+        let constant_name = get_field(vm, constant, "name");
+        if vm.string_value(constant_name)? == name {
+            return Ok(Some(constant));
+        }
+    }
+    let canonical = canonical_name(vm, &class)?.unwrap_or_else(|| "null".to_string());
+    let message = format!("No enum constant {canonical}.{name}");
+    Err(vm.exception("java/lang/IllegalArgumentException", &message))
+}
+
+fn enumeration(vm: &mut Vm) {
+    ClassBuilder::new("java/lang/Enum", "java/lang/Object")
+        .implements("java/lang/Comparable")
+        .implements("java/io/Serializable")
+        .field(
+            "name",
+            "Ljava/lang/String;",
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL,
+        )
+        .field(
+            "ordinal",
+            "I",
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL,
+        )
+        .method("<init>", "(Ljava/lang/String;I)V", |vm, a| {
+            set_field(vm, a[0], "name", a[1]);
+            set_field(vm, a[0], "ordinal", a[2]);
+            Ok(None)
+        })
+        .method("name", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "name")))
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "name")))
+        })
+        .method("ordinal", "()I", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "ordinal")))
+        })
+        .method("equals", "(Ljava/lang/Object;)Z", |_, a| {
+            Ok(Some(Value::Int((a[0] == a[1]) as i32)))
+        })
+        .method("compareTo", "(Ljava/lang/Enum;)I", enum_compare)
+        .method("compareTo", "(Ljava/lang/Object;)I", enum_compare)
+        .method("getDeclaringClass", "()Ljava/lang/Class;", |vm, a| {
+            let class = enum_class(vm, a[0])?;
+            Ok(Some(vm.class_object(&class)?))
+        })
+        .static_method(
+            "valueOf",
+            "(Ljava/lang/Class;Ljava/lang/String;)Ljava/lang/Enum;",
+            enum_value_of,
+        )
+        .install(vm);
+}
+
+/// runs `fillInStackTrace` for the constructors of `Throwable`, which subclasses may
+/// override to leave the stack trace out
+fn fill_in_stack_trace(vm: &mut Vm, this: Value) -> anyhow::Result<()> {
+    let descriptor = "()Ljava/lang/Throwable;";
+    vm.invoke_virtual(
+        "java/lang/Throwable",
+        "fillInStackTrace",
+        descriptor,
+        vec![this],
+    )?;
+    Ok(())
+}
+
+/// elements of an array field, none for null
+fn array_elements(vm: &Vm, array: Value) -> Vec<Value> {
+    match array.as_ref() {
+        Some(r) => vm.heap.get_array(r).elements.clone(),
+        None => Vec::new(),
+    }
+}
+
+fn new_array(vm: &mut Vm, class: &str, elements: Vec<Value>) -> Value {
+    let array = vm.heap.new_array(class, 0);
+    vm.heap.get_array_mut(array.as_ref().unwrap()).elements = elements;
+    array
+}
+
+fn string_field(vm: &mut Vm, this: Value, name: &str) -> anyhow::Result<Option<String>> {
+    match get_field(vm, this, name) {
+        Value::Null => Ok(None),
+        value => vm.string_value(value).map(Some),
+    }
+}
+
+/// string fields `StackTraceElement.equals` compares
+const STACK_TRACE_ELEMENT_FIELDS: [&str; 5] = [
+    "declaringClass",
+    "methodName",
+    "fileName",
+    "moduleName",
+    "moduleVersion",
+];
+
+/// `StackTraceElement.toString`: `module@version/Class.method(File.java:line)`
+fn stack_trace_element(vm: &mut Vm, element: Value) -> anyhow::Result<String> {
+    let mut s = String::new();
+    if let Some(module) = string_field(vm, element, "moduleName")? {
+        s += &module;
+        if let Some(version) = string_field(vm, element, "moduleVersion")? {
+            s += &format!("@{version}");
+        }
+        s += "/";
+    }
+    let class = string_field(vm, element, "declaringClass")?.unwrap_or_default();
+    let method = string_field(vm, element, "methodName")?.unwrap_or_default();
+    let location = match (
+        string_field(vm, element, "fileName")?,
+        get_field(vm, element, "lineNumber").as_int(),
+    ) {
+        (_, -2) => "Native Method".to_string(),
+        (None, _) => "Unknown Source".to_string(),
+        (Some(file), line) if line >= 0 => format!("{file}:{line}"),
+        (Some(file), _) => file,
+    };
+    Ok(format!("{s}{class}.{method}({location})"))
+}
+
+/// adds the lines `printStackTrace` prints for `exception`: its description and frames,
+/// but for those in common with the `enclosing` trace, then its suppressed exceptions
+/// and its cause. Those printed before sit on `vm.roots` from `seen` on
+fn stack_trace_lines(
+    vm: &mut Vm,
+    exception: Value,
+    enclosing: &[String],
+    (caption, prefix): (&str, &str),
+    seen: usize,
+    lines: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let description = vm.java_string(exception, "Ljava/lang/Throwable;")?;
+    if vm.roots[seen..].contains(&exception) {
+        lines.push(format!(
+            "{prefix}{caption}[CIRCULAR REFERENCE: {description}]"
+        ));
+        return Ok(());
+    }
+    vm.roots.push(exception);
+    let trace = array_elements(vm, get_field(vm, exception, "stackTrace"))
+        .into_iter()
+        .map(|element| stack_trace_element(vm, element))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let common = trace
+        .iter()
+        .rev()
+        .zip(enclosing.iter().rev())
+        .take_while(|(frame, enclosing)| frame == enclosing)
+        .count();
+    lines.push(format!("{prefix}{caption}{description}"));
+    for frame in &trace[..trace.len() - common] {
+        lines.push(format!("{prefix}\tat {frame}"));
+    }
+    if common > 0 {
+        lines.push(format!("{prefix}\t... {common} more"));
+    }
+    let suppressed = array_elements(vm, get_field(vm, exception, "suppressedExceptions"));
+    for exception in suppressed {
+        let caption = ("Suppressed: ", &*format!("{prefix}\t"));
+        stack_trace_lines(vm, exception, &trace, caption, seen, lines)?;
+    }
+    let descriptor = "()Ljava/lang/Throwable;";
+    let cause = vm
+        .invoke_virtual(
+            "java/lang/Throwable",
+            "getCause",
+            descriptor,
+            vec![exception],
+        )?
+        .unwrap();
+    if cause != Value::Null {
+        stack_trace_lines(vm, cause, &trace, ("Caused by: ", prefix), seen, lines)?;
+    }
+    Ok(())
+}
+
+/// `Throwable.printStackTrace`, a line at a time through `stream`
+fn print_stack_trace(vm: &mut Vm, this: Value, stream: Value) -> anyhow::Result<Option<Value>> {
+    if stream == Value::Null {
+        return Err(vm.null_pointer());
+    }
+    vm.roots.push(stream);
+    let seen = vm.roots.len();
+    let mut lines = Vec::new();
+    let collected = stack_trace_lines(vm, this, &[], ("", ""), seen, &mut lines);
+    vm.roots.truncate(seen);
+    let printed = collected.and_then(|()| {
+        for line in lines {
+            let line = vm.new_string(line);
+            let descriptor = "(Ljava/lang/String;)V";
+            vm.invoke_virtual(
+                "java/io/PrintStream",
+                "println",
+                descriptor,
+                vec![stream, line],
+            )?;
+        }
+        Ok(None)
+    });
+    vm.roots.pop();
+    printed
+}
+
+fn throwable(vm: &mut Vm) {
+    ClassBuilder::new("java/lang/Throwable", "java/lang/Object")
+        .field(
+            "detailMessage",
+            "Ljava/lang/String;",
+            FieldAccessFlags::PRIVATE,
+        )
+        .field("cause", "Ljava/lang/Throwable;", FieldAccessFlags::PRIVATE)
+        .field(
+            "stackTrace",
+            "[Ljava/lang/StackTraceElement;",
+            FieldAccessFlags::PRIVATE,
+        )
+        .field(
+            "suppressedExceptions",
+            "[Ljava/lang/Throwable;",
+            FieldAccessFlags::PRIVATE,
+        )
+        // constructors are looked up through superclasses, so these serve every subclass
+        .method("<init>", "()V", |vm, a| {
+            fill_in_stack_trace(vm, a[0])?;
+            Ok(None)
+        })
+        .method("<init>", "(Ljava/lang/String;)V", |vm, a| {
+            fill_in_stack_trace(vm, a[0])?;
+            set_field(vm, a[0], "detailMessage", a[1]);
+            Ok(None)
+        })
+        .method(
+            "<init>",
+            "(Ljava/lang/String;Ljava/lang/Throwable;)V",
+            |vm, a| {
+                fill_in_stack_trace(vm, a[0])?;
+                set_field(vm, a[0], "detailMessage", a[1]);
+                set_field(vm, a[0], "cause", a[2]);
+                Ok(None)
+            },
+        )
+        .method("<init>", "(Ljava/lang/Throwable;)V", |vm, a| {
+            fill_in_stack_trace(vm, a[0])?;
+            if a[1] != Value::Null {
+                let message = vm.java_string(a[1], "Ljava/lang/Throwable;")?;
+                let message = vm.new_string(message);
+                set_field(vm, a[0], "detailMessage", message);
+            }
+            set_field(vm, a[0], "cause", a[1]);
+            Ok(None)
+        })
+        .method("getMessage", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "detailMessage")))
+        })
+        .method("getLocalizedMessage", "()Ljava/lang/String;", |vm, a| {
+            let descriptor = "()Ljava/lang/String;";
+            vm.invoke_virtual("java/lang/Throwable", "getMessage", descriptor, vec![a[0]])
+        })
+        .method("getCause", "()Ljava/lang/Throwable;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "cause")))
+        })
+        .method(
+            "initCause",
+            "(Ljava/lang/Throwable;)Ljava/lang/Throwable;",
+            |vm, a| {
+                set_field(vm, a[0], "cause", a[1]);
+                Ok(Some(a[0]))
+            },
+        )
+        .method("fillInStackTrace", "()Ljava/lang/Throwable;", |vm, a| {
+            vm.fill_in_stack_trace(a[0])?;
+            Ok(Some(a[0]))
+        })
+        .method(
+            "getStackTrace",
+            "()[Ljava/lang/StackTraceElement;",
+            |vm, a| {
+                let trace = array_elements(vm, get_field(vm, a[0], "stackTrace"));
+                let class = "[Ljava/lang/StackTraceElement;";
+                Ok(Some(new_array(vm, class, trace)))
+            },
+        )
+        .method(
+            "setStackTrace",
+            "([Ljava/lang/StackTraceElement;)V",
+            |vm, a| {
+                if a[1] == Value::Null {
+                    return Err(vm.null_pointer());
+                }
+                let trace = array_elements(vm, a[1]);
+                if let Some(i) = trace.iter().position(|&element| element == Value::Null) {
+                    let message = format!("stackTrace[{i}]");
+                    return Err(vm.exception("java/lang/NullPointerException", &message));
+                }
+                let trace = new_array(vm, "[Ljava/lang/StackTraceElement;", trace);
+                set_field(vm, a[0], "stackTrace", trace);
+                Ok(None)
+            },
+        )
+        .method("addSuppressed", "(Ljava/lang/Throwable;)V", |vm, a| {
+            if a[1] == a[0] {
+                let message = "Self-suppression not permitted";
+                let err = vm.exception("java/lang/IllegalArgumentException", message);
+                if let Some(&Throw(exception)) = err.downcast_ref::<Throw>() {
+                    set_field(vm, exception, "cause", a[1]);
+                }
+                return Err(err);
+            }
+            if a[1] == Value::Null {
+                let message = "Cannot suppress a null exception.";
+                return Err(vm.exception("java/lang/NullPointerException", message));
+            }
+            let mut suppressed = array_elements(vm, get_field(vm, a[0], "suppressedExceptions"));
+            suppressed.push(a[1]);
+            let suppressed = new_array(vm, "[Ljava/lang/Throwable;", suppressed);
+            set_field(vm, a[0], "suppressedExceptions", suppressed);
+            Ok(None)
+        })
+        .method("getSuppressed", "()[Ljava/lang/Throwable;", |vm, a| {
+            let suppressed = array_elements(vm, get_field(vm, a[0], "suppressedExceptions"));
+            Ok(Some(new_array(vm, "[Ljava/lang/Throwable;", suppressed)))
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let class = vm.heap.class_name(a[0].as_ref().unwrap()).replace('/', ".");
+            let descriptor = "()Ljava/lang/String;";
+            let message = vm
+                .invoke_virtual(
+                    "java/lang/Throwable",
+                    "getLocalizedMessage",
+                    descriptor,
+                    vec![a[0]],
+                )?
+                .unwrap();
+            let string = match message {
+                Value::Null => class,
+                message => format!("{class}: {}", vm.string_value(message)?),
+            };
+            Ok(Some(vm.new_string(string)))
+        })
+        .method("printStackTrace", "()V", |vm, a| {
+            let stream = vm.get_static("java/lang/System", "err", "Ljava/io/PrintStream;")?;
+            print_stack_trace(vm, a[0], stream)
+        })
+        .method("printStackTrace", "(Ljava/io/PrintStream;)V", |vm, a| {
+            print_stack_trace(vm, a[0], a[1])
+        })
+        .install(vm);
+
+    for (super_class, class) in THROWABLES {
+        ClassBuilder::new(class, super_class).install(vm);
+    }
+    // replaces the (Ljava/lang/Throwable;)V constructor, which does not apply to it
+    ClassBuilder::new("java/lang/AssertionError", "java/lang/Error")
+        .method("<init>", "(Ljava/lang/Object;)V", |vm, a| {
+            fill_in_stack_trace(vm, a[0])?;
+            let message = vm.java_string(a[1], "Ljava/lang/Object;")?;
+            let message = vm.new_string(message);
+            set_field(vm, a[0], "detailMessage", message);
+            Ok(None)
+        })
+        .install(vm);
+
+    let mut element = ClassBuilder::new("java/lang/StackTraceElement", "java/lang/Object")
+        .implements("java/io/Serializable");
+    for (field, getter, descriptor) in [
+        ("declaringClass", "getClassName", "Ljava/lang/String;"),
+        ("methodName", "getMethodName", "Ljava/lang/String;"),
+        ("fileName", "getFileName", "Ljava/lang/String;"),
+        ("lineNumber", "getLineNumber", "I"),
+        ("moduleName", "getModuleName", "Ljava/lang/String;"),
+        ("moduleVersion", "getModuleVersion", "Ljava/lang/String;"),
+    ] {
+        element = element
+            .field(field, descriptor, FieldAccessFlags::PRIVATE)
+            .method(getter, &format!("(){descriptor}"), move |vm, a| {
+                Ok(Some(get_field(vm, a[0], field)))
+            });
+    }
+    element
+        .method(
+            "<init>",
+            "(Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;I)V",
+            |vm, a| {
+                for (value, message) in [
+                    (a[1], "Declaring class is null"),
+                    (a[2], "Method name is null"),
+                ] {
+                    if value == Value::Null {
+                        return Err(vm.exception("java/lang/NullPointerException", message));
+                    }
+                }
+                let fields = ["declaringClass", "methodName", "fileName", "lineNumber"];
+                for (field, value) in fields.into_iter().zip(&a[1..]) {
+                    set_field(vm, a[0], field, *value);
+                }
+                Ok(None)
+            },
+        )
+        .method("isNativeMethod", "()Z", |vm, a| {
+            let line = get_field(vm, a[0], "lineNumber").as_int();
+            Ok(Some(Value::Int((line == -2) as i32)))
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let string = stack_trace_element(vm, a[0])?;
+            Ok(Some(vm.new_string(string)))
+        })
+        .method("equals", "(Ljava/lang/Object;)Z", |vm, a| {
+            let equal = match a[1].as_ref() {
+                _ if a[0] == a[1] => true,
+                Some(r) if vm.heap.class_name(r) == "java/lang/StackTraceElement" => {
+                    let mut equal =
+                        get_field(vm, a[0], "lineNumber") == get_field(vm, a[1], "lineNumber");
+                    for field in STACK_TRACE_ELEMENT_FIELDS {
+                        equal = equal
+                            && string_field(vm, a[0], field)? == string_field(vm, a[1], field)?;
+                    }
+                    equal
+                }
+                _ => false,
+            };
+            Ok(Some(Value::Int(equal as i32)))
+        })
+        .method("hashCode", "()I", |vm, a| {
+            let hash = |s: Option<String>| s.map_or(0, |s| string::hash_code(&s));
+            let class = hash(string_field(vm, a[0], "declaringClass")?);
+            let method = hash(string_field(vm, a[0], "methodName")?);
+            // the class loader name, which these elements do not have, hashes to 0
+            let mut result = class.wrapping_mul(31).wrapping_add(method).wrapping_mul(31);
+            for field in ["moduleName", "moduleVersion", "fileName"] {
+                let field = hash(string_field(vm, a[0], field)?);
+                result = result.wrapping_mul(31).wrapping_add(field);
+            }
+            let line = get_field(vm, a[0], "lineNumber").as_int();
+            Ok(Some(Value::Int(result.wrapping_mul(31).wrapping_add(line))))
+        })
+        .install(vm);
+}
+
+pub fn install(vm: &mut Vm) {
+    ClassBuilder::new("java/lang/Object", "")
+        .method("<init>", "()V", |_, _| Ok(None))
+        .method("hashCode", "()I", |_, a| {
+            Ok(Some(Value::Int(identity_hash(a[0].as_ref().unwrap()))))
+        })
+        .method("equals", "(Ljava/lang/Object;)Z", |_, a| {
+            Ok(Some(Value::Int((a[0] == a[1]) as i32)))
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let string = object_to_string(vm, a[0])?;
+            Ok(Some(vm.new_string(string)))
+        })
+        .method("clone", "()Ljava/lang/Object;", |vm, a| {
+            Ok(Some(clone(vm, a[0].as_ref().unwrap())?))
+        })
+        .method("getClass", "()Ljava/lang/Class;", get_class)
+        .method("wait", "()V", |vm, a| wait(vm, a[0], 0))
+        .method("wait", "(J)V", |vm, a| wait(vm, a[0], a[1].as_long()))
+        .method("wait", "(JI)V", |vm, a| {
+            let nanos = a[2].as_int();
+            if !(0..=999_999).contains(&nanos) {
+                let message = "nanosecond timeout value out of range";
+                return Err(vm.exception("java/lang/IllegalArgumentException", message));
+            }
+            let millis = a[1].as_long();
+            wait(vm, a[0], millis.saturating_add((nanos > 0) as i64))
+        })
+        .method("notify", "()V", |vm, a| notify(vm, a[0], false))
+        .method("notifyAll", "()V", |vm, a| notify(vm, a[0], true))
+        .install(vm);
+
+    for interface in [
+        "java/lang/Cloneable",
+        "java/lang/Comparable",
+        "java/lang/CharSequence",
+        "java/lang/Runnable",
+        "java/lang/AutoCloseable",
+        "java/lang/Iterable",
+        "java/io/Serializable",
+    ] {
+        ClassBuilder::interface(interface).install(vm);
+    }
+
+    ClassBuilder::new("java/lang/System", "java/lang/Object")
+        .field(
+            "out",
+            "Ljava/io/PrintStream;",
+            FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+        )
+        .field(
+            "err",
+            "Ljava/io/PrintStream;",
+            FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+        )
+        .static_method("<clinit>", "()V", |vm, _| {
+            for (name, fd) in [("out", 1), ("err", 2)] {
+                let stream = vm.new_object("java/io/PrintStream")?;
+                set_field(vm, stream, "fd", Value::Int(fd));
+                vm.put_static("java/lang/System", name, stream)?;
+            }
+            Ok(None)
+        })
+        .static_method("currentTimeMillis", "()J", |_, _| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            Ok(Some(Value::Long(now.as_millis() as i64)))
+        })
+        .static_method("nanoTime", "()J", |_, _| {
+            static START: OnceLock<Instant> = OnceLock::new();
+            let elapsed = START.get_or_init(Instant::now).elapsed();
+            Ok(Some(Value::Long(elapsed.as_nanos() as i64)))
+        })
+        .static_method(
+            "arraycopy",
+            "(Ljava/lang/Object;ILjava/lang/Object;II)V",
+            array_copy,
+        )
+        .static_method("identityHashCode", "(Ljava/lang/Object;)I", |_, a| {
+            Ok(Some(Value::Int(a[0].as_ref().map_or(0, identity_hash))))
+        })
+        .static_method("lineSeparator", "()Ljava/lang/String;", |vm, _| {
+            Ok(Some(vm.intern("\n")))
+        })
+        .static_method(
+            "getProperty",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            |vm, a| property(vm, a[0], Value::Null),
+        )
+        .static_method(
+            "getProperty",
+            "(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+            |vm, a| property(vm, a[0], a[1]),
+        )
+        .static_method("exit", "(I)V", |vm, a| Err(vm.exit(a[0].as_int())))
+        .install(vm);
+
+    math(vm);
+    throwable(vm);
+    enumeration(vm);
+
+    ClassBuilder::new("java/util/Objects", "java/lang/Object")
+        .static_method(
+            "requireNonNull",
+            "(Ljava/lang/Object;)Ljava/lang/Object;",
+            |vm, a| match a[0] {
+                Value::Null => Err(vm.null_pointer()),
+                value => Ok(Some(value)),
+            },
+        )
+        .static_method(
+            "requireNonNull",
+            "(Ljava/lang/Object;Ljava/lang/String;)Ljava/lang/Object;",
+            |vm, a| match a[0] {
+                Value::Null => {
+                    let message = vm.java_string(a[1], "Ljava/lang/String;")?;
+                    Err(vm.exception("java/lang/NullPointerException", &message))
+                }
+                value => Ok(Some(value)),
+            },
+        )
+        .static_method(
+            "equals",
+            "(Ljava/lang/Object;Ljava/lang/Object;)Z",
+            |vm, a| {
+                if a[0] == a[1] {
+                    return Ok(Some(Value::Int(1)));
+                }
+                if a[0] == Value::Null {
+                    return Ok(Some(Value::Int(0)));
+                }
+                let descriptor = "(Ljava/lang/Object;)Z";
+                vm.invoke_virtual("java/lang/Object", "equals", descriptor, a.to_vec())
+            },
+        )
+        .static_method("hashCode", "(Ljava/lang/Object;)I", |vm, a| match a[0] {
+            Value::Null => Ok(Some(Value::Int(0))),
+            value => vm.invoke_virtual("java/lang/Object", "hashCode", "()I", vec![value]),
+        })
+        .static_method(
+            "toString",
+            "(Ljava/lang/Object;)Ljava/lang/String;",
+            |vm, a| {
+                let string = vm.java_string(a[0], "Ljava/lang/Object;")?;
+                Ok(Some(vm.new_string(string)))
+            },
+        )
+        .static_method("isNull", "(Ljava/lang/Object;)Z", |_, a| {
+            Ok(Some(Value::Int((a[0] == Value::Null) as i32)))
+        })
+        .static_method("nonNull", "(Ljava/lang/Object;)Z", |_, a| {
+            Ok(Some(Value::Int((a[0] != Value::Null) as i32)))
+        })
+        .install(vm);
+}
@@ -0,0 +1,288 @@
+//! `jdk.internal.misc.Unsafe`, which the JDK's class library uses for atomics and for raw
+//! access to fields and array elements. Offsets are made up: an instance field's offset is
+//! its slot in the [`Layout`](crate::vm::heap::Layout), a static field's offset is
+//! [`STATIC_OFFSET`] plus its slot in [`Vm::statics`], and array elements sit at
+//! [`ARRAY_BASE`] plus their index times the size the element has in HotSpot. Arrays can
+//! be accessed at any width, like a byte array read a long at a time
+
+use crate::vm::{exception::Throw, heap::Object, value::Value, Vm};
+
+/// offset of the first element of every array
+const ARRAY_BASE: i64 = 16;
+/// offsets of static fields start here, above any instance field slot
+const STATIC_OFFSET: i64 = 1 << 32;
+
+/// bytes an element or a field with the given descriptor takes
+fn width(descriptor: &str) -> usize {
+    match descriptor.as_bytes()[0] {
+        b'Z' | b'B' => 1,
+        b'C' | b'S' => 2,
+        b'J' | b'D' => 8,
+        _ => 4,
+    }
+}
+
+fn to_bits(value: Value) -> u64 {
+    match value {
+        Value::Int(i) => i as u32 as u64,
+        Value::Long(l) => l as u64,
+        Value::Float(f) => f.to_bits() as u64,
+        Value::Double(d) => d.to_bits(),
+        v => panic!("expected primitive, got {v:?}"),
+    }
+}
+
+fn from_bits(descriptor: &str, bits: u64) -> Value {
+    match descriptor.as_bytes()[0] {
+        b'Z' => Value::Int((bits as u8 != 0) as i32),
+        b'B' => Value::Int(bits as i8 as i32),
+        b'C' => Value::Int(bits as u16 as i32),
+        b'S' => Value::Int(bits as i16 as i32),
+        b'J' => Value::Long(bits as i64),
+        b'F' => Value::Float(f32::from_bits(bits as u32)),
+        b'D' => Value::Double(f64::from_bits(bits)),
+        _ => Value::Int(bits as i32),
+    }
+}
+
+/// reads a value of type `descriptor` at `offset` of `object`, or of a static field
+fn get(vm: &mut Vm, object: Value, offset: i64, descriptor: &str) -> anyhow::Result<Value> {
+    if offset >= STATIC_OFFSET {
+        return Ok(vm.statics[(offset - STATIC_OFFSET) as usize]);
+    }
+    let Some(r) = object.as_ref() else {
+        return Err(vm.null_pointer());
+    };
+    Ok(match vm.heap.get(r) {
+        Object::Instance(instance) => instance.fields[offset as usize],
+        Object::Array(array) => {
+            let size = width(array.component());
+            let start = (offset - ARRAY_BASE) as usize;
+            if matches!(descriptor.as_bytes()[0], b'L' | b'[') {
+                return Ok(array.elements[start / size]);
+            }
+            // little endian, byte by byte from the elements covering the range
+            let mut bits = 0;
+            for i in (0..width(descriptor)).rev() {
+                let element = to_bits(array.elements[(start + i) / size]);
+                bits = bits << 8 | (element >> ((start + i) % size * 8)) & 0xff;
+            }
+            from_bits(descriptor, bits)
+        }
+        object => panic!("unsafe access to {object:?}"),
+    })
+}
+
+/// writes `value` of type `descriptor` at `offset` of `object`, or of a static field
+fn put(
+    vm: &mut Vm,
+    object: Value,
+    offset: i64,
+    descriptor: &str,
+    value: Value,
+) -> anyhow::Result<()> {
+    if offset >= STATIC_OFFSET {
+        vm.statics[(offset - STATIC_OFFSET) as usize] = value;
+        return Ok(());
+    }
+    let Some(r) = object.as_ref() else {
+        return Err(vm.null_pointer());
+    };
+    match vm.heap.get_mut(r) {
+        Object::Instance(instance) => instance.fields[offset as usize] = value,
+        Object::Array(array) => {
+            let component = array.component().to_string();
+            let size = width(&component);
+            let start = (offset - ARRAY_BASE) as usize;
+            if matches!(descriptor.as_bytes()[0], b'L' | b'[') {
+                array.elements[start / size] = value;
+                return Ok(());
+            }
+            let bits = to_bits(value);
+            for i in 0..width(descriptor) {
+                let (index, shift) = ((start + i) / size, (start + i) % size * 8);
+                let element = to_bits(array.elements[index]) & !(0xff << shift);
+                let byte = (bits >> (i * 8) & 0xff) << shift;
+                array.elements[index] = from_bits(&component, element | byte);
+            }
+        }
+        object => panic!("unsafe access to {object:?}"),
+    }
+    Ok(())
+}
+
+/// offset of the instance field `name` declared by the class of `mirror`
+fn field_offset(vm: &mut Vm, mirror: Value, name: &str) -> anyhow::Result<i64> {
+    let class = vm.mirrored_class(mirror)?;
+    match vm.layout(&class)?.declared_slot(&class, name) {
+        Some(slot) => Ok(slot as i64),
+        None => Err(vm.exception("java/lang/InternalError", name)),
+    }
+}
+
+pub fn install(vm: &mut Vm) {
+    const UNSAFE: &str = "jdk/internal/misc/Unsafe";
+    for (kind, descriptor) in [
+        ("Int", "I"),
+        ("Reference", "Ljava/lang/Object;"),
+        ("Boolean", "Z"),
+        ("Byte", "B"),
+        ("Short", "S"),
+        ("Char", "C"),
+        ("Long", "J"),
+        ("Float", "F"),
+        ("Double", "D"),
+    ] {
+        // every thread runs under the interpreter lock, so volatile accesses are plain ones
+        for suffix in ["", "Volatile"] {
+            let getter = format!("(Ljava/lang/Object;J){descriptor}");
+            vm.register_native(
+                UNSAFE,
+                &format!("get{kind}{suffix}"),
+                &getter,
+                move |vm, a| Ok(Some(get(vm, a[1], a[2].as_long(), descriptor)?)),
+            );
+            let setter = format!("(Ljava/lang/Object;J{descriptor})V");
+            vm.register_native(
+                UNSAFE,
+                &format!("put{kind}{suffix}"),
+                &setter,
+                move |vm, a| {
+                    put(vm, a[1], a[2].as_long(), descriptor, a[3])?;
+                    Ok(None)
+                },
+            );
+        }
+    }
+    for (kind, descriptor) in [
+        ("Int", "I"),
+        ("Long", "J"),
+        ("Reference", "Ljava/lang/Object;"),
+    ] {
+        let set = format!("(Ljava/lang/Object;J{descriptor}{descriptor})Z");
+        vm.register_native(
+            UNSAFE,
+            &format!("compareAndSet{kind}"),
+            &set,
+            move |vm, a| {
+                let (object, offset) = (a[1], a[2].as_long());
+                let swapped = get(vm, object, offset, descriptor)? == a[3];
+                if swapped {
+                    put(vm, object, offset, descriptor, a[4])?;
+                }
+                Ok(Some(Value::Int(swapped as i32)))
+            },
+        );
+        let exchange = format!("(Ljava/lang/Object;J{descriptor}{descriptor}){descriptor}");
+        vm.register_native(
+            UNSAFE,
+            &format!("compareAndExchange{kind}"),
+            &exchange,
+            move |vm, a| {
+                let (object, offset) = (a[1], a[2].as_long());
+                let witness = get(vm, object, offset, descriptor)?;
+                if witness == a[3] {
+                    put(vm, object, offset, descriptor, a[4])?;
+                }
+                Ok(Some(witness))
+            },
+        );
+    }
+
+    vm.register_native(
+        UNSAFE,
+        "arrayBaseOffset0",
+        "(Ljava/lang/Class;)I",
+        |_, _| Ok(Some(Value::Int(ARRAY_BASE as i32))),
+    );
+    vm.register_native(
+        UNSAFE,
+        "arrayIndexScale0",
+        "(Ljava/lang/Class;)I",
+        |vm, a| {
+            let class = vm.mirrored_class(a[1])?;
+            Ok(Some(Value::Int(width(&class[1..]) as i32)))
+        },
+    );
+    vm.register_native(
+        UNSAFE,
+        "objectFieldOffset1",
+        "(Ljava/lang/Class;Ljava/lang/String;)J",
+        |vm, a| {
+            let name = vm.string_value(a[2])?;
+            Ok(Some(Value::Long(field_offset(vm, a[1], &name)?)))
+        },
+    );
+    vm.register_native(
+        UNSAFE,
+        "shouldBeInitialized0",
+        "(Ljava/lang/Class;)Z",
+        |vm, a| {
+            let class = vm.mirrored_class(a[1])?;
+            Ok(Some(Value::Int(!vm.initialized.contains(&class) as i32)))
+        },
+    );
+    vm.register_native(
+        UNSAFE,
+        "ensureClassInitialized0",
+        "(Ljava/lang/Class;)V",
+        |vm, a| {
+            let class = vm.mirrored_class(a[1])?;
+            vm.init_class(&class)?;
+            Ok(None)
+        },
+    );
+    vm.register_native(
+        UNSAFE,
+        "allocateInstance",
+        "(Ljava/lang/Class;)Ljava/lang/Object;",
+        |vm, a| {
+            let class = vm.mirrored_class(a[1])?;
+            vm.init_class(&class)?;
+            Ok(Some(vm.new_object(&class)?))
+        },
+    );
+    vm.register_native(
+        UNSAFE,
+        "throwException",
+        "(Ljava/lang/Throwable;)V",
+        |_, a| Err(Throw(a[1]).into()),
+    );
+    vm.register_native(
+        UNSAFE,
+        "copyMemory0",
+        "(Ljava/lang/Object;JLjava/lang/Object;JJ)V",
+        |vm, a| {
+            let (source, destination) = (a[1], a[3]);
+            let (from, to, bytes) = (a[2].as_long(), a[4].as_long(), a[5].as_long());
+            // byte by byte, front to back or back to front like memmove
+            let mut copy = |i: i64| -> anyhow::Result<()> {
+                let byte = get(vm, source, from + i, "B")?;
+                put(vm, destination, to + i, "B", byte)
+            };
+            match source == destination && from < to {
+                true => (0..bytes).rev().try_for_each(&mut copy)?,
+                false => (0..bytes).try_for_each(&mut copy)?,
+            }
+            Ok(None)
+        },
+    );
+    vm.register_native(UNSAFE, "setMemory0", "(Ljava/lang/Object;JJB)V", |vm, a| {
+        let (object, offset) = (a[1], a[2].as_long());
+        for i in 0..a[3].as_long() {
+            put(vm, object, offset + i, "B", a[4])?;
+        }
+        Ok(None)
+    });
+    vm.register_native(UNSAFE, "park", "(ZJ)V", |vm, a| {
+        vm.park_thread(a[1].as_int() != 0, a[2].as_long())?;
+        Ok(None)
+    });
+    vm.register_native(UNSAFE, "unpark", "(Ljava/lang/Object;)V", |vm, a| {
+        vm.unpark_thread(a[1]);
+        Ok(None)
+    });
+    vm.register_native(UNSAFE, "getLoadAverage0", "([DI)I", |_, _| {
+        Ok(Some(Value::Int(-1)))
+    });
+}
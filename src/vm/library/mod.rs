@@ -0,0 +1,154 @@
+//! Built-in part of the java class library. Classes are described with [`ClassBuilder`]
+//! and every method is a `native` bound to a rust closure, so they take part in
+//! inheritance, dispatch and exception matching like classes loaded from disk.
+//! [`jdk`] instead holds the natives for running on the class library of a real JDK
+
+use crate::class::{
+    constant_pool::{ClassInfo, CpInfo, UtfInfo},
+    field::{FieldAccessFlags, FieldInfo},
+    method::{MethodAccessFlags, MethodInfo},
+    Class, ClassAccessFlags,
+};
+
+use super::{value::Value, Vm};
+
+mod boxing;
+mod io;
+pub mod jdk;
+mod lang;
+mod misc;
+mod record;
+mod reflect;
+mod string;
+mod thread;
+mod util;
+
+pub(super) use reflect::component;
+
+type NativeImpl = Box<dyn Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send>;
+
+pub struct ClassBuilder {
+    class: Class,
+    natives: Vec<(String, String, NativeImpl)>,
+}
+
+impl ClassBuilder {
+    pub fn new(name: &str, super_class: &str) -> Self {
+        let mut class = Class {
+            magic: 0xCAFEBABE,
+            minor_version: 0,
+            major_version: 61,
+            cp: Default::default(),
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            this_class: 0,
+            this_class_name: name.to_string(),
+            super_class: 0,
+            super_class_name: super_class.to_string(),
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+        };
+        class.this_class = class_ref(&mut class, name);
+        if !super_class.is_empty() {
+            class.super_class = class_ref(&mut class, super_class);
+        }
+        ClassBuilder {
+            class,
+            natives: vec![],
+        }
+    }
+
+    pub fn interface(name: &str) -> Self {
+        let mut builder = ClassBuilder::new(name, "java/lang/Object");
+        builder.class.access_flags =
+            ClassAccessFlags::PUBLIC | ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT;
+        builder
+    }
+
+    pub fn implements(mut self, interface: &str) -> Self {
+        let index = class_ref(&mut self.class, interface);
+        self.class.interfaces.push(index);
+        self
+    }
+
+    pub fn field(mut self, name: &str, descriptor: &str, access_flags: FieldAccessFlags) -> Self {
+        self.class.fields.push(FieldInfo {
+            access_flags,
+            name: name.to_string(),
+            descriptor: descriptor.to_string(),
+            name_index: 0,
+            descriptor_index: 0,
+            attributes: vec![],
+        });
+        self
+    }
+
+    fn native(
+        mut self,
+        name: &str,
+        descriptor: &str,
+        access_flags: MethodAccessFlags,
+        f: impl Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send + 'static,
+    ) -> Self {
+        self.class.methods.push(MethodInfo {
+            access_flags: access_flags | MethodAccessFlags::PUBLIC | MethodAccessFlags::NATIVE,
+            name: name.to_string(),
+            descriptor: descriptor.to_string(),
+            name_index: 0,
+            descriptor_index: 0,
+            attributes: vec![],
+        });
+        self.natives
+            .push((name.to_string(), descriptor.to_string(), Box::new(f)));
+        self
+    }
+
+    /// instance method, `args[0]` is the receiver
+    pub fn method(
+        self,
+        name: &str,
+        descriptor: &str,
+        f: impl Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send + 'static,
+    ) -> Self {
+        self.native(name, descriptor, MethodAccessFlags::empty(), f)
+    }
+
+    pub fn static_method(
+        self,
+        name: &str,
+        descriptor: &str,
+        f: impl Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send + 'static,
+    ) -> Self {
+        self.native(name, descriptor, MethodAccessFlags::STATIC, f)
+    }
+
+    pub fn install(self, vm: &mut Vm) {
+        let name = self.class.this_class_name.clone();
+        for (method, descriptor, f) in self.natives {
+            vm.register_native(&name, &method, &descriptor, f);
+        }
+        vm.add_class(self.class);
+    }
+}
+
+/// adds a `CONSTANT_Class` entry, so that the name can be looked up like in a parsed class
+fn class_ref(class: &mut Class, name: &str) -> usize {
+    class.cp.push(CpInfo::Utf(UtfInfo {
+        bytes: name.to_string(),
+    }));
+    let name_index = class.cp.len();
+    class.cp.push(CpInfo::Class(ClassInfo { name_index }));
+    class.cp.len()
+}
+
+pub fn install(vm: &mut Vm) {
+    lang::install(vm);
+    reflect::install(vm);
+    record::install(vm);
+    string::install(vm);
+    boxing::install(vm);
+    io::install(vm);
+    thread::install(vm);
+    util::install(vm);
+}
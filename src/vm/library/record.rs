@@ -0,0 +1,100 @@
+//! `java.lang.Record` and the `toString`, `equals` and `hashCode` of record classes, which
+//! javac compiles to an `invokedynamic` of `java.lang.runtime.ObjectMethods.bootstrap`
+
+use anyhow::Context;
+
+use crate::vm::{value::Value, Vm};
+
+use super::{
+    boxing::{compare, hash_code},
+    lang::get_field,
+    reflect::simple_name,
+    ClassBuilder,
+};
+
+impl Vm {
+    /// runs the `ObjectMethods` implementation of method `name` of record class `record`
+    /// on the components listed in its `Record` attribute. `args[0]` is the receiver
+    pub(crate) fn record_method(
+        &mut self,
+        record: &str,
+        name: &str,
+        args: &[Value],
+    ) -> anyhow::Result<Value> {
+        let class = self
+            .load_class(record)?
+            .with_context(|| format!("no record class {record}"))?;
+        let components = class
+            .record_components()
+            .with_context(|| format!("{record} is not a record class"))?;
+        let Some(this) = args[0].as_ref() else {
+            return Err(self.null_pointer());
+        };
+        match name {
+            "toString" => {
+                let mut fields = Vec::with_capacity(components.len());
+                for component in components {
+                    let value = get_field(self, args[0], &component.name);
+                    let string = self.java_string(value, &component.descriptor)?;
+                    fields.push(format!("{}={string}", component.name));
+                }
+                let string = format!("{}[{}]", simple_name(self, record)?, fields.join(", "));
+                Ok(self.new_string(string))
+            }
+            "equals" => {
+                let same_class = args[1]
+                    .as_ref()
+                    .is_some_and(|other| self.heap.class_name(other) == self.heap.class_name(this));
+                if !same_class {
+                    return Ok(Value::Int(0));
+                }
+                for component in components {
+                    let a = get_field(self, args[0], &component.name);
+                    let b = get_field(self, args[1], &component.name);
+                    let equal = match component.descriptor.as_bytes()[0] {
+                        b'L' | b'[' if a == b => true,
+                        b'L' | b'[' if a == Value::Null => false,
+                        b'L' | b'[' => {
+                            let descriptor = "(Ljava/lang/Object;)Z";
+                            let equals = self.invoke_virtual(
+                                "java/lang/Object",
+                                "equals",
+                                descriptor,
+                                vec![a, b],
+                            )?;
+                            equals.unwrap().as_int() != 0
+                        }
+                        _ => compare(a, b) == 0,
+                    };
+                    if !equal {
+                        return Ok(Value::Int(0));
+                    }
+                }
+                Ok(Value::Int(1))
+            }
+            "hashCode" => {
+                let mut hash = 0i32;
+                for component in components {
+                    let value = get_field(self, args[0], &component.name);
+                    let component_hash = match (component.descriptor.as_bytes()[0], value) {
+                        (b'L' | b'[', Value::Null) => 0,
+                        (b'L' | b'[', value) => self
+                            .invoke_virtual("java/lang/Object", "hashCode", "()I", vec![value])?
+                            .unwrap()
+                            .as_int(),
+                        _ => hash_code(value, &component.descriptor),
+                    };
+                    hash = hash.wrapping_mul(31).wrapping_add(component_hash);
+                }
+                Ok(Value::Int(hash))
+            }
+            name => anyhow::bail!("ObjectMethods has no method {name}"),
+        }
+    }
+}
+
+pub fn install(vm: &mut Vm) {
+    ClassBuilder::new("java/lang/Record", "java/lang/Object")
+        .method("<init>", "()V", |_, _| Ok(None))
+        .install(vm);
+}
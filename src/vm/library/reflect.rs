@@ -0,0 +1,1494 @@
+//! `java.lang.Class`, whose objects come from [`Vm::class_object`], and the members of
+//! `java.lang.reflect`. A `Method`, `Field` or `Constructor` holds the `Class` declaring
+//! it and its index in the methods or fields of that class. Using a member checks that
+//! the calling method may access it, unless `setAccessible(true)` was called, which a
+//! named module only allows for the packages it opens to the caller
+
+use std::rc::Rc;
+
+use anyhow::Context;
+
+use crate::{
+    class::{
+        descriptor::{class_name, MethodDescriptor},
+        field::{FieldAccessFlags, FieldInfo},
+        method::MethodAccessFlags,
+        Class, ClassAccessFlags,
+    },
+    source::ByteStream,
+    vm::{
+        access::{is_boot, package, type_name},
+        exception::Throw,
+        module::module_name,
+        value::Value,
+        Vm,
+    },
+};
+
+use super::{
+    boxing::{primitive_name, wrapped_primitive},
+    lang::{get_field, identity_hash, set_field},
+    ClassBuilder,
+};
+
+pub(super) const PRIMITIVES: [&str; 9] = [
+    "boolean", "byte", "char", "short", "int", "long", "float", "double", "void",
+];
+
+pub(super) fn has_flag(vm: &mut Vm, class: &str, flag: ClassAccessFlags) -> anyhow::Result<bool> {
+    Ok(vm
+        .load_class(class)?
+        .is_some_and(|c| c.access_flags.contains(flag)))
+}
+
+/// `Class.getSuperclass`, `None` for `Object`, interfaces and primitive types
+pub(super) fn super_class(vm: &mut Vm, class: &str) -> anyhow::Result<Option<String>> {
+    if class.starts_with('[') {
+        return Ok(Some("java/lang/Object".to_string()));
+    }
+    let Some(c) = vm.load_class(class)? else {
+        return Ok(None);
+    };
+    Ok(match c.super_class {
+        _ if c.access_flags.contains(ClassAccessFlags::INTERFACE) => None,
+        0 => None,
+        _ => Some(c.super_class_name.clone()),
+    })
+}
+
+/// element type of an array class, as a class name or the name of a primitive type
+pub(in crate::vm) fn component(array: &str) -> Option<&str> {
+    let component = array.strip_prefix('[')?;
+    Some(match component.len() {
+        1 => primitive_name(component),
+        _ => class_name(component),
+    })
+}
+
+/// `Class.getSimpleName`, from the `InnerClasses` attribute for nested classes
+pub(super) fn simple_name(vm: &mut Vm, class: &str) -> anyhow::Result<String> {
+    if let Some(component) = component(class) {
+        return Ok(format!("{}[]", simple_name(vm, component)?));
+    }
+    Ok(match vm.load_class(class)? {
+        Some(c) => c.simple_name().to_string(),
+        None => class.rsplit('/').next().unwrap().to_string(),
+    })
+}
+
+/// `Class.getCanonicalName`, `None` for local and anonymous classes and arrays of them
+pub(super) fn canonical_name(vm: &mut Vm, class: &str) -> anyhow::Result<Option<String>> {
+    if let Some(component) = component(class) {
+        return Ok(canonical_name(vm, component)?.map(|name| format!("{name}[]")));
+    }
+    let Some(c) = vm.load_class(class)? else {
+        return Ok(Some(class.replace('/', ".")));
+    };
+    if c.is_anonymous() || c.is_local() {
+        return Ok(None);
+    }
+    Ok(match c.declaring_class() {
+        Some(declaring) => {
+            canonical_name(vm, declaring)?.map(|name| format!("{name}.{}", c.simple_name()))
+        }
+        None => Some(class.replace('/', ".")),
+    })
+}
+
+/// the `Class` of a class named in a class file, or `null`
+fn class_or_null(vm: &mut Vm, class: Option<&str>) -> anyhow::Result<Option<Value>> {
+    match class {
+        Some(class) => Ok(Some(vm.class_object(class)?)),
+        None => Ok(Some(Value::Null)),
+    }
+}
+
+pub(super) fn bool(b: bool) -> anyhow::Result<Option<Value>> {
+    Ok(Some(Value::Int(b as i32)))
+}
+
+/// `int` -> `I`, and the descriptor of a class or array class
+fn descriptor(class: &str) -> String {
+    match PRIMITIVES.iter().position(|p| *p == class) {
+        Some(i) => "ZBCSIJFDV"[i..i + 1].to_string(),
+        None if class.starts_with('[') => class.to_string(),
+        None => format!("L{class};"),
+    }
+}
+
+/// `Class.getName` of a field type: `int`, `java.lang.String` or `[Ljava.lang.String;`
+fn descriptor_name(descriptor: &str) -> String {
+    match descriptor.len() {
+        1 => primitive_name(descriptor).to_string(),
+        _ => class_name(descriptor).replace('/', "."),
+    }
+}
+
+/// the `Class` of a field, parameter or return type
+fn type_class(vm: &mut Vm, descriptor: &str) -> anyhow::Result<Value> {
+    match descriptor.len() {
+        1 => vm.class_object(primitive_name(descriptor)),
+        _ => vm.class_object(class_name(descriptor)),
+    }
+}
+
+/// the class a `Class` stands for, `None` for primitive types and arrays, which have
+/// no members
+fn members(vm: &mut Vm, mirror: Value) -> anyhow::Result<Option<Rc<Class>>> {
+    let class = vm.mirrored_class(mirror)?;
+    match class.starts_with('[') || PRIMITIVES.contains(&class.as_str()) {
+        true => Ok(None),
+        false => vm.load_class(&class),
+    }
+}
+
+/// array of type `class` holding `len` values made by `element`
+fn new_array(
+    vm: &mut Vm,
+    class: &str,
+    len: usize,
+    mut element: impl FnMut(&mut Vm, usize) -> anyhow::Result<Value>,
+) -> anyhow::Result<Option<Value>> {
+    let array = vm.heap.new_array(class, len);
+    vm.roots.push(array);
+    let filled = (0..len).try_for_each(|i| {
+        let value = element(vm, i)?;
+        vm.heap.get_array_mut(array.as_ref().unwrap()).elements[i] = value;
+        anyhow::Ok(())
+    });
+    vm.roots.pop();
+    filled.map(|_| Some(array))
+}
+
+fn class_array(vm: &mut Vm, descriptors: &[String]) -> anyhow::Result<Option<Value>> {
+    new_array(vm, "[Ljava/lang/Class;", descriptors.len(), |vm, i| {
+        type_class(vm, &descriptors[i])
+    })
+}
+
+/// the `Method`, `Field` or `Constructor` object of type `kind` for the member at `slot`
+/// of `class`
+fn member_object(vm: &mut Vm, kind: &str, class: &str, slot: usize) -> anyhow::Result<Value> {
+    let object = vm.new_object(kind)?;
+    vm.roots.push(object);
+    let mirror = vm.class_object(class);
+    vm.roots.pop();
+    set_field(vm, object, "clazz", mirror?);
+    set_field(vm, object, "slot", Value::Int(slot as i32));
+    Ok(object)
+}
+
+/// `Class.getDeclaredMethods0`, or `getDeclaredConstructors0` for `constructors`, of a
+/// JDK's class library, whose reflection objects carry their types and modifiers
+pub(super) fn declared_methods(
+    vm: &mut Vm,
+    mirror: Value,
+    public_only: bool,
+    constructors: bool,
+) -> anyhow::Result<Option<Value>> {
+    let (kind, array) = match constructors {
+        true => (
+            "java/lang/reflect/Constructor",
+            "[Ljava/lang/reflect/Constructor;",
+        ),
+        false => ("java/lang/reflect/Method", "[Ljava/lang/reflect/Method;"),
+    };
+    let c = members(vm, mirror)?;
+    let slots: Vec<_> = c
+        .iter()
+        .flat_map(|c| c.methods.iter().enumerate())
+        .filter(|(_, m)| is_constructor(&m.name) == constructors && m.name != "<clinit>")
+        .filter(|(_, m)| !public_only || m.access_flags.contains(MethodAccessFlags::PUBLIC))
+        .map(|(i, _)| i)
+        .collect();
+    new_array(vm, array, slots.len(), |vm, i| {
+        let c = c.as_ref().unwrap();
+        let method = &c.methods[slots[i]];
+        let descriptor =
+            MethodDescriptor::parse(&method.descriptor).context("invalid descriptor")?;
+        let exceptions: Vec<_> = method
+            .exceptions()
+            .iter()
+            .map(|e| format!("L{e};"))
+            .collect();
+        let object = member_object(vm, kind, &c.this_class_name, slots[i])?;
+        vm.roots.push(object);
+        let modifiers = method.access_flags.bits() & METHOD_MODIFIERS.0;
+        set_field(vm, object, "modifiers", Value::Int(modifiers as i32));
+        let filled = (|| {
+            let parameters = class_array(vm, &descriptor.args)?.unwrap();
+            set_field(vm, object, "parameterTypes", parameters);
+            let exceptions = class_array(vm, &exceptions)?.unwrap();
+            set_field(vm, object, "exceptionTypes", exceptions);
+            if !constructors {
+                let name = vm.intern(&method.name);
+                set_field(vm, object, "name", name);
+                let ret = type_class(vm, &descriptor.ret)?;
+                set_field(vm, object, "returnType", ret);
+            }
+            anyhow::Ok(object)
+        })();
+        vm.roots.pop();
+        filled
+    })
+}
+
+/// `Class.getDeclaredFields0` of a JDK's class library
+pub(super) fn declared_fields(
+    vm: &mut Vm,
+    mirror: Value,
+    public_only: bool,
+) -> anyhow::Result<Option<Value>> {
+    let c = members(vm, mirror)?;
+    let slots: Vec<_> = c
+        .iter()
+        .flat_map(|c| c.fields.iter().enumerate())
+        .filter(|(_, f)| !public_only || f.access_flags.contains(FieldAccessFlags::PUBLIC))
+        .map(|(i, _)| i)
+        .collect();
+    new_array(vm, "[Ljava/lang/reflect/Field;", slots.len(), |vm, i| {
+        let c = c.as_ref().unwrap();
+        let field = &c.fields[slots[i]];
+        let object = member_object(vm, "java/lang/reflect/Field", &c.this_class_name, slots[i])?;
+        vm.roots.push(object);
+        let flags = field.access_flags;
+        let modifiers = flags.bits() & FIELD_MODIFIERS.0;
+        set_field(vm, object, "modifiers", Value::Int(modifiers as i32));
+        // like HotSpot, static final fields may not be changed even with setAccessible
+        let trusted = flags.contains(FieldAccessFlags::STATIC | FieldAccessFlags::FINAL);
+        set_field(vm, object, "trustedFinal", Value::Int(trusted as i32));
+        let filled = (|| {
+            let name = vm.intern(&field.name);
+            set_field(vm, object, "name", name);
+            let field_type = type_class(vm, &field.descriptor)?;
+            set_field(vm, object, "type", field_type);
+            anyhow::Ok(object)
+        })();
+        vm.roots.pop();
+        filled
+    })
+}
+
+/// class declaring the member a reflection object stands for, and its index there
+fn member(vm: &mut Vm, this: Value) -> anyhow::Result<(Rc<Class>, usize)> {
+    let class = vm.mirrored_class(get_field(vm, this, "clazz"))?;
+    let c = vm
+        .load_class(&class)?
+        .with_context(|| format!("no class {class}"))?;
+    Ok((c, get_field(vm, this, "slot").as_int() as usize))
+}
+
+fn is_constructor(name: &str) -> bool {
+    name == "<init>"
+}
+
+/// the parameters in a method descriptor, without the parentheses
+fn parameters(descriptor: &str) -> &str {
+    descriptor[1..]
+        .split_once(')')
+        .map_or("", |(params, _)| params)
+}
+
+/// parameter descriptors for the classes in a `Class[]`, and the names `NoSuchMethodException`
+/// lists them by
+fn parameter_types(vm: &mut Vm, types: Value) -> anyhow::Result<(String, Vec<String>)> {
+    let types = match types.as_ref() {
+        Some(r) => vm.heap.get_array(r).elements.clone(),
+        None => vec![],
+    };
+    let (mut params, mut names) = (String::new(), vec![]);
+    for class in types {
+        if class == Value::Null {
+            params.push_str("null");
+            names.push("null".to_string());
+            continue;
+        }
+        let class = vm.mirrored_class(class)?;
+        params.push_str(&descriptor(&class));
+        names.push(class.replace('/', "."));
+    }
+    Ok((params, names))
+}
+
+fn no_such_method(vm: &mut Vm, class: &str, name: &str, names: &[String]) -> anyhow::Error {
+    let message = format!("{}.{name}({})", class.replace('/', "."), names.join(","));
+    vm.exception("java/lang/NoSuchMethodException", &message)
+}
+
+/// `Class.getMethod`: a public method of the class, its superclasses or its
+/// superinterfaces
+fn public_method(
+    vm: &mut Vm,
+    class: &str,
+    name: &str,
+    params: &str,
+) -> anyhow::Result<Option<(Rc<Class>, usize)>> {
+    let Some(c) = vm.load_class(class)? else {
+        return Ok(None);
+    };
+    let found = c.methods.iter().position(|m| {
+        m.name == name
+            && parameters(&m.descriptor) == params
+            && m.access_flags.contains(MethodAccessFlags::PUBLIC)
+    });
+    if let Some(i) = found {
+        return Ok(Some((c, i)));
+    }
+    if c.super_class != 0 {
+        if let Some(found) = public_method(vm, &c.super_class_name, name, params)? {
+            return Ok(Some(found));
+        }
+    }
+    for interface in c.interface_names() {
+        if let Some(found) = public_method(vm, interface, name, params)? {
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+/// `Class.getDeclaredMethod` and `getMethod`, `public` only for the latter
+fn get_method(vm: &mut Vm, args: &[Value], public: bool) -> anyhow::Result<Option<Value>> {
+    let class = vm.mirrored_class(args[0])?;
+    let name = vm.string_value(args[1])?;
+    let (params, names) = parameter_types(vm, args[2])?;
+    let found = match members(vm, args[0])? {
+        _ if is_constructor(&name) || name == "<clinit>" => None,
+        Some(_) if public => public_method(vm, &class, &name, &params)?,
+        Some(c) => {
+            let i = c
+                .methods
+                .iter()
+                .position(|m| m.name == name && parameters(&m.descriptor) == params);
+            i.map(|i| (c, i))
+        }
+        None => None,
+    };
+    match found {
+        Some((c, i)) => {
+            let method = member_object(vm, "java/lang/reflect/Method", &c.this_class_name, i)?;
+            Ok(Some(method))
+        }
+        None => Err(no_such_method(vm, &class, &name, &names)),
+    }
+}
+
+/// `Class.getDeclaredConstructor` and `getConstructor`, `public` only for the latter
+fn get_constructor(vm: &mut Vm, args: &[Value], public: bool) -> anyhow::Result<Option<Value>> {
+    let class = vm.mirrored_class(args[0])?;
+    let (params, names) = parameter_types(vm, args[1])?;
+    let found = members(vm, args[0])?.and_then(|c| {
+        c.methods.iter().position(|m| {
+            is_constructor(&m.name)
+                && parameters(&m.descriptor) == params
+                && (!public || m.access_flags.contains(MethodAccessFlags::PUBLIC))
+        })
+    });
+    match found {
+        Some(i) => Ok(Some(member_object(
+            vm,
+            "java/lang/reflect/Constructor",
+            &class,
+            i,
+        )?)),
+        None => Err(no_such_method(vm, &class, "<init>", &names)),
+    }
+}
+
+/// `Class.getDeclaredField` and `getField`, which also finds public fields of
+/// superclasses and superinterfaces
+fn get_field_object(vm: &mut Vm, args: &[Value], public: bool) -> anyhow::Result<Option<Value>> {
+    let class = vm.mirrored_class(args[0])?;
+    let name = vm.string_value(args[1])?;
+    let found = match members(vm, args[0])? {
+        Some(_) if public => vm
+            .resolve_field(&class, &name)?
+            .filter(|(c, i)| c.fields[*i].access_flags.contains(FieldAccessFlags::PUBLIC)),
+        Some(c) => c.fields.iter().position(|f| f.name == name).map(|i| (c, i)),
+        None => None,
+    };
+    match found {
+        Some((c, i)) => {
+            let field = member_object(vm, "java/lang/reflect/Field", &c.this_class_name, i)?;
+            Ok(Some(field))
+        }
+        None => Err(vm.exception("java/lang/NoSuchFieldException", &name)),
+    }
+}
+
+/// `Class.forName`, which takes binary names like `a.b.C$D` and `[Ljava.lang.String;`
+/// and initializes the class
+pub(super) fn for_name(
+    vm: &mut Vm,
+    name: Value,
+    initialize: bool,
+) -> anyhow::Result<Option<Value>> {
+    let name = vm.string_value(name)?;
+    let class = name.replace('.', "/");
+    let element = class.trim_start_matches('[');
+    let element = match element.strip_prefix('L') {
+        Some(element) if class.starts_with('[') => element.strip_suffix(';'),
+        _ if class.starts_with('[') => {
+            Some(element).filter(|e| e.len() == 1 && "ZBCSIJFD".contains(*e))
+        }
+        _ => Some(element),
+    };
+    let found = match element {
+        Some(element) if name.contains('/') || element.is_empty() => false,
+        Some(element) if element.len() == 1 && class.starts_with('[') => true,
+        Some(element) => vm.load_class(element)?.is_some(),
+        None => false,
+    };
+    if !found {
+        return Err(vm.exception("java/lang/ClassNotFoundException", &name));
+    }
+    if initialize && !class.starts_with('[') {
+        vm.init_class(&class)?;
+    }
+    Ok(Some(vm.class_object(&class)?))
+}
+
+/// `Class.newInstance`: runs the nullary constructor, whose exceptions are not wrapped
+fn new_instance(vm: &mut Vm, mirror: Value) -> anyhow::Result<Option<Value>> {
+    let class = vm.mirrored_class(mirror)?;
+    let c = members(vm, mirror)?;
+    let instantiable = c.as_ref().is_some_and(|c| {
+        !c.access_flags
+            .intersects(ClassAccessFlags::ABSTRACT | ClassAccessFlags::INTERFACE)
+    });
+    if !instantiable {
+        return Err(vm.exception("java/lang/InstantiationException", &class.replace('/', ".")));
+    }
+    let c = c.unwrap();
+    let nullary = c
+        .methods
+        .iter()
+        .find(|m| is_constructor(&m.name) && m.descriptor == "()V");
+    let Some(nullary) = nullary else {
+        return Err(vm.exception("java/lang/InstantiationException", &class.replace('/', ".")));
+    };
+    check_access(
+        vm,
+        None,
+        &c,
+        nullary.access_flags.bits(),
+        CONSTRUCTOR_MODIFIERS,
+    )?;
+    let object = vm.new_object(&class)?;
+    vm.roots.push(object);
+    let constructed = vm.invoke_special(&class, "<init>", "()V", [object]);
+    vm.roots.pop();
+    constructed?;
+    Ok(Some(object))
+}
+
+/// `Class.getModifiers`: primitive types are `public abstract final`, arrays take the
+/// visibility of their element type
+pub(super) fn class_modifiers(vm: &mut Vm, class: &str) -> anyhow::Result<i32> {
+    const PRIMITIVE: i32 = 0x411;
+    if PRIMITIVES.contains(&class) {
+        return Ok(PRIMITIVE);
+    }
+    if let Some(component) = component(class) {
+        return Ok(class_modifiers(vm, component)? & 0x7 | 0x410);
+    }
+    Ok(vm.load_class(class)?.map_or(0, |c| c.modifiers() as i32))
+}
+
+/// `public static final`, in the order of `Modifier.toString`
+fn modifier_string(modifiers: i32) -> String {
+    const NAMES: [(i32, &str); 12] = [
+        (0x1, "public"),
+        (0x4, "protected"),
+        (0x2, "private"),
+        (0x400, "abstract"),
+        (0x8, "static"),
+        (0x10, "final"),
+        (0x80, "transient"),
+        (0x40, "volatile"),
+        (0x20, "synchronized"),
+        (0x100, "native"),
+        (0x800, "strictfp"),
+        (0x200, "interface"),
+    ];
+    let names: Vec<_> = NAMES
+        .iter()
+        .filter(|(flag, _)| modifiers & flag != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    names.join(" ")
+}
+
+/// `Method.getModifiers`, and the flags of them `Method.toString` shows
+const METHOD_MODIFIERS: (usize, i32) = (0x1DFF, 0xD3F);
+const CONSTRUCTOR_MODIFIERS: (usize, i32) = (0x1DFF, 0x7);
+const FIELD_MODIFIERS: (usize, i32) = (0x50DF, 0xDF);
+
+/// `public int Plugin.run(java.lang.String,int)` and `public Plugin()`
+fn method_string(c: &Class, slot: usize) -> anyhow::Result<String> {
+    let method = &c.methods[slot];
+    let descriptor = MethodDescriptor::parse(&method.descriptor).context("invalid descriptor")?;
+    let (recognized, shown) = match is_constructor(&method.name) {
+        true => CONSTRUCTOR_MODIFIERS,
+        false => METHOD_MODIFIERS,
+    };
+    let modifiers = (method.access_flags.bits() & recognized) as i32 & shown;
+    let mut string = modifier_string(modifiers);
+    if !string.is_empty() {
+        string.push(' ');
+    }
+    let class = type_name(&format!("L{};", c.this_class_name));
+    let params: Vec<_> = descriptor.args.iter().map(|p| type_name(p)).collect();
+    match is_constructor(&method.name) {
+        true => string.push_str(&class),
+        false => {
+            let ret = type_name(&descriptor.ret);
+            string.push_str(&format!("{ret} {class}.{}", method.name));
+        }
+    }
+    string.push_str(&format!("({})", params.join(",")));
+    let exceptions: Vec<_> = method
+        .exceptions()
+        .iter()
+        .map(|e| e.replace('/', "."))
+        .collect();
+    if !exceptions.is_empty() {
+        string.push_str(&format!(" throws {}", exceptions.join(",")));
+    }
+    Ok(string)
+}
+
+/// `private long Doubler.calls`, like `Field.toString`
+fn field_string(c: &Class, slot: usize) -> String {
+    let field = &c.fields[slot];
+    let (recognized, shown) = FIELD_MODIFIERS;
+    let mut string = modifier_string((field.access_flags.bits() & recognized) as i32 & shown);
+    if !string.is_empty() {
+        string.push(' ');
+    }
+    let class = c.this_class_name.replace('/', ".");
+    let field_type = type_name(&field.descriptor);
+    string.push_str(&format!("{field_type} {class}.{}", field.name));
+    string
+}
+
+/// `class Main` or `interface Plugin`, like `Class.toString`
+fn class_string(c: &Class) -> String {
+    let kind = match c.access_flags.contains(ClassAccessFlags::INTERFACE) {
+        true => "interface",
+        false => "class",
+    };
+    format!("{kind} {}", c.this_class_name.replace('/', "."))
+}
+
+/// throws `IllegalAccessException` when the caller may not use the member of `c` with
+/// access `flags`, unless `setAccessible(true)` was called on the reflection object
+fn check_access(
+    vm: &mut Vm,
+    this: Option<Value>,
+    c: &Class,
+    flags: usize,
+    (recognized, _): (usize, i32),
+) -> anyhow::Result<()> {
+    // the JDK's reflection checks access itself before calling into the vm
+    if vm.jdk || this.is_some_and(|this| get_field(vm, this, "override").as_int() != 0) {
+        return Ok(());
+    }
+    if vm.can_reflect(c, flags)? {
+        return Ok(());
+    }
+    let from = vm.frames.last().unwrap().class.clone();
+    let (from_name, name) = (&from.this_class_name, &c.this_class_name);
+    let message = match vm.module_denial(from_name, name, true) {
+        Some(reason) => format!(
+            "{}{} cannot access {}{} because {reason}",
+            class_string(&from),
+            vm.in_module(from_name),
+            class_string(c),
+            vm.in_module(name)
+        ),
+        None => format!(
+            "{}{} cannot access a member of {}{} with modifiers \"{}\"",
+            class_string(&from),
+            vm.in_module(from_name),
+            class_string(c),
+            vm.in_module(name),
+            modifier_string((flags & recognized) as i32)
+        ),
+    };
+    Err(vm.exception("java/lang/IllegalAccessException", &message))
+}
+
+/// why `setAccessible(true)` may not be called on member `this` by the calling method: a
+/// member of a named module needs the module to open its package to the caller, or to
+/// export it when the member and its class are public
+fn inaccessible(vm: &mut Vm, this: Value) -> anyhow::Result<Option<String>> {
+    let (c, slot) = member(vm, this)?;
+    let Some(from) = vm.frames.last().map(|frame| frame.class.clone()) else {
+        return Ok(None);
+    };
+    let name = &c.this_class_name;
+    let Some(module) = vm.modules.of(name) else {
+        return Ok(None);
+    };
+    let caller = vm.modules.of(&from.this_class_name);
+    if caller.is_some_and(|m| m.name == module.name) {
+        return Ok(None);
+    }
+    let field = vm.heap.class_name(this.as_ref().unwrap()) == "java/lang/reflect/Field";
+    let flags = match field {
+        true => c.fields[slot].access_flags.bits(),
+        false => c.methods[slot].access_flags.bits(),
+    };
+    let public = c.access_flags.contains(ClassAccessFlags::PUBLIC)
+        && flags & MethodAccessFlags::PUBLIC.bits() != 0;
+    let package = package(name);
+    if public && module.exports(package, caller) || module.opens(package, caller) {
+        return Ok(None);
+    }
+    let member = match field {
+        true => format!("field {}", field_string(&c, slot)),
+        false => method_string(&c, slot)?,
+    };
+    Ok(Some(format!(
+        "Unable to make {member} accessible: {} does not \"{} {}\" to {}",
+        module_name(Some(module)),
+        if public { "exports" } else { "opens" },
+        package.replace('/', "."),
+        module_name(caller)
+    )))
+}
+
+/// whether the primitive type `from` converts to `to` by identity or a widening
+/// primitive conversion, JLS §5.1.2
+fn widens(from: &str, to: &str) -> bool {
+    from == to
+        || to.len() == 1
+            && match from {
+                "B" => "SIJFD",
+                "S" | "C" => "IJFD",
+                "I" => "JFD",
+                "J" => "FD",
+                "F" => "D",
+                _ => "",
+            }
+            .contains(to)
+}
+
+/// `value` as an argument or field value of type `descriptor`, unboxed and widened for
+/// primitive types. `None` when it does not convert
+fn unreflect(vm: &mut Vm, value: Value, descriptor: &str) -> anyhow::Result<Option<Value>> {
+    if descriptor.starts_with(['L', '[']) {
+        let Some(r) = value.as_ref() else {
+            return Ok(Some(value));
+        };
+        let class = vm.heap.class_name(r).to_string();
+        return Ok(vm
+            .is_assignable(&class, class_name(descriptor))?
+            .then_some(value));
+    }
+    let primitive = value
+        .as_ref()
+        .and_then(|r| wrapped_primitive(vm.heap.class_name(r)));
+    match primitive {
+        Some(primitive) if widens(primitive, descriptor) => {
+            Ok(Some(vm.adapt(value, "Ljava/lang/Object;", descriptor)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// the arguments in the `Object[]` of a reflective call, converted to the parameter types
+fn arguments(vm: &mut Vm, array: Value, params: &[String]) -> anyhow::Result<Vec<Value>> {
+    let args = match array.as_ref() {
+        Some(r) => vm.heap.get_array(r).elements.clone(),
+        None => vec![],
+    };
+    if args.len() != params.len() {
+        let message = "wrong number of arguments";
+        return Err(vm.exception("java/lang/IllegalArgumentException", message));
+    }
+    let mut converted = Vec::with_capacity(args.len());
+    for (arg, param) in args.into_iter().zip(params) {
+        match unreflect(vm, arg, param)? {
+            Some(value) => converted.push(value),
+            None => {
+                let message = "argument type mismatch";
+                return Err(vm.exception("java/lang/IllegalArgumentException", message));
+            }
+        }
+    }
+    Ok(converted)
+}
+
+/// the exception for a `null` receiver of an instance method or field, whose message
+/// comes from the `obj.getClass()` the JDK checks it with
+fn null_receiver(vm: &mut Vm) -> anyhow::Error {
+    let message = "Cannot invoke \"Object.getClass()\" because \"obj\" is null";
+    vm.exception("java/lang/NullPointerException", message)
+}
+
+/// wraps an exception thrown by a method called through reflection into an
+/// `InvocationTargetException`
+fn invocation_target(vm: &mut Vm, err: anyhow::Error) -> anyhow::Error {
+    let Some(&Throw(exception)) = err.downcast_ref::<Throw>() else {
+        return err;
+    };
+    vm.roots.push(exception);
+    let wrapper = vm.exception("java/lang/reflect/InvocationTargetException", "");
+    vm.roots.pop();
+    if let Some(&Throw(target)) = wrapper.downcast_ref::<Throw>() {
+        set_field(vm, target, "cause", exception);
+    }
+    wrapper
+}
+
+/// `Method.invoke`: static methods ignore the receiver, private methods are not
+/// dispatched on it
+pub(super) fn invoke(vm: &mut Vm, args: &[Value]) -> anyhow::Result<Option<Value>> {
+    let (c, slot) = member(vm, args[0])?;
+    let method = &c.methods[slot];
+    let descriptor = MethodDescriptor::parse(&method.descriptor).context("invalid descriptor")?;
+    let class = &c.this_class_name;
+    let flags = method.access_flags;
+    check_access(vm, Some(args[0]), &c, flags.bits(), METHOD_MODIFIERS)?;
+    let mut call_args = vec![];
+    if !flags.contains(MethodAccessFlags::STATIC) {
+        let Some(receiver) = args[1].as_ref() else {
+            return Err(null_receiver(vm));
+        };
+        let receiver_class = vm.heap.class_name(receiver).to_string();
+        if !vm.is_assignable(&receiver_class, class)? {
+            let message = "object is not an instance of declaring class";
+            return Err(vm.exception("java/lang/IllegalArgumentException", message));
+        }
+        call_args.push(args[1]);
+    }
+    call_args.extend(arguments(vm, args[2], &descriptor.args)?);
+    let (name, method_descriptor) = (&method.name, &method.descriptor);
+    let result = match () {
+        _ if flags.contains(MethodAccessFlags::STATIC) => {
+            vm.invoke_static(class, name, method_descriptor, call_args)
+        }
+        _ if flags.contains(MethodAccessFlags::PRIVATE) => {
+            vm.invoke_special(class, name, method_descriptor, call_args)
+        }
+        _ => vm.invoke_virtual(class, name, method_descriptor, call_args),
+    };
+    match result {
+        Ok(Some(value)) if descriptor.ret.len() == 1 => {
+            Ok(Some(vm.box_value(value, &descriptor.ret)?))
+        }
+        Ok(Some(value)) => Ok(Some(value)),
+        Ok(None) => Ok(Some(Value::Null)),
+        Err(err) => Err(invocation_target(vm, err)),
+    }
+}
+
+/// `Constructor.newInstance`
+pub(super) fn construct(vm: &mut Vm, args: &[Value]) -> anyhow::Result<Option<Value>> {
+    let (c, slot) = member(vm, args[0])?;
+    let method = &c.methods[slot];
+    let descriptor = MethodDescriptor::parse(&method.descriptor).context("invalid descriptor")?;
+    let class = &c.this_class_name;
+    let flags = method.access_flags.bits();
+    check_access(vm, Some(args[0]), &c, flags, CONSTRUCTOR_MODIFIERS)?;
+    if c.access_flags.contains(ClassAccessFlags::ENUM) {
+        let message = "Cannot reflectively create enum objects";
+        return Err(vm.exception("java/lang/IllegalArgumentException", message));
+    }
+    if c.access_flags
+        .intersects(ClassAccessFlags::ABSTRACT | ClassAccessFlags::INTERFACE)
+    {
+        return Err(vm.exception("java/lang/InstantiationException", ""));
+    }
+    let mut call_args = arguments(vm, args[1], &descriptor.args)?;
+    let object = vm.new_object(class)?;
+    call_args.insert(0, object);
+    vm.roots.push(object);
+    let constructed = vm.invoke_special(class, "<init>", &method.descriptor, call_args);
+    vm.roots.pop();
+    match constructed {
+        Ok(_) => Ok(Some(object)),
+        Err(err) => Err(invocation_target(vm, err)),
+    }
+}
+
+/// `Can not set static final int field Main.LIMIT to java.lang.String`, which the JDK
+/// reports for fields read or written with a value or receiver of the wrong type. `to`
+/// describes the value
+fn field_message(c: &Class, field: &FieldInfo, to: &str) -> String {
+    let mut message = "Can not set".to_string();
+    if field.access_flags.contains(FieldAccessFlags::STATIC) {
+        message.push_str(" static");
+    }
+    if field.access_flags.contains(FieldAccessFlags::FINAL) {
+        message.push_str(" final");
+    }
+    let class = c.this_class_name.replace('/', ".");
+    let field_type = descriptor_name(&field.descriptor);
+    format!(
+        "{message} {field_type} field {class}.{} to {to}",
+        field.name
+    )
+}
+
+/// class of a value in [`field_message`]
+fn value_class(vm: &Vm, value: Value) -> String {
+    match value.as_ref() {
+        Some(r) => vm.heap.class_name(r).replace('/', "."),
+        None => "null value".to_string(),
+    }
+}
+
+/// checks the object a non-static field is read from or written to
+fn field_receiver(vm: &mut Vm, c: &Class, field: &FieldInfo, object: Value) -> anyhow::Result<()> {
+    let Some(r) = object.as_ref() else {
+        return Err(null_receiver(vm));
+    };
+    let class = vm.heap.class_name(r).to_string();
+    if vm.is_assignable(&class, &c.this_class_name)? {
+        return Ok(());
+    }
+    let message = field_message(c, field, &value_class(vm, object));
+    Err(vm.exception("java/lang/IllegalArgumentException", &message))
+}
+
+/// value of the field of the `Field` object `this` in `object`, after the checks of
+/// `Field.get`. The getters of a primitive type, `primitive`, widen the value of a
+/// primitive field but do not unbox
+fn read_field(
+    vm: &mut Vm,
+    this: Value,
+    object: Value,
+    primitive: Option<&str>,
+) -> anyhow::Result<(Rc<Class>, usize, Value)> {
+    let (c, slot) = member(vm, this)?;
+    let field = &c.fields[slot];
+    let flags = field.access_flags.bits();
+    check_access(vm, Some(this), &c, flags, FIELD_MODIFIERS)?;
+    if let Some(primitive) = primitive.filter(|&p| !widens(&field.descriptor, p)) {
+        let message = format!(
+            "Attempt to get {} field \"{}.{}\" with illegal data type conversion to {}",
+            descriptor_name(&field.descriptor),
+            c.this_class_name.replace('/', "."),
+            field.name,
+            primitive_name(primitive)
+        );
+        return Err(vm.exception("java/lang/IllegalArgumentException", &message));
+    }
+    let value = match field.access_flags.contains(FieldAccessFlags::STATIC) {
+        true => vm.get_static(&c.this_class_name, &field.name, &field.descriptor)?,
+        false => {
+            field_receiver(vm, &c, field, object)?;
+            let index = vm.instance_slot(&c.this_class_name, &field.name)?;
+            vm.heap.get_instance(object.as_ref().unwrap()).fields[index]
+        }
+    };
+    let value = match primitive {
+        Some(primitive) => vm.adapt(value, &field.descriptor, primitive)?,
+        None => value,
+    };
+    Ok((c, slot, value))
+}
+
+/// `Field.get`, boxing primitive values
+fn field_get(vm: &mut Vm, args: &[Value]) -> anyhow::Result<Option<Value>> {
+    let (c, slot, value) = read_field(vm, args[0], args[1], None)?;
+    let descriptor = &c.fields[slot].descriptor;
+    match descriptor.len() {
+        1 => Ok(Some(vm.box_value(value, descriptor)?)),
+        _ => Ok(Some(value)),
+    }
+}
+
+/// `(int)5`, a value of a primitive type in [`field_message`]
+fn primitive_value(vm: &mut Vm, value: Value, primitive: &str) -> anyhow::Result<String> {
+    let string = vm.java_string(value, primitive)?;
+    Ok(format!("({}){string}", primitive_name(primitive)))
+}
+
+/// writes `value` to the field of the `Field` object `this` in `object`, with the checks
+/// of `Field.set`. Final fields can only be written when they are not static and
+/// `setAccessible(true)` was called. `primitive` is the type of the value for the setters
+/// of a primitive type, which widen it but do not box, `None` for `Field.set`, which unboxes
+fn write_field(
+    vm: &mut Vm,
+    [this, object, value]: [Value; 3],
+    primitive: Option<&str>,
+) -> anyhow::Result<()> {
+    let (c, slot) = member(vm, this)?;
+    let field = &c.fields[slot];
+    let flags = field.access_flags.bits();
+    check_access(vm, Some(this), &c, flags, FIELD_MODIFIERS)?;
+    let widened = match primitive {
+        Some(primitive) if widens(primitive, &field.descriptor) => {
+            Some(vm.adapt(value, primitive, &field.descriptor)?)
+        }
+        Some(primitive) => {
+            let message = field_message(&c, field, &primitive_value(vm, value, primitive)?);
+            return Err(vm.exception("java/lang/IllegalArgumentException", &message));
+        }
+        None => None,
+    };
+    let is_static = field.access_flags.contains(FieldAccessFlags::STATIC);
+    if !is_static {
+        field_receiver(vm, &c, field, object)?;
+    }
+    let accessible = get_field(vm, this, "override").as_int() != 0;
+    if field.access_flags.contains(FieldAccessFlags::FINAL) && (is_static || !accessible) {
+        let to = match widened {
+            Some(widened) => primitive_value(vm, widened, &field.descriptor)?,
+            None => value_class(vm, value),
+        };
+        let message = field_message(&c, field, &to);
+        return Err(vm.exception("java/lang/IllegalAccessException", &message));
+    }
+    let converted = match widened {
+        Some(widened) => Some(widened),
+        None => unreflect(vm, value, &field.descriptor)?,
+    };
+    let Some(value) = converted else {
+        let message = field_message(&c, field, &value_class(vm, value));
+        return Err(vm.exception("java/lang/IllegalArgumentException", &message));
+    };
+    match is_static {
+        true => vm.put_static(&c.this_class_name, &field.name, value)?,
+        false => {
+            let index = vm.instance_slot(&c.this_class_name, &field.name)?;
+            vm.heap.get_instance_mut(object.as_ref().unwrap()).fields[index] = value;
+        }
+    }
+    Ok(())
+}
+
+/// `Class.getModule`: the same `Module` object each time, kept in the `cache` of
+/// `java.lang.Module` with `java.base` first, then the unnamed module and those of the
+/// module path
+fn module_object(vm: &mut Vm, class: &str) -> anyhow::Result<Value> {
+    const MODULE: &str = "java/lang/Module";
+    let (index, name) = match vm.modules.position(class) {
+        _ if is_boot(class) => (0, Some("java.base".to_string())),
+        Some(i) => (i + 2, vm.modules.of(class).map(|m| m.name.clone())),
+        None => (1, None),
+    };
+    let cache = match vm.get_static(MODULE, "cache", "[Ljava/lang/Module;")? {
+        Value::Null => {
+            let length = vm.modules.iter().count() + 2;
+            let cache = vm.heap.new_array("[Ljava/lang/Module;", length);
+            vm.put_static(MODULE, "cache", cache)?;
+            cache
+        }
+        cache => cache,
+    };
+    let cache = cache.as_ref().unwrap();
+    if let module @ Value::Ref(_) = vm.heap.get_array(cache).elements[index] {
+        return Ok(module);
+    }
+    let module = vm.new_object(MODULE)?;
+    vm.roots.push(module);
+    let name = match name {
+        Some(name) => vm.new_string(name),
+        None => Value::Null,
+    };
+    vm.roots.pop();
+    set_field(vm, module, "name", name);
+    vm.heap.get_array_mut(cache).elements[index] = module;
+    Ok(module)
+}
+
+pub fn install(vm: &mut Vm) {
+    ClassBuilder::new("java/lang/Module", "java/lang/Object")
+        .field(
+            "cache",
+            "[Ljava/lang/Module;",
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC,
+        )
+        .field("name", "Ljava/lang/String;", FieldAccessFlags::PRIVATE)
+        .method("getName", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "name")))
+        })
+        .method("isNamed", "()Z", |vm, a| {
+            bool(get_field(vm, a[0], "name") != Value::Null)
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let string = match get_field(vm, a[0], "name") {
+                Value::Null => format!(
+                    "unnamed module @{:x}",
+                    identity_hash(a[0].as_ref().unwrap())
+                ),
+                name => format!("module {}", vm.string_value(name)?),
+            };
+            Ok(Some(vm.new_string(string)))
+        })
+        .install(vm);
+
+    ClassBuilder::new("java/lang/Class", "java/lang/Object")
+        .implements("java/io/Serializable")
+        .field("name", "Ljava/lang/String;", FieldAccessFlags::PRIVATE)
+        .method("getName", "()Ljava/lang/String;", |vm, a| {
+            let instance = vm.heap.get_instance(a[0].as_ref().unwrap());
+            Ok(instance.get("name"))
+        })
+        .method("getModule", "()Ljava/lang/Module;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            Ok(Some(module_object(vm, &class)?))
+        })
+        .method("getSimpleName", "()Ljava/lang/String;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let name = simple_name(vm, &class)?;
+            Ok(Some(vm.new_string(name)))
+        })
+        .method("getCanonicalName", "()Ljava/lang/String;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            match canonical_name(vm, &class)? {
+                Some(name) => Ok(Some(vm.new_string(name))),
+                None => Ok(Some(Value::Null)),
+            }
+        })
+        .method("getDeclaringClass", "()Ljava/lang/Class;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let c = vm.load_class(&class)?;
+            class_or_null(vm, c.as_ref().and_then(|c| c.declaring_class()))
+        })
+        .method("getEnclosingClass", "()Ljava/lang/Class;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let c = vm.load_class(&class)?;
+            class_or_null(vm, c.as_ref().and_then(|c| c.enclosing_class()))
+        })
+        .method("isAnonymousClass", "()Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            bool(vm.load_class(&class)?.is_some_and(|c| c.is_anonymous()))
+        })
+        .method("isLocalClass", "()Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            bool(vm.load_class(&class)?.is_some_and(|c| c.is_local()))
+        })
+        .method("isMemberClass", "()Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let c = vm.load_class(&class)?;
+            bool(c.is_some_and(|c| c.declaring_class().is_some()))
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let name = class.replace('/', ".");
+            let string = match () {
+                _ if PRIMITIVES.contains(&class.as_str()) => name,
+                _ if has_flag(vm, &class, ClassAccessFlags::INTERFACE)? => {
+                    format!("interface {name}")
+                }
+                _ => format!("class {name}"),
+            };
+            Ok(Some(vm.new_string(string)))
+        })
+        .method("getSuperclass", "()Ljava/lang/Class;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            match super_class(vm, &class)? {
+                Some(super_class) => Ok(Some(vm.class_object(&super_class)?)),
+                None => Ok(Some(Value::Null)),
+            }
+        })
+        .method("isInterface", "()Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            bool(has_flag(vm, &class, ClassAccessFlags::INTERFACE)?)
+        })
+        .method("isArray", "()Z", |vm, a| {
+            bool(vm.mirrored_class(a[0])?.starts_with('['))
+        })
+        .method("isPrimitive", "()Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            bool(PRIMITIVES.contains(&class.as_str()))
+        })
+        .method("isEnum", "()Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let enum_super = super_class(vm, &class)?.as_deref() == Some("java/lang/Enum");
+            bool(enum_super && has_flag(vm, &class, ClassAccessFlags::ENUM)?)
+        })
+        .method("desiredAssertionStatus", "()Z", |_, _| bool(false))
+        .static_method(
+            "forName",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            |vm, a| for_name(vm, a[0], true),
+        )
+        .method("newInstance", "()Ljava/lang/Object;", |vm, a| {
+            new_instance(vm, a[0])
+        })
+        .method("isInstance", "(Ljava/lang/Object;)Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let Some(object) = a[1].as_ref() else {
+                return bool(false);
+            };
+            let object_class = vm.heap.class_name(object).to_string();
+            bool(vm.is_assignable(&object_class, &class)?)
+        })
+        .method("isAssignableFrom", "(Ljava/lang/Class;)Z", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let from = vm.mirrored_class(a[1])?;
+            bool(vm.is_assignable(&from, &class)?)
+        })
+        .method("cast", "(Ljava/lang/Object;)Ljava/lang/Object;", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            let Some(object) = a[1].as_ref() else {
+                return Ok(Some(Value::Null));
+            };
+            let object_class = vm.heap.class_name(object).to_string();
+            if !vm.is_assignable(&object_class, &class)? {
+                let (from, to) = (object_class.replace('/', "."), class.replace('/', "."));
+                let message = format!("Cannot cast {from} to {to}");
+                return Err(vm.exception("java/lang/ClassCastException", &message));
+            }
+            Ok(Some(a[1]))
+        })
+        .method("getModifiers", "()I", |vm, a| {
+            let class = vm.mirrored_class(a[0])?;
+            Ok(Some(Value::Int(class_modifiers(vm, &class)?)))
+        })
+        .method(
+            "getDeclaredMethods",
+            "()[Ljava/lang/reflect/Method;",
+            |vm, a| {
+                let c = members(vm, a[0])?;
+                let slots: Vec<_> = c
+                    .iter()
+                    .flat_map(|c| {
+                        let methods = c.methods.iter().enumerate();
+                        methods
+                            .filter(|(_, m)| !is_constructor(&m.name) && m.name != "<clinit>")
+                            .map(|(i, _)| i)
+                    })
+                    .collect();
+                new_array(vm, "[Ljava/lang/reflect/Method;", slots.len(), |vm, i| {
+                    let class = &c.as_ref().unwrap().this_class_name;
+                    member_object(vm, "java/lang/reflect/Method", class, slots[i])
+                })
+            },
+        )
+        .method(
+            "getDeclaredMethod",
+            "(Ljava/lang/String;[Ljava/lang/Class;)Ljava/lang/reflect/Method;",
+            |vm, a| get_method(vm, a, false),
+        )
+        .method(
+            "getMethod",
+            "(Ljava/lang/String;[Ljava/lang/Class;)Ljava/lang/reflect/Method;",
+            |vm, a| get_method(vm, a, true),
+        )
+        .method(
+            "getDeclaredConstructors",
+            "()[Ljava/lang/reflect/Constructor;",
+            |vm, a| {
+                let c = members(vm, a[0])?;
+                let slots: Vec<_> = c
+                    .iter()
+                    .flat_map(|c| {
+                        let methods = c.methods.iter().enumerate();
+                        methods
+                            .filter(|(_, m)| is_constructor(&m.name))
+                            .map(|(i, _)| i)
+                    })
+                    .collect();
+                new_array(
+                    vm,
+                    "[Ljava/lang/reflect/Constructor;",
+                    slots.len(),
+                    |vm, i| {
+                        let class = &c.as_ref().unwrap().this_class_name;
+                        member_object(vm, "java/lang/reflect/Constructor", class, slots[i])
+                    },
+                )
+            },
+        )
+        .method(
+            "getDeclaredConstructor",
+            "([Ljava/lang/Class;)Ljava/lang/reflect/Constructor;",
+            |vm, a| get_constructor(vm, a, false),
+        )
+        .method(
+            "getConstructor",
+            "([Ljava/lang/Class;)Ljava/lang/reflect/Constructor;",
+            |vm, a| get_constructor(vm, a, true),
+        )
+        .method(
+            "getDeclaredFields",
+            "()[Ljava/lang/reflect/Field;",
+            |vm, a| {
+                let c = members(vm, a[0])?;
+                let len = c.as_ref().map_or(0, |c| c.fields.len());
+                new_array(vm, "[Ljava/lang/reflect/Field;", len, |vm, i| {
+                    let class = &c.as_ref().unwrap().this_class_name;
+                    member_object(vm, "java/lang/reflect/Field", class, i)
+                })
+            },
+        )
+        .method(
+            "getDeclaredField",
+            "(Ljava/lang/String;)Ljava/lang/reflect/Field;",
+            |vm, a| get_field_object(vm, a, false),
+        )
+        .method(
+            "getField",
+            "(Ljava/lang/String;)Ljava/lang/reflect/Field;",
+            |vm, a| get_field_object(vm, a, true),
+        )
+        .install(vm);
+    members_install(vm);
+}
+
+/// `Method.getModifiers` and friends: the flags `Modifier` knows of
+fn modifiers(access_flags: usize, (recognized, _): (usize, i32)) -> anyhow::Result<Option<Value>> {
+    Ok(Some(Value::Int((access_flags & recognized) as i32)))
+}
+
+fn members_install(vm: &mut Vm) {
+    ClassBuilder::interface("java/lang/reflect/Member").install(vm);
+    ClassBuilder::new("java/lang/reflect/AccessibleObject", "java/lang/Object")
+        .field("override", "Z", FieldAccessFlags::empty())
+        .method("setAccessible", "(Z)V", |vm, a| {
+            if a[1].as_int() != 0 {
+                if let Some(message) = inaccessible(vm, a[0])? {
+                    let class = "java/lang/reflect/InaccessibleObjectException";
+                    return Err(vm.exception(class, &message));
+                }
+            }
+            set_field(vm, a[0], "override", a[1]);
+            Ok(None)
+        })
+        .method("trySetAccessible", "()Z", |vm, a| {
+            if inaccessible(vm, a[0])?.is_some() {
+                return bool(false);
+            }
+            set_field(vm, a[0], "override", Value::Int(1));
+            bool(true)
+        })
+        .method("isAccessible", "()Z", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "override")))
+        })
+        .install(vm);
+
+    let object = FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL;
+    ClassBuilder::new(
+        "java/lang/reflect/Executable",
+        "java/lang/reflect/AccessibleObject",
+    )
+    .implements("java/lang/reflect/Member")
+    .field("clazz", "Ljava/lang/Class;", object)
+    .field("slot", "I", object)
+    .method("getDeclaringClass", "()Ljava/lang/Class;", |vm, a| {
+        Ok(Some(get_field(vm, a[0], "clazz")))
+    })
+    .method("getParameterTypes", "()[Ljava/lang/Class;", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        let descriptor =
+            MethodDescriptor::parse(&c.methods[slot].descriptor).context("invalid descriptor")?;
+        class_array(vm, &descriptor.args)
+    })
+    .method("getParameterCount", "()I", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        let descriptor =
+            MethodDescriptor::parse(&c.methods[slot].descriptor).context("invalid descriptor")?;
+        Ok(Some(Value::Int(descriptor.args.len() as i32)))
+    })
+    .method("getExceptionTypes", "()[Ljava/lang/Class;", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        let exceptions: Vec<_> = c.methods[slot]
+            .exceptions()
+            .iter()
+            .map(|e| format!("L{e};"))
+            .collect();
+        class_array(vm, &exceptions)
+    })
+    .method("isVarArgs", "()Z", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        bool(
+            c.methods[slot]
+                .access_flags
+                .contains(MethodAccessFlags::VARARGS),
+        )
+    })
+    .method("toString", "()Ljava/lang/String;", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        let string = method_string(&c, slot)?;
+        Ok(Some(vm.new_string(string)))
+    })
+    .install(vm);
+
+    ClassBuilder::new("java/lang/reflect/Method", "java/lang/reflect/Executable")
+        .method("getName", "()Ljava/lang/String;", |vm, a| {
+            let (c, slot) = member(vm, a[0])?;
+            Ok(Some(vm.intern(&c.methods[slot].name)))
+        })
+        .method("getModifiers", "()I", |vm, a| {
+            let (c, slot) = member(vm, a[0])?;
+            modifiers(c.methods[slot].access_flags.bits(), METHOD_MODIFIERS)
+        })
+        .method("getReturnType", "()Ljava/lang/Class;", |vm, a| {
+            let (c, slot) = member(vm, a[0])?;
+            let descriptor = MethodDescriptor::parse(&c.methods[slot].descriptor)
+                .context("invalid descriptor")?;
+            Ok(Some(type_class(vm, &descriptor.ret)?))
+        })
+        .method(
+            "invoke",
+            "(Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+            invoke,
+        )
+        .install(vm);
+
+    ClassBuilder::new(
+        "java/lang/reflect/Constructor",
+        "java/lang/reflect/Executable",
+    )
+    .method("getName", "()Ljava/lang/String;", |vm, a| {
+        let (c, _) = member(vm, a[0])?;
+        Ok(Some(vm.new_string(c.this_class_name.replace('/', "."))))
+    })
+    .method("getModifiers", "()I", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        modifiers(c.methods[slot].access_flags.bits(), CONSTRUCTOR_MODIFIERS)
+    })
+    .method(
+        "newInstance",
+        "([Ljava/lang/Object;)Ljava/lang/Object;",
+        construct,
+    )
+    .install(vm);
+
+    let mut field = ClassBuilder::new(
+        "java/lang/reflect/Field",
+        "java/lang/reflect/AccessibleObject",
+    )
+    .implements("java/lang/reflect/Member")
+    .field("clazz", "Ljava/lang/Class;", object)
+    .field("slot", "I", object)
+    .method("getDeclaringClass", "()Ljava/lang/Class;", |vm, a| {
+        Ok(Some(get_field(vm, a[0], "clazz")))
+    })
+    .method("getName", "()Ljava/lang/String;", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        Ok(Some(vm.intern(&c.fields[slot].name)))
+    })
+    .method("getModifiers", "()I", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        modifiers(c.fields[slot].access_flags.bits(), FIELD_MODIFIERS)
+    })
+    .method("getType", "()Ljava/lang/Class;", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        Ok(Some(type_class(vm, &c.fields[slot].descriptor)?))
+    })
+    .method("get", "(Ljava/lang/Object;)Ljava/lang/Object;", field_get)
+    .method("set", "(Ljava/lang/Object;Ljava/lang/Object;)V", |vm, a| {
+        write_field(vm, [a[0], a[1], a[2]], None)?;
+        Ok(None)
+    })
+    .method("toString", "()Ljava/lang/String;", |vm, a| {
+        let (c, slot) = member(vm, a[0])?;
+        let string = field_string(&c, slot);
+        Ok(Some(vm.new_string(string)))
+    });
+    for (primitive, name) in [
+        ("Z", "Boolean"),
+        ("B", "Byte"),
+        ("C", "Char"),
+        ("S", "Short"),
+        ("I", "Int"),
+        ("J", "Long"),
+        ("F", "Float"),
+        ("D", "Double"),
+    ] {
+        let getter = format!("(Ljava/lang/Object;){primitive}");
+        let setter = format!("(Ljava/lang/Object;{primitive})V");
+        field = field
+            .method(&format!("get{name}"), &getter, move |vm, a| {
+                Ok(Some(read_field(vm, a[0], a[1], Some(primitive))?.2))
+            })
+            .method(&format!("set{name}"), &setter, move |vm, a| {
+                write_field(vm, [a[0], a[1], a[2]], Some(primitive))?;
+                Ok(None)
+            });
+    }
+    field.install(vm);
+
+    let mut modifier = ClassBuilder::new("java/lang/reflect/Modifier", "java/lang/Object")
+        .static_method("toString", "(I)Ljava/lang/String;", |vm, a| {
+            Ok(Some(vm.new_string(modifier_string(a[0].as_int()))))
+        });
+    for (name, flag) in [
+        ("isPublic", 0x1),
+        ("isPrivate", 0x2),
+        ("isProtected", 0x4),
+        ("isStatic", 0x8),
+        ("isFinal", 0x10),
+        ("isSynchronized", 0x20),
+        ("isVolatile", 0x40),
+        ("isTransient", 0x80),
+        ("isNative", 0x100),
+        ("isInterface", 0x200),
+        ("isAbstract", 0x400),
+        ("isStrict", 0x800),
+    ] {
+        modifier =
+            modifier.static_method(name, "(I)Z", move |_, a| bool(a[0].as_int() & flag != 0));
+    }
+    modifier.install(vm);
+
+    ClassBuilder::new(
+        "java/lang/reflect/InvocationTargetException",
+        "java/lang/ReflectiveOperationException",
+    )
+    .method("getTargetException", "()Ljava/lang/Throwable;", |vm, a| {
+        Ok(Some(get_field(vm, a[0], "cause")))
+    })
+    .install(vm);
+
+    const LOOKUP: &str = "java/lang/invoke/MethodHandles$Lookup";
+    ClassBuilder::new("java/lang/invoke/MethodHandles", "java/lang/Object")
+        .static_method(
+            "lookup",
+            "()Ljava/lang/invoke/MethodHandles$Lookup;",
+            |vm, _| {
+                let caller = match vm.frames.last() {
+                    Some(frame) => frame.class.this_class_name.clone(),
+                    None => "java/lang/Object".to_string(),
+                };
+                let mirror = vm.class_object(&caller)?;
+                let lookup = vm.new_object(LOOKUP)?;
+                set_field(vm, lookup, "lookupClass", mirror);
+                Ok(Some(lookup))
+            },
+        )
+        .install(vm);
+    ClassBuilder::new(LOOKUP, "java/lang/Object")
+        .field(
+            "lookupClass",
+            "Ljava/lang/Class;",
+            FieldAccessFlags::PRIVATE,
+        )
+        .method("lookupClass", "()Ljava/lang/Class;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "lookupClass")))
+        })
+        // defines a class in the package of the lookup class, verifying it right away
+        .method("defineClass", "([B)Ljava/lang/Class;", |vm, a| {
+            let Some(array) = a[1].as_ref() else {
+                return Err(vm.null_pointer());
+            };
+            let elements = &vm.heap.get_array(array).elements;
+            let bytes: Vec<u8> = elements.iter().map(|b| b.as_int() as u8).collect();
+            let Some(class) = Class::parse(&mut ByteStream::from(bytes)) else {
+                return Err(vm.exception("java/lang/ClassFormatError", "Malformed class file"));
+            };
+            let name = class.this_class_name.clone();
+            let lookup_class = get_field(vm, a[0], "lookupClass");
+            let lookup_class = vm.mirrored_class(lookup_class)?;
+            if package(&name) != package(&lookup_class) {
+                let message = "Class not in same package as lookup class";
+                return Err(vm.exception("java/lang/IllegalArgumentException", message));
+            }
+            if vm.classes.contains_key(&name) {
+                let message = format!("attempted duplicate class definition for {name}");
+                return Err(vm.exception("java/lang/LinkageError", &message));
+            }
+            vm.define_class(class)?;
+            Ok(Some(vm.class_object(&name)?))
+        })
+        .install(vm);
+}
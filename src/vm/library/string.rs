@@ -0,0 +1,806 @@
+use crate::vm::{heap::Object, value::Value, Vm};
+
+use super::ClassBuilder;
+
+/// `Double.toString`: plain decimal in `[1e-3, 1e7)`, scientific notation outside of it
+pub fn format_double(v: f64) -> String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if v == 0.0 || (1e-3..1e7).contains(&v.abs()) {
+        return format!("{v:?}");
+    }
+    scientific(format!("{v:e}"))
+}
+
+/// `Float.toString`, same rules as [`format_double`]
+pub fn format_float(v: f32) -> String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if v == 0.0 || (1e-3..1e7).contains(&v.abs()) {
+        return format!("{v:?}");
+    }
+    scientific(format!("{v:e}"))
+}
+
+/// `1e7` -> `1.0E7`
+fn scientific(s: String) -> String {
+    let (mantissa, exponent) = s.split_once('e').unwrap();
+    if mantissa.contains('.') {
+        format!("{mantissa}E{exponent}")
+    } else {
+        format!("{mantissa}.0E{exponent}")
+    }
+}
+
+/// strings are stored as rust strings, but java indexes them by UTF-16 code unit
+fn units(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+fn from_units(units: &[u16]) -> String {
+    String::from_utf16_lossy(units)
+}
+
+fn char_value(c: i32) -> String {
+    from_units(&[c as u16])
+}
+
+fn bool_value(b: bool) -> Option<Value> {
+    Some(Value::Int(b as i32))
+}
+
+fn index_of(haystack: &[u16], needle: &[u16], from: i32) -> i32 {
+    let from = from.max(0) as usize;
+    if from > haystack.len() {
+        return -1;
+    }
+    if needle.is_empty() {
+        return from as i32;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map_or(-1, |i| (i + from) as i32)
+}
+
+fn last_index_of(haystack: &[u16], needle: &[u16]) -> i32 {
+    if needle.is_empty() {
+        return haystack.len() as i32;
+    }
+    haystack
+        .windows(needle.len())
+        .rposition(|w| w == needle)
+        .map_or(-1, |i| i as i32)
+}
+
+fn compare(a: &[u16], b: &[u16]) -> i32 {
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return *x as i32 - *y as i32;
+        }
+    }
+    a.len() as i32 - b.len() as i32
+}
+
+pub(super) fn hash_code(s: &str) -> i32 {
+    s.encode_utf16()
+        .fold(0i32, |h, c| h.wrapping_mul(31).wrapping_add(c as i32))
+}
+
+/// java's `trim` removes every character up to and including the space
+fn trim(s: &str) -> &str {
+    s.trim_matches(|c: char| c <= ' ')
+}
+
+/// `String.split` for the patterns that are used in practice: `\s+` and plain literals,
+/// with escaped characters unescaped. Trailing empty strings are removed like in java
+fn split(s: &str, regex: &str) -> Vec<String> {
+    let mut parts: Vec<String> = match regex {
+        "\\s+" | "\\s" | " +" => {
+            let parts = s.split(|c: char| c.is_whitespace());
+            match regex {
+                "\\s" => parts.map(str::to_string).collect(),
+                _ => {
+                    let mut parts: Vec<String> = parts
+                        .filter(|p| !p.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                    if s.starts_with(char::is_whitespace) {
+                        parts.insert(0, String::new());
+                    }
+                    parts
+                }
+            }
+        }
+        _ => {
+            let literal = regex.replace('\\', "");
+            if literal.is_empty() {
+                s.chars().map(String::from).collect()
+            } else {
+                s.split(literal.as_str()).map(str::to_string).collect()
+            }
+        }
+    };
+    while parts.len() > 1 && parts.last().is_some_and(String::is_empty) {
+        parts.pop();
+    }
+    parts
+}
+
+impl Vm {
+    fn char_array(&mut self, s: &str) -> Value {
+        let array = self.heap.new_array("[C", 0);
+        let elements = s.encode_utf16().map(|c| Value::Int(c as i32)).collect();
+        self.heap.get_array_mut(array.as_ref().unwrap()).elements = elements;
+        array
+    }
+
+    fn char_array_value(&mut self, value: Value) -> anyhow::Result<Vec<u16>> {
+        match value.as_ref() {
+            Some(r) => Ok(self
+                .heap
+                .get_array(r)
+                .elements
+                .iter()
+                .map(|c| c.as_int() as u16)
+                .collect()),
+            None => Err(self.null_pointer()),
+        }
+    }
+
+    fn string_index_error(&mut self, message: String) -> anyhow::Error {
+        self.exception("java/lang/StringIndexOutOfBoundsException", &message)
+    }
+
+    /// textual form of a value of the given type, as used by string concatenation.
+    /// Objects other than strings are converted with their `toString` method
+    pub(crate) fn java_string(&mut self, value: Value, descriptor: &str) -> anyhow::Result<String> {
+        Ok(match (value, descriptor) {
+            (Value::Int(v), "Z") => (v != 0).to_string(),
+            (Value::Int(v), "C") => char_value(v),
+            (Value::Int(v), _) => v.to_string(),
+            (Value::Long(v), _) => v.to_string(),
+            (Value::Float(v), _) => format_float(v),
+            (Value::Double(v), _) => format_double(v),
+            (Value::Null, _) => "null".to_string(),
+            (Value::Ref(r), _) => match self.heap.get(r) {
+                _ if self.is_string(r) => self.string_value(value)?,
+                Object::Array(a) if descriptor == "[C" => {
+                    let units: Vec<u16> = a.elements.iter().map(|c| c.as_int() as u16).collect();
+                    from_units(&units)
+                }
+                _ => {
+                    let descriptor = "()Ljava/lang/String;";
+                    let string = self
+                        .invoke_virtual("java/lang/Object", "toString", descriptor, vec![value])?
+                        .unwrap();
+                    match string {
+                        Value::Null => "null".to_string(),
+                        string => self.string_value(string)?,
+                    }
+                }
+            },
+        })
+    }
+
+    /// `String.format` with the conversions `s d x o c b f e n %`,
+    /// the flags `- 0 + ,` as well as width and precision
+    pub(crate) fn format(&mut self, format: &str, args: &[Value]) -> anyhow::Result<String> {
+        let mut out = String::new();
+        let mut args = args.iter();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            let mut flags = String::new();
+            while let Some(&f) = chars.peek().filter(|f| "-0+,# ".contains(**f)) {
+                flags.push(f);
+                chars.next();
+            }
+            let mut width = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                width.push(d);
+                chars.next();
+            }
+            let mut precision = None;
+            if chars.peek() == Some(&'.') {
+                chars.next();
+                let mut p = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    p.push(d);
+                    chars.next();
+                }
+                precision = p.parse::<usize>().ok();
+            }
+            // floats are formatted with as many digits as the precision asks for
+            self.reserve(precision.unwrap_or(0))?;
+            let Some(conversion) = chars.next() else {
+                let message = "Format specifier '%'".to_string();
+                return Err(self.exception("java/util/UnknownFormatConversionException", &message));
+            };
+            let mut arg = || match args.next() {
+                Some(&arg) => Ok(arg),
+                None => {
+                    let message = format!("Format specifier '%{conversion}'");
+                    Err(message)
+                }
+            };
+            let text = match conversion {
+                'n' => {
+                    out.push('\n');
+                    continue;
+                }
+                '%' => "%".to_string(),
+                _ => {
+                    let arg = match arg() {
+                        Ok(arg) => arg,
+                        Err(message) => {
+                            let class = "java/util/MissingFormatArgumentException";
+                            return Err(self.exception(class, &message));
+                        }
+                    };
+                    let value = self.unbox(arg).unwrap_or(arg);
+                    match (conversion.to_ascii_lowercase(), value) {
+                        ('s', _) => {
+                            let s = self.java_string(arg, "Ljava/lang/Object;")?;
+                            match precision {
+                                Some(p) => s.chars().take(p).collect(),
+                                None => s,
+                            }
+                        }
+                        ('b', Value::Null) => "false".to_string(),
+                        ('b', Value::Int(v)) if self.is_boolean(arg) => (v != 0).to_string(),
+                        ('b', _) => "true".to_string(),
+                        ('c', Value::Int(v)) => char_value(v),
+                        ('d', Value::Int(v)) => group(v.to_string(), &flags),
+                        ('d', Value::Long(v)) => group(v.to_string(), &flags),
+                        ('x', Value::Int(v)) => format!("{:x}", v),
+                        ('x', Value::Long(v)) => format!("{:x}", v),
+                        ('o', Value::Int(v)) => format!("{:o}", v),
+                        ('o', Value::Long(v)) => format!("{:o}", v),
+                        ('f', Value::Float(v)) => {
+                            group(format!("{:.*}", precision.unwrap_or(6), v), &flags)
+                        }
+                        ('f', Value::Double(v)) => {
+                            group(format!("{:.*}", precision.unwrap_or(6), v), &flags)
+                        }
+                        ('e', Value::Float(v)) => exponent(v as f64, precision.unwrap_or(6)),
+                        ('e', Value::Double(v)) => exponent(v, precision.unwrap_or(6)),
+                        (_, Value::Null) => "null".to_string(),
+                        _ => {
+                            let class = self.heap.class_name(arg.as_ref().unwrap());
+                            let message = format!("{conversion} != {}", class.replace('/', "."));
+                            let class = "java/util/IllegalFormatConversionException";
+                            return Err(self.exception(class, &message));
+                        }
+                    }
+                }
+            };
+            let text = if conversion.is_uppercase() {
+                text.to_uppercase()
+            } else {
+                text
+            };
+            let text =
+                if flags.contains('+') && !text.starts_with('-') && "dfe".contains(conversion) {
+                    format!("+{text}")
+                } else {
+                    text
+                };
+            let width = width.parse::<usize>().unwrap_or(0);
+            let padding = width.saturating_sub(text.chars().count());
+            self.reserve(padding)?;
+            if flags.contains('-') {
+                out.push_str(&text);
+                out.extend(std::iter::repeat_n(' ', padding));
+            } else if flags.contains('0') {
+                let (sign, digits) = match text.strip_prefix(['-', '+']) {
+                    Some(digits) => (&text[..1], digits),
+                    None => ("", text.as_str()),
+                };
+                out.push_str(sign);
+                out.extend(std::iter::repeat_n('0', padding));
+                out.push_str(digits);
+            } else {
+                out.extend(std::iter::repeat_n(' ', padding));
+                out.push_str(&text);
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// digit grouping for the `,` flag
+fn group(number: String, flags: &str) -> String {
+    if !flags.contains(',') {
+        return number;
+    }
+    let (sign, rest) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number.as_str()),
+    };
+    let (int, frac) = match rest.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (rest, None),
+    };
+    let mut grouped = String::new();
+    for (i, d) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(d);
+    }
+    match frac {
+        Some(frac) => format!("{sign}{grouped}.{frac}"),
+        None => format!("{sign}{grouped}"),
+    }
+}
+
+/// `%e`: `1.500000e+01`
+fn exponent(v: f64, precision: usize) -> String {
+    let s = format!("{:.*e}", precision, v);
+    let (mantissa, exp) = s.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{mantissa}e{sign}{:02}", exp.abs())
+}
+
+fn this_string(vm: &Vm, a: &[Value]) -> String {
+    vm.heap.get_string(a[0].as_ref().unwrap()).to_string()
+}
+
+fn this_builder<'a>(vm: &'a mut Vm, a: &[Value]) -> &'a mut String {
+    cast!(
+        vm.heap.get_mut(a[0].as_ref().unwrap()),
+        Object::StringBuilder
+    )
+}
+
+/// replaces the empty string allocated by `new` with the constructed contents
+fn construct(vm: &mut Vm, this: Value, s: String) -> anyhow::Result<Option<Value>> {
+    *vm.heap.get_mut(this.as_ref().unwrap()) = Object::String(s);
+    Ok(None)
+}
+
+fn string(vm: &mut Vm) {
+    let mut string = ClassBuilder::new("java/lang/String", "java/lang/Object")
+        .implements("java/lang/CharSequence")
+        .implements("java/lang/Comparable")
+        .implements("java/io/Serializable")
+        .method("<init>", "()V", |vm, a| construct(vm, a[0], String::new()))
+        .method("<init>", "(Ljava/lang/String;)V", |vm, a| {
+            let s = vm.string_value(a[1])?;
+            construct(vm, a[0], s)
+        })
+        .method("<init>", "([C)V", |vm, a| {
+            let units = vm.char_array_value(a[1])?;
+            construct(vm, a[0], from_units(&units))
+        })
+        .method("<init>", "([CII)V", |vm, a| {
+            let units = vm.char_array_value(a[1])?;
+            let (offset, count) = (a[2].as_int(), a[3].as_int());
+            if offset < 0 || count < 0 || offset as usize + count as usize > units.len() {
+                let message = format!("offset {offset}, count {count}, length {}", units.len());
+                return Err(vm.string_index_error(message));
+            }
+            let units = &units[offset as usize..(offset + count) as usize];
+            construct(vm, a[0], from_units(units))
+        })
+        .method("<init>", "(Ljava/lang/StringBuilder;)V", |vm, a| {
+            let s = vm.java_string(a[1], "Ljava/lang/Object;")?;
+            construct(vm, a[0], s)
+        })
+        .method("length", "()I", |vm, a| {
+            let s = vm.heap.get_string(a[0].as_ref().unwrap());
+            let len = if s.is_ascii() {
+                s.len()
+            } else {
+                s.encode_utf16().count()
+            };
+            Ok(Some(Value::Int(len as i32)))
+        })
+        .method("isEmpty", "()Z", |vm, a| {
+            Ok(bool_value(this_string(vm, a).is_empty()))
+        })
+        .method("charAt", "(I)C", |vm, a| {
+            let s = vm.heap.get_string(a[0].as_ref().unwrap());
+            let index = a[1].as_int();
+            let c = if s.is_ascii() {
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|i| s.as_bytes().get(i))
+                    .map(|&b| b as u16)
+            } else {
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|i| s.encode_utf16().nth(i))
+            };
+            match c {
+                Some(c) => Ok(Some(Value::Int(c as i32))),
+                None => {
+                    let message = format!("String index out of range: {index}");
+                    Err(vm.string_index_error(message))
+                }
+            }
+        })
+        .method("equals", "(Ljava/lang/Object;)Z", |vm, a| {
+            let equal = match a[1].as_ref().map(|r| vm.heap.get(r)) {
+                Some(Object::String(other)) => other == vm.heap.get_string(a[0].as_ref().unwrap()),
+                _ => false,
+            };
+            Ok(bool_value(equal))
+        })
+        .method("equalsIgnoreCase", "(Ljava/lang/String;)Z", |vm, a| {
+            let equal = match a[1].as_ref() {
+                Some(r) => {
+                    let other = vm.heap.get_string(r).to_lowercase();
+                    other == this_string(vm, a).to_lowercase()
+                }
+                None => false,
+            };
+            Ok(bool_value(equal))
+        })
+        .method("hashCode", "()I", |vm, a| {
+            Ok(Some(Value::Int(hash_code(&this_string(vm, a)))))
+        })
+        .method("toString", "()Ljava/lang/String;", |_, a| Ok(Some(a[0])))
+        .method("intern", "()Ljava/lang/String;", |vm, a| {
+            let s = this_string(vm, a);
+            Ok(Some(vm.intern(&s)))
+        })
+        .method("compareToIgnoreCase", "(Ljava/lang/String;)I", |vm, a| {
+            let other = vm.string_value(a[1])?.to_lowercase();
+            let this = this_string(vm, a).to_lowercase();
+            Ok(Some(Value::Int(compare(&units(&this), &units(&other)))))
+        })
+        .method("substring", "(I)Ljava/lang/String;", |vm, a| {
+            let units = units(&this_string(vm, a));
+            let begin = a[1].as_int();
+            if begin < 0 || begin as usize > units.len() {
+                let message = format!("begin {begin}, end {len}, length {len}", len = units.len());
+                return Err(vm.string_index_error(message));
+            }
+            Ok(Some(vm.new_string(from_units(&units[begin as usize..]))))
+        })
+        .method("substring", "(II)Ljava/lang/String;", |vm, a| {
+            let units = units(&this_string(vm, a));
+            let (begin, end) = (a[1].as_int(), a[2].as_int());
+            if begin < 0 || begin > end || end as usize > units.len() {
+                let message = format!("begin {begin}, end {end}, length {}", units.len());
+                return Err(vm.string_index_error(message));
+            }
+            let s = from_units(&units[begin as usize..end as usize]);
+            Ok(Some(vm.new_string(s)))
+        })
+        .method("indexOf", "(I)I", |vm, a| {
+            let needle = [a[1].as_int() as u16];
+            Ok(Some(Value::Int(index_of(
+                &units(&this_string(vm, a)),
+                &needle,
+                0,
+            ))))
+        })
+        .method("indexOf", "(II)I", |vm, a| {
+            let needle = [a[1].as_int() as u16];
+            let haystack = units(&this_string(vm, a));
+            Ok(Some(Value::Int(index_of(
+                &haystack,
+                &needle,
+                a[2].as_int(),
+            ))))
+        })
+        .method("indexOf", "(Ljava/lang/String;)I", |vm, a| {
+            let needle = units(&vm.string_value(a[1])?);
+            Ok(Some(Value::Int(index_of(
+                &units(&this_string(vm, a)),
+                &needle,
+                0,
+            ))))
+        })
+        .method("indexOf", "(Ljava/lang/String;I)I", |vm, a| {
+            let needle = units(&vm.string_value(a[1])?);
+            let haystack = units(&this_string(vm, a));
+            Ok(Some(Value::Int(index_of(
+                &haystack,
+                &needle,
+                a[2].as_int(),
+            ))))
+        })
+        .method("lastIndexOf", "(I)I", |vm, a| {
+            let needle = [a[1].as_int() as u16];
+            Ok(Some(Value::Int(last_index_of(
+                &units(&this_string(vm, a)),
+                &needle,
+            ))))
+        })
+        .method("lastIndexOf", "(Ljava/lang/String;)I", |vm, a| {
+            let needle = units(&vm.string_value(a[1])?);
+            Ok(Some(Value::Int(last_index_of(
+                &units(&this_string(vm, a)),
+                &needle,
+            ))))
+        })
+        .method("contains", "(Ljava/lang/CharSequence;)Z", |vm, a| {
+            let needle = vm.java_string(a[1], "Ljava/lang/CharSequence;")?;
+            Ok(bool_value(this_string(vm, a).contains(&needle)))
+        })
+        .method("startsWith", "(Ljava/lang/String;)Z", |vm, a| {
+            let prefix = vm.string_value(a[1])?;
+            Ok(bool_value(this_string(vm, a).starts_with(&prefix)))
+        })
+        .method("endsWith", "(Ljava/lang/String;)Z", |vm, a| {
+            let suffix = vm.string_value(a[1])?;
+            Ok(bool_value(this_string(vm, a).ends_with(&suffix)))
+        })
+        .method(
+            "concat",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            |vm, a| {
+                let other = vm.string_value(a[1])?;
+                Ok(Some(vm.new_string(this_string(vm, a) + &other)))
+            },
+        )
+        .method("toUpperCase", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(vm.new_string(this_string(vm, a).to_uppercase())))
+        })
+        .method("toLowerCase", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(vm.new_string(this_string(vm, a).to_lowercase())))
+        })
+        .method("trim", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(vm.new_string(trim(&this_string(vm, a)).to_string())))
+        })
+        .method("strip", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(vm.new_string(this_string(vm, a).trim().to_string())))
+        })
+        .method("isBlank", "()Z", |vm, a| {
+            Ok(bool_value(this_string(vm, a).trim().is_empty()))
+        })
+        .method("repeat", "(I)Ljava/lang/String;", |vm, a| {
+            let count = a[1].as_int();
+            if count < 0 {
+                let message = format!("count is negative: {count}");
+                return Err(vm.exception("java/lang/IllegalArgumentException", &message));
+            }
+            let len = this_string(vm, a).len();
+            if len.saturating_mul(count as usize) > i32::MAX as usize {
+                let message = "Required length exceeds implementation limit";
+                return Err(vm.exception("java/lang/OutOfMemoryError", message));
+            }
+            vm.reserve(len * count as usize)?;
+            Ok(Some(
+                vm.new_string(this_string(vm, a).repeat(count as usize)),
+            ))
+        })
+        .method("replace", "(CC)Ljava/lang/String;", |vm, a| {
+            let (from, to) = (a[1].as_int() as u16, a[2].as_int() as u16);
+            let units: Vec<u16> = units(&this_string(vm, a))
+                .into_iter()
+                .map(|c| if c == from { to } else { c })
+                .collect();
+            Ok(Some(vm.new_string(from_units(&units))))
+        })
+        .method(
+            "replace",
+            "(Ljava/lang/CharSequence;Ljava/lang/CharSequence;)Ljava/lang/String;",
+            |vm, a| {
+                let from = vm.java_string(a[1], "Ljava/lang/CharSequence;")?;
+                let to = vm.java_string(a[2], "Ljava/lang/CharSequence;")?;
+                Ok(Some(vm.new_string(this_string(vm, a).replace(&from, &to))))
+            },
+        )
+        .method("toCharArray", "()[C", |vm, a| {
+            Ok(Some(vm.char_array(&this_string(vm, a))))
+        })
+        .method(
+            "split",
+            "(Ljava/lang/String;)[Ljava/lang/String;",
+            |vm, a| {
+                let regex = vm.string_value(a[1])?;
+                let parts = split(&this_string(vm, a), &regex);
+                let array = vm.heap.new_array("[Ljava/lang/String;", 0);
+                let elements = parts.into_iter().map(|p| vm.new_string(p)).collect();
+                vm.heap.get_array_mut(array.as_ref().unwrap()).elements = elements;
+                Ok(Some(array))
+            },
+        )
+        .method(
+            "formatted",
+            "([Ljava/lang/Object;)Ljava/lang/String;",
+            |vm, a| {
+                let args = vm.heap.get_array(a[1].as_ref().unwrap()).elements.clone();
+                let s = vm.format(&this_string(vm, a), &args)?;
+                Ok(Some(vm.new_string(s)))
+            },
+        )
+        .static_method(
+            "format",
+            "(Ljava/lang/String;[Ljava/lang/Object;)Ljava/lang/String;",
+            |vm, a| {
+                let format = vm.string_value(a[0])?;
+                let args = vm.heap.get_array(a[1].as_ref().unwrap()).elements.clone();
+                let s = vm.format(&format, &args)?;
+                Ok(Some(vm.new_string(s)))
+            },
+        )
+        .static_method(
+            "join",
+            "(Ljava/lang/CharSequence;[Ljava/lang/CharSequence;)Ljava/lang/String;",
+            |vm, a| {
+                let delimiter = vm.java_string(a[0], "Ljava/lang/CharSequence;")?;
+                let elements = vm.heap.get_array(a[1].as_ref().unwrap()).elements.clone();
+                let mut parts = Vec::new();
+                for element in elements {
+                    parts.push(vm.java_string(element, "Ljava/lang/CharSequence;")?);
+                }
+                Ok(Some(vm.new_string(parts.join(&delimiter))))
+            },
+        )
+        .static_method("valueOf", "([C)Ljava/lang/String;", |vm, a| {
+            let units = vm.char_array_value(a[0])?;
+            Ok(Some(vm.new_string(from_units(&units))))
+        });
+    // comparing through `Comparable` uses the erased descriptor
+    for descriptor in ["(Ljava/lang/String;)I", "(Ljava/lang/Object;)I"] {
+        string = string.method("compareTo", descriptor, |vm, a| {
+            let other = units(&vm.string_value(a[1])?);
+            let this = units(&this_string(vm, a));
+            Ok(Some(Value::Int(compare(&this, &other))))
+        });
+    }
+    for descriptor in ["Z", "C", "I", "J", "F", "D", "Ljava/lang/Object;"] {
+        string = string.static_method(
+            "valueOf",
+            &format!("({descriptor})Ljava/lang/String;"),
+            move |vm, a| {
+                let s = vm.java_string(a[0], descriptor)?;
+                Ok(Some(vm.new_string(s)))
+            },
+        );
+    }
+    string.install(vm);
+}
+
+fn string_builder(vm: &mut Vm) {
+    let mut builder = ClassBuilder::new("java/lang/StringBuilder", "java/lang/Object")
+        .implements("java/lang/CharSequence")
+        .method("<init>", "()V", |_, _| Ok(None))
+        .method("<init>", "(I)V", |_, _| Ok(None))
+        .method("<init>", "(Ljava/lang/String;)V", |vm, a| {
+            *this_builder(vm, a) = vm.string_value(a[1])?;
+            Ok(None)
+        })
+        .method("<init>", "(Ljava/lang/CharSequence;)V", |vm, a| {
+            *this_builder(vm, a) = vm.java_string(a[1], "Ljava/lang/CharSequence;")?;
+            Ok(None)
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let s = this_builder(vm, a).clone();
+            Ok(Some(vm.new_string(s)))
+        })
+        .method("length", "()I", |vm, a| {
+            Ok(Some(Value::Int(units(this_builder(vm, a)).len() as i32)))
+        })
+        .method("charAt", "(I)C", |vm, a| {
+            let index = a[1].as_int();
+            let units = units(this_builder(vm, a));
+            match usize::try_from(index).ok().and_then(|i| units.get(i)) {
+                Some(&c) => Ok(Some(Value::Int(c as i32))),
+                None => {
+                    let message = format!("index {index},length {}", units.len());
+                    Err(vm.string_index_error(message))
+                }
+            }
+        })
+        .method("reverse", "()Ljava/lang/StringBuilder;", |vm, a| {
+            let s = this_builder(vm, a);
+            *s = s.chars().rev().collect();
+            Ok(Some(a[0]))
+        })
+        .method("setLength", "(I)V", |vm, a| {
+            let length = a[1].as_int();
+            if length < 0 {
+                return Err(vm.string_index_error(format!("String index out of range: {length}")));
+            }
+            vm.reserve(length as usize)?;
+            let mut units = units(this_builder(vm, a));
+            units.resize(length as usize, 0);
+            *this_builder(vm, a) = from_units(&units);
+            Ok(None)
+        })
+        .method("deleteCharAt", "(I)Ljava/lang/StringBuilder;", |vm, a| {
+            let index = a[1].as_int();
+            let mut units = units(this_builder(vm, a));
+            if index < 0 || index as usize >= units.len() {
+                let message = format!("index {index},length {}", units.len());
+                return Err(vm.string_index_error(message));
+            }
+            units.remove(index as usize);
+            *this_builder(vm, a) = from_units(&units);
+            Ok(Some(a[0]))
+        })
+        .method("delete", "(II)Ljava/lang/StringBuilder;", |vm, a| {
+            let mut units = units(this_builder(vm, a));
+            let (start, end) = (a[1].as_int(), a[2].as_int().min(units.len() as i32));
+            if start < 0 || start > end {
+                let message = format!("start {start}, end {end}, length {}", units.len());
+                return Err(vm.string_index_error(message));
+            }
+            units.drain(start as usize..end as usize);
+            *this_builder(vm, a) = from_units(&units);
+            Ok(Some(a[0]))
+        })
+        .method("setCharAt", "(IC)V", |vm, a| {
+            let index = a[1].as_int();
+            let mut units = units(this_builder(vm, a));
+            if index < 0 || index as usize >= units.len() {
+                let message = format!("index {index},length {}", units.len());
+                return Err(vm.string_index_error(message));
+            }
+            units[index as usize] = a[2].as_int() as u16;
+            *this_builder(vm, a) = from_units(&units);
+            Ok(None)
+        })
+        .method("indexOf", "(Ljava/lang/String;)I", |vm, a| {
+            let needle = units(&vm.string_value(a[1])?);
+            let haystack = units(this_builder(vm, a));
+            Ok(Some(Value::Int(index_of(&haystack, &needle, 0))))
+        })
+        .method("isEmpty", "()Z", |vm, a| {
+            Ok(bool_value(this_builder(vm, a).is_empty()))
+        });
+    for descriptor in [
+        "Z",
+        "C",
+        "I",
+        "J",
+        "F",
+        "D",
+        "[C",
+        "Ljava/lang/String;",
+        "Ljava/lang/Object;",
+        "Ljava/lang/CharSequence;",
+    ] {
+        builder = builder
+            .method(
+                "append",
+                &format!("({descriptor})Ljava/lang/StringBuilder;"),
+                move |vm, a| {
+                    let s = vm.java_string(a[1], descriptor)?;
+                    this_builder(vm, a).push_str(&s);
+                    Ok(Some(a[0]))
+                },
+            )
+            .method(
+                "insert",
+                &format!("(I{descriptor})Ljava/lang/StringBuilder;"),
+                move |vm, a| {
+                    let s = vm.java_string(a[2], descriptor)?;
+                    let offset = a[1].as_int();
+                    let mut units = units(this_builder(vm, a));
+                    if offset < 0 || offset as usize > units.len() {
+                        let message = format!("offset {offset}, length {}", units.len());
+                        return Err(vm.string_index_error(message));
+                    }
+                    units.splice(offset as usize..offset as usize, s.encode_utf16());
+                    *this_builder(vm, a) = from_units(&units);
+                    Ok(Some(a[0]))
+                },
+            );
+    }
+    builder.install(vm);
+}
+
+pub fn install(vm: &mut Vm) {
+    string(vm);
+    string_builder(vm);
+}
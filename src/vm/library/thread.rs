@@ -0,0 +1,199 @@
+use crate::{
+    class::field::FieldAccessFlags,
+    vm::{value::Value, Vm},
+};
+
+use super::{
+    lang::{get_field, set_field},
+    ClassBuilder,
+};
+
+const THREAD: &str = "java/lang/Thread";
+
+/// next number of the counter in static field `name`
+fn next_number(vm: &mut Vm, name: &str, descriptor: &str) -> anyhow::Result<Value> {
+    let number = vm.get_static(THREAD, name, descriptor)?;
+    let next = match number {
+        Value::Int(n) => Value::Int(n + 1),
+        Value::Long(n) => Value::Long(n + 1),
+        _ => unreachable!(),
+    };
+    vm.put_static(THREAD, name, next)?;
+    Ok(number)
+}
+
+/// `Thread(Runnable target, String name)`, a null name picks the next "Thread-N"
+fn init(vm: &mut Vm, this: Value, target: Value, name: Value) -> anyhow::Result<Option<Value>> {
+    let name = match name {
+        Value::Null => {
+            let number = next_number(vm, "threadInitNumber", "I")?.as_int();
+            vm.new_string(format!("Thread-{number}"))
+        }
+        name => name,
+    };
+    let id = next_number(vm, "threadSeqNumber", "J")?;
+    // new threads inherit whether the creating thread is a daemon
+    let daemon = match vm.thread {
+        Value::Null => Value::Int(0),
+        thread => get_field(vm, thread, "daemon"),
+    };
+    for (field, value) in [
+        ("name", name),
+        ("target", target),
+        ("daemon", daemon),
+        ("priority", Value::Int(5)),
+        ("tid", id),
+        ("eetop", Value::Long(0)),
+        ("interrupted", Value::Int(0)),
+        ("threadStatus", Value::Int(0)),
+    ] {
+        set_field(vm, this, field, value);
+    }
+    Ok(None)
+}
+
+pub fn install(vm: &mut Vm) {
+    ClassBuilder::new(THREAD, "java/lang/Object")
+        .implements("java/lang/Runnable")
+        .field("name", "Ljava/lang/String;", FieldAccessFlags::PRIVATE)
+        .field("target", "Ljava/lang/Runnable;", FieldAccessFlags::PRIVATE)
+        .field("daemon", "Z", FieldAccessFlags::PRIVATE)
+        .field("priority", "I", FieldAccessFlags::PRIVATE)
+        .field("tid", "J", FieldAccessFlags::PRIVATE)
+        .field("eetop", "J", FieldAccessFlags::PRIVATE)
+        .field("interrupted", "Z", FieldAccessFlags::PRIVATE)
+        .field("threadStatus", "I", FieldAccessFlags::PRIVATE)
+        .field(
+            "threadInitNumber",
+            "I",
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC,
+        )
+        .field(
+            "threadSeqNumber",
+            "J",
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC,
+        )
+        .method("<init>", "()V", |vm, a| {
+            init(vm, a[0], Value::Null, Value::Null)
+        })
+        .method("<init>", "(Ljava/lang/Runnable;)V", |vm, a| {
+            init(vm, a[0], a[1], Value::Null)
+        })
+        .method("<init>", "(Ljava/lang/String;)V", |vm, a| match a[1] {
+            Value::Null => {
+                Err(vm.exception("java/lang/NullPointerException", "name cannot be null"))
+            }
+            name => init(vm, a[0], Value::Null, name),
+        })
+        .method(
+            "<init>",
+            "(Ljava/lang/Runnable;Ljava/lang/String;)V",
+            |vm, a| match a[2] {
+                Value::Null => {
+                    Err(vm.exception("java/lang/NullPointerException", "name cannot be null"))
+                }
+                name => init(vm, a[0], a[1], name),
+            },
+        )
+        .method("start", "()V", |vm, a| {
+            vm.start_thread(a[0])?;
+            Ok(None)
+        })
+        .method("run", "()V", |vm, a| {
+            match get_field(vm, a[0], "target") {
+                Value::Null => {}
+                target => {
+                    vm.invoke_virtual("java/lang/Runnable", "run", "()V", vec![target])?;
+                }
+            }
+            Ok(None)
+        })
+        .method("join", "()V", |vm, a| {
+            vm.join(a[0], 0)?;
+            Ok(None)
+        })
+        .method("join", "(J)V", |vm, a| {
+            vm.join(a[0], a[1].as_long())?;
+            Ok(None)
+        })
+        .method("isAlive", "()Z", |vm, a| {
+            Ok(Some(Value::Int(vm.is_alive(a[0]) as i32)))
+        })
+        .method("getName", "()Ljava/lang/String;", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "name")))
+        })
+        .method("setName", "(Ljava/lang/String;)V", |vm, a| {
+            if a[1] == Value::Null {
+                return Err(vm.exception("java/lang/NullPointerException", "name cannot be null"));
+            }
+            set_field(vm, a[0], "name", a[1]);
+            Ok(None)
+        })
+        .method("getId", "()J", |vm, a| Ok(Some(get_field(vm, a[0], "tid"))))
+        .method("isDaemon", "()Z", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "daemon")))
+        })
+        .method("setDaemon", "(Z)V", |vm, a| {
+            if vm.is_alive(a[0]) {
+                return Err(vm.exception("java/lang/IllegalThreadStateException", ""));
+            }
+            set_field(vm, a[0], "daemon", a[1]);
+            Ok(None)
+        })
+        .method("getPriority", "()I", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "priority")))
+        })
+        .method("setPriority", "(I)V", |vm, a| {
+            if !(1..=10).contains(&a[1].as_int()) {
+                return Err(vm.exception("java/lang/IllegalArgumentException", ""));
+            }
+            set_field(vm, a[0], "priority", a[1]);
+            Ok(None)
+        })
+        .method("interrupt", "()V", |vm, a| {
+            vm.interrupt(a[0]);
+            Ok(None)
+        })
+        .method("isInterrupted", "()Z", |vm, a| {
+            Ok(Some(get_field(vm, a[0], "interrupted")))
+        })
+        .method("toString", "()Ljava/lang/String;", |vm, a| {
+            let name = get_field(vm, a[0], "name");
+            let name = vm.string_value(name)?;
+            let priority = get_field(vm, a[0], "priority").as_int();
+            // terminated threads leave their thread group
+            let group = match vm.is_terminated(a[0]) {
+                true => "",
+                false => "main",
+            };
+            Ok(Some(
+                vm.new_string(format!("Thread[{name},{priority},{group}]")),
+            ))
+        })
+        .static_method("interrupted", "()Z", |vm, _| {
+            Ok(Some(Value::Int(vm.interrupted() as i32)))
+        })
+        .static_method("currentThread", "()Ljava/lang/Thread;", |vm, _| {
+            Ok(Some(vm.thread))
+        })
+        .static_method("sleep", "(J)V", |vm, a| {
+            vm.sleep(a[0].as_long())?;
+            Ok(None)
+        })
+        .static_method("yield", "()V", |vm, _| {
+            vm.yield_now();
+            Ok(None)
+        })
+        .static_method("onSpinWait", "()V", |vm, _| {
+            vm.yield_now();
+            Ok(None)
+        })
+        .install(vm);
+
+    vm.put_static(THREAD, "threadSeqNumber", Value::Long(1))
+        .unwrap();
+    let thread = vm.new_object(THREAD).unwrap();
+    let name = vm.intern("main");
+    init(vm, thread, Value::Null, name).unwrap();
+    vm.attach_main_thread(thread);
+}
@@ -0,0 +1,297 @@
+use crate::vm::{
+    exec::class_cast_message,
+    heap::{Lambda, Object},
+    invokedynamic::{MethodHandle, REF_INVOKE_STATIC},
+    value::Value,
+    Vm,
+};
+
+use super::ClassBuilder;
+
+const COMPARE: &str = "(Ljava/lang/Object;Ljava/lang/Object;)I";
+const TEST: &str = "(Ljava/lang/Object;)Z";
+const APPLY: &str = "(Ljava/lang/Object;)Ljava/lang/Object;";
+
+/// instance of `interface` whose method `name` calls the static native `target` of the
+/// interface with the captured values first, like javac compiles lambdas in default methods
+fn lambda(
+    vm: &mut Vm,
+    interface: &str,
+    (name, descriptor): (&str, &str),
+    (target, target_descriptor): (&str, &str),
+    captured: Vec<Value>,
+) -> Value {
+    vm.heap.alloc(Object::Lambda(Lambda {
+        interface: interface.to_string(),
+        name: name.to_string(),
+        descriptor: descriptor.to_string(),
+        target: MethodHandle {
+            kind: REF_INVOKE_STATIC,
+            class: interface.to_string(),
+            name: target.to_string(),
+            descriptor: target_descriptor.to_string(),
+        },
+        captured,
+    }))
+}
+
+fn compare(vm: &mut Vm, comparator: Value, a: Value, b: Value) -> anyhow::Result<i32> {
+    let result = vm.invoke_virtual(
+        "java/util/Comparator",
+        "compare",
+        COMPARE,
+        [comparator, a, b],
+    )?;
+    Ok(result.unwrap().as_int())
+}
+
+fn test(vm: &mut Vm, predicate: Value, value: Value) -> anyhow::Result<bool> {
+    let result = vm.invoke_virtual(
+        "java/util/function/Predicate",
+        "test",
+        TEST,
+        [predicate, value],
+    )?;
+    Ok(result.unwrap().as_int() != 0)
+}
+
+fn apply(vm: &mut Vm, function: Value, value: Value) -> anyhow::Result<Value> {
+    let result = vm.invoke_virtual(
+        "java/util/function/Function",
+        "apply",
+        APPLY,
+        [function, value],
+    )?;
+    Ok(result.unwrap())
+}
+
+/// `compareTo` of `Comparable` elements, which `Arrays.sort` uses without a comparator
+fn compare_natural(vm: &mut Vm, a: Value, b: Value) -> anyhow::Result<i32> {
+    for value in [a, b] {
+        let Some(r) = value.as_ref() else {
+            return Err(vm.null_pointer());
+        };
+        let class = vm.heap.class_name(r).to_string();
+        if !vm.is_assignable(&class, "java/lang/Comparable")? {
+            let message = class_cast_message(vm, &class, "java/lang/Comparable");
+            return Err(vm.exception("java/lang/ClassCastException", &message));
+        }
+    }
+    let descriptor = "(Ljava/lang/Object;)I";
+    let result = vm.invoke_virtual("java/lang/Comparable", "compareTo", descriptor, [a, b])?;
+    Ok(result.unwrap().as_int())
+}
+
+/// stable merge sort, as `Arrays.sort` of objects is, with a comparison which may throw.
+/// The values stay reachable from the array being sorted while java code runs
+fn merge_sort(
+    vm: &mut Vm,
+    values: &mut Vec<Value>,
+    mut compare: impl FnMut(&mut Vm, Value, Value) -> anyhow::Result<i32>,
+) -> anyhow::Result<()> {
+    let n = values.len();
+    let mut merged = values.clone();
+    let mut width = 1;
+    while width < n {
+        for start in (0..n).step_by(2 * width) {
+            let mid = (start + width).min(n);
+            let end = (start + 2 * width).min(n);
+            let (mut i, mut j) = (start, mid);
+            for slot in &mut merged[start..end] {
+                let left = j == end || i < mid && compare(vm, values[i], values[j])? <= 0;
+                *slot = match left {
+                    true => values[i],
+                    false => values[j],
+                };
+                match left {
+                    true => i += 1,
+                    false => j += 1,
+                }
+            }
+        }
+        std::mem::swap(values, &mut merged);
+        width *= 2;
+    }
+    Ok(())
+}
+
+fn comparator(vm: &mut Vm) {
+    let interface = "java/util/Comparator";
+    let reversed = (
+        "lambda$reversed$0",
+        "(Ljava/util/Comparator;Ljava/lang/Object;Ljava/lang/Object;)I",
+    );
+    let then_comparing = (
+        "lambda$thenComparing$0",
+        "(Ljava/util/Comparator;Ljava/util/Comparator;Ljava/lang/Object;Ljava/lang/Object;)I",
+    );
+    ClassBuilder::interface(interface)
+        .method("reversed", "()Ljava/util/Comparator;", move |vm, a| {
+            let captured = vec![a[0]];
+            Ok(Some(lambda(
+                vm,
+                interface,
+                ("compare", COMPARE),
+                reversed,
+                captured,
+            )))
+        })
+        .static_method(reversed.0, reversed.1, |vm, a| {
+            Ok(Some(Value::Int(compare(vm, a[0], a[2], a[1])?)))
+        })
+        .method(
+            "thenComparing",
+            "(Ljava/util/Comparator;)Ljava/util/Comparator;",
+            move |vm, a| {
+                if a[1] == Value::Null {
+                    return Err(vm.null_pointer());
+                }
+                let captured = a.to_vec();
+                let site = ("compare", COMPARE);
+                Ok(Some(lambda(vm, interface, site, then_comparing, captured)))
+            },
+        )
+        .static_method(then_comparing.0, then_comparing.1, |vm, a| {
+            let result = match compare(vm, a[0], a[2], a[3])? {
+                0 => compare(vm, a[1], a[2], a[3])?,
+                result => result,
+            };
+            Ok(Some(Value::Int(result)))
+        })
+        .install(vm);
+}
+
+fn predicate(vm: &mut Vm) {
+    let interface = "java/util/function/Predicate";
+    let negate = (
+        "lambda$negate$0",
+        "(Ljava/util/function/Predicate;Ljava/lang/Object;)Z",
+    );
+    let and = (
+        "lambda$and$0",
+        "(Ljava/util/function/Predicate;Ljava/util/function/Predicate;Ljava/lang/Object;)Z",
+    );
+    let or = (
+        "lambda$or$0",
+        "(Ljava/util/function/Predicate;Ljava/util/function/Predicate;Ljava/lang/Object;)Z",
+    );
+    let combine = "(Ljava/util/function/Predicate;)Ljava/util/function/Predicate;";
+    ClassBuilder::interface(interface)
+        .method(
+            "negate",
+            "()Ljava/util/function/Predicate;",
+            move |vm, a| {
+                Ok(Some(lambda(
+                    vm,
+                    interface,
+                    ("test", TEST),
+                    negate,
+                    vec![a[0]],
+                )))
+            },
+        )
+        .static_method(negate.0, negate.1, |vm, a| {
+            Ok(Some(Value::Int(!test(vm, a[0], a[1])? as i32)))
+        })
+        .method("and", combine, move |vm, a| {
+            if a[1] == Value::Null {
+                return Err(vm.null_pointer());
+            }
+            Ok(Some(lambda(vm, interface, ("test", TEST), and, a.to_vec())))
+        })
+        .static_method(and.0, and.1, |vm, a| {
+            let result = test(vm, a[0], a[2])? && test(vm, a[1], a[2])?;
+            Ok(Some(Value::Int(result as i32)))
+        })
+        .method("or", combine, move |vm, a| {
+            if a[1] == Value::Null {
+                return Err(vm.null_pointer());
+            }
+            Ok(Some(lambda(vm, interface, ("test", TEST), or, a.to_vec())))
+        })
+        .static_method(or.0, or.1, |vm, a| {
+            let result = test(vm, a[0], a[2])? || test(vm, a[1], a[2])?;
+            Ok(Some(Value::Int(result as i32)))
+        })
+        .install(vm);
+}
+
+fn function(vm: &mut Vm) {
+    let interface = "java/util/function/Function";
+    let site = ("apply", APPLY);
+    let and_then = (
+        "lambda$andThen$0",
+        "(Ljava/util/function/Function;Ljava/util/function/Function;Ljava/lang/Object;)Ljava/lang/Object;",
+    );
+    let identity = ("lambda$identity$0", APPLY);
+    let combine = "(Ljava/util/function/Function;)Ljava/util/function/Function;";
+    ClassBuilder::interface(interface)
+        .method("andThen", combine, move |vm, a| {
+            if a[1] == Value::Null {
+                return Err(vm.null_pointer());
+            }
+            Ok(Some(lambda(vm, interface, site, and_then, a.to_vec())))
+        })
+        .method("compose", combine, move |vm, a| {
+            if a[1] == Value::Null {
+                return Err(vm.null_pointer());
+            }
+            // `f.compose(g)` is `g.andThen(f)`
+            Ok(Some(lambda(
+                vm,
+                interface,
+                site,
+                and_then,
+                vec![a[1], a[0]],
+            )))
+        })
+        .static_method(and_then.0, and_then.1, |vm, a| {
+            let value = apply(vm, a[0], a[2])?;
+            vm.roots.push(value);
+            let result = apply(vm, a[1], value);
+            vm.roots.pop();
+            Ok(Some(result?))
+        })
+        .static_method(
+            "identity",
+            "()Ljava/util/function/Function;",
+            move |vm, _| Ok(Some(lambda(vm, interface, site, identity, vec![]))),
+        )
+        .static_method(identity.0, identity.1, |_, a| Ok(Some(a[0])))
+        .install(vm);
+}
+
+/// `Arrays.sort` of objects, in natural order when the comparator is null
+fn sort(vm: &mut Vm, array: Value, comparator: Value) -> anyhow::Result<()> {
+    let Some(r) = array.as_ref() else {
+        return Err(vm.null_pointer());
+    };
+    let mut values = vm.heap.get_array(r).elements.clone();
+    match comparator {
+        Value::Null => merge_sort(vm, &mut values, compare_natural)?,
+        _ => merge_sort(vm, &mut values, |vm, a, b| compare(vm, comparator, a, b))?,
+    }
+    vm.heap.get_array_mut(r).elements = values;
+    Ok(())
+}
+
+pub fn install(vm: &mut Vm) {
+    comparator(vm);
+    predicate(vm);
+    function(vm);
+
+    ClassBuilder::new("java/util/Arrays", "java/lang/Object")
+        .static_method("sort", "([Ljava/lang/Object;)V", |vm, a| {
+            sort(vm, a[0], Value::Null)?;
+            Ok(None)
+        })
+        .static_method(
+            "sort",
+            "([Ljava/lang/Object;Ljava/util/Comparator;)V",
+            |vm, a| {
+                sort(vm, a[0], a[1])?;
+                Ok(None)
+            },
+        )
+        .install(vm);
+}
@@ -0,0 +1,298 @@
+//! limits for running untrusted code, see [`Limits`]. Running out of fuel or time or
+//! loading too many classes fails with a [`LimitExceeded`] error, which java code cannot
+//! catch, and keeps failing every thread until new limits are set. The stack depth and the
+//! heap size are limits of the JVM itself, which throw `StackOverflowError` and
+//! `OutOfMemoryError` like HotSpot does. Calls also throw `StackOverflowError` before the
+//! rust stack of the thread runs out, see [`set_thread_stack`].
+//!
+//! The VM has no natives reading files or using the network, so java code only gets to do
+//! that through natives the host registers. [`Limits::natives`] restricts which natives,
+//! including those of the built-in library, java code may call. The exception are the file
+//! system natives a JDK's class library needs: they only see files of the JDK itself, and
+//! the rest of the host's files only once [`Limits::natives`] names them, see
+//! [`FILE_NATIVES`]
+
+use std::{
+    cell::Cell,
+    fmt::Display,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use super::{thread::Exit, trace::glob, Vm};
+
+/// frames a thread may have on its stack, which fit into the 8 MiB stack of a main thread
+/// in release builds
+pub const DEFAULT_STACK_DEPTH: usize = 4000;
+/// rust stack a java frame takes at most, in debug builds which take several times the
+/// stack of release builds
+const FRAME_STACK: usize = 64 << 10;
+/// bounds of the rust stack of threads running java code
+const MIN_STACK: usize = 8 << 20;
+const MAX_STACK: usize = 1 << 30;
+/// deepest stack the threads of the vm have room for
+pub const MAX_STACK_DEPTH: usize = MAX_STACK / FRAME_STACK;
+/// rust stack assumed left on a thread of the host when it calls into the vm, the default
+/// of threads spawned by the standard library, see [`VmBuilder::host_stack`]
+///
+/// [`VmBuilder::host_stack`]: crate::VmBuilder::host_stack
+pub const HOST_STACK: usize = 2 << 20;
+/// rust stack kept free for natives and for creating the `StackOverflowError`
+const RED_ZONE: usize = 4 * FRAME_STACK;
+/// natives of a JDK's class library which look at the host's files, which java code may
+/// only call when [`Limits::natives`] names them. `canonicalize0` and
+/// `getBooleanAttributes0`, which the JDK needs for its own files, see files outside of
+/// the JDK only then
+pub const FILE_NATIVES: &[&str] = &[
+    "java.io.UnixFileSystem.checkAccess",
+    "java.io.UnixFileSystem.getLength",
+    "java.io.UnixFileSystem.getLastModifiedTime",
+    "java.io.UnixFileSystem.list",
+];
+/// safepoints passed between looking at the clock
+const CLOCK_INTERVAL: u32 = 1024;
+
+thread_local! {
+    /// address near the start of the rust stack of the current thread and its size, for
+    /// threads started to run java code
+    static STACK: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// address of the top of the rust stack, which grows down
+#[inline(never)]
+fn stack_pointer() -> usize {
+    let marker = 0u8;
+    black_box(&marker) as *const u8 as usize
+}
+
+/// records that the current thread has `size` bytes of rust stack from here on, like the
+/// threads spawned with a stack of [`Limits::stack_size`]. Other threads of the host have
+/// the stack given to [`VmBuilder::host_stack`] from where they call into the vm
+///
+/// [`VmBuilder::host_stack`]: crate::VmBuilder::host_stack
+pub fn set_thread_stack(size: usize) {
+    STACK.set(Some((stack_pointer(), size)));
+}
+
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// instructions the program may run
+    pub fuel: Option<u64>,
+    /// wall clock time the program may run, including time spent waiting
+    pub timeout: Option<Duration>,
+    /// frames on the stack of a thread, beyond which calls throw `StackOverflowError`. At
+    /// most [`MAX_STACK_DEPTH`]
+    pub max_stack_depth: usize,
+    /// classes the program may load from the classpath
+    pub max_classes: Option<usize>,
+    /// globs of `pkg.Class` or `pkg.Class.method` for the natives java code may call,
+    /// any when `None`. Others throw `SecurityException`
+    pub natives: Option<Vec<String>>,
+    /// globs of `pkg.Class` for the classes which may be loaded from the classpath, any
+    /// when `None`. Others throw `SecurityException`
+    pub classes: Option<Vec<String>>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            fuel: None,
+            timeout: None,
+            max_stack_depth: DEFAULT_STACK_DEPTH,
+            max_classes: None,
+            natives: None,
+            classes: None,
+        }
+    }
+}
+
+impl Limits {
+    /// rust stack for a thread running java code, which fits `max_stack_depth` frames
+    pub fn stack_size(&self) -> usize {
+        (self.max_stack_depth * FRAME_STACK).clamp(MIN_STACK, MAX_STACK)
+    }
+}
+
+/// error of a program which went over one of its [`Limits`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitExceeded {
+    Fuel(u64),
+    Timeout(Duration),
+    Classes(usize),
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Fuel(fuel) => write!(f, "used up the fuel of {fuel} instructions"),
+            LimitExceeded::Timeout(timeout) => write!(f, "timed out after {timeout:?}"),
+            LimitExceeded::Classes(max) => write!(f, "loaded more than {max} classes"),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// what is left of the limits while the program runs
+#[derive(Default)]
+pub(super) struct Budget {
+    fuel: Option<u64>,
+    pub deadline: Option<Instant>,
+    /// safepoints left until looking at the clock
+    clock: u32,
+    classes: usize,
+    exceeded: Option<LimitExceeded>,
+    /// whether a `StackOverflowError` is being created, which may call further
+    overflowing: bool,
+}
+
+/// whether `pattern` matches class `class` or method `name` of it
+fn allows(patterns: &Option<Vec<String>>, class: &str, name: Option<&str>) -> bool {
+    let Some(patterns) = patterns else {
+        return true;
+    };
+    let class = class.replace('/', ".");
+    let method = name.map(|name| format!("{class}.{name}"));
+    patterns.iter().any(|pattern| {
+        glob(pattern, &class) || method.as_ref().is_some_and(|method| glob(pattern, method))
+    })
+}
+
+impl Vm {
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// applies `limits` from now on, with all of the fuel and time. Natives are checked
+    /// when a call site first links to them, so the allowed natives are best set before
+    /// running any java code
+    pub fn set_limits(&mut self, mut limits: Limits) {
+        limits.max_stack_depth = limits.max_stack_depth.min(MAX_STACK_DEPTH);
+        self.budget = Budget {
+            fuel: limits.fuel,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+            clock: CLOCK_INTERVAL,
+            classes: 0,
+            exceeded: None,
+            overflowing: false,
+        };
+        self.limits = limits;
+    }
+
+    /// instructions the program may still run, if limited
+    pub fn fuel_left(&self) -> Option<u64> {
+        self.budget.fuel
+    }
+
+    pub(super) fn is_fueled(&self) -> bool {
+        self.budget.fuel.is_some()
+    }
+
+    /// stops every thread, which wake up to find the limit exceeded
+    fn exceed(&mut self, exceeded: LimitExceeded) -> anyhow::Error {
+        self.budget.exceeded = Some(exceeded);
+        self.event();
+        exceeded.into()
+    }
+
+    /// takes the fuel for one instruction
+    pub(super) fn burn_fuel(&mut self) -> anyhow::Result<()> {
+        match &mut self.budget.fuel {
+            Some(0) => Err(self.exceed(LimitExceeded::Fuel(self.limits.fuel.unwrap()))),
+            Some(fuel) => {
+                *fuel -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// fails once the program exited, a limit was exceeded or the time is up, looking at
+    /// the clock every few calls only unless `now`
+    pub(super) fn check_limits(&mut self, now: bool) -> anyhow::Result<()> {
+        if let Some(status) = self.exit_status() {
+            return Err(Exit(status).into());
+        }
+        if let Some(exceeded) = self.budget.exceeded {
+            return Err(exceeded.into());
+        }
+        self.check_stopped()?;
+        let Some(deadline) = self.budget.deadline else {
+            return Ok(());
+        };
+        self.budget.clock -= 1;
+        if now || self.budget.clock == 0 {
+            self.budget.clock = CLOCK_INTERVAL;
+            if Instant::now() >= deadline {
+                let timeout = self.limits.timeout.unwrap();
+                return Err(self.exceed(LimitExceeded::Timeout(timeout)));
+            }
+        }
+        Ok(())
+    }
+
+    /// notes where the host calls into the vm, from where a thread of the host has the
+    /// stack given to [`VmBuilder::host_stack`](crate::VmBuilder::host_stack)
+    pub(super) fn enter_from_host(&mut self) {
+        if STACK.get().is_none() {
+            self.host_stack.0 = stack_pointer();
+        }
+    }
+
+    /// rust stack the current thread used since it started running java code, and its size
+    fn stack_usage(&self) -> (usize, usize) {
+        let (base, size) = STACK.get().unwrap_or(self.host_stack);
+        (base.saturating_sub(stack_pointer()), size)
+    }
+
+    /// throws `StackOverflowError` when a call would go beyond the stack depth or the rust
+    /// stack is about to run out, which calls through natives get to first
+    pub(super) fn check_stack_depth(&mut self) -> anyhow::Result<()> {
+        if self.budget.overflowing {
+            return Ok(());
+        }
+        let (used, size) = self.stack_usage();
+        if self.frames.len() < self.limits.max_stack_depth && used + RED_ZONE < size {
+            return Ok(());
+        }
+        self.budget.overflowing = true;
+        let err = self.exception("java/lang/StackOverflowError", "");
+        self.budget.overflowing = false;
+        Err(err)
+    }
+
+    /// throws `SecurityException` when java code calls a native which is not allowed, the
+    /// host calling one directly is fine and so are static initializers of the library
+    pub(super) fn check_native(&mut self, class: &str, name: &str) -> anyhow::Result<()> {
+        let method = format!("{}.{name}", class.replace('/', "."));
+        let allowed = match FILE_NATIVES.contains(&method.as_str()) {
+            true => self.grants_native(class, name),
+            false => name == "<clinit>" || allows(&self.limits.natives, class, Some(name)),
+        };
+        if self.frames.is_empty() || allowed {
+            return Ok(());
+        }
+        let message = format!("native method {method} is not allowed");
+        Err(self.exception("java/lang/SecurityException", &message))
+    }
+
+    /// whether [`Limits::natives`] names the native, rather than allowing any
+    pub(crate) fn grants_native(&self, class: &str, name: &str) -> bool {
+        self.limits.natives.is_some() && allows(&self.limits.natives, class, Some(name))
+    }
+
+    /// counts a class about to be loaded from the classpath
+    pub(super) fn check_class(&mut self, name: &str) -> anyhow::Result<()> {
+        if !allows(&self.limits.classes, name, None) {
+            let message = format!("class {} is not allowed", name.replace('/', "."));
+            return Err(self.exception("java/lang/SecurityException", &message));
+        }
+        if let Some(max) = self.limits.max_classes {
+            if self.budget.classes >= max {
+                return Err(self.exceed(LimitExceeded::Classes(max)));
+            }
+        }
+        self.budget.classes += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,636 @@
+//! Link step turning the parsed code of a method into the instruction stream run by
+//! [`exec`](super::exec). Branch targets become instruction indexes, constants are folded
+//! into the instructions, and field and method references become sites shared by every
+//! instruction using the same constant pool entry, which remember what they resolved to
+
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use crate::{
+    class::{
+        attribute::CodeAttribute,
+        constant_pool::CpInfo,
+        descriptor::MethodDescriptor,
+        method::{MethodAccessFlags, MethodInfo},
+        Class,
+    },
+    code::OpCode,
+};
+
+use super::{exec::primitive_array_class, invokedynamic::CallSite, native::NativeFn, value::Value};
+
+/// what invoking a method runs
+#[derive(Clone)]
+pub enum Target {
+    Native(NativeFn),
+    Code(Rc<Class>, Rc<Linked>),
+}
+
+/// a `Fieldref`. Static field sites cache the slot of the field in [`Vm::statics`](super::Vm)
+#[derive(Debug)]
+pub struct FieldSite {
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+    /// slot in [`Vm::statics`](super::Vm::statics), or in the objects for instance
+    /// fields, once resolved
+    pub slot: Cell<Option<usize>>,
+    /// whether the accessing class passed the access check
+    pub checked: Cell<bool>,
+}
+
+/// a `Methodref` or `InterfaceMethodref` with the method it resolved to. Virtual call sites
+/// remember the target for the last receiver class only
+pub struct MethodSite {
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+    /// number of arguments, without the receiver
+    pub args: usize,
+    /// receiver class (empty for static and special calls) and its target
+    pub cache: RefCell<Option<(String, Target)>>,
+    /// whether the calling class passed the access check
+    pub checked: Cell<bool>,
+}
+
+impl MethodSite {
+    pub fn cached(&self, receiver: &str) -> Option<Target> {
+        match &*self.cache.borrow() {
+            Some((class, target)) if class == receiver => Some(target.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn remember(&self, receiver: &str, target: &Target) {
+        *self.cache.borrow_mut() = Some((receiver.to_string(), target.clone()));
+    }
+}
+
+/// `ldc` of a string or another constant which has to be created on first use
+#[derive(Debug)]
+pub struct ConstantSite {
+    pub index: usize,
+    pub value: Cell<Option<Value>>,
+}
+
+/// an `invokedynamic`, linked to its [`CallSite`] on first execution
+#[derive(Debug)]
+pub struct DynamicSite {
+    pub index: usize,
+    /// number of arguments taken from the stack
+    pub args: usize,
+    pub call_site: OnceCell<CallSite>,
+}
+
+/// conversion applied to values stored into arrays of bytes, chars and shorts
+pub type Narrow = fn(Value) -> Value;
+
+#[derive(Clone)]
+pub enum Instr {
+    Nop,
+    /// every instruction pushing a constant known at link time
+    Const(Value),
+    Ldc(Rc<ConstantSite>),
+    /// loads and stores of every type, wide values take one stack entry
+    Load(usize),
+    Store(usize),
+    ArrayLoad,
+    ArrayStore(Narrow),
+    /// stores a reference, checking it against the element type of the array
+    AAStore,
+
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+
+    IAdd,
+    LAdd,
+    FAdd,
+    DAdd,
+    ISub,
+    LSub,
+    FSub,
+    DSub,
+    IMul,
+    LMul,
+    FMul,
+    DMul,
+    IDiv,
+    LDiv,
+    FDiv,
+    DDiv,
+    IRem,
+    LRem,
+    FRem,
+    DRem,
+    INeg,
+    LNeg,
+    FNeg,
+    DNeg,
+    IShl,
+    LShl,
+    IShr,
+    LShr,
+    IUShr,
+    LUShr,
+    IAnd,
+    LAnd,
+    IOr,
+    LOr,
+    IXor,
+    LXor,
+    Iinc(usize, i32),
+
+    I2L,
+    I2F,
+    I2D,
+    L2I,
+    L2F,
+    L2D,
+    F2I,
+    F2L,
+    F2D,
+    D2I,
+    D2L,
+    D2F,
+    I2B,
+    I2C,
+    I2S,
+
+    LCmp,
+    FCmpL,
+    FCmpG,
+    DCmpL,
+    DCmpG,
+
+    /// branch targets are instruction indexes
+    IfEq(usize),
+    IfNe(usize),
+    IfLt(usize),
+    IfGe(usize),
+    IfGt(usize),
+    IfLe(usize),
+    IfICmpEq(usize),
+    IfICmpNe(usize),
+    IfICmpLt(usize),
+    IfICmpGe(usize),
+    IfICmpGt(usize),
+    IfICmpLe(usize),
+    IfACmpEq(usize),
+    IfACmpNe(usize),
+    IfNull(usize),
+    IfNonNull(usize),
+    Goto(usize),
+    /// lowest key, the targets of the keys from it on and the default target
+    TableSwitch(i32, Vec<usize>, usize),
+    /// targets sorted by key and the default target
+    LookupSwitch(Vec<(i32, usize)>, usize),
+
+    /// every return of a value
+    ReturnValue,
+    Return,
+
+    GetStatic(Rc<FieldSite>),
+    PutStatic(Rc<FieldSite>),
+    GetField(Rc<FieldSite>),
+    PutField(Rc<FieldSite>),
+    /// virtual and interface calls
+    InvokeVirtual(Rc<MethodSite>),
+    InvokeSpecial(Rc<MethodSite>),
+    InvokeStatic(Rc<MethodSite>),
+    InvokeDynamic(Rc<DynamicSite>),
+
+    New(String),
+    /// array class descriptor of the created array
+    NewArray(String),
+    MultiANewArray(String, usize),
+    ArrayLength,
+    /// class names, or descriptors of array classes
+    CheckCast(String),
+    InstanceOf(String),
+    AThrow,
+    MonitorEnter,
+    MonitorExit,
+    /// instructions the interpreter does not run yet
+    Unsupported(OpCode),
+}
+
+/// exception table entry with instruction indexes
+#[derive(Debug)]
+pub struct Handler {
+    pub start: usize,
+    pub end: usize,
+    pub handler: usize,
+    /// `None` catches everything
+    pub catch: Option<String>,
+}
+
+/// code of a method ready to run
+pub struct Linked {
+    /// index of the method in [`Class::methods`]
+    pub method: usize,
+    pub code: Vec<Instr>,
+    /// whether the method is `synchronized` and whether it is static, locking its class
+    pub synchronized: bool,
+    pub is_static: bool,
+    pub max_locals: usize,
+    pub max_stack: usize,
+    pub handlers: Vec<Handler>,
+    /// bytecode offset of every instruction
+    pub pcs: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Call {
+    Virtual,
+    Special,
+    Static,
+}
+
+/// sites of a class, one for each constant pool entry and kind of use
+struct Linker<'a> {
+    class: &'a Class,
+    fields: HashMap<usize, Rc<FieldSite>>,
+    methods: HashMap<(usize, Call), Rc<MethodSite>>,
+    constants: HashMap<usize, Rc<ConstantSite>>,
+}
+
+impl<'a> Linker<'a> {
+    fn field(&mut self, index: usize) -> Rc<FieldSite> {
+        let cp = &self.class.cp;
+        self.fields
+            .entry(index)
+            .or_insert_with(|| {
+                let field = cp.get_member_ref(index).unwrap();
+                Rc::new(FieldSite {
+                    class: field.class.to_string(),
+                    name: field.name.to_string(),
+                    descriptor: field.descriptor.to_string(),
+                    slot: Cell::new(None),
+                    checked: Cell::new(false),
+                })
+            })
+            .clone()
+    }
+
+    fn method(&mut self, index: usize, call: Call) -> Rc<MethodSite> {
+        let cp = &self.class.cp;
+        self.methods
+            .entry((index, call))
+            .or_insert_with(|| {
+                let method = cp.get_member_ref(index).unwrap();
+                let args = MethodDescriptor::parse(method.descriptor)
+                    .map_or(0, |descriptor| descriptor.args.len());
+                Rc::new(MethodSite {
+                    class: method.class.to_string(),
+                    name: method.name.to_string(),
+                    descriptor: method.descriptor.to_string(),
+                    args,
+                    cache: RefCell::new(None),
+                    checked: Cell::new(false),
+                })
+            })
+            .clone()
+    }
+
+    fn constant(&mut self, index: usize) -> Instr {
+        match self.class.cp.get(index) {
+            Some(CpInfo::Integer(i)) => Instr::Const(Value::Int(i.val as i32)),
+            Some(CpInfo::Float(f)) => Instr::Const(Value::Float(f.val)),
+            Some(CpInfo::Long(l)) => Instr::Const(Value::Long(l.val)),
+            Some(CpInfo::Double(d)) => Instr::Const(Value::Double(d.val)),
+            _ => Instr::Ldc(
+                self.constants
+                    .entry(index)
+                    .or_insert_with(|| {
+                        Rc::new(ConstantSite {
+                            index,
+                            value: Cell::new(None),
+                        })
+                    })
+                    .clone(),
+            ),
+        }
+    }
+
+    fn class_name(&self, index: usize) -> String {
+        self.class.cp.get_class_name(index).unwrap().to_string()
+    }
+
+    fn link(&mut self, index: usize, method: &MethodInfo, code: &CodeAttribute) -> Linked {
+        // targets are only checked by the verifier, which runs after linking
+        let target = |i: usize, offset: isize| {
+            let pc = (code.op_to_bytecode[i] as isize + offset) as usize;
+            code.bytecode_to_op.get(pc).copied().unwrap_or(usize::MAX)
+        };
+        let mut linked = Vec::with_capacity(code.code.len());
+        for (i, op) in code.code.iter().enumerate() {
+            linked.push(match *op {
+                OpCode::Nop => Instr::Nop,
+                OpCode::AConstNull => Instr::Const(Value::Null),
+                OpCode::IConstM1 => Instr::Const(Value::Int(-1)),
+                OpCode::IConst0 => Instr::Const(Value::Int(0)),
+                OpCode::IConst1 => Instr::Const(Value::Int(1)),
+                OpCode::IConst2 => Instr::Const(Value::Int(2)),
+                OpCode::IConst3 => Instr::Const(Value::Int(3)),
+                OpCode::IConst4 => Instr::Const(Value::Int(4)),
+                OpCode::IConst5 => Instr::Const(Value::Int(5)),
+                OpCode::LConst0 => Instr::Const(Value::Long(0)),
+                OpCode::LConst1 => Instr::Const(Value::Long(1)),
+                OpCode::FConst0 => Instr::Const(Value::Float(0.0)),
+                OpCode::FConst1 => Instr::Const(Value::Float(1.0)),
+                OpCode::FConst2 => Instr::Const(Value::Float(2.0)),
+                OpCode::DConst0 => Instr::Const(Value::Double(0.0)),
+                OpCode::DConst1 => Instr::Const(Value::Double(1.0)),
+                OpCode::BiPush(value) | OpCode::SiPush(value) => {
+                    Instr::Const(Value::Int(value as i32))
+                }
+                OpCode::Ldc(index) | OpCode::LdcW(index) | OpCode::Ldc2W(index) => {
+                    self.constant(index)
+                }
+
+                OpCode::ILoad(n)
+                | OpCode::LLoad(n)
+                | OpCode::FLoad(n)
+                | OpCode::DLoad(n)
+                | OpCode::ALoad(n) => Instr::Load(n),
+                OpCode::ILoad0
+                | OpCode::LLoad0
+                | OpCode::FLoad0
+                | OpCode::DLoad0
+                | OpCode::ALoad0 => Instr::Load(0),
+                OpCode::ILoad1
+                | OpCode::LLoad1
+                | OpCode::FLoad1
+                | OpCode::DLoad1
+                | OpCode::ALoad1 => Instr::Load(1),
+                OpCode::ILoad2
+                | OpCode::LLoad2
+                | OpCode::FLoad2
+                | OpCode::DLoad2
+                | OpCode::ALoad2 => Instr::Load(2),
+                OpCode::ILoad3
+                | OpCode::LLoad3
+                | OpCode::FLoad3
+                | OpCode::DLoad3
+                | OpCode::ALoad3 => Instr::Load(3),
+                OpCode::IStore(n)
+                | OpCode::LStore(n)
+                | OpCode::FStore(n)
+                | OpCode::DStore(n)
+                | OpCode::AStore(n) => Instr::Store(n),
+                OpCode::IStore0
+                | OpCode::LStore0
+                | OpCode::FStore0
+                | OpCode::DStore0
+                | OpCode::AStore0 => Instr::Store(0),
+                OpCode::IStore1
+                | OpCode::LStore1
+                | OpCode::FStore1
+                | OpCode::DStore1
+                | OpCode::AStore1 => Instr::Store(1),
+                OpCode::IStore2
+                | OpCode::LStore2
+                | OpCode::FStore2
+                | OpCode::DStore2
+                | OpCode::AStore2 => Instr::Store(2),
+                OpCode::IStore3
+                | OpCode::LStore3
+                | OpCode::FStore3
+                | OpCode::DStore3
+                | OpCode::AStore3 => Instr::Store(3),
+
+                OpCode::IALoad
+                | OpCode::LALoad
+                | OpCode::FALoad
+                | OpCode::DALoad
+                | OpCode::AALoad
+                | OpCode::BALoad
+                | OpCode::CALoad
+                | OpCode::SALoad => Instr::ArrayLoad,
+                OpCode::IAStore | OpCode::LAStore | OpCode::FAStore | OpCode::DAStore => {
+                    Instr::ArrayStore(|v| v)
+                }
+                OpCode::AAStore => Instr::AAStore,
+                OpCode::BAStore => Instr::ArrayStore(|v| Value::Int(v.as_int() as i8 as i32)),
+                OpCode::CAStore => Instr::ArrayStore(|v| Value::Int(v.as_int() as u16 as i32)),
+                OpCode::SAStore => Instr::ArrayStore(|v| Value::Int(v.as_int() as i16 as i32)),
+
+                OpCode::Pop => Instr::Pop,
+                OpCode::Pop2 => Instr::Pop2,
+                OpCode::Dup => Instr::Dup,
+                OpCode::DupX1 => Instr::DupX1,
+                OpCode::DupX2 => Instr::DupX2,
+                OpCode::Dup2 => Instr::Dup2,
+                OpCode::Dup2X1 => Instr::Dup2X1,
+                OpCode::Dup2X2 => Instr::Dup2X2,
+                OpCode::Swap => Instr::Swap,
+
+                OpCode::IAdd => Instr::IAdd,
+                OpCode::LAdd => Instr::LAdd,
+                OpCode::FAdd => Instr::FAdd,
+                OpCode::DAdd => Instr::DAdd,
+                OpCode::ISub => Instr::ISub,
+                OpCode::LSub => Instr::LSub,
+                OpCode::FSub => Instr::FSub,
+                OpCode::DSub => Instr::DSub,
+                OpCode::IMul => Instr::IMul,
+                OpCode::LMul => Instr::LMul,
+                OpCode::FMul => Instr::FMul,
+                OpCode::DMul => Instr::DMul,
+                OpCode::IDiv => Instr::IDiv,
+                OpCode::LDiv => Instr::LDiv,
+                OpCode::FDiv => Instr::FDiv,
+                OpCode::DDiv => Instr::DDiv,
+                OpCode::IRem => Instr::IRem,
+                OpCode::LRem => Instr::LRem,
+                OpCode::FRem => Instr::FRem,
+                OpCode::DRem => Instr::DRem,
+                OpCode::INeg => Instr::INeg,
+                OpCode::LNeg => Instr::LNeg,
+                OpCode::FNeg => Instr::FNeg,
+                OpCode::DNeg => Instr::DNeg,
+                OpCode::IShl => Instr::IShl,
+                OpCode::LShl => Instr::LShl,
+                OpCode::IShr => Instr::IShr,
+                OpCode::LShr => Instr::LShr,
+                OpCode::IUShr => Instr::IUShr,
+                OpCode::LUShr => Instr::LUShr,
+                OpCode::IAnd => Instr::IAnd,
+                OpCode::LAnd => Instr::LAnd,
+                OpCode::IOr => Instr::IOr,
+                OpCode::LOr => Instr::LOr,
+                OpCode::IXor => Instr::IXor,
+                OpCode::LXor => Instr::LXor,
+                OpCode::Iinc(n, increment) => Instr::Iinc(n, increment as i32),
+
+                OpCode::I2L => Instr::I2L,
+                OpCode::I2F => Instr::I2F,
+                OpCode::I2D => Instr::I2D,
+                OpCode::L2I => Instr::L2I,
+                OpCode::L2F => Instr::L2F,
+                OpCode::L2D => Instr::L2D,
+                OpCode::F2I => Instr::F2I,
+                OpCode::F2L => Instr::F2L,
+                OpCode::F2D => Instr::F2D,
+                OpCode::D2I => Instr::D2I,
+                OpCode::D2L => Instr::D2L,
+                OpCode::D2F => Instr::D2F,
+                OpCode::I2B => Instr::I2B,
+                OpCode::I2C => Instr::I2C,
+                OpCode::I2S => Instr::I2S,
+
+                OpCode::LCmp => Instr::LCmp,
+                OpCode::FCmpL => Instr::FCmpL,
+                OpCode::FCmpG => Instr::FCmpG,
+                OpCode::DCmpL => Instr::DCmpL,
+                OpCode::DCmpG => Instr::DCmpG,
+
+                OpCode::IfEq(offset) => Instr::IfEq(target(i, offset)),
+                OpCode::IfNe(offset) => Instr::IfNe(target(i, offset)),
+                OpCode::IfLt(offset) => Instr::IfLt(target(i, offset)),
+                OpCode::IfGe(offset) => Instr::IfGe(target(i, offset)),
+                OpCode::IfGt(offset) => Instr::IfGt(target(i, offset)),
+                OpCode::IfLe(offset) => Instr::IfLe(target(i, offset)),
+                OpCode::IfICmpEq(offset) => Instr::IfICmpEq(target(i, offset)),
+                OpCode::IfICmpNe(offset) => Instr::IfICmpNe(target(i, offset)),
+                OpCode::IfICmpLt(offset) => Instr::IfICmpLt(target(i, offset)),
+                OpCode::IfICmpGe(offset) => Instr::IfICmpGe(target(i, offset)),
+                OpCode::IfICmpGt(offset) => Instr::IfICmpGt(target(i, offset)),
+                OpCode::IfICmpLe(offset) => Instr::IfICmpLe(target(i, offset)),
+                OpCode::IfACmpEq(offset) => Instr::IfACmpEq(target(i, offset)),
+                OpCode::IfACmpNe(offset) => Instr::IfACmpNe(target(i, offset)),
+                OpCode::IfNull(offset) => Instr::IfNull(target(i, offset)),
+                OpCode::IfNonNull(offset) => Instr::IfNonNull(target(i, offset)),
+                OpCode::Goto(offset) | OpCode::GotoW(offset) => Instr::Goto(target(i, offset)),
+                OpCode::TableSwitch(default, low, ref offsets) => {
+                    let targets = offsets.iter().map(|&offset| target(i, offset)).collect();
+                    Instr::TableSwitch(low, targets, target(i, default))
+                }
+                OpCode::LookupSwitch(default, ref pairs) => {
+                    let targets = pairs
+                        .iter()
+                        .map(|&(key, offset)| (key, target(i, offset)))
+                        .collect();
+                    Instr::LookupSwitch(targets, target(i, default))
+                }
+
+                OpCode::IReturn
+                | OpCode::LReturn
+                | OpCode::FReturn
+                | OpCode::DReturn
+                | OpCode::AReturn => Instr::ReturnValue,
+                OpCode::Return => Instr::Return,
+
+                OpCode::GetStatic(index) => Instr::GetStatic(self.field(index)),
+                OpCode::PutStatic(index) => Instr::PutStatic(self.field(index)),
+                OpCode::GetField(index) => Instr::GetField(self.field(index)),
+                OpCode::PutField(index) => Instr::PutField(self.field(index)),
+                OpCode::InvokeVirtual(index) | OpCode::InvokeInterface(index, _) => {
+                    Instr::InvokeVirtual(self.method(index, Call::Virtual))
+                }
+                OpCode::InvokeSpecial(index) => {
+                    Instr::InvokeSpecial(self.method(index, Call::Special))
+                }
+                OpCode::InvokeStatic(index) => {
+                    Instr::InvokeStatic(self.method(index, Call::Static))
+                }
+                OpCode::InvokeDynamic(index) => {
+                    let cp = &self.class.cp;
+                    let indy = cp.get_invoke_dynamic(index).unwrap();
+                    let name_type = cp.get_name_and_type(indy.name_and_type_index).unwrap();
+                    let descriptor = &cp.get_utf(name_type.descriptor_index).unwrap().bytes;
+                    let args = MethodDescriptor::parse(descriptor)
+                        .map_or(0, |descriptor| descriptor.args.len());
+                    Instr::InvokeDynamic(Rc::new(DynamicSite {
+                        index,
+                        args,
+                        call_site: OnceCell::new(),
+                    }))
+                }
+
+                OpCode::New(index) => Instr::New(self.class_name(index)),
+                OpCode::NewArray(atype) => {
+                    Instr::NewArray(primitive_array_class(atype).to_string())
+                }
+                OpCode::ANewArray(index) => {
+                    let component = self.class_name(index);
+                    Instr::NewArray(match component.starts_with('[') {
+                        true => format!("[{component}"),
+                        false => format!("[L{component};"),
+                    })
+                }
+                OpCode::MultiANewArray(index, dimensions) => {
+                    Instr::MultiANewArray(self.class_name(index), dimensions)
+                }
+                OpCode::ArrayLength => Instr::ArrayLength,
+                OpCode::AThrow => Instr::AThrow,
+                OpCode::MonitorEnter => Instr::MonitorEnter,
+                OpCode::MonitorExit => Instr::MonitorExit,
+                OpCode::CheckCast(index) => Instr::CheckCast(self.class_name(index)),
+                OpCode::InstanceOf(index) => Instr::InstanceOf(self.class_name(index)),
+                ref op @ (OpCode::Jsr(_) | OpCode::JsrW(_) | OpCode::Ret(_)) => {
+                    Instr::Unsupported(op.clone())
+                }
+            });
+        }
+
+        let op = |pc: usize| match pc {
+            pc if pc >= code.code_raw.len() => code.code.len(),
+            pc => code.bytecode_to_op[pc],
+        };
+        let handlers = code
+            .exception_table
+            .iter()
+            .map(|entry| Handler {
+                start: op(entry.start_pc),
+                end: op(entry.end_pc),
+                handler: op(entry.handler_pc),
+                catch: match entry.catch_type {
+                    0 => None,
+                    index => Some(self.class_name(index)),
+                },
+            })
+            .collect();
+        Linked {
+            method: index,
+            code: linked,
+            synchronized: method
+                .access_flags
+                .contains(MethodAccessFlags::SYNCHRONIZED),
+            is_static: method.access_flags.contains(MethodAccessFlags::STATIC),
+            max_locals: code.max_locals,
+            max_stack: code.max_stack,
+            handlers,
+            pcs: code.op_to_bytecode.clone(),
+        }
+    }
+}
+
+/// links every method with code, in the order of [`Class::methods`]
+pub fn link_class(class: &Class) -> Vec<Option<Rc<Linked>>> {
+    let mut linker = Linker {
+        class,
+        fields: HashMap::new(),
+        methods: HashMap::new(),
+        constants: HashMap::new(),
+    };
+    class
+        .methods
+        .iter()
+        .enumerate()
+        .map(|(i, method)| {
+            let code = method.code()?;
+            Some(Rc::new(linker.link(i, method, code)))
+        })
+        .collect()
+}
@@ -0,0 +1,75 @@
+//! `java.lang.Class` objects, one per class, which `ldc` and `Object.getClass` hand out.
+//! A mirror is an instance of `java.lang.Class` whose `name` field holds the binary name,
+//! the way the JDK's `Class.getName` caches it. With a JDK the mirrors also carry the
+//! fields HotSpot sets, the module and the component type of arrays
+
+use super::{heap::Object, library::component, value::Value, Vm};
+
+impl Vm {
+    /// the `java.lang.Class` object of the class with internal name `class`, or of the
+    /// array class with descriptor `class`
+    pub(crate) fn class_object(&mut self, class: &str) -> anyhow::Result<Value> {
+        if let Some(&mirror) = self.mirrors.get(class) {
+            return Ok(mirror);
+        }
+        let mirror = self.new_object("java/lang/Class")?;
+        self.roots.push(mirror);
+        let name = self.new_string(class.replace('/', "."));
+        self.roots.pop();
+        let instance = self.heap.get_instance_mut(mirror.as_ref().unwrap());
+        instance.set("name", name);
+        self.mirrors.insert(class.to_string(), mirror);
+        if self.jdk {
+            let instance = self.heap.get_instance_mut(mirror.as_ref().unwrap());
+            instance.set("module", self.unnamed_module);
+            if let Some(component) = component(class) {
+                let component = self.class_object(component)?;
+                let instance = self.heap.get_instance_mut(mirror.as_ref().unwrap());
+                instance.set("componentType", component);
+            }
+        }
+        Ok(mirror)
+    }
+
+    /// with a JDK, puts every class into an unnamed module, which exports and opens all
+    /// its packages. The module system itself is not booted
+    pub(super) fn create_unnamed_module(&mut self) -> anyhow::Result<()> {
+        let module = self.new_object("java/lang/Module")?;
+        self.roots.push(module);
+        let constructed = self.invoke_special(
+            "java/lang/Module",
+            "<init>",
+            "(Ljava/lang/ClassLoader;)V",
+            vec![module, Value::Null],
+        );
+        self.roots.pop();
+        constructed?;
+        self.set_unnamed_module(module);
+        Ok(())
+    }
+
+    /// makes `module` the module of every class, like `BootLoader` does once it created
+    /// the unnamed module of the boot loader
+    pub(crate) fn set_unnamed_module(&mut self, module: Value) {
+        self.unnamed_module = module;
+        for mirror in self.mirrors.values() {
+            let instance = self.heap.get_instance_mut(mirror.as_ref().unwrap());
+            instance.set("module", module);
+        }
+    }
+
+    /// internal name of the class a `java.lang.Class` object stands for
+    pub(crate) fn mirrored_class(&mut self, mirror: Value) -> anyhow::Result<String> {
+        let Some(r) = mirror.as_ref() else {
+            return Err(self.null_pointer());
+        };
+        let name = match self.heap.get(r) {
+            Object::Instance(instance) => instance.get("name"),
+            _ => None,
+        };
+        match name {
+            Some(name) if name != Value::Null => Ok(self.string_value(name)?.replace('.', "/")),
+            _ => anyhow::bail!("not a class object"),
+        }
+    }
+}
@@ -0,0 +1,702 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    ptr,
+    rc::Rc,
+    time::Instant,
+};
+
+use anyhow::Context;
+
+use crate::{
+    class::{
+        constant_pool::CpInfo,
+        descriptor::class_name,
+        field::FieldAccessFlags,
+        method::{MethodAccessFlags, MethodInfo},
+        Class,
+    },
+    source::ByteStream,
+};
+
+use self::{
+    builder::VmBuilder,
+    classpath::ClassSource,
+    debug::Debugger,
+    exec::Frame,
+    heap::{Heap, Instance, Layout, LayoutField, Object},
+    jdwp::Jdwp,
+    limits::{Budget, Limits, HOST_STACK},
+    link::{Linked, Target},
+    module::Modules,
+    native::Natives,
+    profile::Profiler,
+    thread::Threads,
+    trace::Tracer,
+    value::Value,
+};
+
+mod access;
+pub(crate) mod builder;
+pub mod classpath;
+pub mod debug;
+pub(crate) mod exception;
+pub(crate) mod exec;
+pub(crate) mod heap;
+pub(crate) mod invokedynamic;
+pub(crate) mod jdwp;
+mod library;
+pub mod limits;
+mod link;
+mod mirror;
+pub mod module;
+pub(crate) mod native;
+pub mod profile;
+mod sealed;
+mod strings;
+pub(crate) mod thread;
+pub mod trace;
+pub(crate) mod value;
+mod verifier;
+
+pub struct Vm {
+    pub(crate) classpath: Vec<ClassSource>,
+    /// modules of the module path, see [`module`]
+    pub(crate) modules: Modules,
+    /// whether the java library comes from a JDK instead of the built-in one
+    pub(crate) jdk: bool,
+    /// `java.lang.Thread` object of the running thread
+    pub(crate) thread: Value,
+    pub(crate) threads: Threads,
+    /// logs executed instructions, see [`trace`]
+    pub(crate) tracer: Option<Tracer>,
+    /// stops the program between instructions, see [`debug`]
+    pub(crate) debugger: Option<Debugger>,
+    /// debugger attached over a socket, see [`jdwp`]
+    pub(crate) jdwp: Option<Jdwp>,
+    /// counts instructions and times calls, see [`profile`]
+    pub(crate) profiler: Option<Profiler>,
+    pub(crate) classes: HashMap<String, Rc<Class>>,
+    pub(crate) initialized: HashSet<String>,
+    /// values of static fields, see [`Vm::static_slot`]
+    pub(crate) statics: Vec<Value>,
+    static_slots: HashMap<(String, String), usize>,
+    /// linked code of the methods of each class, in the order of [`Class::methods`]
+    linked: HashMap<String, Vec<Option<Rc<Linked>>>>,
+    /// instance field slots of each class, see [`Vm::layout`]
+    layouts: HashMap<String, Rc<Layout>>,
+    pub(crate) heap: Heap,
+    /// frames of the methods being interpreted, innermost last
+    pub(crate) frames: Vec<Frame>,
+    /// values rust code holds on to while java code runs, which the collector keeps alive
+    pub roots: Vec<Value>,
+    pub(crate) natives: Natives,
+    /// `java.lang.Class` objects by class name, see [`mirror`]
+    mirrors: HashMap<String, Value>,
+    /// with a JDK, the unnamed `java.lang.Module` every class belongs to
+    unnamed_module: Value,
+    /// system properties set by the host, like the `-D` options of `java`
+    pub(crate) properties: Vec<(String, String)>,
+    /// logs every class loaded from the classpath to stdout, like `-verbose:class`
+    pub(crate) verbose_class: bool,
+    pub(crate) started: Instant,
+    /// see [`limits`]
+    limits: Limits,
+    budget: Budget,
+    /// where the host last called into the vm on a thread of its own, and the rust stack
+    /// the thread has left from there
+    host_stack: (usize, usize),
+}
+
+impl Vm {
+    /// starts setting up a vm, the way to embed one in another program
+    pub fn builder() -> VmBuilder {
+        VmBuilder::default()
+    }
+
+    /// modules of the module path, see [`module`]
+    pub fn modules(&self) -> &Modules {
+        &self.modules
+    }
+
+    /// tells an attached debugger that the program ended and writes out the rest of the
+    /// trace and the profile
+    pub fn shutdown(&mut self) -> anyhow::Result<()> {
+        self.jdwp_exit();
+        if let Some(tracer) = &mut self.tracer {
+            tracer.flush().context("failed to write the trace")?;
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.finish().context("failed to write the profile")?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn new(classpath: Vec<ClassSource>) -> Self {
+        let mut vm = Vm::empty(classpath);
+        library::install(&mut vm);
+        vm
+    }
+
+    /// vm taking the java library from the JDK at `home`, see [`Vm::boot`]
+    pub(crate) fn with_jdk(home: &Path, classpath: Vec<ClassSource>) -> anyhow::Result<Self> {
+        let mut sources = vec![ClassSource::open(home)?];
+        sources.extend(classpath);
+        let mut vm = Vm::empty(sources);
+        vm.jdk = true;
+        library::jdk::install(&mut vm, home);
+        Ok(vm)
+    }
+
+    fn empty(classpath: Vec<ClassSource>) -> Self {
+        Vm {
+            classpath,
+            modules: Modules::default(),
+            jdk: false,
+            thread: Value::Null,
+            threads: Threads::default(),
+            tracer: None,
+            debugger: None,
+            jdwp: None,
+            profiler: None,
+            classes: HashMap::new(),
+            initialized: HashSet::new(),
+            statics: Vec::new(),
+            static_slots: HashMap::new(),
+            linked: HashMap::new(),
+            layouts: HashMap::new(),
+            heap: Heap::default(),
+            frames: Vec::new(),
+            roots: Vec::new(),
+            natives: Natives::default(),
+            mirrors: HashMap::new(),
+            unnamed_module: Value::Null,
+            properties: Vec::new(),
+            verbose_class: false,
+            started: Instant::now(),
+            limits: Limits::default(),
+            budget: Budget::default(),
+            host_stack: (0, HOST_STACK),
+        }
+    }
+
+    pub(crate) fn add_class(&mut self, class: Class) -> Rc<Class> {
+        let class = Rc::new(class);
+        self.linked.remove(&class.this_class_name);
+        self.layouts.remove(&class.this_class_name);
+        self.classes
+            .insert(class.this_class_name.clone(), class.clone());
+        class
+    }
+
+    /// adds a class from outside the java library, which has to be permitted by sealed
+    /// supertypes and pass the verifier first
+    pub fn define_class(&mut self, class: Class) -> anyhow::Result<Rc<Class>> {
+        self.define(class, None)
+    }
+
+    /// [`Vm::define_class`], logging the class as loaded from classpath entry `source`
+    /// once its supertypes are loaded, like HotSpot does
+    fn define(&mut self, class: Class, source: Option<usize>) -> anyhow::Result<Rc<Class>> {
+        let class = self.add_class(class);
+        let defined = self.check_sealed(&class).and_then(|()| {
+            if let Some(source) = source {
+                self.log_class_load(&class.this_class_name, source);
+            }
+            self.verify(&class)
+        });
+        if let Err(err) = defined {
+            self.classes.remove(&class.this_class_name);
+            return Err(err);
+        }
+        self.jdwp_class_prepared(&class);
+        Ok(class)
+    }
+
+    fn log_class_load(&self, name: &str, source: usize) {
+        if self.verbose_class {
+            println!(
+                "[{:.3}s][info][class,load] {} source: {}",
+                self.started.elapsed().as_secs_f64(),
+                name.replace('/', "."),
+                self.classpath[source].location()
+            );
+        }
+    }
+
+    /// parses and defines a class given as the bytes of its class file
+    pub fn define_class_file(&mut self, bytes: Vec<u8>) -> anyhow::Result<Rc<Class>> {
+        let Some(class) = Class::parse(&mut ByteStream::from(bytes)) else {
+            return Err(self.exception("java/lang/ClassFormatError", "Malformed class file"));
+        };
+        self.define_class(class)
+    }
+
+    /// returns `None` when the class is not on the classpath
+    pub fn load_class(&mut self, name: &str) -> anyhow::Result<Option<Rc<Class>>> {
+        if let Some(class) = self.classes.get(name) {
+            return Ok(Some(class.clone()));
+        }
+        // classes of a package in a module come from that module only
+        let sources = match self.modules.source(name) {
+            Some(i) => i..i + 1,
+            None => 0..self.classpath.len(),
+        };
+        let mut found = None;
+        for i in sources {
+            if let Some(data) = self.classpath[i].read(name)? {
+                found = Some((i, data));
+                break;
+            }
+        }
+        let Some((source, data)) = found else {
+            return Ok(None);
+        };
+        // classes of the JDK are trusted, like HotSpot does by default
+        let trusted = self.jdk && source == 0;
+        if !trusted {
+            self.check_class(name)?;
+        }
+        let Some(class) = Class::parse(&mut ByteStream::from(data)) else {
+            let message = format!("Malformed class file {name}");
+            return Err(self.exception("java/lang/ClassFormatError", &message));
+        };
+        if trusted {
+            let class = self.add_class(class);
+            self.log_class_load(name, source);
+            self.jdwp_class_prepared(&class);
+            return Ok(Some(class));
+        }
+        self.define(class, Some(source)).map(Some)
+    }
+
+    /// runs static initializers of the class and its superclasses on first use
+    pub fn init_class(&mut self, name: &str) -> anyhow::Result<()> {
+        if self.initialized.contains(name) {
+            return Ok(());
+        }
+        let Some(class) = self.load_class(name)? else {
+            return Ok(());
+        };
+        self.initialized.insert(name.to_string());
+        // static fields exist before superclasses are initialized, which may use them
+        for field in &class.fields {
+            if field.access_flags.contains(FieldAccessFlags::STATIC) {
+                let value = match field.constant_value() {
+                    Some(index) => self.constant(&class, index)?,
+                    None => Value::default_for(&field.descriptor),
+                };
+                self.new_static(name, &field.name, value);
+            }
+        }
+        if class.super_class != 0 {
+            self.init_class(&class.super_class_name)?;
+        }
+        if let Some(clinit) = class.get_method("<clinit>", "()V") {
+            self.invoke(&class, clinit, vec![])?;
+        }
+        Ok(())
+    }
+
+    /// value of an int, float, long, double, string or class constant pool entry
+    pub(crate) fn constant(&mut self, class: &Class, index: usize) -> anyhow::Result<Value> {
+        Ok(
+            match class.cp.get(index).context("invalid constant index")? {
+                CpInfo::Integer(i) => Value::Int(i.val as i32),
+                CpInfo::Float(f) => Value::Float(f.val),
+                CpInfo::Long(l) => Value::Long(l.val),
+                CpInfo::Double(d) => Value::Double(d.val),
+                CpInfo::String(s) => {
+                    let string = &class.cp.get_utf(s.string_index).unwrap().bytes;
+                    self.intern(string)
+                }
+                CpInfo::Class(c) => {
+                    let name = &class.cp.get_utf(c.name_index).unwrap().bytes;
+                    self.class_object(name)?
+                }
+                constant => anyhow::bail!("unsupported constant {constant:?}"),
+            },
+        )
+    }
+
+    /// finds the method in the class, its superclasses or default methods of its interfaces
+    pub(crate) fn resolve_method(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> anyhow::Result<Option<(Rc<Class>, usize)>> {
+        let Some(c) = self.load_class(class)? else {
+            return Ok(None);
+        };
+        if let Some(i) = c
+            .methods
+            .iter()
+            .position(|m| m.name == name && m.descriptor == descriptor)
+        {
+            return Ok(Some((c, i)));
+        }
+        if c.super_class != 0 {
+            if let Some(found) = self.resolve_method(&c.super_class_name, name, descriptor)? {
+                return Ok(Some(found));
+            }
+        }
+        for interface in c.interface_names() {
+            if let Some((ic, i)) = self.resolve_method(interface, name, descriptor)? {
+                if !ic.methods[i]
+                    .access_flags
+                    .contains(MethodAccessFlags::ABSTRACT)
+                {
+                    return Ok(Some((ic, i)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// method a virtual call of `class.name` selects for a receiver of `runtime_class`: the
+    /// resolved method when it is private, else the one found from the runtime class
+    /// (JVMS §5.4.6)
+    pub(crate) fn select_method(
+        &mut self,
+        class: &str,
+        runtime_class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> anyhow::Result<Option<(Rc<Class>, usize)>> {
+        if let Some((c, i)) = self.resolve_method(class, name, descriptor)? {
+            if c.methods[i]
+                .access_flags
+                .contains(MethodAccessFlags::PRIVATE)
+            {
+                return Ok(Some((c, i)));
+            }
+        }
+        self.resolve_method(runtime_class, name, descriptor)
+    }
+
+    /// class declaring the field and its index there, searching superinterfaces and then
+    /// superclasses like JVMS §5.4.3.2
+    pub(crate) fn resolve_field(
+        &mut self,
+        class: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<(Rc<Class>, usize)>> {
+        let Some(c) = self.load_class(class)? else {
+            return Ok(None);
+        };
+        if let Some(i) = c.fields.iter().position(|f| f.name == name) {
+            return Ok(Some((c, i)));
+        }
+        for interface in c.interface_names() {
+            if let Some(found) = self.resolve_field(interface, name)? {
+                return Ok(Some(found));
+            }
+        }
+        if c.super_class != 0 {
+            return self.resolve_field(&c.super_class_name, name);
+        }
+        Ok(None)
+    }
+
+    fn new_static(&mut self, class: &str, name: &str, value: Value) -> usize {
+        let slot = self.statics.len();
+        self.statics.push(value);
+        self.static_slots
+            .insert((class.to_string(), name.to_string()), slot);
+        slot
+    }
+
+    /// slot of a static field declared in a loaded class, initializing the class.
+    /// `None` when there is no such field
+    fn field_slot(&mut self, class: &str, name: &str) -> anyhow::Result<Option<usize>> {
+        let Some((owner, _)) = self.resolve_field(class, name)? else {
+            return Ok(None);
+        };
+        let owner = owner.this_class_name.clone();
+        self.init_class(&owner)?;
+        Ok(Some(self.static_slots[&(owner, name.to_string())]))
+    }
+
+    /// slot in the [`Layout`] of its objects of the instance field `class.name` resolves to
+    pub(crate) fn instance_slot(&mut self, class: &str, name: &str) -> anyhow::Result<usize> {
+        let (owner, _) = self
+            .resolve_field(class, name)?
+            .with_context(|| format!("no field {class}.{name}"))?;
+        let owner = owner.this_class_name.clone();
+        self.layout(&owner)?
+            .declared_slot(&owner, name)
+            .with_context(|| format!("no instance field {owner}.{name}"))
+    }
+
+    /// slot of a static field in [`Vm::statics`]. Static fields of classes missing from
+    /// the classpath, like `System.out`, read as an empty instance of the field type
+    pub(crate) fn static_slot(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> anyhow::Result<usize> {
+        if let Some(slot) = self.field_slot(class, name)? {
+            return Ok(slot);
+        }
+        let key = (class.to_string(), name.to_string());
+        if let Some(&slot) = self.static_slots.get(&key) {
+            return Ok(slot);
+        }
+        let value = match descriptor.as_bytes()[0] {
+            b'L' => self.new_object(class_name(descriptor))?,
+            _ => Value::default_for(descriptor),
+        };
+        Ok(self.new_static(class, name, value))
+    }
+
+    pub fn get_static(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> anyhow::Result<Value> {
+        let slot = self.static_slot(class, name, descriptor)?;
+        Ok(self.statics[slot])
+    }
+
+    pub fn put_static(&mut self, class: &str, name: &str, value: Value) -> anyhow::Result<()> {
+        let slot = self
+            .field_slot(class, name)?
+            .with_context(|| format!("no static field {class}.{name}"))?;
+        self.statics[slot] = value;
+        Ok(())
+    }
+
+    /// frees unreachable objects. Every live value has to be reachable from the frames,
+    /// [`Vm::roots`], static fields, class objects or other threads, which holds between
+    /// two instructions
+    pub fn gc(&mut self) {
+        let frames = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.locals.iter().chain(&frame.stack));
+        let roots = frames
+            .chain(&self.roots)
+            .chain(&self.statics)
+            .chain(self.mirrors.values())
+            .chain([&self.thread, &self.unnamed_module])
+            .copied()
+            .chain(self.threads.roots());
+        self.heap.collect(roots);
+    }
+
+    /// makes room for allocating about `bytes`, collecting garbage once the heap has grown
+    /// enough. Throws `OutOfMemoryError` when the heap would exceed its maximum size
+    pub(crate) fn reserve(&mut self, bytes: usize) -> anyhow::Result<()> {
+        if self.heap.used.saturating_add(bytes) < self.heap.next_collection {
+            return Ok(());
+        }
+        self.gc();
+        if self.heap.used.saturating_add(bytes) > self.heap.max {
+            return Err(self.exception("java/lang/OutOfMemoryError", "Java heap space"));
+        }
+        Ok(())
+    }
+
+    /// allocates an instance with every field of the class hierarchy set to its default
+    pub fn new_object(&mut self, class: &str) -> anyhow::Result<Value> {
+        match class {
+            _ if self.jdk => {}
+            "java/lang/String" => return Ok(self.heap.alloc(Object::String(String::new()))),
+            "java/lang/StringBuilder" => {
+                return Ok(self.heap.alloc(Object::StringBuilder(String::new())))
+            }
+            _ => {}
+        }
+        self.init_class(class)?;
+        let layout = self.layout(class)?;
+        let fields = layout
+            .fields
+            .iter()
+            .map(|f| Value::default_for(&f.descriptor))
+            .collect();
+        Ok(self.heap.alloc(Object::Instance(Instance {
+            class: class.to_string(),
+            layout,
+            fields,
+        })))
+    }
+
+    /// instance field slots of a class, those of its superclasses first
+    pub(crate) fn layout(&mut self, class: &str) -> anyhow::Result<Rc<Layout>> {
+        if let Some(layout) = self.layouts.get(class) {
+            return Ok(layout.clone());
+        }
+        let Some(c) = self.load_class(class)? else {
+            return Ok(Rc::default());
+        };
+        let mut fields = match c.super_class {
+            0 => Vec::new(),
+            _ => self.layout(&c.super_class_name)?.fields.clone(),
+        };
+        for field in &c.fields {
+            if !field.access_flags.contains(FieldAccessFlags::STATIC) {
+                fields.push(LayoutField {
+                    class: class.to_string(),
+                    name: field.name.clone(),
+                    descriptor: field.descriptor.clone(),
+                });
+            }
+        }
+        let layout = Rc::new(Layout { fields });
+        self.layouts.insert(class.to_string(), layout.clone());
+        Ok(layout)
+    }
+
+    /// what calling the method runs, linking the methods of its class on first use.
+    /// Registered natives take precedence over the bytecode
+    pub(crate) fn target(
+        &mut self,
+        class: &Rc<Class>,
+        method: &MethodInfo,
+    ) -> anyhow::Result<Target> {
+        let class_name = &class.this_class_name;
+        if let Some(native) = self
+            .natives
+            .get(class_name, &method.name, &method.descriptor)
+        {
+            self.check_native(class_name, &method.name)?;
+            return Ok(Target::Native(native));
+        }
+        if method.access_flags.contains(MethodAccessFlags::NATIVE) {
+            let message = format!("{class_name}.{}{}", method.name, method.descriptor);
+
+            return Err(self.exception("java/lang/UnsatisfiedLinkError", &message));
+        }
+        let index = class.methods.iter().position(|m| ptr::eq(m, method));
+        let linked = self
+            .linked
+            .entry(class_name.clone())
+            .or_insert_with(|| link::link_class(class));
+        let code = index.and_then(|i| linked[i].clone()).with_context(|| {
+            format!(
+                "method {class_name}.{}{} has no code",
+                method.name, method.descriptor
+            )
+        })?;
+        Ok(Target::Code(class.clone(), code))
+    }
+
+    /// runs a method target with the given arguments, placing wide values into two local slots
+    pub(crate) fn call(
+        &mut self,
+        target: &Target,
+        args: Vec<Value>,
+    ) -> anyhow::Result<Option<Value>> {
+        self.run_for_host(|vm| match target {
+            Target::Native(native) => native(vm, &args),
+            Target::Code(class, code) => {
+                let mut locals = vec![Value::Null; code.max_locals];
+                let mut slot = 0;
+                for arg in args {
+                    locals[slot] = arg;
+                    slot += if arg.is_wide() { 2 } else { 1 };
+                }
+                exec::exec(vm, class, code, locals)
+            }
+        })
+    }
+
+    pub fn invoke(
+        &mut self,
+        class: &Rc<Class>,
+        method: &MethodInfo,
+        args: impl Into<Vec<Value>>,
+    ) -> anyhow::Result<Option<Value>> {
+        let target = self.target(class, method)?;
+        self.call(&target, args.into())
+    }
+
+    /// initializes the class first, like `invokestatic`
+    pub fn invoke_static(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        args: impl Into<Vec<Value>>,
+    ) -> anyhow::Result<Option<Value>> {
+        let args = args.into();
+        self.init_class(class)?;
+        match self.resolve_method(class, name, descriptor)? {
+            Some((c, i)) => self.invoke(&c, &c.methods[i], args),
+            None => self.invoke_missing(&[class], name, descriptor, args),
+        }
+    }
+
+    /// constructors, private methods and `super` calls: no virtual dispatch
+    pub fn invoke_special(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        args: impl Into<Vec<Value>>,
+    ) -> anyhow::Result<Option<Value>> {
+        let args = args.into();
+        match self.resolve_method(class, name, descriptor)? {
+            Some((c, i)) => self.invoke(&c, &c.methods[i], args),
+            None => self.invoke_missing(&[class], name, descriptor, args),
+        }
+    }
+
+    /// dispatches on the runtime class of the receiver in `args[0]`
+    pub fn invoke_virtual(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        args: impl Into<Vec<Value>>,
+    ) -> anyhow::Result<Option<Value>> {
+        let mut args = args.into();
+        let Some(receiver) = args[0].as_ref() else {
+            return Err(self.null_pointer());
+        };
+        if let Object::Lambda(lambda) = self.heap.get(receiver) {
+            if lambda.name == name {
+                let lambda = lambda.clone();
+                args.remove(0);
+                return lambda.invoke(self, args);
+            }
+        }
+        let runtime_class = match self.heap.class_name(receiver) {
+            class if class.starts_with('[') => "java/lang/Object".to_string(),
+            class => class.to_string(),
+        };
+        match self.select_method(class, &runtime_class, name, descriptor)? {
+            Some((c, i)) => self.invoke(&c, &c.methods[i], args),
+            None => self.invoke_missing(&[&runtime_class, class], name, descriptor, args),
+        }
+    }
+
+    /// methods of classes which are not on the classpath can only be natives.
+    /// Looks through the loaded part of each class hierarchy for one
+    fn invoke_missing(
+        &mut self,
+        classes: &[&str],
+        name: &str,
+        descriptor: &str,
+        args: Vec<Value>,
+    ) -> anyhow::Result<Option<Value>> {
+        for &class in classes {
+            let mut current = class.to_string();
+            loop {
+                if let Some(native) = self.natives.get(&current, name, descriptor) {
+                    self.check_native(&current, name)?;
+                    return self.run_for_host(|vm| native(vm, &args));
+                }
+                match self.load_class(&current)? {
+                    Some(c) if c.super_class != 0 => current = c.super_class_name.clone(),
+                    _ => break,
+                }
+            }
+        }
+        let message = format!("{}.{name}{descriptor}", classes[classes.len() - 1]);
+        Err(self.exception("java/lang/NoSuchMethodError", &message))
+    }
+}
@@ -0,0 +1,341 @@
+//! modules on the module path, which `java --module-path` takes. A named module declares in
+//! `module-info.class` the modules it reads and the packages it exports, while a plain jar
+//! on the module path is an automatic module, which reads every module and exports every
+//! package. Resolving the module `-m` names finds the modules it requires, whose classes are
+//! then loaded from the module declaring their package. Classes on the classpath are in the
+//! unnamed module, and the java library is taken to be the modules of the JDK, which every
+//! module reads
+//!
+//! Packages which are open are exported at run time, for code and for reflection alike
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::{
+    class::{
+        attribute::{ModuleAttribute, ModuleFlags, RequiresFlags},
+        Class,
+    },
+    source::ByteStream,
+};
+
+use super::{access::package, classpath::ClassSource, Vm};
+
+/// error resolving the modules of the module path, which `java` reports as
+/// `java.lang.module.FindException` before starting the program
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindException(pub String);
+
+impl Display for FindException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "java.lang.module.FindException: {}", self.0)
+    }
+}
+
+impl std::error::Error for FindException {}
+
+pub struct Module {
+    pub name: String,
+    /// from the descriptor, or the jar file name of an automatic module
+    pub version: Option<String>,
+    /// `None` for automatic modules
+    pub descriptor: Option<ModuleAttribute>,
+    /// in internal form, like `b/internal`
+    pub packages: HashSet<String>,
+    /// class `-m` launches when it names no class, from `ModuleMainClass` or the
+    /// `Main-Class` of the manifest of an automatic module
+    pub main_class: Option<String>,
+    /// index in the classpath of the jar or directory holding the classes
+    pub source: usize,
+    /// modules it reads besides those of the JDK
+    reads: HashSet<String>,
+}
+
+/// modules of the JDK, which are always there
+fn is_system(name: &str) -> bool {
+    name.starts_with("java.") || name.starts_with("jdk.")
+}
+
+/// name and version of an automatic module from its jar file: `z-tools-1.2.jar` ->
+/// `z.tools` and `1.2`. `Automatic-Module-Name` in the manifest overrides the name
+fn automatic_name(path: &Path) -> (String, Option<String>) {
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = file.strip_suffix(".jar").unwrap_or(&file);
+    // a version starts with a hyphen followed by digits and a dot or the end
+    let (mut name, mut version) = (stem, None);
+    for (i, _) in stem.match_indices('-') {
+        let rest = &stem[i + 1..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 && matches!(rest[digits..].chars().next(), None | Some('.')) {
+            (name, version) = (&stem[..i], Some(rest.to_string()));
+            break;
+        }
+    }
+    let name = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".");
+    (name, version)
+}
+
+/// `module lib` or `unnamed module`, as messages name the module of a class
+pub(super) fn module_name(module: Option<&Module>) -> String {
+    match module {
+        Some(module) => format!("module {}", module.name),
+        None => "unnamed module".to_string(),
+    }
+}
+
+impl Module {
+    /// reads the module in a jar or a directory with a `module-info.class`
+    fn open(path: &Path) -> anyhow::Result<(Module, ClassSource)> {
+        let source = ClassSource::open(path)?;
+        let module = match source.read("module-info")? {
+            Some(data) => {
+                let class = Class::parse(&mut ByteStream::from(data)).with_context(|| {
+                    format!("could not parse module-info of {}", path.display())
+                })?;
+                let descriptor = class
+                    .module()
+                    .with_context(|| format!("no module declared in {}", path.display()))?
+                    .clone();
+                let packages = match class.module_packages() {
+                    Some(packages) => packages.iter().cloned().collect(),
+                    None => source.packages()?,
+                };
+                Module {
+                    name: descriptor.name.clone(),
+                    version: descriptor.version.clone(),
+                    main_class: class.module_main_class().map(str::to_string),
+                    descriptor: Some(descriptor),
+                    packages,
+                    source: 0,
+                    reads: HashSet::new(),
+                }
+            }
+            None => {
+                let manifest = match &source {
+                    ClassSource::Archive(_, archive) => archive.manifest()?,
+                    _ => HashMap::new(),
+                };
+                let (name, version) = automatic_name(path);
+                Module {
+                    name: manifest
+                        .get("Automatic-Module-Name")
+                        .cloned()
+                        .unwrap_or(name),
+                    version,
+                    descriptor: None,
+                    packages: source.packages()?,
+                    main_class: manifest.get("Main-Class").map(|c| c.replace('.', "/")),
+                    source: 0,
+                    reads: HashSet::new(),
+                }
+            }
+        };
+        Ok((module, source))
+    }
+
+    pub fn is_automatic(&self) -> bool {
+        self.descriptor.is_none()
+    }
+
+    /// whether the module reads `other`. Automatic modules read every module
+    pub fn reads(&self, other: &Module) -> bool {
+        self.is_automatic() || self.name == other.name || self.reads.contains(&other.name)
+    }
+
+    /// whether the module exports or opens `package` to module `to`, `None` being the
+    /// unnamed module, which only gets the packages exported to every module
+    pub fn exports(&self, package: &str, to: Option<&Module>) -> bool {
+        let Some(descriptor) = &self.descriptor else {
+            return true;
+        };
+        descriptor.flags.contains(ModuleFlags::OPEN)
+            || descriptor.exports.iter().chain(&descriptor.opens).any(|e| {
+                e.package == package
+                    && (e.to.is_empty() || to.is_some_and(|to| e.to.contains(&to.name)))
+            })
+    }
+
+    /// whether the module opens `package` to module `to` for deep reflection, which
+    /// `setAccessible` needs for members which are not public
+    pub fn opens(&self, package: &str, to: Option<&Module>) -> bool {
+        let Some(descriptor) = &self.descriptor else {
+            return true;
+        };
+        descriptor.flags.contains(ModuleFlags::OPEN)
+            || descriptor.opens.iter().any(|e| {
+                e.package == package
+                    && (e.to.is_empty() || to.is_some_and(|to| e.to.contains(&to.name)))
+            })
+    }
+}
+
+/// modules in the entries of a module path, which are modules or directories of them.
+/// The first module of a name wins
+fn find(module_path: &[PathBuf]) -> anyhow::Result<Vec<(Module, ClassSource)>> {
+    let mut found: Vec<(Module, ClassSource)> = Vec::new();
+    for entry in module_path {
+        let candidates = match entry.is_dir() && !entry.join("module-info.class").is_file() {
+            true => {
+                let mut children = fs::read_dir(entry)
+                    .with_context(|| format!("failed to read {}", entry.display()))?
+                    .map(|child| child.map(|child| child.path()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                children.sort();
+                children.retain(|path| {
+                    path.extension().is_some_and(|ext| ext == "jar")
+                        || path.join("module-info.class").is_file()
+                });
+                children
+            }
+            false => vec![entry.clone()],
+        };
+        for candidate in candidates {
+            let (module, source) = Module::open(&candidate)
+                .with_context(|| format!("bad module path entry {}", candidate.display()))?;
+            if !found.iter().any(|(m, _)| m.name == module.name) {
+                found.push((module, source));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// modules `module` reads: those it requires, and those they require transitively.
+/// Reading an automatic module reads them all
+fn readable(modules: &[Module], module: &Module) -> HashSet<String> {
+    let mut reads = HashSet::new();
+    let Some(descriptor) = &module.descriptor else {
+        return reads;
+    };
+    let mut pending: Vec<&str> = descriptor
+        .requires
+        .iter()
+        .map(|r| r.module.as_str())
+        .collect();
+    while let Some(name) = pending.pop() {
+        // modules of the JDK and absent `requires static` ones are not among them
+        let Some(required) = modules.iter().find(|m| m.name == name) else {
+            continue;
+        };
+        if !reads.insert(name.to_string()) {
+            continue;
+        }
+        match &required.descriptor {
+            Some(descriptor) => pending.extend(
+                descriptor
+                    .requires
+                    .iter()
+                    .filter(|r| r.flags.contains(RequiresFlags::TRANSITIVE))
+                    .map(|r| r.module.as_str()),
+            ),
+            None => pending.extend(
+                modules
+                    .iter()
+                    .filter(|m| m.is_automatic())
+                    .map(|m| m.name.as_str()),
+            ),
+        }
+    }
+    reads
+}
+
+/// the modules resolved at startup, see [`VmBuilder::main_module`](crate::VmBuilder)
+#[derive(Default)]
+pub struct Modules {
+    modules: Vec<Module>,
+    /// index of the module declaring each package
+    packages: HashMap<String, usize>,
+}
+
+impl Modules {
+    pub fn get(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|m| m.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Module> {
+        self.modules.iter()
+    }
+
+    /// index in [`Modules::iter`] of the module of a class
+    pub fn position(&self, class: &str) -> Option<usize> {
+        self.packages.get(package(class)).copied()
+    }
+
+    /// module of a class, `None` for classes of the unnamed module and the JDK
+    pub fn of(&self, class: &str) -> Option<&Module> {
+        Some(&self.modules[self.position(class)?])
+    }
+
+    /// index in [`Vm::classpath`] of the module a class has to come from, if any
+    pub(super) fn source(&self, class: &str) -> Option<usize> {
+        self.of(class).map(|module| module.source)
+    }
+}
+
+impl Vm {
+    /// finds the modules on `module_path`, resolves module `root` and those it requires,
+    /// and loads the classes of their packages from them instead of the classpath
+    pub(crate) fn resolve_modules(
+        &mut self,
+        module_path: &[PathBuf],
+        root: &str,
+    ) -> anyhow::Result<()> {
+        let found = find(module_path)?;
+        let mut resolved = HashSet::new();
+        let mut pending = VecDeque::from([(root.to_string(), None::<String>)]);
+        while let Some((name, required_by)) = pending.pop_front() {
+            if is_system(&name) || resolved.contains(&name) {
+                continue;
+            }
+            let Some((module, _)) = found.iter().find(|(m, _)| m.name == name) else {
+                let message = match required_by {
+                    Some(by) => format!("Module {name} not found, required by {by}"),
+                    None => format!("Module {name} not found"),
+                };
+                return Err(FindException(message).into());
+            };
+            if let Some(descriptor) = &module.descriptor {
+                for requires in &descriptor.requires {
+                    if !requires.flags.contains(RequiresFlags::STATIC_PHASE) {
+                        pending.push_back((requires.module.clone(), Some(name.clone())));
+                    }
+                }
+            }
+            resolved.insert(name);
+        }
+        // resolving an automatic module resolves all of them, which read each other
+        let automatic = found
+            .iter()
+            .any(|(m, _)| m.is_automatic() && resolved.contains(&m.name));
+
+        let (mut modules, sources): (Vec<_>, Vec<_>) = found
+            .into_iter()
+            .filter(|(m, _)| resolved.contains(&m.name) || automatic && m.is_automatic())
+            .unzip();
+        let reads: Vec<_> = modules.iter().map(|m| readable(&modules, m)).collect();
+        for (i, (module, (source, reads))) in modules
+            .iter_mut()
+            .zip(sources.into_iter().zip(reads))
+            .enumerate()
+        {
+            module.source = self.classpath.len();
+            module.reads = reads;
+            self.classpath.push(source);
+            for package in &module.packages {
+                self.modules.packages.entry(package.clone()).or_insert(i);
+            }
+        }
+        self.modules.modules = modules;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use std::{collections::HashMap, rc::Rc};
+
+use super::{value::Value, Vm};
+
+/// rust implementation of a java method. Receives the receiver (if any) followed by the arguments,
+/// java exceptions are thrown by returning the error from [`Vm::exception`]. Natives are `Send`
+/// as every java thread runs on an OS thread of its own
+pub type NativeFn = Rc<dyn Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send>;
+
+/// methods implemented in rust, by class, name and descriptor, in nested maps so lookups
+/// need not allocate a key. Used for `native` methods, for methods of classes missing
+/// from the classpath and as intrinsics which take precedence over the bytecode of a method
+#[derive(Clone, Default)]
+pub struct Natives {
+    methods: HashMap<String, HashMap<String, HashMap<String, NativeFn>>>,
+}
+
+impl Natives {
+    pub fn register(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        f: impl Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send + 'static,
+    ) {
+        self.methods
+            .entry(class.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_default()
+            .insert(descriptor.to_string(), Rc::new(f));
+    }
+
+    pub fn get(&self, class: &str, name: &str, descriptor: &str) -> Option<NativeFn> {
+        self.methods.get(class)?.get(name)?.get(descriptor).cloned()
+    }
+}
+
+impl Vm {
+    /// implements `class.name` with `f`, which runs on the OS thread of whichever java thread
+    /// calls it
+    pub fn register_native(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        f: impl Fn(&mut Vm, &[Value]) -> anyhow::Result<Option<Value>> + Send + 'static,
+    ) {
+        self.natives.register(class, name, descriptor, f);
+    }
+}
@@ -0,0 +1,347 @@
+//! `--profile`: counts the instructions run and measures the wall clock time spent in every
+//! java method. Calls are recorded in a call tree per thread, which is written out as
+//! collapsed stacks for flamegraph tools, weighted by instructions, along with a summary
+//! of the hottest methods and of the bytecode offsets running most often
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    io::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::class::Class;
+
+use super::{link::Linked, value::Value, Vm};
+
+/// bytecode offsets listed in the summary
+const HOT_SPOTS: usize = 20;
+
+struct Method {
+    class: Rc<Class>,
+    /// index in the methods of the class
+    index: usize,
+    /// bytecode offset of each linked instruction
+    pcs: Vec<usize>,
+    /// times each linked instruction ran
+    counts: Vec<u64>,
+}
+
+impl Method {
+    fn name(&self) -> String {
+        let method = &self.class.methods[self.index];
+        format!(
+            "{}.{}",
+            self.class.this_class_name.replace('/', "."),
+            method.name
+        )
+    }
+}
+
+/// call path in the call tree, the roots are threads
+struct Node {
+    /// `None` for a thread
+    method: Option<usize>,
+    name: String,
+    children: HashMap<usize, usize>,
+    calls: u64,
+    /// instructions run in the method itself
+    instructions: u64,
+    /// time spent in the method itself, and in natives it called
+    time: Duration,
+}
+
+/// call of the profiled method running in a thread
+struct Call {
+    node: usize,
+    start: Instant,
+    /// time spent in methods it called
+    callees: Duration,
+}
+
+pub struct Profiler {
+    /// where the collapsed stacks and the summary go when the program ends
+    stacks: Box<dyn Write + Send>,
+    summary: Box<dyn Write + Send>,
+    methods: Vec<Method>,
+    /// index in `methods` by class and method index
+    method_ids: HashMap<(*const Class, usize), usize>,
+    nodes: Vec<Node>,
+    /// root node of each thread
+    threads: HashMap<usize, usize>,
+    calls: HashMap<usize, Vec<Call>>,
+    /// thread and node of the method running last, which saves looking up the thread
+    /// between instructions
+    running: Option<(usize, usize, usize)>,
+}
+
+/// totals of a method over the call tree
+#[derive(Default)]
+struct Totals {
+    calls: u64,
+    instructions: u64,
+    time: Duration,
+    total_instructions: u64,
+    total_time: Duration,
+}
+
+impl Profiler {
+    pub fn new(stacks: Box<dyn Write + Send>, summary: Box<dyn Write + Send>) -> Self {
+        Profiler {
+            stacks,
+            summary,
+            methods: Vec::new(),
+            method_ids: HashMap::new(),
+            nodes: Vec::new(),
+            threads: HashMap::new(),
+            calls: HashMap::new(),
+            running: None,
+        }
+    }
+
+    /// writes out the profile of the program
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        let mut stacks = std::mem::replace(&mut self.stacks, Box::new(std::io::sink()));
+        self.write_stacks(&mut stacks)?;
+        stacks.flush()?;
+        let mut summary = std::mem::replace(&mut self.summary, Box::new(std::io::sink()));
+        self.write_summary(&mut summary)?;
+        summary.flush()
+    }
+
+    fn method_id(&mut self, class: &Rc<Class>, code: &Linked) -> usize {
+        let key = (Rc::as_ptr(class), code.method);
+        if let Some(&id) = self.method_ids.get(&key) {
+            return id;
+        }
+        self.methods.push(Method {
+            class: class.clone(),
+            index: code.method,
+            pcs: code.pcs.clone(),
+            counts: vec![0; code.pcs.len()],
+        });
+        self.method_ids.insert(key, self.methods.len() - 1);
+        self.methods.len() - 1
+    }
+
+    fn node(&mut self, method: Option<usize>, name: String) -> usize {
+        self.nodes.push(Node {
+            method,
+            name,
+            children: HashMap::new(),
+            calls: 0,
+            instructions: 0,
+            time: Duration::ZERO,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// instructions and time of the subtree at `node`, adding up the totals of each method
+    /// in `totals`. `active` holds the methods on the path, whose totals already include
+    /// the subtree of a recursive call
+    fn totals(
+        &self,
+        node: usize,
+        totals: &mut [Totals],
+        active: &mut Vec<usize>,
+    ) -> (u64, Duration) {
+        let node = &self.nodes[node];
+        let (mut instructions, mut time) = (node.instructions, node.time);
+        if let Some(method) = node.method {
+            active.push(method);
+        }
+        for &child in node.children.values() {
+            let (child_instructions, child_time) = self.totals(child, totals, active);
+            instructions += child_instructions;
+            time += child_time;
+        }
+        if let Some(method) = node.method {
+            active.pop();
+            let method_totals = &mut totals[method];
+            method_totals.calls += node.calls;
+            method_totals.instructions += node.instructions;
+            method_totals.time += node.time;
+            if !active.contains(&method) {
+                method_totals.total_instructions += instructions;
+                method_totals.total_time += time;
+            }
+        }
+        (instructions, time)
+    }
+
+    /// one line per call path: frames from the thread down separated by `;`, then the
+    /// instructions run in the innermost one
+    fn write_stacks(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut roots: Vec<_> = self.threads.iter().collect();
+        roots.sort();
+        let mut pending: Vec<_> = roots
+            .into_iter()
+            .rev()
+            .map(|(_, &root)| (root, self.nodes[root].name.clone()))
+            .collect();
+        while let Some((node, path)) = pending.pop() {
+            let node = &self.nodes[node];
+            if node.instructions > 0 {
+                writeln!(out, "{path} {}", node.instructions)?;
+            }
+            let mut children: Vec<_> = node.children.values().collect();
+            children.sort();
+            for &child in children.into_iter().rev() {
+                pending.push((child, format!("{path};{}", self.nodes[child].name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// methods sorted by the time spent in them, then the instructions running most
+    fn write_summary(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut totals: Vec<_> = self.methods.iter().map(|_| Totals::default()).collect();
+        let mut roots: Vec<_> = self.threads.values().copied().collect();
+        roots.sort();
+        let (mut instructions, mut time) = (0, Duration::ZERO);
+        for root in roots {
+            let (root_instructions, root_time) = self.totals(root, &mut totals, &mut vec![]);
+            instructions += root_instructions;
+            time += root_time;
+        }
+
+        let mut methods: Vec<_> = totals.iter().enumerate().collect();
+        methods.sort_by_key(|(_, totals)| Reverse((totals.time, totals.instructions)));
+        let percent = |part: f64, whole: f64| match whole {
+            0.0 => 0.0,
+            _ => 100.0 * part / whole,
+        };
+        writeln!(
+            out,
+            "{:>7} {:>10} {:>10} {:>12} {:>12} {:>9}  method",
+            "self %", "self ms", "total ms", "self instr", "total instr", "calls"
+        )?;
+        for (id, totals) in methods {
+            let method = &self.methods[id];
+            let descriptor = &method.class.methods[method.index].descriptor;
+            writeln!(
+                out,
+                "{:>6.2}% {:>10.3} {:>10.3} {:>12} {:>12} {:>9}  {}{descriptor}",
+                percent(totals.time.as_secs_f64(), time.as_secs_f64()),
+                totals.time.as_secs_f64() * 1000.0,
+                totals.total_time.as_secs_f64() * 1000.0,
+                totals.instructions,
+                totals.total_instructions,
+                totals.calls,
+                method.name(),
+            )?;
+        }
+
+        let mut spots: Vec<_> = self
+            .methods
+            .iter()
+            .flat_map(|method| {
+                let counts = method.counts.iter().zip(&method.pcs);
+                counts.map(move |(&count, &pc)| (count, method, pc))
+            })
+            .filter(|&(count, _, _)| count > 0)
+            .collect();
+        spots.sort_by_key(|&(count, _, _)| Reverse(count));
+        writeln!(out)?;
+        writeln!(out, "{:>7} {:>12}  location", "instr %", "instr")?;
+        for (count, method, pc) in spots.into_iter().take(HOT_SPOTS) {
+            let code = method.class.methods[method.index].code();
+            let line = match code.and_then(|code| code.line_number(pc)) {
+                Some(line) => format!(" line {line}"),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "{:>6.2}% {count:>12}  {} @{pc}{line}",
+                percent(count as f64, instructions as f64),
+                method.name(),
+            )?;
+        }
+        writeln!(
+            out,
+            "\n{instructions} instructions in {:.3} ms",
+            time.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+impl Vm {
+    /// records a call of `code`, before its first instruction runs
+    pub(crate) fn profile_enter(&mut self, class: &Rc<Class>, code: &Linked) {
+        let thread = self.threads.current;
+        if !self
+            .profiler
+            .as_ref()
+            .unwrap()
+            .threads
+            .contains_key(&thread)
+        {
+            // the JDK runs code before the main thread exists
+            let name = match self.thread {
+                Value::Null => "main".to_string(),
+                thread => {
+                    let name = self.thread_field(thread, "name");
+                    self.string_value(name).unwrap_or_default()
+                }
+            };
+            let profiler = self.profiler.as_mut().unwrap();
+            let root = profiler.node(None, name);
+            profiler.threads.insert(thread, root);
+        }
+        let profiler = self.profiler.as_mut().unwrap();
+        let method = profiler.method_id(class, code);
+        let calls = profiler.calls.entry(thread).or_default();
+        let parent = match calls.last() {
+            Some(call) => call.node,
+            None => profiler.threads[&thread],
+        };
+        let node = match profiler.nodes[parent].children.get(&method) {
+            Some(&node) => node,
+            None => {
+                let name = profiler.methods[method].name();
+                let node = profiler.node(Some(method), name);
+                profiler.nodes[parent].children.insert(method, node);
+                node
+            }
+        };
+        profiler.nodes[node].calls += 1;
+        profiler.calls.get_mut(&thread).unwrap().push(Call {
+            node,
+            start: Instant::now(),
+            callees: Duration::ZERO,
+        });
+        profiler.running = Some((thread, node, method));
+    }
+
+    /// counts instruction `i` of the method running in the innermost frame
+    pub(crate) fn profile_instruction(&mut self, i: usize) {
+        let thread = self.threads.current;
+        let profiler = self.profiler.as_mut().unwrap();
+        let (node, method) = match profiler.running {
+            Some((running, node, method)) if running == thread => (node, method),
+            _ => {
+                let node = profiler.calls[&thread].last().unwrap().node;
+                let method = profiler.nodes[node].method.unwrap();
+                profiler.running = Some((thread, node, method));
+                (node, method)
+            }
+        };
+        profiler.nodes[node].instructions += 1;
+        profiler.methods[method].counts[i] += 1;
+    }
+
+    /// records that the call of the innermost frame completed
+    pub(crate) fn profile_exit(&mut self) {
+        let thread = self.threads.current;
+        let profiler = self.profiler.as_mut().unwrap();
+        let calls = profiler.calls.get_mut(&thread).unwrap();
+        let call = calls.pop().unwrap();
+        let elapsed = call.start.elapsed();
+        if let Some(caller) = calls.last_mut() {
+            caller.callees += elapsed;
+        }
+        profiler.nodes[call.node].time += elapsed.saturating_sub(call.callees);
+        profiler.running = None;
+    }
+}
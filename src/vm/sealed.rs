@@ -0,0 +1,58 @@
+//! sealed classes and interfaces, which list the classes allowed to extend or implement
+//! them in their `PermittedSubclasses` attribute. A class defined with a sealed direct
+//! superclass or superinterface which does not permit it fails with
+//! `IncompatibleClassChangeError`, following the rules of HotSpot for JDK 17 class files
+
+use crate::class::{Class, ClassAccessFlags};
+
+use super::{access::package, Vm};
+
+/// first class file version where `PermittedSubclasses` is honored
+const SEALED_VERSION: usize = 61;
+
+/// whether `sealed` permits `class` as a direct subclass
+fn permits(vm: &Vm, sealed: &Class, class: &Class) -> bool {
+    let Some(permitted) = sealed.permitted_subclasses() else {
+        return true;
+    };
+    if sealed.major_version < SEALED_VERSION {
+        return true;
+    }
+    let (name, sealed_name) = (&class.this_class_name, &sealed.this_class_name);
+    // a permitted subclass has to be in the same module, and in the same package unless
+    // it is public
+    vm.class_location(name) == vm.class_location(sealed_name)
+        && (class.access_flags.contains(ClassAccessFlags::PUBLIC)
+            || package(name) == package(sealed_name))
+        && permitted.contains(name)
+}
+
+impl Vm {
+    /// checks the direct superclass and superinterfaces of a class being defined
+    pub(super) fn check_sealed(&mut self, class: &Class) -> anyhow::Result<()> {
+        let name = class.this_class_name.replace('/', ".");
+        if class.super_class != 0 {
+            if let Some(super_class) = self.load_class(&class.super_class_name)? {
+                if !permits(self, &super_class, class) {
+                    let message = format!(
+                        "class {name} cannot inherit from sealed class {}",
+                        super_class.this_class_name.replace('/', ".")
+                    );
+                    return Err(self.exception("java/lang/IncompatibleClassChangeError", &message));
+                }
+            }
+        }
+        for interface in class.interface_names() {
+            if let Some(interface) = self.load_class(interface)? {
+                if !permits(self, &interface, class) {
+                    let message = format!(
+                        "class {name} cannot implement sealed interface {}",
+                        interface.this_class_name.replace('/', ".")
+                    );
+                    return Err(self.exception("java/lang/IncompatibleClassChangeError", &message));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! `java.lang.String` objects. With the built-in library a string is an [`Object::String`],
+//! with a JDK's library it is an instance of its `String` class, holding the characters
+//! in a `byte[] value` either as latin-1 or as little endian UTF-16, as told by `coder`
+
+use super::{
+    heap::{Array, Instance, ObjRef, Object},
+    value::Value,
+    Vm,
+};
+
+const LATIN1: i32 = 0;
+const UTF16: i32 = 1;
+
+impl Vm {
+    pub fn new_string(&mut self, s: String) -> Value {
+        if !self.jdk {
+            return self.heap.alloc(Object::String(s));
+        }
+        let (bytes, coder): (Vec<u8>, _) = if s.chars().all(|c| (c as u32) < 0x100) {
+            (s.chars().map(|c| c as u8).collect(), LATIN1)
+        } else {
+            (s.encode_utf16().flat_map(u16::to_le_bytes).collect(), UTF16)
+        };
+        let value = self.heap.alloc(Object::Array(Array {
+            class: "[B".to_string(),
+            elements: bytes
+                .into_iter()
+                .map(|b| Value::Int(b as i8 as i32))
+                .collect(),
+        }));
+        let layout = self
+            .layout("java/lang/String")
+            .expect("String of the JDK is loadable");
+        let fields = layout
+            .fields
+            .iter()
+            .map(|f| Value::default_for(&f.descriptor))
+            .collect();
+        let mut instance = Instance {
+            class: "java/lang/String".to_string(),
+            layout,
+            fields,
+        };
+        instance.set("value", value);
+        instance.set("coder", Value::Int(coder));
+        self.heap.alloc(Object::Instance(instance))
+    }
+
+    /// `String[]` holding the values, `None` for null
+    pub fn new_string_array(&mut self, values: Vec<Option<String>>) -> Value {
+        let array = self.heap.new_array("[Ljava/lang/String;", 0);
+        let elements = values
+            .into_iter()
+            .map(|v| v.map_or(Value::Null, |s| self.new_string(s)))
+            .collect();
+        self.heap.get_array_mut(array.as_ref().unwrap()).elements = elements;
+        array
+    }
+
+    /// returns the same object for equal strings, as needed for literals
+    pub fn intern(&mut self, s: &str) -> Value {
+        if let Some(value) = self.heap.interned(s) {
+            return value;
+        }
+        let value = self.new_string(s.to_string());
+        self.heap.add_interned(s, value);
+        value
+    }
+
+    pub(crate) fn is_string(&self, r: ObjRef) -> bool {
+        self.heap.class_name(r) == "java/lang/String"
+    }
+
+    /// contents of a `java.lang.String`, throwing `NullPointerException` for null
+    pub fn string_value(&mut self, value: Value) -> anyhow::Result<String> {
+        let Some(r) = value.as_ref() else {
+            return Err(self.null_pointer());
+        };
+        let instance = match self.heap.get(r) {
+            Object::String(s) => return Ok(s.clone()),
+            Object::Instance(instance) => instance,
+            _ => panic!("expected string, got {:?}", self.heap.get(r)),
+        };
+        let coder = instance.get("coder").unwrap().as_int();
+        let Some(value) = instance.get("value").unwrap().as_ref() else {
+            return Ok(String::new());
+        };
+        let bytes = self
+            .heap
+            .get_array(value)
+            .elements
+            .iter()
+            .map(|b| b.as_int() as u8);
+        Ok(match coder {
+            LATIN1 => bytes.map(char::from).collect(),
+            _ => {
+                let bytes: Vec<u8> = bytes.collect();
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+        })
+    }
+}
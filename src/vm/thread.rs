@@ -0,0 +1,673 @@
+//! java threads. Every thread runs on an OS thread of its own, but only the one holding the
+//! [`Gil`] touches the [`Vm`]. The running thread hands the lock over at safepoints and
+//! whenever it blocks, so threads interleave like green threads while each keeps its own
+//! rust stack for the interpreter. Handing over the lock orders every memory access, which
+//! gives all fields, `volatile` or not, sequentially consistent semantics.
+//!
+//! Monitors are plain bookkeeping under the lock: a thread which has to wait for a monitor,
+//! a notification, another thread or some time releases the lock until the next event.
+//!
+//! The host holds the lock as the main thread. Calls from the host end every other thread
+//! before they return, so the vm the threads share does not move while they use it. The vm
+//! itself is not `Send`, but what the host puts into it, like natives, has to be, as it runs
+//! on the threads of java code too.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    mem,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    exception::Throw, exec::Frame, heap::ObjRef, limits::set_thread_stack, value::Value, Vm,
+};
+
+/// internal id of the main thread, stored in the `eetop` field of `java.lang.Thread` objects
+/// like HotSpot stores its native thread there. Threads not started yet or terminated have 0
+pub const MAIN_THREAD: usize = 1;
+/// `threadStatus` of running threads, `JVMTI_THREAD_STATE_ALIVE | JVMTI_THREAD_STATE_RUNNABLE`
+const RUNNABLE: i32 = 5;
+/// `threadStatus` of terminated threads
+const TERMINATED: i32 = 2;
+/// instructions run between offering the lock to other threads
+const TIME_SLICE: u32 = 10_000;
+
+#[derive(Default)]
+struct GilState {
+    owner: Option<usize>,
+    /// threads waiting to take the lock, in order
+    queue: VecDeque<usize>,
+    /// counts monitor exits, notifications, interrupts and terminated threads
+    events: u64,
+}
+
+/// global interpreter lock
+#[derive(Default)]
+struct Gil {
+    state: Mutex<GilState>,
+    changed: Condvar,
+    /// the vm, which a new thread takes once it holds the lock
+    vm: AtomicPtr<Vm>,
+}
+
+impl Gil {
+    fn acquire(&self, id: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.push_back(id);
+        while state.owner.is_some() || state.queue.front() != Some(&id) {
+            state = self.changed.wait(state).unwrap();
+        }
+        state.queue.pop_front();
+        state.owner = Some(id);
+    }
+
+    fn release(&self) {
+        self.state.lock().unwrap().owner = None;
+        self.changed.notify_all();
+    }
+
+    fn contended(&self) -> bool {
+        !self.state.lock().unwrap().queue.is_empty()
+    }
+
+    fn events(&self) -> u64 {
+        self.state.lock().unwrap().events
+    }
+
+    fn event(&self) {
+        self.state.lock().unwrap().events += 1;
+        self.changed.notify_all();
+    }
+
+    /// releases the lock until there was an event after `seen` or the deadline passed
+    fn wait_event(&self, id: usize, seen: u64, deadline: Option<Instant>) {
+        let mut state = self.state.lock().unwrap();
+        state.owner = None;
+        self.changed.notify_all();
+        while state.events == seen {
+            state = match deadline {
+                None => self.changed.wait(state).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    self.changed.wait_timeout(state, deadline - now).unwrap().0
+                }
+            };
+        }
+        drop(state);
+        self.acquire(id);
+    }
+}
+
+/// what the vm holds for the running thread, kept here while the thread does not run
+struct Parked {
+    object: Value,
+    frames: Vec<Frame>,
+    roots: Vec<Value>,
+}
+
+/// object or class a monitor belongs to. Static synchronized methods lock their class
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Lock {
+    Object(ObjRef),
+    Class(String),
+}
+
+#[derive(Default)]
+struct Monitor {
+    owner: usize,
+    /// number of times the owner entered the monitor, 0 when it is free
+    count: usize,
+    /// threads in `Object.wait`, until notified
+    waiting: Vec<usize>,
+}
+
+/// error ending every thread once `System.exit` was called, with the exit status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exit(pub i32);
+
+impl Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "exited with status {}", self.0)
+    }
+}
+
+impl std::error::Error for Exit {}
+
+/// error ending the daemon threads still running when a call of the host returns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stopped;
+
+impl Display for Stopped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stopped when returning to the host")
+    }
+}
+
+impl std::error::Error for Stopped {}
+
+pub struct Threads {
+    gil: Arc<Gil>,
+    /// id of the running thread
+    pub current: usize,
+    next_id: usize,
+    /// threads started and not terminated, other than the running one
+    parked: HashMap<usize, Parked>,
+    monitors: HashMap<Lock, Monitor>,
+    /// threads `Unsafe.unpark` gave a permit to
+    permits: HashSet<usize>,
+    ticks: u32,
+    /// status passed to `System.exit`
+    exit: Option<i32>,
+    /// whether threads other than the main one stop at their next safepoint
+    stopping: bool,
+    /// first error other than an exception a thread ended with, like an exceeded limit,
+    /// which the call from the host returns
+    error: Option<anyhow::Error>,
+}
+
+impl Default for Threads {
+    fn default() -> Self {
+        let gil = Arc::new(Gil::default());
+        gil.acquire(MAIN_THREAD);
+        Threads {
+            gil,
+            current: MAIN_THREAD,
+            next_id: MAIN_THREAD + 1,
+            parked: HashMap::new(),
+            monitors: HashMap::new(),
+            permits: HashSet::new(),
+            ticks: TIME_SLICE,
+            exit: None,
+            stopping: false,
+            error: None,
+        }
+    }
+}
+
+impl Threads {
+    /// values held by threads which are not running, for the garbage collector
+    pub fn roots(&self) -> impl Iterator<Item = Value> + '_ {
+        self.parked.values().flat_map(|parked| {
+            let frames = parked
+                .frames
+                .iter()
+                .flat_map(|frame| frame.locals.iter().chain(&frame.stack));
+            frames.chain(&parked.roots).chain([&parked.object]).copied()
+        })
+    }
+}
+
+impl Vm {
+    pub(super) fn thread_field(&self, thread: Value, name: &str) -> Value {
+        let instance = self.heap.get_instance(thread.as_ref().unwrap());
+        instance.get(name).unwrap_or(Value::Int(0))
+    }
+
+    fn set_thread_field(&mut self, thread: Value, name: &str, value: Value) {
+        let instance = self.heap.get_instance_mut(thread.as_ref().unwrap());
+        instance.set(name, value);
+    }
+
+    /// internal ids and objects of the running thread and every other live thread
+    pub(super) fn live_threads(&self) -> Vec<(usize, Value)> {
+        let parked = self.threads.parked.iter();
+        let mut threads: Vec<_> = parked.map(|(&id, parked)| (id, parked.object)).collect();
+        threads.sort_by_key(|&(id, _)| id);
+        threads.insert(0, (self.threads.current, self.thread));
+        threads
+    }
+
+    /// interpreted frames of thread `id`, innermost last
+    pub(super) fn thread_frames(&self, id: usize) -> Option<&[Frame]> {
+        match id == self.threads.current {
+            true => Some(&self.frames),
+            false => self
+                .threads
+                .parked
+                .get(&id)
+                .map(|parked| parked.frames.as_slice()),
+        }
+    }
+
+    fn park(&mut self) {
+        let parked = Parked {
+            object: self.thread,
+            frames: mem::take(&mut self.frames),
+            roots: mem::take(&mut self.roots),
+        };
+        self.threads.parked.insert(self.threads.current, parked);
+    }
+
+    fn unpark(&mut self, id: usize) {
+        let parked = self.threads.parked.remove(&id).unwrap();
+        self.threads.current = id;
+        self.thread = parked.object;
+        self.frames = parked.frames;
+        self.roots = parked.roots;
+    }
+
+    /// lets other runnable threads run first
+    pub(crate) fn yield_now(&mut self) {
+        if !self.threads.gil.contended() {
+            return;
+        }
+        let id = self.threads.current;
+        self.park();
+        let gil = self.threads.gil.clone();
+        gil.release();
+        gil.acquire(id);
+        self.unpark(id);
+    }
+
+    /// counts down the time slice of the running thread, called between instructions
+    pub(crate) fn tick(&mut self) {
+        self.threads.ticks -= 1;
+        if self.threads.ticks == 0 {
+            self.threads.ticks = TIME_SLICE;
+            self.yield_now();
+        }
+    }
+
+    /// lets other threads run until the next event or the deadline, failing once the
+    /// limits of the program are exceeded
+    fn block(&mut self, deadline: Option<Instant>) -> anyhow::Result<()> {
+        let id = self.threads.current;
+        let gil = self.threads.gil.clone();
+        let seen = gil.events();
+        let deadline = match (deadline, self.budget.deadline) {
+            (Some(deadline), Some(timeout)) => Some(deadline.min(timeout)),
+            (deadline, timeout) => deadline.or(timeout),
+        };
+        self.park();
+        gil.wait_event(id, seen, deadline);
+        self.unpark(id);
+        self.check_limits(true)
+    }
+
+    pub(super) fn event(&self) {
+        self.threads.gil.event();
+    }
+
+    /// `System.exit`, which ends the running thread and every other one at its next
+    /// safepoint
+    pub(crate) fn exit(&mut self, status: i32) -> anyhow::Error {
+        self.threads.exit = Some(status);
+        self.event();
+        Exit(status).into()
+    }
+
+    /// status the program passed to `System.exit`, if it did
+    pub fn exit_status(&self) -> Option<i32> {
+        self.threads.exit
+    }
+
+    /// whether the running thread was interrupted, clearing the flag
+    pub(crate) fn interrupted(&mut self) -> bool {
+        let thread = self.thread;
+        let interrupted = self.thread_field(thread, "interrupted") == Value::Int(1);
+        if interrupted {
+            self.set_thread_field(thread, "interrupted", Value::Int(0));
+        }
+        interrupted
+    }
+
+    pub(crate) fn interrupt(&mut self, thread: Value) {
+        self.set_thread_field(thread, "interrupted", Value::Int(1));
+        self.event();
+    }
+
+    fn check_interrupt(&mut self, message: &str) -> anyhow::Result<()> {
+        match self.interrupted() {
+            true => Err(self.exception("java/lang/InterruptedException", message)),
+            false => Ok(()),
+        }
+    }
+
+    /// `Unsafe.park`: waits until `Unsafe.unpark` gives the running thread a permit, it is
+    /// interrupted or the time is up. The time is a deadline in milliseconds since the epoch
+    /// when `absolute`, otherwise a timeout in nanoseconds where 0 waits forever
+    pub(crate) fn park_thread(&mut self, absolute: bool, time: i64) -> anyhow::Result<()> {
+        let deadline = match (absolute, time) {
+            (false, 0) => None,
+            (false, nanos) if nanos < 0 => return Ok(()),
+            (false, nanos) => Some(Instant::now() + Duration::from_nanos(nanos as u64)),
+            (true, millis) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+                match millis - now {
+                    ..=0 => return Ok(()),
+                    left => Some(Instant::now() + Duration::from_millis(left as u64)),
+                }
+            }
+        };
+        let id = self.threads.current;
+        loop {
+            if self.threads.permits.remove(&id) {
+                return Ok(());
+            }
+            let thread = self.thread;
+            let interrupted = self.thread_field(thread, "interrupted") == Value::Int(1);
+            if interrupted || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(());
+            }
+            self.block(deadline)?;
+        }
+    }
+
+    /// `Unsafe.unpark`, giving `thread` a permit if it runs
+    pub(crate) fn unpark_thread(&mut self, thread: Value) {
+        if let Value::Long(id) = self.thread_field(thread, "eetop") {
+            if id != 0 {
+                self.threads.permits.insert(id as usize);
+                self.event();
+            }
+        }
+    }
+
+    pub(crate) fn is_alive(&self, thread: Value) -> bool {
+        self.thread_field(thread, "eetop") != Value::Long(0)
+    }
+
+    pub(crate) fn is_terminated(&self, thread: Value) -> bool {
+        self.thread_field(thread, "threadStatus") == Value::Int(TERMINATED)
+    }
+
+    /// makes `thread` the object of the main thread, which runs from the start
+    pub(crate) fn attach_main_thread(&mut self, thread: Value) {
+        self.set_thread_field(thread, "eetop", Value::Long(MAIN_THREAD as i64));
+        self.set_thread_field(thread, "threadStatus", Value::Int(RUNNABLE));
+        self.thread = thread;
+    }
+
+    /// `Thread.start`, running the `run` method of the thread on a new OS thread
+    pub(super) fn start_thread(&mut self, thread: Value) -> anyhow::Result<()> {
+        if self.thread_field(thread, "threadStatus") != Value::Int(0) {
+            return Err(self.exception("java/lang/IllegalThreadStateException", ""));
+        }
+        let id = self.threads.next_id;
+        self.threads.next_id += 1;
+        self.set_thread_field(thread, "eetop", Value::Long(id as i64));
+        self.set_thread_field(thread, "threadStatus", Value::Int(RUNNABLE));
+        let parked = Parked {
+            object: thread,
+            frames: Vec::new(),
+            roots: Vec::new(),
+        };
+        self.threads.parked.insert(id, parked);
+
+        let name = self.thread_field(thread, "name");
+        let name = match name {
+            Value::Null => format!("Thread-{id}"),
+            name => self.string_value(name)?,
+        };
+        let gil = self.threads.gil.clone();
+        gil.vm.store(self, Ordering::Release);
+        let stack = self.limits().stack_size();
+        thread::Builder::new()
+            .name(name)
+            .stack_size(stack)
+            .spawn(move || {
+                set_thread_stack(stack);
+                gil.acquire(id);
+                // SAFETY: the vm is only used by the thread holding the lock, which every
+                // thread takes before touching it and releases before blocking, so its `Rc`s
+                // and `RefCell`s are never used by two threads at once and handing over the
+                // lock orders their accesses. What the host put into the vm runs on this
+                // thread as well, which is why natives and the writers and readers of the
+                // tracer, profiler and debugger have to be `Send`. Calls of the host return
+                // only once every thread ended, so the vm stays where it is and the `Rc`s
+                // the host holds are not touched while the host runs
+                let vm = unsafe { &mut *gil.vm.load(Ordering::Acquire) };
+                vm.unpark(id);
+                vm.run_thread(thread);
+                gil.release();
+            })?;
+        Ok(())
+    }
+
+    fn run_thread(&mut self, thread: Value) {
+        self.jdwp_thread_event(true);
+        let result = self.invoke_virtual("java/lang/Thread", "run", "()V", vec![thread]);
+        let result = match result {
+            Err(err) if err.is::<Throw>() => {
+                let name = self.thread_field(thread, "name");
+                let name = self.string_value(name).unwrap_or_default();
+                let exception = err.downcast_ref::<Throw>().unwrap().0;
+                self.print_uncaught(&name, exception);
+                Ok(None)
+            }
+            result => result,
+        };
+        let result = match self.jdk && self.threads.exit.is_none() && !self.threads.stopping {
+            // removes the thread from its thread group
+            true => result
+                .and_then(|_| self.invoke_special("java/lang/Thread", "exit", "()V", vec![thread])),
+            false => result,
+        };
+        if let Err(err) = result {
+            if !err.is::<Stopped>() && self.threads.error.is_none() {
+                self.threads.error = Some(err);
+            }
+        }
+        self.jdwp_thread_event(false);
+        // wakes up threads joining this one, which wait on its monitor with a JDK library
+        self.set_thread_field(thread, "eetop", Value::Long(0));
+        self.set_thread_field(thread, "threadStatus", Value::Int(TERMINATED));
+        let lock = Lock::Object(thread.as_ref().unwrap());
+        if let Some(monitor) = self.threads.monitors.get_mut(&lock) {
+            monitor.waiting.clear();
+        }
+        self.threads.permits.remove(&self.threads.current);
+        self.event();
+    }
+
+    /// `Thread.join`, a timeout of 0 waits forever
+    pub(crate) fn join(&mut self, thread: Value, millis: i64) -> anyhow::Result<()> {
+        if millis < 0 {
+            let message = "timeout value is negative";
+            return Err(self.exception("java/lang/IllegalArgumentException", message));
+        }
+        let deadline = (millis > 0).then(|| Instant::now() + Duration::from_millis(millis as u64));
+        while self.is_alive(thread) {
+            self.check_interrupt("")?;
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            self.block(deadline)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn sleep(&mut self, millis: i64) -> anyhow::Result<()> {
+        if millis < 0 {
+            let message = "timeout value is negative";
+            return Err(self.exception("java/lang/IllegalArgumentException", message));
+        }
+        let deadline = Instant::now() + Duration::from_millis(millis as u64);
+        loop {
+            self.check_interrupt("sleep interrupted")?;
+            if Instant::now() >= deadline {
+                return Ok(());
+            }
+            self.block(Some(deadline))?;
+        }
+    }
+
+    /// runs java code for the host, which only gets control back once every thread the
+    /// code started ended: threads other than daemons run to their end, daemons stop at
+    /// their next safepoint. No thread uses the vm while the host holds it then
+    pub(super) fn run_for_host(
+        &mut self,
+        run: impl FnOnce(&mut Vm) -> anyhow::Result<Option<Value>>,
+    ) -> anyhow::Result<Option<Value>> {
+        if !self.frames.is_empty() || self.threads.current != MAIN_THREAD {
+            return run(self);
+        }
+        self.enter_from_host();
+        let result = run(self);
+        if !self.threads.parked.is_empty() {
+            self.end_threads(&result);
+        }
+        // a limit exceeded or an exit in another thread fails the call as well
+        match (result, self.threads.error.take()) {
+            (Ok(_), Some(err)) => Err(err),
+            (result, _) => result,
+        }
+    }
+
+    /// waits for the threads other than daemons to end and stops the daemons
+    fn end_threads(&mut self, result: &anyhow::Result<Option<Value>>) {
+        // the result stays reachable while the other threads run
+        let roots = self.roots.len();
+        match result {
+            Ok(Some(value)) => self.roots.push(*value),
+            Err(err) => self
+                .roots
+                .extend(err.downcast_ref::<Throw>().map(|throw| throw.0)),
+            Ok(None) => {}
+        }
+        self.join_threads();
+        self.threads.stopping = true;
+        self.event();
+        let id = self.threads.current;
+        let gil = self.threads.gil.clone();
+        while !self.threads.parked.is_empty() {
+            let seen = gil.events();
+            self.park();
+            gil.wait_event(id, seen, None);
+            self.unpark(id);
+        }
+        self.threads.stopping = false;
+        // monitors of the stopped threads are free again
+        for monitor in self.threads.monitors.values_mut() {
+            monitor.waiting.clear();
+            if monitor.owner != MAIN_THREAD {
+                monitor.count = 0;
+            }
+        }
+        self.roots.truncate(roots);
+    }
+
+    /// fails with [`Stopped`] in threads other than the main one once they have to stop
+    pub(super) fn check_stopped(&self) -> anyhow::Result<()> {
+        match self.threads.stopping && self.threads.current != MAIN_THREAD {
+            true => Err(Stopped.into()),
+            false => Ok(()),
+        }
+    }
+
+    /// waits until every thread other than daemons and the running one terminated
+    fn join_threads(&mut self) {
+        loop {
+            let parked = self.threads.parked.values();
+            let running = parked
+                .map(|parked| parked.object)
+                .any(|thread| self.thread_field(thread, "daemon") != Value::Int(1));
+            // the threads stop by themselves once the limits are exceeded
+            if !running || self.block(None).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// lock of a `monitorenter` or `synchronized` method on `object`
+    pub(crate) fn object_lock(&mut self, object: Value) -> anyhow::Result<Lock> {
+        match object.as_ref() {
+            Some(object) => Ok(Lock::Object(object)),
+            None => Err(self.null_pointer()),
+        }
+    }
+
+    pub(crate) fn monitor_enter(&mut self, lock: Lock) -> anyhow::Result<()> {
+        let id = self.threads.current;
+        loop {
+            let monitor = self.threads.monitors.entry(lock.clone()).or_default();
+            if monitor.count == 0 || monitor.owner == id {
+                monitor.owner = id;
+                monitor.count += 1;
+                return Ok(());
+            }
+            self.block(None)?;
+        }
+    }
+
+    /// the monitor of the running thread, throwing `IllegalMonitorStateException` if it
+    /// does not own it
+    fn owned_monitor(&mut self, lock: &Lock) -> anyhow::Result<&mut Monitor> {
+        if !self.holds_lock(lock) {
+            let message = "current thread is not owner";
+            return Err(self.exception("java/lang/IllegalMonitorStateException", message));
+        }
+        Ok(self.threads.monitors.get_mut(lock).unwrap())
+    }
+
+    pub(crate) fn holds_lock(&self, lock: &Lock) -> bool {
+        let monitor = self.threads.monitors.get(lock);
+        monitor.is_some_and(|m| m.count > 0 && m.owner == self.threads.current)
+    }
+
+    pub(crate) fn monitor_exit(&mut self, lock: &Lock) -> anyhow::Result<()> {
+        let monitor = self.owned_monitor(lock)?;
+        monitor.count -= 1;
+        if monitor.count == 0 {
+            if monitor.waiting.is_empty() {
+                self.threads.monitors.remove(lock);
+            }
+            self.event();
+        }
+        Ok(())
+    }
+
+    /// `Object.wait`, a timeout of 0 waits until notified
+    pub(crate) fn wait(&mut self, lock: Lock, millis: i64) -> anyhow::Result<()> {
+        if millis < 0 {
+            let message = "timeout value is negative";
+            return Err(self.exception("java/lang/IllegalArgumentException", message));
+        }
+        let id = self.threads.current;
+        let monitor = self.owned_monitor(&lock)?;
+        let count = mem::take(&mut monitor.count);
+        monitor.waiting.push(id);
+        self.event();
+
+        let deadline = (millis > 0).then(|| Instant::now() + Duration::from_millis(millis as u64));
+        loop {
+            let interrupted = self.thread_field(self.thread, "interrupted") == Value::Int(1);
+            let monitor = self.threads.monitors.get(&lock);
+            let notified = monitor.is_none_or(|monitor| !monitor.waiting.contains(&id));
+            let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if interrupted || notified || timed_out {
+                break;
+            }
+            self.block(deadline)?;
+        }
+        if let Some(monitor) = self.threads.monitors.get_mut(&lock) {
+            monitor.waiting.retain(|&waiting| waiting != id);
+        }
+        self.monitor_enter(lock.clone())?;
+        self.threads.monitors.get_mut(&lock).unwrap().count = count;
+        self.check_interrupt("")
+    }
+
+    /// `Object.notify` and `Object.notifyAll`
+    pub(crate) fn notify(&mut self, lock: &Lock, all: bool) -> anyhow::Result<()> {
+        let monitor = self.owned_monitor(lock)?;
+        match all {
+            true => monitor.waiting.clear(),
+            false if !monitor.waiting.is_empty() => {
+                monitor.waiting.remove(0);
+            }
+            false => {}
+        }
+        self.event();
+        Ok(())
+    }
+}
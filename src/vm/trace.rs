@@ -0,0 +1,225 @@
+//! `--trace`: logs every instruction the interpreter runs, with the operand stack and locals
+//! of its frame before and after it. Lines start with the depth of the frame, so that a
+//! trace can be diffed against the trace of a reference run
+
+use std::io::Write;
+
+use crate::{
+    class::{constant_pool::CpInfo, Class},
+    code::OpCode,
+};
+
+use super::{exception::Throw, link::Linked, thread::MAIN_THREAD, value::Value, Vm};
+
+pub struct Tracer {
+    /// globs matched against `pkg.Class` and `pkg.Class.method`, empty traces everything
+    patterns: Vec<String>,
+    out: Box<dyn Write + Send>,
+}
+
+impl Tracer {
+    pub fn new(patterns: Vec<String>, out: Box<dyn Write + Send>) -> Self {
+        Tracer { patterns, out }
+    }
+
+    fn traces(&self, class: &str, method: &str) -> bool {
+        if self.patterns.is_empty() {
+            return true;
+        }
+        let class = class.replace('/', ".");
+        let method = format!("{class}.{method}");
+        self.patterns
+            .iter()
+            .any(|pattern| glob(pattern, &class) || glob(pattern, &method))
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// whether `text` matches `pattern`, in which `*` stands for any run of characters
+pub(super) fn glob(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` and of the text it matched up to
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// symbolic operand of an instruction referring to the constant pool
+fn operand(class: &Class, op: &OpCode) -> Option<String> {
+    let cp = &class.cp;
+    match *op {
+        OpCode::Ldc(index) | OpCode::LdcW(index) | OpCode::Ldc2W(index) => {
+            Some(match cp.get(index)? {
+                CpInfo::String(s) => format!("{:?}", cp.get_utf(s.string_index)?.bytes),
+                CpInfo::Integer(i) => i.val.to_string(),
+                CpInfo::Float(f) => format!("{:?}f", f.val),
+                CpInfo::Long(l) => format!("{}L", l.val),
+                CpInfo::Double(d) => format!("{:?}d", d.val),
+                CpInfo::Class(_) => format!("{}.class", cp.get_class_name(index)?),
+                other => format!("{other:?}"),
+            })
+        }
+        OpCode::GetStatic(index)
+        | OpCode::PutStatic(index)
+        | OpCode::GetField(index)
+        | OpCode::PutField(index)
+        | OpCode::InvokeVirtual(index)
+        | OpCode::InvokeSpecial(index)
+        | OpCode::InvokeStatic(index)
+        | OpCode::InvokeInterface(index, _) => {
+            let member = cp.get_member_ref(index)?;
+            Some(format!(
+                "{}.{}:{}",
+                member.class, member.name, member.descriptor
+            ))
+        }
+        OpCode::InvokeDynamic(index) => {
+            let info = cp.get_invoke_dynamic(index)?;
+            let name_and_type = cp.get_name_and_type(info.name_and_type_index)?;
+            Some(format!(
+                "#{}:{}:{}",
+                info.bootstrap_method_attr_index,
+                cp.get_utf(name_and_type.name_index)?.bytes,
+                cp.get_utf(name_and_type.descriptor_index)?.bytes
+            ))
+        }
+        OpCode::New(index)
+        | OpCode::ANewArray(index)
+        | OpCode::CheckCast(index)
+        | OpCode::InstanceOf(index)
+        | OpCode::MultiANewArray(index, _) => cp.get_class_name(index).map(str::to_string),
+        _ => None,
+    }
+}
+
+/// `Class.method(descriptor) @pc op operand` for instruction `i` of `code`
+pub(super) fn describe_instruction(class: &Class, code: &Linked, i: usize) -> String {
+    let method = &class.methods[code.method];
+    let op = &method.code().unwrap().code[i];
+    let operand = match operand(class, op) {
+        Some(operand) => format!(" {operand}"),
+        None => String::new(),
+    };
+    format!(
+        "{}.{}{} @{} {op:?}{operand}",
+        class.this_class_name.replace('/', "."),
+        method.name,
+        method.descriptor,
+        code.pcs[i]
+    )
+}
+
+impl Vm {
+    /// whether the instructions of `code` are traced
+    pub(crate) fn tracing(&self, class: &Class, code: &Linked) -> bool {
+        let Some(tracer) = &self.tracer else {
+            return false;
+        };
+        let method = &class.methods[code.method];
+        tracer.traces(&class.this_class_name, &method.name)
+    }
+
+    /// short form of a value, with the contents of strings
+    pub(super) fn show_value(&mut self, value: Value) -> String {
+        match value {
+            Value::Int(i) => i.to_string(),
+            Value::Long(l) => format!("{l}L"),
+            Value::Float(f) => format!("{f:?}f"),
+            Value::Double(d) => format!("{d:?}d"),
+            Value::Null => "null".to_string(),
+            Value::Ref(r) if self.is_string(r) => match self.string_value(value) {
+                Ok(s) => format!("{s:?}"),
+                Err(_) => format!("java.lang.String@{r}"),
+            },
+            Value::Ref(r) => format!("{}@{r}", self.heap.class_name(r).replace('/', ".")),
+        }
+    }
+
+    fn trace_values(&mut self, values: Vec<Value>) -> String {
+        let values: Vec<_> = values.into_iter().map(|v| self.show_value(v)).collect();
+        format!("[{}]", values.join(", "))
+    }
+
+    /// stack and locals of the innermost frame
+    fn trace_frame(&mut self) -> String {
+        let frame = self.frames.last().unwrap();
+        let (stack, locals) = (frame.stack.clone(), frame.locals.clone());
+        let stack = self.trace_values(stack);
+        let locals = self.trace_values(locals);
+        format!("stack={stack} locals={locals}")
+    }
+
+    fn trace_line(&mut self, line: &str) -> anyhow::Result<()> {
+        let depth = self.frames.len();
+        let thread = match self.threads.current {
+            MAIN_THREAD => String::new(),
+            id => format!("[thread {id}] "),
+        };
+        let out = &mut self.tracer.as_mut().unwrap().out;
+        writeln!(out, "{thread}{depth:>3} {line}")?;
+        Ok(())
+    }
+
+    /// logs instruction `i` of `code` and the frame before running it
+    pub(crate) fn trace_instruction(
+        &mut self,
+        class: &Class,
+        code: &Linked,
+        i: usize,
+    ) -> anyhow::Result<()> {
+        let frame = self.trace_frame();
+        let line = format!("{} {frame}", describe_instruction(class, code, i));
+        self.trace_line(&line)
+    }
+
+    /// logs the frame after an instruction completed normally
+    pub(crate) fn trace_state(&mut self) -> anyhow::Result<()> {
+        let frame = self.trace_frame();
+        self.trace_line(&format!("  -> {frame}"))
+    }
+
+    /// logs an exception caught by the handler at bytecode offset `pc`
+    pub(crate) fn trace_catch(&mut self, exception: Value, pc: usize) -> anyhow::Result<()> {
+        let exception = self.show_value(exception);
+        self.trace_line(&format!("  -> catch {exception} @{pc}"))
+    }
+
+    /// logs how the method of the innermost frame completed
+    pub(crate) fn trace_exit(
+        &mut self,
+        result: &anyhow::Result<Option<Value>>,
+    ) -> anyhow::Result<()> {
+        let line = match result {
+            Ok(Some(value)) => format!("  -> return {}", self.show_value(*value)),
+            Ok(None) => "  -> return".to_string(),
+            Err(err) => match err.downcast_ref::<Throw>() {
+                Some(&Throw(exception)) => format!("  -> throw {}", self.show_value(exception)),
+                None => return Ok(()),
+            },
+        };
+        self.trace_line(&line)
+    }
+}
@@ -0,0 +1,55 @@
+use super::heap::ObjRef;
+
+/// single operand stack entry or local variable.
+/// long and double take one entry on the stack, but still occupy two local slots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Ref(ObjRef),
+    Null,
+}
+
+impl Value {
+    /// initial value of a field with the given descriptor
+    pub fn default_for(descriptor: &str) -> Value {
+        match descriptor.as_bytes().first() {
+            Some(b'J') => Value::Long(0),
+            Some(b'F') => Value::Float(0.0),
+            Some(b'D') => Value::Double(0.0),
+            Some(b'L' | b'[') => Value::Null,
+            _ => Value::Int(0),
+        }
+    }
+
+    pub fn as_int(self) -> i32 {
+        cast!(self, Value::Int)
+    }
+
+    pub fn as_long(self) -> i64 {
+        cast!(self, Value::Long)
+    }
+
+    pub fn as_float(self) -> f32 {
+        cast!(self, Value::Float)
+    }
+
+    pub fn as_double(self) -> f64 {
+        cast!(self, Value::Double)
+    }
+
+    /// `None` for null
+    pub fn as_ref(self) -> Option<ObjRef> {
+        match self {
+            Value::Ref(r) => Some(r),
+            Value::Null => None,
+            v => panic!("expected reference, got {v:?}"),
+        }
+    }
+
+    pub fn is_wide(self) -> bool {
+        matches!(self, Value::Long(_) | Value::Double(_))
+    }
+}
@@ -0,0 +1,1324 @@
+//! Type checking verifier (JVMS §4.10.1). Every method is checked instruction by instruction
+//! against the frames recorded in its `StackMapTable`, so the interpreter can rely on operands
+//! of the right type and never under- or overflows the operand stack or the locals.
+//! Class files before version 50 have no stack maps, their frames are inferred by flowing
+//! the state of each instruction into its successors instead (JVMS §4.10.2)
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    class::{
+        attribute::{Attribute, CodeAttribute, StackMapFrame, VerificationTypeInfo},
+        constant_pool::{CpInfo, MemberRef},
+        descriptor::{class_name, MethodDescriptor},
+        method::{MethodAccessFlags, MethodInfo},
+        Class, ClassAccessFlags,
+    },
+    code::OpCode,
+};
+
+use super::{exec::primitive_array_class, Vm};
+
+/// type of a local or stack entry. Long and double take two local slots,
+/// the second one being `Top`, but a single stack entry
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    Top,
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    /// `this` in a constructor before the super constructor ran
+    UninitializedThis,
+    /// result of the `new` at this bytecode offset before its constructor ran
+    Uninitialized(usize),
+    /// class name, or descriptor for arrays
+    Reference(String),
+}
+
+impl Type {
+    /// verification type of a field descriptor, booleans, bytes, chars and shorts are ints
+    fn of(descriptor: &str) -> Type {
+        match descriptor.bytes().next() {
+            Some(b'F') => Type::Float,
+            Some(b'J') => Type::Long,
+            Some(b'D') => Type::Double,
+            Some(b'L' | b'[') => Type::Reference(class_name(descriptor).to_string()),
+            _ => Type::Int,
+        }
+    }
+
+    fn is_wide(&self) -> bool {
+        matches!(self, Type::Long | Type::Double)
+    }
+
+    /// number of stack or local slots taken
+    fn size(&self) -> usize {
+        1 + self.is_wide() as usize
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Top => write!(f, "top"),
+            Type::Int => write!(f, "integer"),
+            Type::Float => write!(f, "float"),
+            Type::Long => write!(f, "long"),
+            Type::Double => write!(f, "double"),
+            Type::Null => write!(f, "null"),
+            Type::UninitializedThis => write!(f, "uninitializedThis"),
+            Type::Uninitialized(pc) => write!(f, "uninitialized({pc})"),
+            Type::Reference(class) => write!(f, "'{class}'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    locals: Vec<Type>,
+    stack: Vec<Type>,
+}
+
+/// whether a reference of class `from` can be used as one of class `to`. Like the JVMS,
+/// interfaces are treated as `Object`, and classes missing from the classpath are assumed
+/// to fit, their use fails later anyway
+fn assignable_class(vm: &mut Vm, from: &str, to: &str) -> anyhow::Result<bool> {
+    if from == to || to == "java/lang/Object" {
+        return Ok(true);
+    }
+    if let Some(to_element) = to.strip_prefix('[') {
+        let Some(from_element) = from.strip_prefix('[') else {
+            return Ok(false);
+        };
+        let reference = |element: &str| matches!(element.bytes().next(), Some(b'L' | b'['));
+        if reference(from_element) && reference(to_element) {
+            return assignable_class(vm, class_name(from_element), class_name(to_element));
+        }
+        return Ok(from_element == to_element);
+    }
+    if from.starts_with('[') {
+        return Ok(matches!(to, "java/lang/Cloneable" | "java/io/Serializable"));
+    }
+    match vm.load_class(to)? {
+        Some(target) if !target.access_flags.contains(ClassAccessFlags::INTERFACE) => {}
+        _ => return Ok(true),
+    }
+    let mut current = from.to_string();
+    loop {
+        if current == to {
+            return Ok(true);
+        }
+        match vm.load_class(&current)? {
+            None => return Ok(true),
+            Some(c) if c.super_class == 0 => return Ok(false),
+            Some(c) => current = c.super_class_name.clone(),
+        }
+    }
+}
+
+/// the closest class both `a` and `b` extend, where arrays of references are covariant
+/// and interfaces are treated as `Object`
+fn common_superclass(vm: &mut Vm, a: &str, b: &str) -> anyhow::Result<String> {
+    if a == b {
+        return Ok(a.to_string());
+    }
+    let object = "java/lang/Object".to_string();
+    if let (Some(a), Some(b)) = (a.strip_prefix('['), b.strip_prefix('[')) {
+        let reference = |element: &str| matches!(element.bytes().next(), Some(b'L' | b'['));
+        if !reference(a) || !reference(b) {
+            return Ok(object);
+        }
+        let element = common_superclass(vm, class_name(a), class_name(b))?;
+        return Ok(match element.starts_with('[') {
+            true => format!("[{element}"),
+            false => format!("[L{element};"),
+        });
+    }
+    if a.starts_with('[') || b.starts_with('[') {
+        return Ok(object);
+    }
+    // superclasses of `a`, then the first superclass of `b` among them
+    let mut supers = Vec::new();
+    let mut current = a.to_string();
+    loop {
+        match vm.load_class(&current)? {
+            Some(c) if c.access_flags.contains(ClassAccessFlags::INTERFACE) => return Ok(object),
+            Some(c) if c.super_class != 0 => {
+                let next = c.super_class_name.clone();
+                supers.push(std::mem::replace(&mut current, next));
+            }
+            Some(_) => break supers.push(current),
+            None => return Ok(object),
+        }
+    }
+    let mut current = b.to_string();
+    loop {
+        if supers.contains(&current) {
+            return Ok(current);
+        }
+        match vm.load_class(&current)? {
+            Some(c) if c.access_flags.contains(ClassAccessFlags::INTERFACE) => return Ok(object),
+            Some(c) if c.super_class != 0 => current = c.super_class_name.clone(),
+            _ => return Ok(object),
+        }
+    }
+}
+
+struct Verifier<'a> {
+    vm: &'a mut Vm,
+    class: &'a Class,
+    method: &'a MethodInfo,
+    code: &'a CodeAttribute,
+    /// frames of the stack map table by bytecode offset, or the inferred ones
+    frames: HashMap<usize, Frame>,
+    /// whether frames are inferred rather than taken from the stack map
+    infer: bool,
+    /// offsets whose inferred frame changed and has to be checked again
+    pending: Vec<usize>,
+    /// `None` for void methods
+    returns: Option<Type>,
+    frame: Frame,
+    pc: usize,
+}
+
+impl<'a> Verifier<'a> {
+    fn error(&mut self, headline: &str, reason: Option<String>) -> anyhow::Error {
+        let mut message = format!(
+            "{headline}\nException Details:\n  Location:\n    {}.{}{} @{}",
+            self.class.this_class_name, self.method.name, self.method.descriptor, self.pc
+        );
+        if let Some(reason) = reason {
+            message.push_str("\n  Reason:\n    ");
+            message.push_str(&reason);
+        }
+        self.vm.exception("java/lang/VerifyError", &message)
+    }
+
+    fn fail<T>(&mut self, headline: &str) -> anyhow::Result<T> {
+        Err(self.error(headline, None))
+    }
+
+    fn mismatch<T>(
+        &mut self,
+        headline: &str,
+        found: &Type,
+        expected: impl Display,
+    ) -> anyhow::Result<T> {
+        let reason = format!("Type {found} is not assignable to {expected}");
+        Err(self.error(headline, Some(reason)))
+    }
+
+    fn is_boundary(&self, pc: usize) -> bool {
+        pc < self.code.code_raw.len()
+            && self.code.op_to_bytecode[self.code.bytecode_to_op[pc]] == pc
+    }
+
+    fn constant(&self, index: usize) -> Option<&'a CpInfo> {
+        let class: &'a Class = self.class;
+        (index > 0).then(|| class.cp.get(index)).flatten()
+    }
+
+    fn class_constant(&mut self, index: usize) -> anyhow::Result<&'a str> {
+        let class: &'a Class = self.class;
+        match class.cp.get_class_name(index) {
+            Some(name) => Ok(name),
+            None => self.fail("Illegal constant pool index"),
+        }
+    }
+
+    /// field reference for field instructions, method reference otherwise
+    fn member(&mut self, index: usize, field: bool) -> anyhow::Result<MemberRef<'a>> {
+        let class: &'a Class = self.class;
+        let is_field = matches!(self.constant(index), Some(CpInfo::Fieldref(_)));
+        match class.cp.get_member_ref(index) {
+            Some(member) if is_field == field => Ok(member),
+            _ => self.fail("Illegal constant pool index"),
+        }
+    }
+
+    fn assignable(&mut self, from: &Type, to: &Type) -> anyhow::Result<bool> {
+        Ok(match (from, to) {
+            _ if from == to => true,
+            (_, Type::Top) => true,
+            (Type::Null, Type::Reference(_)) => true,
+            (Type::Reference(from), Type::Reference(to)) => assignable_class(self.vm, from, to)?,
+            _ => false,
+        })
+    }
+
+    /// checks that the state in `from` can flow into a frame of the stack map
+    fn check_frame(&mut self, from: &Frame, to: &Frame, headline: &str) -> anyhow::Result<()> {
+        if from.stack.len() != to.stack.len() {
+            let reason = "Current frame's stack size doesn't match stackmap.".to_string();
+            return Err(self.error(headline, Some(reason)));
+        }
+        let pairs = from.locals.iter().zip(&to.locals);
+        for (a, b) in pairs.chain(from.stack.iter().zip(&to.stack)) {
+            if !self.assignable(a, b)? {
+                return self.mismatch(headline, a, b);
+            }
+        }
+        Ok(())
+    }
+
+    fn verification_type(&mut self, info: &VerificationTypeInfo) -> anyhow::Result<Type> {
+        Ok(match info {
+            VerificationTypeInfo::Top => Type::Top,
+            VerificationTypeInfo::Integer => Type::Int,
+            VerificationTypeInfo::Float => Type::Float,
+            VerificationTypeInfo::Long => Type::Long,
+            VerificationTypeInfo::Double => Type::Double,
+            VerificationTypeInfo::Null => Type::Null,
+            VerificationTypeInfo::UninitializedThis => Type::UninitializedThis,
+            VerificationTypeInfo::Object(index) => {
+                Type::Reference(self.class_constant(*index)?.to_string())
+            }
+            VerificationTypeInfo::Uninitialized(pc) => {
+                let new = self.is_boundary(*pc)
+                    && matches!(
+                        self.code.code[self.code.bytecode_to_op[*pc]],
+                        OpCode::New(_)
+                    );
+                if !new {
+                    return self.fail("StackMapTable error: bad uninitialized offset");
+                }
+                Type::Uninitialized(*pc)
+            }
+        })
+    }
+
+    /// expands the stack map table, whose frames are deltas to the previous one,
+    /// starting with `locals` of the initial frame
+    fn stack_map(&mut self, mut locals: Vec<Type>) -> anyhow::Result<HashMap<usize, Frame>> {
+        let entries = self
+            .code
+            .attributes
+            .iter()
+            .find_map(|a| match &a.attribute {
+                Attribute::StackMapTable(table) => Some(table.entries.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let mut frames = HashMap::new();
+        let mut offset = None;
+        for entry in entries {
+            let (delta, stack) = match entry {
+                StackMapFrame::SameFrame { frame_type } => (*frame_type, vec![]),
+                StackMapFrame::SameLocals1StackItemFrame { frame_type, stack } => {
+                    (frame_type - 64, vec![self.verification_type(stack)?])
+                }
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    offset_delta,
+                    stack,
+                    ..
+                } => (*offset_delta, vec![self.verification_type(stack)?]),
+                StackMapFrame::ChopFrame {
+                    frame_type,
+                    offset_delta,
+                } => {
+                    let Some(len) = locals.len().checked_sub(251 - frame_type) else {
+                        return self.fail("StackMapTable error: bad chop frame");
+                    };
+                    locals.truncate(len);
+                    (*offset_delta, vec![])
+                }
+                StackMapFrame::SameFrameExtended { offset_delta, .. } => (*offset_delta, vec![]),
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals: appended,
+                    ..
+                } => {
+                    for info in appended {
+                        let t = self.verification_type(info)?;
+                        locals.push(t);
+                    }
+                    (*offset_delta, vec![])
+                }
+                StackMapFrame::FullFrame {
+                    offset_delta,
+                    locals: full,
+                    stack,
+                    ..
+                } => {
+                    locals.clear();
+                    for info in full {
+                        let t = self.verification_type(info)?;
+                        locals.push(t);
+                    }
+                    let mut types = Vec::with_capacity(stack.len());
+                    for info in stack {
+                        types.push(self.verification_type(info)?);
+                    }
+                    (*offset_delta, types)
+                }
+            };
+            let pc = offset.map_or(delta, |previous| previous + delta + 1);
+            offset = Some(pc);
+            self.pc = pc;
+            if !self.is_boundary(pc) {
+                return self.fail("StackMapTable error: bad offset");
+            }
+            let mut slots = Vec::with_capacity(self.code.max_locals);
+            for t in &locals {
+                slots.push(t.clone());
+                if t.is_wide() {
+                    slots.push(Type::Top);
+                }
+            }
+            if slots.len() > self.code.max_locals {
+                return self.fail("StackMapTable error: local size exceeds max_locals");
+            }
+            slots.resize(self.code.max_locals, Type::Top);
+            if stack.iter().map(Type::size).sum::<usize>() > self.code.max_stack {
+                return self.fail("StackMapTable error: stack size exceeds max_stack");
+            }
+            frames.insert(
+                pc,
+                Frame {
+                    locals: slots,
+                    stack,
+                },
+            );
+        }
+        Ok(frames)
+    }
+
+    fn push(&mut self, t: Type) -> anyhow::Result<()> {
+        let depth: usize = self.frame.stack.iter().map(Type::size).sum();
+        if depth + t.size() > self.code.max_stack {
+            return self.fail("Operand stack overflow");
+        }
+        self.frame.stack.push(t);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> anyhow::Result<Type> {
+        match self.frame.stack.pop() {
+            Some(t) => Ok(t),
+            None => self.fail("Operand stack underflow"),
+        }
+    }
+
+    fn pop_as(&mut self, expected: &Type) -> anyhow::Result<Type> {
+        let t = self.pop()?;
+        if !self.assignable(&t, expected)? {
+            return self.mismatch("Bad type on operand stack", &t, expected);
+        }
+        Ok(t)
+    }
+
+    /// pops an initialized reference or null
+    fn pop_reference(&mut self) -> anyhow::Result<Type> {
+        let t = self.pop()?;
+        if !matches!(t, Type::Null | Type::Reference(_)) {
+            return self.mismatch("Bad type on operand stack", &t, "reference");
+        }
+        Ok(t)
+    }
+
+    /// int, float or reference, the values `dup`, `swap` and `pop` move alone
+    fn pop_category1(&mut self) -> anyhow::Result<Type> {
+        let t = self.pop()?;
+        if t.is_wide() {
+            return self.mismatch("Bad type on operand stack", &t, "category 1 type");
+        }
+        Ok(t)
+    }
+
+    /// pops an array whose element descriptor passes `element`, returning the element type,
+    /// or null for a null array
+    fn pop_array(&mut self, element: fn(&str) -> bool) -> anyhow::Result<Type> {
+        let array = self.pop()?;
+        match &array {
+            Type::Null => Ok(Type::Null),
+            Type::Reference(class) => match class.strip_prefix('[') {
+                Some(e) if element(e) => Ok(Type::of(e)),
+                _ => self.mismatch("Bad type on operand stack", &array, "array"),
+            },
+            _ => self.mismatch("Bad type on operand stack", &array, "array"),
+        }
+    }
+
+    fn local(&mut self, index: usize, size: usize) -> anyhow::Result<Type> {
+        if index + size > self.code.max_locals {
+            return self.fail("Illegal local variable number");
+        }
+        Ok(self.frame.locals[index].clone())
+    }
+
+    fn load(&mut self, index: usize, t: Type) -> anyhow::Result<()> {
+        let local = self.local(index, t.size())?;
+        if local != t || (t.is_wide() && self.frame.locals[index + 1] != Type::Top) {
+            return self.mismatch("Bad local variable type", &local, &t);
+        }
+        self.push(t)
+    }
+
+    /// `aload`, which may also load uninitialized objects
+    fn load_reference(&mut self, index: usize) -> anyhow::Result<()> {
+        let local = self.local(index, 1)?;
+        if matches!(
+            local,
+            Type::Top | Type::Int | Type::Float | Type::Long | Type::Double
+        ) {
+            return self.mismatch("Bad local variable type", &local, "reference");
+        }
+        self.push(local)
+    }
+
+    fn store(&mut self, index: usize, t: Type) -> anyhow::Result<()> {
+        self.local(index, t.size())?;
+        // storing into the second half of a long or double destroys it
+        if index > 0 && self.frame.locals[index - 1].is_wide() {
+            self.frame.locals[index - 1] = Type::Top;
+        }
+        if t.is_wide() {
+            self.frame.locals[index + 1] = Type::Top;
+        }
+        self.frame.locals[index] = t;
+        Ok(())
+    }
+
+    fn pop_store(&mut self, index: usize, t: Type) -> anyhow::Result<()> {
+        self.pop_as(&t)?;
+        self.store(index, t)
+    }
+
+    /// `astore`, which may also store uninitialized objects
+    fn pop_store_reference(&mut self, index: usize) -> anyhow::Result<()> {
+        let t = self.pop()?;
+        if matches!(t, Type::Int | Type::Float | Type::Long | Type::Double) {
+            return self.mismatch("Bad type on operand stack", &t, "reference");
+        }
+        self.store(index, t)
+    }
+
+    fn unary(&mut self, from: Type, to: Type) -> anyhow::Result<()> {
+        self.pop_as(&from)?;
+        self.push(to)
+    }
+
+    fn binary(&mut self, t: Type) -> anyhow::Result<()> {
+        self.pop_as(&t)?;
+        self.pop_as(&t)?;
+        self.push(t)
+    }
+
+    fn shift(&mut self, t: Type) -> anyhow::Result<()> {
+        self.pop_as(&Type::Int)?;
+        self.pop_as(&t)?;
+        self.push(t)
+    }
+
+    fn compare(&mut self, t: Type) -> anyhow::Result<()> {
+        self.pop_as(&t)?;
+        self.pop_as(&t)?;
+        self.push(Type::Int)
+    }
+
+    fn array_load(&mut self, element: fn(&str) -> bool, t: Type) -> anyhow::Result<()> {
+        self.pop_as(&Type::Int)?;
+        self.pop_array(element)?;
+        self.push(t)
+    }
+
+    fn array_store(&mut self, element: fn(&str) -> bool, t: Type) -> anyhow::Result<()> {
+        self.pop_as(&t)?;
+        self.pop_as(&Type::Int)?;
+        self.pop_array(element)?;
+        Ok(())
+    }
+
+    /// checks the current state against the frame at the target of a jump
+    fn branch(&mut self, offset: isize) -> anyhow::Result<()> {
+        let target = self.pc as isize + offset;
+        if target < 0 || !self.is_boundary(target as usize) {
+            return self.fail("Illegal target of jump or branch");
+        }
+        let target = target as usize;
+        if self.infer {
+            let current = self.frame.clone();
+            return self.merge(target, current);
+        }
+        let Some(frame) = self.frames.get(&target).cloned() else {
+            return self.fail(&format!(
+                "Expecting a stackmap frame at branch target {target}"
+            ));
+        };
+        let current = self.frame.clone();
+        self.check_frame(
+            &current,
+            &frame,
+            &format!("Inconsistent stackmap frames at branch target {target}"),
+        )
+    }
+
+    /// checks the locals against the frames of the handlers covering the current instruction
+    fn check_handlers(&mut self) -> anyhow::Result<()> {
+        let code = self.code;
+        for entry in &code.exception_table {
+            if self.pc < entry.start_pc || self.pc >= entry.end_pc {
+                continue;
+            }
+            let handler = entry.handler_pc;
+            let exception = match entry.catch_type {
+                0 => "java/lang/Throwable",
+                index => self.class_constant(index)?,
+            };
+            let current = Frame {
+                locals: self.frame.locals.clone(),
+                stack: vec![Type::Reference(exception.to_string())],
+            };
+            if self.infer {
+                self.merge(handler, current)?;
+                continue;
+            }
+            let Some(frame) = self.frames.get(&handler).cloned() else {
+                return self.fail(&format!(
+                    "Expecting a stackmap frame at branch target {handler}"
+                ));
+            };
+            let headline =
+                format!("Stack map does not match the one at exception handler {handler}");
+            self.check_frame(&current, &frame, &headline)?;
+        }
+        Ok(())
+    }
+
+    /// merges `frame` into the inferred frame at `pc`, queueing `pc` when that one changed
+    fn merge(&mut self, pc: usize, frame: Frame) -> anyhow::Result<()> {
+        let Some(old) = self.frames.get(&pc).cloned() else {
+            self.frames.insert(pc, frame);
+            self.pending.push(pc);
+            return Ok(());
+        };
+        if old.stack.len() != frame.stack.len() {
+            let (old, new) = (old.stack.len(), frame.stack.len());
+            return self.fail(&format!("Inconsistent stack height {new} != {old}"));
+        }
+        let mut locals = Vec::with_capacity(old.locals.len());
+        for (a, b) in old.locals.iter().zip(&frame.locals) {
+            // locals which differ are unusable from here on
+            locals.push(self.join(a, b)?.unwrap_or(Type::Top));
+        }
+        let mut stack = Vec::with_capacity(old.stack.len());
+        for (a, b) in old.stack.iter().zip(&frame.stack) {
+            match self.join(a, b)? {
+                Some(t) => stack.push(t),
+                None => return self.fail("Mismatched stack types"),
+            }
+        }
+        if locals != old.locals || stack != old.stack {
+            self.frames.insert(pc, Frame { locals, stack });
+            self.pending.push(pc);
+        }
+        Ok(())
+    }
+
+    /// the type both `a` and `b` are assignable to, `None` when they don't have one
+    fn join(&mut self, a: &Type, b: &Type) -> anyhow::Result<Option<Type>> {
+        Ok(match (a, b) {
+            _ if a == b => Some(a.clone()),
+            (Type::Null, Type::Reference(_)) => Some(b.clone()),
+            (Type::Reference(_), Type::Null) => Some(a.clone()),
+            (Type::Reference(a), Type::Reference(b)) => {
+                Some(Type::Reference(common_superclass(self.vm, a, b)?))
+            }
+            _ => None,
+        })
+    }
+
+    /// `invokespecial` of a constructor, which turns every copy of the receiver
+    /// into an initialized reference
+    fn initialize(&mut self, owner: &str) -> anyhow::Result<()> {
+        let receiver = self.pop()?;
+        let initialized = match &receiver {
+            Type::UninitializedThis => {
+                let this = &self.class.this_class_name;
+                if owner != this && owner != self.class.super_class_name {
+                    return self.fail("Bad <init> method call");
+                }
+                Type::Reference(this.clone())
+            }
+            Type::Uninitialized(pc) => {
+                let OpCode::New(index) = self.code.code[self.code.bytecode_to_op[*pc]] else {
+                    unreachable!("uninitialized types are only created by new");
+                };
+                let class = self.class_constant(index)?;
+                if class != owner {
+                    return self.fail("Call to wrong <init> method");
+                }
+                Type::Reference(class.to_string())
+            }
+            _ => return self.mismatch("Bad type on operand stack", &receiver, "uninitialized"),
+        };
+        let frame = &mut self.frame;
+        for t in frame.locals.iter_mut().chain(frame.stack.iter_mut()) {
+            if *t == receiver {
+                *t = initialized.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn invoke(&mut self, op: &OpCode, index: usize) -> anyhow::Result<()> {
+        let method = self.member(index, false)?;
+        let interface = matches!(self.constant(index), Some(CpInfo::InterfaceMethodref(_)));
+        match op {
+            OpCode::InvokeInterface(..) if !interface => {
+                return self.fail("Illegal constant pool index");
+            }
+            OpCode::InvokeVirtual(_) if interface => {
+                return self.fail("Illegal constant pool index");
+            }
+            _ => {}
+        }
+        let init = method.name == "<init>" && matches!(op, OpCode::InvokeSpecial(_));
+        if method.name.starts_with('<') && !init {
+            return self.fail("Illegal call to internal method");
+        }
+        let Some(descriptor) = MethodDescriptor::parse(method.descriptor) else {
+            return self.fail("Illegal method signature");
+        };
+        for arg in descriptor.args.iter().rev() {
+            self.pop_as(&Type::of(arg))?;
+        }
+        if init {
+            self.initialize(method.class)?;
+        } else if !matches!(op, OpCode::InvokeStatic(_)) {
+            self.pop_as(&Type::Reference(method.class.to_string()))?;
+        }
+        if descriptor.ret != "V" {
+            self.push(Type::of(&descriptor.ret))?;
+        }
+        Ok(())
+    }
+
+    /// `kind` is the type returned by the instruction, `None` for `return`
+    fn ret(&mut self, kind: Option<Type>) -> anyhow::Result<()> {
+        match (kind, self.returns.clone()) {
+            (None, None) => {
+                let constructor = self.method.name == "<init>";
+                if constructor && self.frame.locals.contains(&Type::UninitializedThis) {
+                    return self.fail("Constructor must call super() or this() before return");
+                }
+            }
+            (Some(Type::Reference(_)), Some(expected @ Type::Reference(_))) => {
+                self.pop_as(&expected)?;
+            }
+            (Some(kind), Some(expected)) if kind == expected => {
+                self.pop_as(&kind)?;
+            }
+            (None, Some(_)) => return self.fail("Method expects a return value"),
+            _ => return self.fail("Bad return type"),
+        }
+        Ok(())
+    }
+
+    /// applies an instruction to the current frame, returns whether execution can continue
+    /// with the next instruction
+    fn step(&mut self, op: &OpCode) -> anyhow::Result<bool> {
+        let int = |e: &str| e == "I";
+        let reference = |e: &str| e.starts_with(['L', '[']);
+        match op {
+            OpCode::Nop => {}
+            OpCode::AConstNull => self.push(Type::Null)?,
+            OpCode::IConstM1
+            | OpCode::IConst0
+            | OpCode::IConst1
+            | OpCode::IConst2
+            | OpCode::IConst3
+            | OpCode::IConst4
+            | OpCode::IConst5
+            | OpCode::BiPush(_)
+            | OpCode::SiPush(_) => self.push(Type::Int)?,
+            OpCode::LConst0 | OpCode::LConst1 => self.push(Type::Long)?,
+            OpCode::FConst0 | OpCode::FConst1 | OpCode::FConst2 => self.push(Type::Float)?,
+            OpCode::DConst0 | OpCode::DConst1 => self.push(Type::Double)?,
+            OpCode::Ldc(index) | OpCode::LdcW(index) => {
+                let t = match self.constant(*index) {
+                    Some(CpInfo::Integer(_)) => Type::Int,
+                    Some(CpInfo::Float(_)) => Type::Float,
+                    Some(CpInfo::String(_)) => Type::Reference("java/lang/String".to_string()),
+                    Some(CpInfo::Class(_)) => Type::Reference("java/lang/Class".to_string()),
+                    Some(CpInfo::MethodType(_)) => {
+                        Type::Reference("java/lang/invoke/MethodType".to_string())
+                    }
+                    Some(CpInfo::MethodHandle(_)) => {
+                        Type::Reference("java/lang/invoke/MethodHandle".to_string())
+                    }
+                    _ => return self.fail("Invalid index in ldc"),
+                };
+                self.push(t)?;
+            }
+            OpCode::Ldc2W(index) => {
+                let t = match self.constant(*index) {
+                    Some(CpInfo::Long(_)) => Type::Long,
+                    Some(CpInfo::Double(_)) => Type::Double,
+                    _ => return self.fail("Invalid index in ldc2_w"),
+                };
+                self.push(t)?;
+            }
+
+            OpCode::ILoad(n) => self.load(*n, Type::Int)?,
+            OpCode::LLoad(n) => self.load(*n, Type::Long)?,
+            OpCode::FLoad(n) => self.load(*n, Type::Float)?,
+            OpCode::DLoad(n) => self.load(*n, Type::Double)?,
+            OpCode::ALoad(n) => self.load_reference(*n)?,
+            OpCode::ILoad0 => self.load(0, Type::Int)?,
+            OpCode::ILoad1 => self.load(1, Type::Int)?,
+            OpCode::ILoad2 => self.load(2, Type::Int)?,
+            OpCode::ILoad3 => self.load(3, Type::Int)?,
+            OpCode::LLoad0 => self.load(0, Type::Long)?,
+            OpCode::LLoad1 => self.load(1, Type::Long)?,
+            OpCode::LLoad2 => self.load(2, Type::Long)?,
+            OpCode::LLoad3 => self.load(3, Type::Long)?,
+            OpCode::FLoad0 => self.load(0, Type::Float)?,
+            OpCode::FLoad1 => self.load(1, Type::Float)?,
+            OpCode::FLoad2 => self.load(2, Type::Float)?,
+            OpCode::FLoad3 => self.load(3, Type::Float)?,
+            OpCode::DLoad0 => self.load(0, Type::Double)?,
+            OpCode::DLoad1 => self.load(1, Type::Double)?,
+            OpCode::DLoad2 => self.load(2, Type::Double)?,
+            OpCode::DLoad3 => self.load(3, Type::Double)?,
+            OpCode::ALoad0 => self.load_reference(0)?,
+            OpCode::ALoad1 => self.load_reference(1)?,
+            OpCode::ALoad2 => self.load_reference(2)?,
+            OpCode::ALoad3 => self.load_reference(3)?,
+
+            OpCode::IALoad => self.array_load(int, Type::Int)?,
+            OpCode::LALoad => self.array_load(|e| e == "J", Type::Long)?,
+            OpCode::FALoad => self.array_load(|e| e == "F", Type::Float)?,
+            OpCode::DALoad => self.array_load(|e| e == "D", Type::Double)?,
+            OpCode::AALoad => {
+                self.pop_as(&Type::Int)?;
+                let element = self.pop_array(reference)?;
+                self.push(element)?;
+            }
+            OpCode::BALoad => self.array_load(|e| e == "B" || e == "Z", Type::Int)?,
+            OpCode::CALoad => self.array_load(|e| e == "C", Type::Int)?,
+            OpCode::SALoad => self.array_load(|e| e == "S", Type::Int)?,
+
+            OpCode::IStore(n) => self.pop_store(*n, Type::Int)?,
+            OpCode::LStore(n) => self.pop_store(*n, Type::Long)?,
+            OpCode::FStore(n) => self.pop_store(*n, Type::Float)?,
+            OpCode::DStore(n) => self.pop_store(*n, Type::Double)?,
+            OpCode::AStore(n) => self.pop_store_reference(*n)?,
+            OpCode::IStore0 => self.pop_store(0, Type::Int)?,
+            OpCode::IStore1 => self.pop_store(1, Type::Int)?,
+            OpCode::IStore2 => self.pop_store(2, Type::Int)?,
+            OpCode::IStore3 => self.pop_store(3, Type::Int)?,
+            OpCode::LStore0 => self.pop_store(0, Type::Long)?,
+            OpCode::LStore1 => self.pop_store(1, Type::Long)?,
+            OpCode::LStore2 => self.pop_store(2, Type::Long)?,
+            OpCode::LStore3 => self.pop_store(3, Type::Long)?,
+            OpCode::FStore0 => self.pop_store(0, Type::Float)?,
+            OpCode::FStore1 => self.pop_store(1, Type::Float)?,
+            OpCode::FStore2 => self.pop_store(2, Type::Float)?,
+            OpCode::FStore3 => self.pop_store(3, Type::Float)?,
+            OpCode::DStore0 => self.pop_store(0, Type::Double)?,
+            OpCode::DStore1 => self.pop_store(1, Type::Double)?,
+            OpCode::DStore2 => self.pop_store(2, Type::Double)?,
+            OpCode::DStore3 => self.pop_store(3, Type::Double)?,
+            OpCode::AStore0 => self.pop_store_reference(0)?,
+            OpCode::AStore1 => self.pop_store_reference(1)?,
+            OpCode::AStore2 => self.pop_store_reference(2)?,
+            OpCode::AStore3 => self.pop_store_reference(3)?,
+
+            OpCode::IAStore => self.array_store(int, Type::Int)?,
+            OpCode::LAStore => self.array_store(|e| e == "J", Type::Long)?,
+            OpCode::FAStore => self.array_store(|e| e == "F", Type::Float)?,
+            OpCode::DAStore => self.array_store(|e| e == "D", Type::Double)?,
+            OpCode::AAStore => {
+                // the element type is checked at runtime, ArrayStoreException
+                self.pop_reference()?;
+                self.pop_as(&Type::Int)?;
+                self.pop_array(reference)?;
+            }
+            OpCode::BAStore => self.array_store(|e| e == "B" || e == "Z", Type::Int)?,
+            OpCode::CAStore => self.array_store(|e| e == "C", Type::Int)?,
+            OpCode::SAStore => self.array_store(|e| e == "S", Type::Int)?,
+
+            OpCode::Pop => {
+                self.pop_category1()?;
+            }
+            OpCode::Pop2 => {
+                if !self.pop()?.is_wide() {
+                    self.pop_category1()?;
+                }
+            }
+            OpCode::Dup => {
+                let v1 = self.pop_category1()?;
+                self.push(v1.clone())?;
+                self.push(v1)?;
+            }
+            OpCode::DupX1 => {
+                let v1 = self.pop_category1()?;
+                let v2 = self.pop_category1()?;
+                for t in [v1.clone(), v2, v1] {
+                    self.push(t)?;
+                }
+            }
+            OpCode::DupX2 => {
+                let v1 = self.pop_category1()?;
+                let v2 = self.pop()?;
+                let values = match v2.is_wide() {
+                    true => vec![v1.clone(), v2, v1],
+                    false => {
+                        let v3 = self.pop_category1()?;
+                        vec![v1.clone(), v3, v2, v1]
+                    }
+                };
+                for t in values {
+                    self.push(t)?;
+                }
+            }
+            OpCode::Dup2 => {
+                let v1 = self.pop()?;
+                let values = match v1.is_wide() {
+                    true => vec![v1.clone(), v1],
+                    false => {
+                        let v2 = self.pop_category1()?;
+                        vec![v2.clone(), v1.clone(), v2, v1]
+                    }
+                };
+                for t in values {
+                    self.push(t)?;
+                }
+            }
+            OpCode::Dup2X1 => {
+                let v1 = self.pop()?;
+                let values = match v1.is_wide() {
+                    true => {
+                        let v2 = self.pop_category1()?;
+                        vec![v1.clone(), v2, v1]
+                    }
+                    false => {
+                        let v2 = self.pop_category1()?;
+                        let v3 = self.pop_category1()?;
+                        vec![v2.clone(), v1.clone(), v3, v2, v1]
+                    }
+                };
+                for t in values {
+                    self.push(t)?;
+                }
+            }
+            OpCode::Dup2X2 => {
+                let v1 = self.pop()?;
+                let values = if v1.is_wide() {
+                    let v2 = self.pop()?;
+                    match v2.is_wide() {
+                        true => vec![v1.clone(), v2, v1],
+                        false => {
+                            let v3 = self.pop_category1()?;
+                            vec![v1.clone(), v3, v2, v1]
+                        }
+                    }
+                } else {
+                    let v2 = self.pop_category1()?;
+                    let v3 = self.pop()?;
+                    match v3.is_wide() {
+                        true => vec![v2.clone(), v1.clone(), v3, v2, v1],
+                        false => {
+                            let v4 = self.pop_category1()?;
+                            vec![v2.clone(), v1.clone(), v4, v3, v2, v1]
+                        }
+                    }
+                };
+                for t in values {
+                    self.push(t)?;
+                }
+            }
+            OpCode::Swap => {
+                let v1 = self.pop_category1()?;
+                let v2 = self.pop_category1()?;
+                self.push(v1)?;
+                self.push(v2)?;
+            }
+
+            OpCode::IAdd
+            | OpCode::ISub
+            | OpCode::IMul
+            | OpCode::IDiv
+            | OpCode::IRem
+            | OpCode::IAnd
+            | OpCode::IOr
+            | OpCode::IXor => self.binary(Type::Int)?,
+            OpCode::LAdd
+            | OpCode::LSub
+            | OpCode::LMul
+            | OpCode::LDiv
+            | OpCode::LRem
+            | OpCode::LAnd
+            | OpCode::LOr
+            | OpCode::LXor => self.binary(Type::Long)?,
+            OpCode::FAdd | OpCode::FSub | OpCode::FMul | OpCode::FDiv | OpCode::FRem => {
+                self.binary(Type::Float)?
+            }
+            OpCode::DAdd | OpCode::DSub | OpCode::DMul | OpCode::DDiv | OpCode::DRem => {
+                self.binary(Type::Double)?
+            }
+            OpCode::INeg => self.unary(Type::Int, Type::Int)?,
+            OpCode::LNeg => self.unary(Type::Long, Type::Long)?,
+            OpCode::FNeg => self.unary(Type::Float, Type::Float)?,
+            OpCode::DNeg => self.unary(Type::Double, Type::Double)?,
+            OpCode::IShl | OpCode::IShr | OpCode::IUShr => self.shift(Type::Int)?,
+            OpCode::LShl | OpCode::LShr | OpCode::LUShr => self.shift(Type::Long)?,
+            OpCode::Iinc(n, _) => {
+                let local = self.local(*n, 1)?;
+                if local != Type::Int {
+                    return self.mismatch("Bad local variable type", &local, Type::Int);
+                }
+            }
+
+            OpCode::I2L => self.unary(Type::Int, Type::Long)?,
+            OpCode::I2F => self.unary(Type::Int, Type::Float)?,
+            OpCode::I2D => self.unary(Type::Int, Type::Double)?,
+            OpCode::L2I => self.unary(Type::Long, Type::Int)?,
+            OpCode::L2F => self.unary(Type::Long, Type::Float)?,
+            OpCode::L2D => self.unary(Type::Long, Type::Double)?,
+            OpCode::F2I => self.unary(Type::Float, Type::Int)?,
+            OpCode::F2L => self.unary(Type::Float, Type::Long)?,
+            OpCode::F2D => self.unary(Type::Float, Type::Double)?,
+            OpCode::D2I => self.unary(Type::Double, Type::Int)?,
+            OpCode::D2L => self.unary(Type::Double, Type::Long)?,
+            OpCode::D2F => self.unary(Type::Double, Type::Float)?,
+            OpCode::I2B | OpCode::I2C | OpCode::I2S => self.unary(Type::Int, Type::Int)?,
+
+            OpCode::LCmp => self.compare(Type::Long)?,
+            OpCode::FCmpL | OpCode::FCmpG => self.compare(Type::Float)?,
+            OpCode::DCmpL | OpCode::DCmpG => self.compare(Type::Double)?,
+
+            OpCode::IReturn => {
+                self.ret(Some(Type::Int))?;
+                return Ok(false);
+            }
+            OpCode::LReturn => {
+                self.ret(Some(Type::Long))?;
+                return Ok(false);
+            }
+            OpCode::FReturn => {
+                self.ret(Some(Type::Float))?;
+                return Ok(false);
+            }
+            OpCode::DReturn => {
+                self.ret(Some(Type::Double))?;
+                return Ok(false);
+            }
+            OpCode::AReturn => {
+                self.ret(Some(Type::Reference("java/lang/Object".to_string())))?;
+                return Ok(false);
+            }
+            OpCode::Return => {
+                self.ret(None)?;
+                return Ok(false);
+            }
+
+            OpCode::GetStatic(index) => {
+                let field = self.member(*index, true)?;
+                self.push(Type::of(field.descriptor))?;
+            }
+            OpCode::PutStatic(index) => {
+                let field = self.member(*index, true)?;
+                self.pop_as(&Type::of(field.descriptor))?;
+            }
+            OpCode::GetField(index) => {
+                let field = self.member(*index, true)?;
+                self.pop_as(&Type::Reference(field.class.to_string()))?;
+                self.push(Type::of(field.descriptor))?;
+            }
+            OpCode::PutField(index) => {
+                let field = self.member(*index, true)?;
+                self.pop_as(&Type::of(field.descriptor))?;
+                let object = self.pop()?;
+                // constructors set their own fields before calling the super constructor
+                let own =
+                    object == Type::UninitializedThis && field.class == self.class.this_class_name;
+                let owner = Type::Reference(field.class.to_string());
+                if !own && !self.assignable(&object, &owner)? {
+                    return self.mismatch("Bad type on operand stack", &object, owner);
+                }
+            }
+            OpCode::InvokeVirtual(index)
+            | OpCode::InvokeSpecial(index)
+            | OpCode::InvokeStatic(index)
+            | OpCode::InvokeInterface(index, _) => self.invoke(op, *index)?,
+            OpCode::InvokeDynamic(index) => {
+                let class: &'a Class = self.class;
+                let Some(CpInfo::InvokeDynamic(info)) = self.constant(*index) else {
+                    return self.fail("Illegal constant pool index");
+                };
+                let name_and_type = class.cp.get_name_and_type(info.name_and_type_index);
+                let descriptor = name_and_type
+                    .and_then(|nt| class.cp.get_utf(nt.descriptor_index))
+                    .and_then(|utf| MethodDescriptor::parse(&utf.bytes));
+                let Some(descriptor) = descriptor else {
+                    return self.fail("Illegal method signature");
+                };
+                for arg in descriptor.args.iter().rev() {
+                    self.pop_as(&Type::of(arg))?;
+                }
+                if descriptor.ret != "V" {
+                    self.push(Type::of(&descriptor.ret))?;
+                }
+            }
+            OpCode::New(index) => {
+                self.class_constant(*index)?;
+                self.push(Type::Uninitialized(self.pc))?;
+            }
+            OpCode::NewArray(atype) => {
+                if !(4..=11).contains(atype) {
+                    return self.fail("Illegal newarray instruction");
+                }
+                self.pop_as(&Type::Int)?;
+                self.push(Type::Reference(primitive_array_class(*atype).to_string()))?;
+            }
+            OpCode::ANewArray(index) => {
+                let element = self.class_constant(*index)?;
+                self.pop_as(&Type::Int)?;
+                let class = match element.starts_with('[') {
+                    true => format!("[{element}"),
+                    false => format!("[L{element};"),
+                };
+                self.push(Type::Reference(class))?;
+            }
+            OpCode::MultiANewArray(index, dimensions) => {
+                let class = self.class_constant(*index)?;
+                let depth = class.bytes().take_while(|&b| b == b'[').count();
+                if *dimensions == 0 || depth < *dimensions {
+                    return self.fail("Illegal dimension in multianewarray instruction");
+                }
+                for _ in 0..*dimensions {
+                    self.pop_as(&Type::Int)?;
+                }
+                self.push(Type::Reference(class.to_string()))?;
+            }
+            OpCode::ArrayLength => {
+                self.pop_array(|_| true)?;
+                self.push(Type::Int)?;
+            }
+            OpCode::AThrow => {
+                self.pop_as(&Type::Reference("java/lang/Throwable".to_string()))?;
+                return Ok(false);
+            }
+            OpCode::CheckCast(index) => {
+                let class = self.class_constant(*index)?;
+                self.pop_reference()?;
+                self.push(Type::Reference(class.to_string()))?;
+            }
+            OpCode::InstanceOf(index) => {
+                self.class_constant(*index)?;
+                self.pop_reference()?;
+                self.push(Type::Int)?;
+            }
+            OpCode::MonitorEnter | OpCode::MonitorExit => {
+                self.pop_reference()?;
+            }
+
+            OpCode::IfEq(offset)
+            | OpCode::IfNe(offset)
+            | OpCode::IfLt(offset)
+            | OpCode::IfGe(offset)
+            | OpCode::IfGt(offset)
+            | OpCode::IfLe(offset) => {
+                self.pop_as(&Type::Int)?;
+                self.branch(*offset)?;
+            }
+            OpCode::IfICmpEq(offset)
+            | OpCode::IfICmpNe(offset)
+            | OpCode::IfICmpLt(offset)
+            | OpCode::IfICmpGe(offset)
+            | OpCode::IfICmpGt(offset)
+            | OpCode::IfICmpLe(offset) => {
+                self.pop_as(&Type::Int)?;
+                self.pop_as(&Type::Int)?;
+                self.branch(*offset)?;
+            }
+            OpCode::IfACmpEq(offset) | OpCode::IfACmpNe(offset) => {
+                self.pop_reference()?;
+                self.pop_reference()?;
+                self.branch(*offset)?;
+            }
+            OpCode::IfNull(offset) | OpCode::IfNonNull(offset) => {
+                self.pop_reference()?;
+                self.branch(*offset)?;
+            }
+            OpCode::Goto(offset) | OpCode::GotoW(offset) => {
+                self.branch(*offset)?;
+                return Ok(false);
+            }
+            // subroutines are assumed to return with the state they were called with,
+            // the interpreter does not run them anyway
+            OpCode::Jsr(offset) | OpCode::JsrW(offset) if self.infer => {
+                self.push(Type::Top)?;
+                self.branch(*offset)?;
+                self.pop()?;
+            }
+            OpCode::Ret(index) if self.infer => {
+                self.local(*index, 1)?;
+                return Ok(false);
+            }
+            OpCode::Jsr(_) | OpCode::JsrW(_) | OpCode::Ret(_) => {
+                return self
+                    .fail("jsr and ret are not allowed in class files of version 50 and above");
+            }
+            OpCode::TableSwitch(default, _, offsets) => {
+                self.pop_as(&Type::Int)?;
+                for offset in offsets.iter().chain([default]) {
+                    self.branch(*offset)?;
+                }
+                return Ok(false);
+            }
+            OpCode::LookupSwitch(default, pairs) => {
+                if pairs.windows(2).any(|w| w[0].0 >= w[1].0) {
+                    return self.fail("Bad lookupswitch instruction");
+                }
+                self.pop_as(&Type::Int)?;
+                for offset in pairs.iter().map(|(_, offset)| offset).chain([default]) {
+                    self.branch(*offset)?;
+                }
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn run(&mut self) -> anyhow::Result<()> {
+        let Some(descriptor) = MethodDescriptor::parse(&self.method.descriptor) else {
+            return self.fail("Illegal method signature");
+        };
+        self.returns = (descriptor.ret != "V").then(|| Type::of(&descriptor.ret));
+
+        // the initial frame holds `this` and the arguments
+        let mut locals = Vec::new();
+        if !self.method.access_flags.contains(MethodAccessFlags::STATIC) {
+            let this = &self.class.this_class_name;
+            locals.push(
+                match self.method.name == "<init>" && this != "java/lang/Object" {
+                    true => Type::UninitializedThis,
+                    false => Type::Reference(this.clone()),
+                },
+            );
+        }
+        locals.extend(descriptor.args.iter().map(|arg| Type::of(arg)));
+        if !self.infer {
+            self.frames = self.stack_map(locals.clone())?;
+        }
+        self.pc = 0;
+        let mut slots = Vec::with_capacity(self.code.max_locals);
+        for t in locals {
+            if t.is_wide() {
+                slots.extend([t, Type::Top]);
+            } else {
+                slots.push(t);
+            }
+        }
+        if slots.len() > self.code.max_locals {
+            return self.fail("Arguments can't fit into locals");
+        }
+        slots.resize(self.code.max_locals, Type::Top);
+        self.frame = Frame {
+            locals: slots,
+            stack: vec![],
+        };
+
+        let code = self.code;
+        let len = code.code_raw.len();
+        for entry in &code.exception_table {
+            let end = entry.end_pc == len || self.is_boundary(entry.end_pc);
+            if entry.start_pc >= entry.end_pc || !self.is_boundary(entry.start_pc) || !end {
+                return self.fail("Illegal exception table range");
+            }
+            if !self.is_boundary(entry.handler_pc) {
+                return self.fail("Illegal exception table handler");
+            }
+        }
+
+        if self.infer {
+            return self.infer_frames();
+        }
+
+        // whether the previous instruction continues with this one
+        let mut live = true;
+        for (i, op) in code.code.iter().enumerate() {
+            self.pc = code.op_to_bytecode[i];
+            if let Some(frame) = self.frames.get(&self.pc).cloned() {
+                if live {
+                    let current = self.frame.clone();
+                    let headline =
+                        format!("Inconsistent stackmap frames at branch target {}", self.pc);
+                    self.check_frame(&current, &frame, &headline)?;
+                }
+                self.frame = frame;
+            } else if !live {
+                return self.fail(&format!(
+                    "Expecting a stackmap frame at branch target {}",
+                    self.pc
+                ));
+            }
+            self.check_handlers()?;
+            live = self.step(op)?;
+            // stores change the locals seen by handlers covering the instruction
+            if live {
+                self.check_handlers()?;
+            }
+        }
+        if live {
+            return self.fail("Falling off the end of the code");
+        }
+        Ok(())
+    }
+
+    /// checks each instruction with the frame merged from all its predecessors,
+    /// starting with the initial frame, until no frame changes
+    fn infer_frames(&mut self) -> anyhow::Result<()> {
+        let code = self.code;
+        let initial = self.frame.clone();
+        self.merge(0, initial)?;
+        while let Some(pc) = self.pending.pop() {
+            self.pc = pc;
+            self.frame = self.frames[&pc].clone();
+            let i = code.bytecode_to_op[pc];
+            self.check_handlers()?;
+            if self.step(&code.code[i])? {
+                self.check_handlers()?;
+                let Some(&next) = code.op_to_bytecode.get(i + 1) else {
+                    return self.fail("Falling off the end of the code");
+                };
+                let current = self.frame.clone();
+                self.merge(next, current)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Vm {
+    /// type checks the code of every method, throwing `VerifyError` at the first problem
+    pub(crate) fn verify(&mut self, class: &Class) -> anyhow::Result<()> {
+        for method in &class.methods {
+            let Some(code) = method.code() else {
+                continue;
+            };
+            Verifier {
+                vm: self,
+                class,
+                method,
+                code,
+                frames: HashMap::new(),
+                infer: class.major_version < 50,
+                pending: Vec::new(),
+                returns: None,
+                frame: Frame {
+                    locals: vec![],
+                    stack: vec![],
+                },
+                pc: 0,
+            }
+            .run()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+class Counter {
+    private int count;
+    protected String label = "counter";
+
+    void increment() {
+        count++;
+    }
+
+    int count() {
+        return count;
+    }
+
+    protected String describe() {
+        return label + " " + count;
+    }
+}
+
+class LoudCounter extends Counter {
+    @Override
+    protected String describe() {
+        return super.describe().toUpperCase() + "!";
+    }
+}
+
+class Main {
+    private static int created;
+    private final String name;
+
+    private Main(String name) {
+        this.name = name;
+        created++;
+    }
+
+    private String greet() {
+        return "hello " + name;
+    }
+
+    static class Nested {
+        private int secret = 42;
+
+        private static String reveal(Main main) {
+            return main.name + " " + main.greet();
+        }
+    }
+
+    class Inner {
+        private int id = created;
+
+        String owner() {
+            return name + " " + id + " " + new Nested().secret;
+        }
+    }
+
+    static class Sub extends Main {
+        Sub() {
+            super("sub");
+        }
+
+        // does not override the private method of Main
+        public String greet() {
+            return "Sub.greet";
+        }
+    }
+
+    interface Greeter {
+        private String prefix() {
+            return "> ";
+        }
+
+        default String greet(String who) {
+            return prefix() + who;
+        }
+    }
+
+    public static void main(String[] args) {
+        Main main = new Main("main");
+        System.out.println(Nested.reveal(main));
+        System.out.println(main.new Inner().owner());
+        System.out.println(new Nested().secret);
+
+        Runnable lambda = () -> System.out.println(new Main("lambda").greet());
+        lambda.run();
+        Object anonymous = new Object() {
+            @Override
+            public String toString() {
+                return main.greet() + " from " + created;
+            }
+        };
+        System.out.println(anonymous);
+        System.out.println(new Greeter() {
+        }.greet("nest"));
+
+        Counter counter = new LoudCounter();
+        counter.increment();
+        counter.increment();
+        System.out.println(counter.count() + " " + counter.describe() + " " + counter.label);
+
+        Main sub = new Sub();
+        System.out.println(sub.greet() + " | " + ((Sub) sub).greet() + " | " + Nested.reveal(sub));
+        System.out.println(new Greeter() {
+            public String prefix() {
+                return "! ";
+            }
+        }.greet("private"));
+    }
+}
@@ -0,0 +1,76 @@
+interface Shape {
+}
+
+class Square implements Shape {
+}
+
+class Main {
+    static void cast(Runnable r) {
+        try {
+            r.run();
+        } catch (ClassCastException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    public static void main(String[] args) {
+        Object string = "x";
+        Object integer = 1;
+        Object square = new Square();
+        Object main = new Main();
+        Object ints = new int[1];
+        Object strings = new String[1];
+        Object squares = new Square[1];
+        cast(() -> System.out.println((Integer) integer));
+        cast(() -> System.out.println((Integer) string));
+        cast(() -> System.out.println((Main) string));
+        cast(() -> System.out.println((String) main));
+        cast(() -> System.out.println((Main) square));
+        cast(() -> System.out.println((Shape) main));
+        cast(() -> System.out.println(((String[]) ints).length));
+        cast(() -> System.out.println(((Square[]) strings).length));
+        cast(() -> System.out.println(((Main[]) squares).length));
+        cast(() -> System.out.println(((int[][]) strings).length));
+        Object[] objects = new String[2];
+        cast(() -> System.out.println(((Integer[]) objects).length));
+
+        System.out.println(objects instanceof Object[]);
+        System.out.println(objects instanceof String[]);
+        System.out.println(ints instanceof Object);
+        System.out.println(squares instanceof Shape[]);
+        System.out.println(ints instanceof Cloneable);
+        System.out.println(strings instanceof java.io.Serializable);
+        System.out.println(ints instanceof long[]);
+        System.out.println(ints instanceof Object[]);
+
+        Object nothing = null;
+        System.out.println(nothing instanceof String);
+        System.out.println((String) nothing);
+
+        Object[][] nested = new Square[1][1];
+        System.out.println(nested instanceof Shape[][]);
+        System.out.println(nested instanceof Object[]);
+        System.out.println(nested instanceof Cloneable[]);
+
+        Object[] shapes = new Shape[3];
+        shapes[0] = square;
+        shapes[1] = null;
+        store(shapes, main);
+        store(objects, integer);
+        store(objects, ints);
+        store(nested, new Main[0]);
+        store(nested, new Square[2]);
+        Object[] anything = new Object[1];
+        store(anything, ints);
+        System.out.println(shapes[0] == square);
+    }
+
+    static void store(Object[] array, Object value) {
+        try {
+            array[array.length - 1] = value;
+            System.out.println("stored");
+        } catch (ArrayStoreException e) {
+            System.out.println("ArrayStoreException: " + e.getMessage());
+        }
+    }
+}
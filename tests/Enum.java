@@ -0,0 +1,116 @@
+interface Shape {
+    double area(double size);
+}
+
+enum Planet {
+    MERCURY(3.303e+23, 2.4397e6),
+    EARTH(5.976e+24, 6.37814e6),
+    JUPITER(1.9e+27, 7.1492e7);
+
+    private final double mass;
+    private final double radius;
+
+    Planet(double mass, double radius) {
+        this.mass = mass;
+        this.radius = radius;
+    }
+
+    double surfaceGravity() {
+        return 6.67300E-11 * mass / (radius * radius);
+    }
+}
+
+enum Operation {
+    PLUS("+") {
+        int apply(int a, int b) {
+            return a + b;
+        }
+    },
+    TIMES("*") {
+        int apply(int a, int b) {
+            return a * b;
+        }
+    };
+
+    final String symbol;
+
+    Operation(String symbol) {
+        this.symbol = symbol;
+    }
+
+    abstract int apply(int a, int b);
+}
+
+enum Square implements Shape {
+    UNIT;
+
+    public double area(double size) {
+        return size * size;
+    }
+}
+
+class Main {
+    enum Color {
+        RED, GREEN, BLUE
+    }
+
+    static String describe(Color c) {
+        switch (c) {
+            case RED:
+                return "warm";
+            case BLUE:
+                return "cold";
+            default:
+                return "neutral " + c;
+        }
+    }
+
+    static int weight(Color c) {
+        return switch (c) {
+            case RED -> 3;
+            case GREEN -> 2;
+            case BLUE -> 1;
+        };
+    }
+
+    public static void main(String[] args) {
+        for (Color c : Color.values()) {
+            System.out.println(c + " " + c.ordinal() + " " + c.name() + " " + describe(c) + " " + weight(c));
+        }
+        Color[] colors = Color.values();
+        colors[0] = null;
+        System.out.println(Color.values()[0]);
+        System.out.println(Color.valueOf("GREEN") == Color.GREEN);
+        System.out.println(Color.RED.compareTo(Color.BLUE));
+        System.out.println(Color.RED.equals(Color.RED) + " " + Color.RED.equals(Color.GREEN));
+        System.out.println(Color.BLUE.getDeclaringClass());
+        System.out.println(Color.BLUE.getClass().getName());
+        System.out.println(Color.class.isEnum() + " " + Main.class.isEnum());
+        try {
+            Color.valueOf("PURPLE");
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            Color.valueOf(null);
+        } catch (NullPointerException e) {
+            System.out.println(e.getMessage());
+        }
+
+        for (Planet p : Planet.values()) {
+            System.out.printf("%s %.2f%n", p, p.surfaceGravity());
+        }
+
+        for (Operation op : Operation.values()) {
+            System.out.println(op + " " + op.symbol + " " + op.apply(6, 7));
+            System.out.println(op.getDeclaringClass() + " " + (op.getClass() == Operation.class));
+        }
+        System.out.println(Operation.valueOf("TIMES").apply(3, 4));
+
+        Shape shape = Square.UNIT;
+        System.out.println(shape.area(3));
+        Object object = Square.UNIT;
+        System.out.println(object instanceof Enum);
+        System.out.println(object instanceof Comparable);
+    }
+}
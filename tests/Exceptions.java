@@ -0,0 +1,82 @@
+class Boom extends RuntimeException {
+    Boom(String message) {
+        super(message);
+    }
+
+    Boom(String message, Throwable cause) {
+        super(message, cause);
+    }
+}
+
+class Resource implements AutoCloseable {
+    public void close() {
+        throw new IllegalStateException("close failed");
+    }
+}
+
+class Main {
+    static int depth(int n) {
+        if (n == 0) {
+            throw new Boom("bottom");
+        }
+        return depth(n - 1) + 1;
+    }
+
+    static int divide(int a, int b) {
+        return a / b;
+    }
+
+    static void wrap() {
+        try {
+            divide(1, 0);
+        } catch (ArithmeticException e) {
+            throw new Boom("wrapped", e);
+        }
+    }
+
+    static void fail() {
+        throw new Boom("uncaught", new IllegalStateException("inner"));
+    }
+
+    public static void main(String[] args) {
+        try {
+            System.out.println(depth(3));
+        } catch (Boom e) {
+            System.out.println(e.getMessage());
+            e.printStackTrace();
+        }
+        try {
+            Object o = null;
+            java.util.Objects.requireNonNull(o);
+        } catch (NullPointerException e) {
+            System.out.println("caught npe");
+        } finally {
+            System.out.println("finally");
+        }
+
+        StackTraceElement[] trace = new Boom("here").getStackTrace();
+        System.out.println(trace.length + " " + trace[0]);
+        System.out.println(trace[0].getClassName() + " " + trace[0].getMethodName() + " "
+                + trace[0].getFileName() + " " + trace[0].getLineNumber());
+
+        try (Resource r = new Resource()) {
+            throw new Boom("body");
+        } catch (Boom e) {
+            System.out.println(e.getSuppressed().length + " suppressed");
+            e.printStackTrace(System.out);
+        }
+        try {
+            wrap();
+        } catch (Boom e) {
+            e.printStackTrace();
+        }
+
+        Boom moved = new Boom("moved");
+        moved.setStackTrace(new StackTraceElement[] {
+            new StackTraceElement("Somewhere", "else", "Somewhere.java", 42),
+            new StackTraceElement("Nowhere", "unknown", null, -1),
+        });
+        moved.printStackTrace(System.out);
+        fail();
+    }
+}
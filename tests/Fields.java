@@ -0,0 +1,59 @@
+class Base {
+    public int x = 1;
+    protected String name = "base";
+
+    int baseX() {
+        return x;
+    }
+
+    String baseName() {
+        return name;
+    }
+}
+
+class Derived extends Base {
+    public int x = 2;
+    protected String name = "derived";
+
+    int bothX() {
+        return x * 10 + super.x;
+    }
+
+    void bump() {
+        x += 5;
+        super.x += 100;
+    }
+}
+
+class Leaf extends Derived {
+    int leafX() {
+        return x;
+    }
+}
+
+class Main {
+    public static void main(String[] args) throws Exception {
+        Derived d = new Derived();
+        Base b = d;
+        System.out.println(d.x + " " + b.x + " " + ((Base) d).x);
+        System.out.println(d.baseX() + " " + d.bothX());
+        System.out.println(d.name + " " + b.name + " " + d.baseName());
+
+        d.bump();
+        System.out.println(d.x + " " + b.x + " " + d.baseX());
+
+        Leaf leaf = new Leaf();
+        leaf.x = 7;
+        ((Base) leaf).x = 8;
+        System.out.println(leaf.leafX() + " " + leaf.baseX() + " " + leaf.bothX());
+
+        System.out.println(Base.class.getField("x").get(d));
+        System.out.println(Derived.class.getField("x").get(d));
+        System.out.println(Leaf.class.getField("x").get(leaf));
+        Base.class.getField("x").set(d, 42);
+        Derived.class.getField("x").set(d, 24);
+        System.out.println(b.x + " " + d.x + " " + d.bothX());
+        System.out.println(Base.class.getDeclaredField("name").get(d) + " "
+                + Derived.class.getDeclaredField("name").get(d));
+    }
+}
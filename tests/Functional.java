@@ -0,0 +1,99 @@
+import java.util.Arrays;
+import java.util.Comparator;
+import java.util.function.Function;
+import java.util.function.Predicate;
+
+class Person {
+    final String name;
+    final int age;
+
+    Person(String name, int age) {
+        this.name = name;
+        this.age = age;
+    }
+
+    @Override
+    public String toString() {
+        return name + "(" + age + ")";
+    }
+}
+
+class ByLength implements Comparator<String> {
+    @Override
+    public int compare(String a, String b) {
+        return a.length() - b.length();
+    }
+}
+
+class Main {
+    static String show(Object[] values) {
+        StringBuilder sb = new StringBuilder("[");
+        for (int i = 0; i < values.length; i++) {
+            if (i > 0) {
+                sb.append(", ");
+            }
+            sb.append(values[i]);
+        }
+        return sb.append("]").toString();
+    }
+
+    public static void main(String[] args) {
+        Comparator<Integer> ascending = (a, b) -> Integer.compare(a, b);
+        Integer[] numbers = {5, 3, 9, 1, 7, 3};
+        Arrays.sort(numbers, ascending);
+        System.out.println(show(numbers));
+        Arrays.sort(numbers, ascending.reversed());
+        System.out.println(show(numbers));
+
+        Person[] people = {
+            new Person("ann", 31), new Person("bob", 25), new Person("cy", 31), new Person("di", 25),
+        };
+        Comparator<Person> byAge = (a, b) -> a.age - b.age;
+        Arrays.sort(people, byAge);
+        System.out.println(show(people));
+        Arrays.sort(people, byAge.reversed().thenComparing((a, b) -> a.name.compareTo(b.name)));
+        System.out.println(show(people));
+
+        String[] words = {"pear", "fig", "banana", "kiwi", "apple"};
+        Arrays.sort(words, new ByLength());
+        System.out.println(show(words));
+        Arrays.sort(words, new ByLength().reversed());
+        System.out.println(show(words));
+        Arrays.sort(words);
+        System.out.println(show(words));
+        Arrays.sort(words, null);
+        System.out.println(show(words));
+
+        Function<Integer, Integer> twice = x -> x * 2;
+        Function<Integer, String> label = x -> "<" + x + ">";
+        System.out.println(twice.andThen(label).apply(21));
+        System.out.println(twice.compose((Integer x) -> x + 1).apply(4));
+        System.out.println(Function.<String>identity().andThen(String::length).apply("hello"));
+
+        Predicate<String> empty = String::isEmpty;
+        Predicate<String> shortWord = s -> s.length() < 4;
+        System.out.println(empty.negate().test("") + " " + empty.negate().test("x"));
+        System.out.println(shortWord.and(empty.negate()).test("abc") + " "
+                + shortWord.and(empty.negate()).test(""));
+        System.out.println(shortWord.negate().or(empty).test("") + " "
+                + shortWord.negate().or(empty).test("ab"));
+
+        try {
+            Arrays.sort(new Object[] {new Object(), new Object()});
+        } catch (ClassCastException e) {
+            System.out.println("not comparable");
+        }
+        try {
+            twice.andThen(null);
+        } catch (NullPointerException e) {
+            System.out.println("null function");
+        }
+        try {
+            Arrays.sort(words, (a, b) -> {
+                throw new IllegalStateException("from comparator");
+            });
+        } catch (IllegalStateException e) {
+            System.out.println(e.getMessage() + " " + show(words));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+class Main {
+    static class Node {
+        int value;
+        Node next;
+
+        Node(int value, Node next) {
+            this.value = value;
+            this.next = next;
+        }
+    }
+
+    static Node kept;
+
+    static int churn(int rounds) {
+        int sum = 0;
+        for (int i = 0; i < rounds; i++) {
+            Node node = new Node(i, null);
+            int[] array = new int[16];
+            array[i % 16] = node.value;
+            sum += array[i % 16] % 7;
+        }
+        return sum;
+    }
+
+    static String strings(int rounds) {
+        String last = "";
+        for (int i = 0; i < rounds; i++) {
+            last = "item " + i;
+        }
+        return last;
+    }
+
+    public static void main(String[] args) {
+        // a list reachable from a static field survives every collection
+        for (int i = 0; i < 1000; i++) {
+            kept = new Node(i, kept);
+        }
+        // and so does one only reachable from a local variable
+        Node local = null;
+        for (int i = 0; i < 1000; i++) {
+            local = new Node(-i, local);
+        }
+
+        System.out.println(churn(400000));
+        System.out.println(strings(200000));
+
+        int sum = 0;
+        for (Node node = kept; node != null; node = node.next) {
+            sum += node.value;
+        }
+        for (Node node = local; node != null; node = node.next) {
+            sum += node.value;
+        }
+        System.out.println(sum);
+
+        long[][] grid = new long[200][200];
+        for (int i = 0; i < 200; i++) {
+            grid[i][i] = i;
+        }
+        churn(100000);
+        System.out.println(grid[199][199]);
+
+        try {
+            long[] huge = new long[Integer.MAX_VALUE];
+            System.out.println(huge.length);
+        } catch (OutOfMemoryError e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            long[][] huge = new long[100000][100000];
+            System.out.println(huge.length);
+        } catch (OutOfMemoryError e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(churn(1000));
+    }
+}
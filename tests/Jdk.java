@@ -0,0 +1,41 @@
+import java.util.ArrayList;
+import java.util.HashMap;
+import java.util.List;
+import java.util.Map;
+
+// Needs the class library of a real JDK, whose HashMap and Formatter the built-in one
+// lacks. Booting the JDK the system java runs on prints what java does:
+//
+//   jrust --jdk $JAVA_HOME Main
+
+class Main {
+    public static void main(String[] args) {
+        Map<String, Integer> counts = new HashMap<>();
+        for (String word : "the quick brown fox jumps over the lazy dog and the end".split(" ")) {
+            counts.merge(word, 1, Integer::sum);
+        }
+        System.out.println(counts);
+        System.out.println(counts.get("the") + " " + counts.size() + " " + counts.containsKey("cat"));
+
+        Map<Integer, String> squares = new HashMap<>();
+        for (int i = 0; i < 100; i++) {
+            squares.put(i * i, "n" + i);
+        }
+        squares.remove(49);
+        System.out.println(squares.size() + " " + squares.get(81) + " " + squares.get(49));
+
+        List<String> words = new ArrayList<>(counts.keySet());
+        words.sort((a, b) -> a.length() != b.length() ? a.length() - b.length() : a.compareTo(b));
+        System.out.println(words);
+
+        System.out.println(String.format("%s has %d letters, %.2f%%", "fox", 3, 12.5));
+        System.out.println(String.format("[%5d|%-5d|%05d|%x|%X|%o]", 42, 42, 42, 255, 255, 8));
+        System.out.println(String.format("[%10.3f|%-10s|%e|%b|%c]", Math.PI, "left", 12345.678, true, 'z'));
+        System.out.println(String.format("%,d %+d %s", 1234567, 5, List.of(1, 2, 3)));
+        try {
+            String.format("%d", "not a number");
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getClass().getSimpleName() + ": " + e.getMessage());
+        }
+    }
+}
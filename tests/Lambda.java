@@ -0,0 +1,45 @@
+interface IntOp {
+    int apply(int a, int b);
+
+    default IntOp andThenAdd(int k) {
+        return (a, b) -> apply(a, b) + k;
+    }
+}
+
+interface IntConsumer {
+    void accept(int value);
+}
+
+class Main {
+    int base;
+
+    Main(int base) {
+        this.base = base;
+    }
+
+    void forEach(int n, IntConsumer consumer) {
+        for (int i = 0; i < n; i++) {
+            consumer.accept(base + i);
+        }
+    }
+
+    static int mul(int a, int b) {
+        return a * b;
+    }
+
+    public static void main(String[] args) {
+        Runnable r = () -> System.out.println("run");
+        r.run();
+
+        int k = 7;
+        IntOp add = (a, b) -> a + b + k;
+        System.out.println(add.andThenAdd(10).apply(1, 2));
+
+        IntOp mul = Main::mul;
+        System.out.println(mul.apply(6, 7));
+
+        Main m = new Main(100);
+        m.forEach(2, System.out::println);
+        System.out.println("k=" + k);
+    }
+}
@@ -0,0 +1,142 @@
+class Main {
+    static class Handle {
+        Object copy() throws CloneNotSupportedException {
+            return clone();
+        }
+    }
+
+    static class Point implements Cloneable {
+        int x, y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        Object copy() throws CloneNotSupportedException {
+            return clone();
+        }
+
+        @Override
+        public String toString() {
+            return "Point(" + x + ", " + y + ")";
+        }
+    }
+
+    static void strings() {
+        String s = "Hello, World";
+        System.out.println(s.length());
+        System.out.println(s.charAt(4));
+        System.out.println(s.substring(7));
+        System.out.println(s.substring(0, 5).toUpperCase());
+        System.out.println(s.indexOf("World") + " " + s.indexOf('z') + " " + s.lastIndexOf('o'));
+        System.out.println(s.contains("lo, W") + " " + s.startsWith("Hell") + " " + s.endsWith("x"));
+        System.out.println(s.replace('l', 'L') + " " + s.replace("World", "there"));
+        System.out.println("  padded  ".trim() + "|" + "ab".repeat(3) + "|" + "".isEmpty());
+        System.out.println(s.hashCode() + " " + "a".compareTo("b") + " " + "abc".compareTo("ab"));
+        System.out.println(s.equals("Hello, " + "World") + " " + "ABC".equalsIgnoreCase("abc"));
+        String[] parts = "a,b,,c,,".split(",");
+        System.out.println(parts.length + " " + String.join("-", parts));
+        char[] chars = "stressed".toCharArray();
+        System.out.println(new String(chars, 2, 4) + " " + String.valueOf(chars));
+        System.out.println(String.format("%5d|%-5s|%.2f|%05d|%x|%,d", 42, "ab", Math.PI, -7, 255, 1234567));
+        System.out.println(String.valueOf(3.0f) + " " + String.valueOf(true) + " " + String.valueOf('c'));
+    }
+
+    static void builders() {
+        StringBuilder sb = new StringBuilder();
+        for (int i = 0; i < 5; i++) {
+            sb.append(i).append(',');
+        }
+        sb.setLength(sb.length() - 1);
+        System.out.println(sb);
+        sb.reverse();
+        System.out.println(sb.toString() + " " + sb.length() + " " + sb.charAt(2));
+        sb.insert(0, "x=").deleteCharAt(2).append(1.5).append(true).append((Object) null);
+        System.out.println(sb);
+    }
+
+    static void boxing() {
+        Integer a = 127, b = 127, c = 1000, d = 1000;
+        System.out.println((a == b) + " " + (c == d) + " " + c.equals(d));
+        int sum = a + c;
+        System.out.println(sum + " " + Integer.parseInt("-123") + " " + Long.parseLong("9000000000"));
+        System.out.println(Integer.MAX_VALUE + " " + Integer.toHexString(-1) + " " + Integer.toBinaryString(10));
+        System.out.println(Double.parseDouble("2.5") * 2 + " " + Integer.valueOf(5).hashCode() + " " + Boolean.TRUE);
+        System.out.println(Character.isDigit('7') + " " + Character.toUpperCase('q') + " " + Character.isLetter('!'));
+        Long big = 123456789012L;
+        Double half = 0.5;
+        System.out.println(big.hashCode() + " " + half + " " + Double.compare(0.0, -0.0));
+        try {
+            Integer.parseInt("12x");
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void math() {
+        System.out.println(Math.max(3, 9) + " " + Math.min(-1L, 4L) + " " + Math.abs(-2.5));
+        System.out.println(Math.sqrt(16) + " " + Math.pow(2, 10) + " " + Math.floor(-1.5) + " " + Math.ceil(1.2));
+        System.out.println(Math.round(2.5) + " " + Math.round(-2.5) + " " + Math.floorMod(-7, 3));
+        double r = Math.random();
+        System.out.println(r >= 0 && r < 1);
+        System.out.println(1e7 + " " + 1.0e-4 + " " + 123.456 + " " + (0.1 + 0.2) + " " + 100.0f / 3);
+        try {
+            Math.addExact(Integer.MAX_VALUE, 1);
+        } catch (ArithmeticException e) {
+            System.out.println(e);
+        }
+    }
+
+    static void arrays() throws CloneNotSupportedException {
+        int[] src = {1, 2, 3, 4, 5};
+        int[] dst = new int[7];
+        System.arraycopy(src, 1, dst, 2, 3);
+        System.out.println(dst[2] + " " + dst[4] + " " + dst[5] + " " + src[0]);
+        System.arraycopy(src, 0, src, 1, 4);
+        System.out.println(src[0] + " " + src[1] + " " + src[4]);
+        try {
+            System.arraycopy(src, 3, dst, 0, 5);
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println("out of bounds");
+        }
+        Point p = new Point(1, 2);
+        Object q = p.copy();
+        p.x = 5;
+        System.out.println(p + " " + q);
+        Object o = new Object();
+        System.out.println(o.equals(o) + " " + o.equals(p) + " " + o.toString().startsWith("java.lang.Object@"));
+    }
+
+    static void exceptions() {
+        try {
+            throw new IllegalStateException("bad state", new RuntimeException("root"));
+        } catch (RuntimeException e) {
+            System.out.println(e + " caused by " + e.getCause().getMessage());
+        }
+        try {
+            "abc".charAt(5);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new Handle().copy();
+        } catch (CloneNotSupportedException e) {
+            System.out.println(e);
+        } catch (Exception e) {
+            System.out.println("wrong handler");
+        }
+        Throwable t = new Error(new ArithmeticException("inner"));
+        System.out.println(t.getMessage());
+        System.err.println("to stderr");
+    }
+
+    public static void main(String[] args) throws Exception {
+        strings();
+        builders();
+        boxing();
+        math();
+        arrays();
+        exceptions();
+    }
+}
@@ -0,0 +1,138 @@
+import java.util.Arrays;
+import java.util.Comparator;
+
+// Without arguments this runs into the limits of the JVM itself, the heap and the stack,
+// like java does, with the default heap as well as with -Xmx16m. The other limits are
+// those jrust runs untrusted code with:
+//
+//   jrust --fuel 100000 Main spin                 Error: used up the fuel of 100000 instructions
+//   jrust --timeout 0.5 Main spin                 Error: timed out after 500ms
+//   jrust --fuel 100000 Main worker               Error: used up the fuel of 100000 instructions
+//   jrust --max-classes 1 Main classes            Error: loaded more than 1 classes
+//   jrust --allow-classes=Main Main classes       class Helper is not allowed
+//   jrust --allow-natives=java.io.*,java.lang.String.*,java.lang.Throwable.* Main natives
+//       native method java.lang.System.nanoTime is not allowed
+
+class Helper {
+    static String greet() {
+        return "helper loaded";
+    }
+}
+
+class Node {
+    Node next;
+
+    @Override
+    public String toString() {
+        return "(" + next + ")";
+    }
+}
+
+class Main {
+    static int depth;
+    static Comparator<Integer> comparator;
+
+    static void recurse() {
+        depth++;
+        recurse();
+    }
+
+    static void heap() {
+        try {
+            "abcd".repeat(Integer.MAX_VALUE);
+        } catch (OutOfMemoryError e) {
+            System.out.println("repeat: " + e.getMessage());
+        }
+        try {
+            long[] huge = new long[Integer.MAX_VALUE];
+            System.out.println(huge.length);
+        } catch (OutOfMemoryError e) {
+            System.out.println("array: " + e.getMessage());
+        }
+        try {
+            new StringBuilder("x").setLength(Integer.MAX_VALUE);
+        } catch (OutOfMemoryError e) {
+            System.out.println("setLength: " + e.getClass().getName());
+        }
+        // the heap fills up with arrays which stay reachable, until they no longer do
+        Object[] chain = null;
+        int arrays = 0;
+        try {
+            while (true) {
+                chain = new Object[] {chain, new long[1 << 16]};
+                arrays++;
+            }
+        } catch (OutOfMemoryError e) {
+            chain = null;
+            System.out.println("heap exhausted: " + e.getMessage() + " " + (arrays > 10));
+        }
+        System.out.println("allocating again: " + new long[1 << 16].length);
+    }
+
+    static void stack() {
+        try {
+            recurse();
+        } catch (StackOverflowError e) {
+            System.out.println("recursion overflow " + (depth > 1000));
+        }
+        // calls through natives overflow too
+        Node node = null;
+        for (int i = 0; i < 20_000; i++) {
+            Node next = new Node();
+            next.next = node;
+            node = next;
+        }
+        try {
+            System.out.println(node.toString().length());
+        } catch (StackOverflowError e) {
+            System.out.println("toString overflow");
+        }
+        comparator = (a, b) -> comparator.compare(b, a);
+        try {
+            Arrays.sort(new Integer[] {1, 2}, comparator);
+        } catch (StackOverflowError e) {
+            System.out.println("comparator overflow");
+        }
+        depth = 0;
+        try {
+            recurse();
+        } catch (StackOverflowError e) {
+            System.out.println("overflow again " + (depth > 1000));
+        }
+    }
+
+    static void spin() {
+        System.out.println("spinning");
+        long n = 0;
+        while (true) {
+            n++;
+        }
+    }
+
+    public static void main(String[] args) {
+        String mode = args.length > 0 ? args[0] : "jvm";
+        switch (mode) {
+            case "spin" -> spin();
+            // the limits hold for every thread
+            case "worker" -> new Thread(Main::spin).start();
+            case "classes" -> {
+                try {
+                    System.out.println(Helper.greet());
+                } catch (SecurityException e) {
+                    System.out.println(e.getMessage());
+                }
+            }
+            case "natives" -> {
+                try {
+                    System.out.println(System.nanoTime() != 0);
+                } catch (SecurityException e) {
+                    System.out.println(e.getMessage());
+                }
+            }
+            default -> {
+                heap();
+                stack();
+            }
+        }
+    }
+}
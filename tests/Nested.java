@@ -0,0 +1,67 @@
+class Outer {
+    static class Member {
+        class Deep {
+            enum Level {
+                LOW, HIGH
+            }
+        }
+    }
+
+    interface Callback {
+        String call();
+    }
+}
+
+class Main {
+    static void describe(Class<?> c) {
+        System.out.println(c.getName() + " | " + c.getSimpleName() + " | " + c.getCanonicalName());
+        System.out.println("  declaring " + c.getDeclaringClass() + ", enclosing " + c.getEnclosingClass());
+        System.out.println("  anonymous " + c.isAnonymousClass() + ", local " + c.isLocalClass()
+                + ", member " + c.isMemberClass());
+    }
+
+    public static void main(String[] args) {
+        class Local {
+        }
+        record Point(int x, int y) {
+        }
+        enum Mode {
+            ON, OFF
+        }
+        Outer.Callback anonymous = new Outer.Callback() {
+            public String call() {
+                return "called";
+            }
+        };
+        Runnable lambda = () -> {
+        };
+
+        describe(Main.class);
+        describe(Outer.Member.class);
+        describe(Outer.Member.Deep.class);
+        describe(Outer.Member.Deep.Level.class);
+        describe(Outer.Callback.class);
+        describe(Local.class);
+        describe(Point.class);
+        describe(Mode.class);
+        describe(anonymous.getClass());
+        describe(Outer.Member.Deep[].class);
+        describe(Local[][].class);
+        describe(int[].class);
+
+        System.out.println(anonymous.call());
+        System.out.println(new Point(1, 2));
+        System.out.println(Mode.valueOf("OFF"));
+        System.out.println(lambda.getClass().isAnonymousClass());
+        try {
+            Outer.Member.Deep.Level.valueOf("MEDIUM");
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            Mode.valueOf("STANDBY");
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
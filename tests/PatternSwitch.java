@@ -0,0 +1,112 @@
+sealed interface Shape permits Circle, Square, Rect {
+}
+
+record Circle(double radius) implements Shape {
+}
+
+record Square(double side) implements Shape {
+}
+
+record Rect(double width, double height) implements Shape {
+}
+
+record Pair(Object first, Object second) {
+}
+
+enum Suit {
+    HEARTS, SPADES, CLUBS, DIAMONDS
+}
+
+class Main {
+    static String describe(Object o) {
+        return switch (o) {
+            case null -> "null";
+            case Integer i -> "int " + (i + 1);
+            case Long l -> "long " + l;
+            case String s -> "string of " + s.length();
+            case Suit s -> "suit " + s.ordinal();
+            case int[] a -> "ints " + a.length;
+            case Shape s -> "shape " + area(s);
+            case CharSequence cs -> "chars " + cs;
+            default -> "other " + o.getClass().getName();
+        };
+    }
+
+    static double area(Shape shape) {
+        return switch (shape) {
+            case Circle c -> 3 * c.radius() * c.radius();
+            case Square s -> s.side() * s.side();
+            case Rect r -> r.width() * r.height();
+        };
+    }
+
+    static String color(Suit suit) {
+        return switch (suit) {
+            case HEARTS, DIAMONDS -> "red";
+            case Suit s -> "black " + s.name().toLowerCase();
+        };
+    }
+
+    static String statement(Object o) {
+        String result = "none";
+        switch (o) {
+            case Character c:
+                result = "char " + c;
+                break;
+            case Number n:
+                result = "number " + n.intValue();
+                break;
+            default:
+                break;
+        }
+        return result;
+    }
+
+    static String guarded(Object o) {
+        return switch (o) {
+            case Integer i && i > 100 -> "big int " + i;
+            case Integer i && i < 0 -> "negative int " + i;
+            case Integer i -> "int " + i;
+            case Rect r && r.width() == r.height() -> "square rect " + r.width();
+            case Rect r -> "rect " + r.width() + "x" + r.height();
+            case Suit s && s == Suit.HEARTS -> "hearts";
+            case (String s) && s.isEmpty() -> "empty string";
+            case Pair p && p.first() instanceof Circle c && p.second() instanceof Pair q
+                    && q.first() instanceof Integer n -> "circle " + c.radius() + " and " + n;
+            case Pair p && p.first() instanceof String a && p.second() instanceof String b
+                    && a.equals(b) -> "same pair " + a;
+            case Pair p -> "pair " + p.first() + " " + p.second();
+            default -> "something " + o;
+        };
+    }
+
+    public static void main(String[] args) {
+        Object[] values = {
+            null, 41, 7L, "hello", Suit.CLUBS, new int[3], new Circle(1), new Rect(2, 3),
+            new StringBuilder("sb"), 2.5
+        };
+        for (Object value : values) {
+            System.out.println(describe(value));
+        }
+        for (Suit suit : Suit.values()) {
+            System.out.println(suit + " " + color(suit));
+        }
+        System.out.println(statement('x'));
+        System.out.println(statement(3.9));
+        System.out.println(statement("s"));
+        Object[] guards = {
+            500, -3, 7, new Rect(2, 2), new Rect(2, 5), Suit.HEARTS, Suit.SPADES, "", "text",
+            new Pair(new Circle(2), new Pair(9, null)), new Pair(new Circle(2), new Pair("x", 1)),
+            new Pair("a", "a"), new Pair("a", "b")
+        };
+        for (Object value : guards) {
+            System.out.println(guarded(value));
+        }
+        try {
+            describe(null);
+            color(null);
+        } catch (NullPointerException e) {
+            System.out.println("NullPointerException");
+        }
+    }
+}
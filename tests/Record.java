@@ -0,0 +1,93 @@
+interface Shape {
+    double area();
+}
+
+record Point(int x, int y) {
+    static Point origin() {
+        return new Point(0, 0);
+    }
+
+    Point plus(Point other) {
+        return new Point(x + other.x, y + other.y);
+    }
+}
+
+record Circle(Point center, double radius) implements Shape {
+    Circle {
+        if (radius < 0) {
+            throw new IllegalArgumentException("negative radius " + radius);
+        }
+    }
+
+    public double area() {
+        return Math.PI * radius * radius;
+    }
+}
+
+record Person(String name, long id, boolean active, char grade, float score) {
+}
+
+record Pair<A, B>(A first, B second) {
+}
+
+record Empty() {
+}
+
+record Named(String name) {
+    @Override
+    public String toString() {
+        return "Named " + name;
+    }
+}
+
+class Main {
+    public static void main(String[] args) {
+        Point p = new Point(1, 2);
+        Point q = new Point(1, 2);
+        System.out.println(p);
+        System.out.println(p.x() + " " + p.y());
+        System.out.println(p.equals(q) + " " + (p == q) + " " + p.equals(Point.origin()));
+        System.out.println(p.equals(null) + " " + p.equals("Point[x=1, y=2]"));
+        System.out.println(p.hashCode() == q.hashCode());
+        System.out.println(p.hashCode());
+        System.out.println(p.plus(q));
+
+        Circle c = new Circle(p, 2.5);
+        System.out.println(c);
+        System.out.println(c.equals(new Circle(new Point(1, 2), 2.5)));
+        System.out.println(c.hashCode());
+        System.out.printf("%.3f%n", c.area());
+        try {
+            new Circle(p, -1);
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+
+        Person person = new Person("Ada", 36L, true, 'A', 1.5f);
+        System.out.println(person);
+        System.out.println(person.hashCode());
+        System.out.println(person.equals(new Person("Ada", 36L, true, 'A', 1.5f)));
+        System.out.println(person.equals(new Person("Ada", 36L, false, 'A', 1.5f)));
+        Person nobody = new Person(null, 0, false, 'z', Float.NaN);
+        System.out.println(nobody);
+        System.out.println(nobody.hashCode());
+        System.out.println(nobody.equals(new Person(null, 0, false, 'z', Float.NaN)));
+
+        Pair<String, Point> pair = new Pair<>("p", p);
+        System.out.println(pair);
+        System.out.println(pair.equals(new Pair<>("p", q)));
+        System.out.println(new Pair<>(1, 2L).hashCode());
+
+        System.out.println(new Empty());
+        System.out.println(new Empty().equals(new Empty()) + " " + new Empty().hashCode());
+        System.out.println(new Named("x"));
+        System.out.println(new Named("x").equals(new Named("x")));
+
+        Object object = c;
+        if (object instanceof Shape shape) {
+            System.out.printf("%.1f%n", shape.area());
+        }
+        System.out.println(object instanceof Record);
+        System.out.println(c.getClass().getSimpleName());
+    }
+}
@@ -0,0 +1,201 @@
+import java.lang.reflect.Constructor;
+import java.lang.reflect.Field;
+import java.lang.reflect.Method;
+import java.lang.reflect.Modifier;
+
+interface Plugin {
+    String name();
+
+    int apply(int value);
+}
+
+abstract class BasePlugin implements Plugin {
+    public static int created;
+    protected String prefix = "base";
+
+    BasePlugin() {
+        created++;
+    }
+
+    public String describe() {
+        return prefix + ":" + name();
+    }
+}
+
+class Doubler extends BasePlugin {
+    public static final int FACTOR = 2;
+    private long calls;
+    public double scale = 1.5;
+    int[] history = new int[2];
+
+    public Doubler() {
+    }
+
+    public Doubler(String prefix) {
+        this.prefix = prefix;
+    }
+
+    public String name() {
+        return "doubler";
+    }
+
+    public int apply(int value) {
+        calls++;
+        return value * FACTOR;
+    }
+
+    public static long add(long a, int b) {
+        return a + b;
+    }
+
+    private String secret(char c, boolean upper) {
+        String s = "secret " + c;
+        return upper ? s.toUpperCase() : s;
+    }
+
+    void fail(String message) {
+        throw new IllegalStateException(message);
+    }
+
+    static void touch() {
+    }
+}
+
+class Main {
+    static void print(Object o) {
+        System.out.println(o);
+    }
+
+    static void attempt(String label, Runnable body) {
+        try {
+            body.run();
+        } catch (RuntimeException e) {
+            Throwable cause = e.getCause() != null ? e.getCause() : e;
+            System.out.println(label + ": " + cause.getClass().getName() + ": " + cause.getMessage());
+        }
+    }
+
+    interface Reflective {
+        void run() throws Exception;
+    }
+
+    static Runnable wrap(Reflective body) {
+        return () -> {
+            try {
+                body.run();
+            } catch (Exception e) {
+                throw new RuntimeException(e);
+            }
+        };
+    }
+
+    @SuppressWarnings("deprecation")
+    public static void main(String[] args) throws Exception {
+        Class<?> c = Class.forName("Doubler");
+        print(c + " " + c.getSuperclass() + " " + Modifier.toString(c.getModifiers()));
+        print(Modifier.toString(BasePlugin.class.getModifiers()) + " | "
+                + Modifier.toString(Plugin.class.getModifiers()));
+        print(c.isInstance(new Doubler()) + " " + c.isInstance("no") + " " + c.isInstance(null));
+        print(Plugin.class.isAssignableFrom(c) + " " + c.isAssignableFrom(Plugin.class));
+        print(int.class + " " + Integer.TYPE.isPrimitive() + " " + void.class + " "
+                + Modifier.toString(int.class.getModifiers()));
+
+        Object plugin = c.newInstance();
+        print(((BasePlugin) plugin).describe());
+        print("created " + BasePlugin.created);
+
+        Method[] methods = c.getDeclaredMethods();
+        String[] names = new String[methods.length];
+        for (int i = 0; i < methods.length; i++) {
+            names[i] = methods[i].toString();
+        }
+        for (int i = 1; i < names.length; i++) {
+            for (int j = i; j > 0 && names[j - 1].compareTo(names[j]) > 0; j--) {
+                String swap = names[j];
+                names[j] = names[j - 1];
+                names[j - 1] = swap;
+            }
+        }
+        for (String name : names) {
+            print(name);
+        }
+        for (Field field : c.getDeclaredFields()) {
+            print(field + " : " + field.getType().getName() + " " + field.getModifiers());
+        }
+        for (Constructor<?> constructor : c.getDeclaredConstructors()) {
+            print(constructor + " " + constructor.getParameterCount());
+        }
+
+        Method apply = c.getMethod("apply", int.class);
+        print(apply.invoke(plugin, 21) + " " + apply.getReturnType() + " "
+                + apply.getParameterTypes()[0] + " " + apply.getParameterTypes().length);
+        Method add = c.getDeclaredMethod("add", long.class, int.class);
+        print(add.invoke(null, 40, (short) 2));
+        Method describe = c.getMethod("describe");
+        print(describe.invoke(plugin) + " from " + describe.getDeclaringClass());
+        Method name = Plugin.class.getMethod("name");
+        print(name.invoke(plugin));
+        Method secret = c.getDeclaredMethod("secret", char.class, boolean.class);
+        attempt("inaccessible", wrap(() -> secret.invoke(plugin, 'x', true)));
+        secret.setAccessible(true);
+        print(secret.invoke(plugin, 'x', true) + " " + Modifier.isPrivate(secret.getModifiers()));
+        print(c.getDeclaredMethod("touch").invoke(null));
+        Method hash = Object.class.getMethod("hashCode");
+        print(hash.getDeclaringClass().getName());
+
+        Field calls = c.getDeclaredField("calls");
+        attempt("inaccessible field", wrap(() -> calls.get(plugin)));
+        calls.setAccessible(true);
+        print(calls.get(plugin));
+        calls.set(plugin, 41);
+        apply.invoke(plugin, 0);
+        print(calls.get(plugin));
+        calls.setInt(plugin, 7);
+        calls.setChar(plugin, 'a');
+        print(calls.getLong(plugin) + " " + calls.getFloat(plugin) + " " + calls.getDouble(plugin));
+        attempt("getInt", wrap(() -> calls.getInt(plugin)));
+        attempt("setDouble", wrap(() -> calls.setDouble(plugin, 1.5)));
+        attempt("setBoolean", wrap(() -> calls.setBoolean(plugin, true)));
+        Field scale = c.getField("scale");
+        scale.set(plugin, 2.5f);
+        print(scale.get(plugin));
+        scale.setLong(plugin, 3L);
+        print(scale.getDouble(plugin));
+        attempt("getFloat", wrap(() -> scale.getFloat(plugin)));
+        attempt("final int", wrap(() -> c.getField("FACTOR").setInt(null, 3)));
+        print(c.getField("FACTOR").getInt(null) + " " + c.getField("FACTOR").getLong(null));
+        attempt("getInt receiver", wrap(() -> calls.getInt("x")));
+        attempt("reference", wrap(() -> c.getSuperclass().getDeclaredField("prefix").getInt(plugin)));
+        Field prefix = c.getSuperclass().getDeclaredField("prefix");
+        prefix.set(plugin, "custom");
+        print(((BasePlugin) plugin).describe());
+        Field created = c.getField("created");
+        created.set(null, 10);
+        print(BasePlugin.created + " " + created.getDeclaringClass());
+        print(c.getField("FACTOR").get(null));
+
+        Object named = c.getConstructor(String.class).newInstance("named");
+        print(((BasePlugin) named).describe());
+
+        attempt("fail", wrap(() -> c.getDeclaredMethod("fail", String.class).invoke(plugin, "boom")));
+        attempt("missing", wrap(() -> c.getMethod("apply", long.class)));
+        attempt("missing constructor", wrap(() -> c.getConstructor(int.class, String[].class)));
+        attempt("private", wrap(() -> c.getMethod("secret", char.class, boolean.class)));
+        attempt("field", wrap(() -> c.getField("calls")));
+        attempt("class", wrap(() -> Class.forName("NoSuchPlugin")));
+        attempt("arguments", wrap(() -> apply.invoke(plugin)));
+        attempt("mismatch", wrap(() -> apply.invoke(plugin, "x")));
+        attempt("narrowing", wrap(() -> apply.invoke(plugin, 3L)));
+        attempt("receiver", wrap(() -> apply.invoke("x", 1)));
+        attempt("null receiver", wrap(() -> apply.invoke(null, 1)));
+        attempt("final", wrap(() -> c.getField("FACTOR").set(null, 3)));
+        attempt("field type", wrap(() -> calls.set(plugin, "x")));
+        attempt("null field", wrap(() -> calls.set(plugin, null)));
+        attempt("abstract", wrap(() -> BasePlugin.class.getDeclaredConstructor().newInstance()));
+        attempt("cast", () -> Integer.class.cast("x"));
+
+        Class<?> array = Class.forName("[Ljava.lang.String;");
+        print(array.getName() + " " + array.getSimpleName() + " " + array.getDeclaredMethods().length);
+        print(Class.forName("[[I").getName() + " " + Class.forName("java.lang.String").getSimpleName());
+    }
+}
@@ -0,0 +1,79 @@
+sealed interface Expr permits Num, Add, Mul, Neg {
+}
+
+record Num(int value) implements Expr {
+}
+
+record Add(Expr left, Expr right) implements Expr {
+}
+
+record Mul(Expr left, Expr right) implements Expr {
+}
+
+final class Neg implements Expr {
+    final Expr operand;
+
+    Neg(Expr operand) {
+        this.operand = operand;
+    }
+}
+
+abstract sealed class Vehicle permits Car, Truck, Bike {
+    abstract int wheels();
+}
+
+final class Car extends Vehicle {
+    int wheels() {
+        return 4;
+    }
+}
+
+non-sealed class Truck extends Vehicle {
+    int wheels() {
+        return 6;
+    }
+}
+
+class BigTruck extends Truck {
+    int wheels() {
+        return 18;
+    }
+}
+
+sealed class Bike extends Vehicle {
+    int wheels() {
+        return 2;
+    }
+}
+
+final class Tandem extends Bike {
+}
+
+class Main {
+    static int eval(Expr expr) {
+        if (expr instanceof Num n) {
+            return n.value();
+        } else if (expr instanceof Add a) {
+            return eval(a.left()) + eval(a.right());
+        } else if (expr instanceof Mul m) {
+            return eval(m.left()) * eval(m.right());
+        } else if (expr instanceof Neg n) {
+            return -eval(n.operand);
+        }
+        throw new IllegalStateException();
+    }
+
+    public static void main(String[] args) {
+        Expr expr = new Add(new Num(2), new Mul(new Num(3), new Neg(new Num(4))));
+        System.out.println(expr instanceof Add);
+        System.out.println(eval(expr));
+
+        Vehicle[] vehicles = {new Car(), new Truck(), new BigTruck(), new Bike(), new Tandem()};
+        int total = 0;
+        for (Vehicle vehicle : vehicles) {
+            total += vehicle.wheels();
+            System.out.println(vehicle.getClass().getName() + " " + vehicle.wheels());
+        }
+        System.out.println(total);
+    }
+}
@@ -0,0 +1,93 @@
+class Main {
+    static final int IDLE = 0, RUNNING = 1, PAUSED = 2, STOPPED = 3;
+
+    // a state machine over dense keys, which javac turns into a tableswitch
+    static int step(int state, char event) {
+        switch (state) {
+            case IDLE:
+                return event == 's' ? RUNNING : IDLE;
+            case RUNNING:
+                if (event == 'p') {
+                    return PAUSED;
+                }
+                // falls through
+            case PAUSED:
+                return event == 'x' ? STOPPED : RUNNING;
+            default:
+                return STOPPED;
+        }
+    }
+
+    // sparse keys make a lookupswitch
+    static String status(int code) {
+        switch (code) {
+            case -1:
+                return "unknown";
+            case 200:
+                return "ok";
+            case 404:
+                return "not found";
+            case 500:
+                return "error";
+            default:
+                return "code " + code;
+        }
+    }
+
+    static int command(String name) {
+        switch (name) {
+            case "start":
+                return 1;
+            case "stop":
+                return 2;
+            // "Aa" and "BB" have the same hash code
+            case "Aa":
+                return 3;
+            case "BB":
+                return 4;
+            default:
+                return 0;
+        }
+    }
+
+    static String kind(char c) {
+        return switch (c) {
+            case 'a', 'e', 'i', 'o', 'u' -> "vowel";
+            case ' ' -> "space";
+            default -> {
+                if (Character.isDigit(c)) {
+                    yield "digit";
+                }
+                yield "consonant";
+            }
+        };
+    }
+
+    public static void main(String[] args) {
+        int state = IDLE;
+        for (char event : "spsxq".toCharArray()) {
+            state = step(state, event);
+            System.out.println(event + " -> " + state);
+        }
+        System.out.println(step(42, 's'));
+        for (int code : new int[] {-1, 200, 404, 500, 302, Integer.MIN_VALUE}) {
+            System.out.println(status(code));
+        }
+        for (String name : new String[] {"start", "stop", "Aa", "BB", "pause"}) {
+            System.out.println(name + " " + command(name));
+        }
+        for (char c : "hi 2u".toCharArray()) {
+            System.out.println(c + " " + kind(c));
+        }
+        long total = 0;
+        for (int i = 0; i < 100000; i++) {
+            switch (i % 5) {
+                case 0 -> total += i;
+                case 1 -> total -= 1;
+                case 3 -> total *= 1;
+                default -> {}
+            }
+        }
+        System.out.println(total);
+    }
+}
@@ -0,0 +1,167 @@
+class Main {
+    static class Counter {
+        private int count;
+
+        synchronized void increment() {
+            count++;
+        }
+
+        synchronized int get() {
+            return count;
+        }
+    }
+
+    static class Queue {
+        private final int[] items = new int[4];
+        private int head;
+        private int size;
+
+        synchronized void put(int item) throws InterruptedException {
+            while (size == items.length) {
+                wait();
+            }
+            items[(head + size) % items.length] = item;
+            size++;
+            notifyAll();
+        }
+
+        synchronized int take() throws InterruptedException {
+            while (size == 0) {
+                wait();
+            }
+            int item = items[head];
+            head = (head + 1) % items.length;
+            size--;
+            notifyAll();
+            return item;
+        }
+    }
+
+    static class Worker extends Thread {
+        long sum;
+
+        Worker(String name) {
+            super(name);
+        }
+
+        @Override
+        public void run() {
+            for (int i = 1; i <= 100000; i++) {
+                sum += i;
+            }
+        }
+    }
+
+    static int shared;
+    static final Object LOCK = new Object();
+
+    static synchronized void addShared(int n) {
+        shared += n;
+    }
+
+    public static void main(String[] args) throws Exception {
+        System.out.println(Thread.currentThread().getName());
+
+        Counter counter = new Counter();
+        Thread[] threads = new Thread[4];
+        for (int i = 0; i < threads.length; i++) {
+            Thread thread = new Thread(() -> {
+                for (int j = 0; j < 20000; j++) {
+                    counter.increment();
+                    addShared(1);
+                    synchronized (LOCK) {
+                        shared++;
+                    }
+                }
+            });
+            threads[i] = thread;
+            thread.start();
+        }
+        for (Thread thread : threads) {
+            thread.join();
+        }
+        System.out.println(counter.get() + " " + shared);
+
+        Queue queue = new Queue();
+        Thread producer = new Thread(() -> {
+            try {
+                for (int i = 1; i <= 100; i++) {
+                    queue.put(i);
+                }
+                queue.put(-1);
+            } catch (InterruptedException e) {
+                System.out.println("producer interrupted");
+            }
+        }, "producer");
+        producer.start();
+        int total = 0;
+        for (int item = queue.take(); item != -1; item = queue.take()) {
+            total += item;
+        }
+        producer.join();
+        System.out.println(producer.getName() + " " + total + " " + producer.isAlive());
+
+        Worker worker = new Worker("worker");
+        System.out.println(worker.isAlive());
+        worker.start();
+        worker.join();
+        System.out.println(worker + " " + worker.sum);
+        try {
+            worker.start();
+        } catch (IllegalThreadStateException e) {
+            System.out.println("cannot restart");
+        }
+
+        Thread sleeper = new Thread(() -> {
+            try {
+                Thread.sleep(60000);
+                System.out.println("woke up");
+            } catch (InterruptedException e) {
+                System.out.println(e.getMessage() + " " + Thread.currentThread().isInterrupted());
+            }
+        });
+        sleeper.start();
+        Thread.sleep(20);
+        sleeper.interrupt();
+        sleeper.join();
+
+        Thread.currentThread().interrupt();
+        System.out.println(Thread.interrupted() + " " + Thread.interrupted());
+        Thread.currentThread().interrupt();
+        try {
+            synchronized (LOCK) {
+                LOCK.wait();
+            }
+        } catch (InterruptedException e) {
+            System.out.println("wait interrupted");
+        }
+
+        try {
+            LOCK.notify();
+        } catch (IllegalMonitorStateException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            LOCK.wait(10);
+        } catch (IllegalMonitorStateException e) {
+            System.out.println(e.getMessage());
+        }
+
+        long start = System.currentTimeMillis();
+        synchronized (LOCK) {
+            LOCK.wait(30);
+        }
+        System.out.println(System.currentTimeMillis() - start >= 30);
+
+        Thread last = new Thread(() -> {
+            try {
+                Thread.sleep(30);
+            } catch (InterruptedException e) {
+                return;
+            }
+            System.out.println("last thread done");
+        });
+        last.start();
+        System.out.println("main done");
+    }
+}
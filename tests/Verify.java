@@ -0,0 +1,126 @@
+import java.lang.invoke.MethodHandles;
+
+class Main {
+    static class Shape {
+        final String name;
+
+        Shape(String name) {
+            this.name = name;
+        }
+    }
+
+    static class Square extends Shape {
+        final int side;
+
+        Square(int side) {
+            super(side > 10 ? "big square" : "square");
+            this.side = side;
+        }
+
+        Square() {
+            this(Math.max(1, 2));
+        }
+    }
+
+    static long wide(long a, double b, int c) {
+        long total = a;
+        double scaled = b;
+        for (int i = 0; i < c; i++) {
+            total += i;
+            scaled *= 2;
+        }
+        return total + (long) scaled;
+    }
+
+    static Object pick(boolean flag) {
+        Object result = null;
+        if (flag) {
+            result = new Square(12);
+        } else {
+            result = "nothing";
+        }
+        return result;
+    }
+
+    static int retries(int failures) {
+        int attempts = 0;
+        while (true) {
+            try {
+                attempts++;
+                if (attempts <= failures) {
+                    throw new IllegalStateException("attempt " + attempts);
+                }
+                return attempts;
+            } catch (IllegalStateException e) {
+                String message = e.getMessage();
+                if (message.endsWith("3")) {
+                    return -attempts;
+                }
+            } finally {
+                attempts += 0;
+            }
+        }
+    }
+
+    static int[][] grid(int n) {
+        int[][] cells = new int[n][n];
+        for (int i = 0; i < n; i++) {
+            for (int j = 0; j < n; j++) {
+                cells[i][j] = i * j;
+            }
+        }
+        return cells;
+    }
+
+    // class Bad { static int run() { return 1; } } compiled for Java 8, with the code of
+    // run being iconst_1 ireturn
+    static final String BAD = "cafebabe00000034000f0a000200030700040c000500060100106a6176612f6c616e672f4f626a65"
+            + "63740100063c696e69743e010003282956070008010003426164010004436f646501000f4c696e65"
+            + "4e756d6265725461626c6501000372756e01000328294901000a536f7572636546696c6501000842"
+            + "61642e6a617661002000070002000000000002000000050006000100090000001d00010001000000"
+            + "052ab70001b100000001000a000000060001000000010008000b000c000100090000001a00010000"
+            + "0000000204ac00000001000a000000060001000000030001000d00000002000e";
+
+    // defines Bad renamed to `name` of three letters, with `code` as the code of run
+    static void define(String name, String code) {
+        StringBuilder utf = new StringBuilder("010003");
+        for (char c : name.toCharArray()) {
+            utf.append(Integer.toHexString(c));
+        }
+        String hex = BAD.replace("010003426164", utf.toString()).replace("04ac", code);
+        byte[] bytes = new byte[hex.length() / 2];
+        for (int i = 0; i < bytes.length; i++) {
+            bytes[i] = (byte) Integer.parseInt(hex.substring(2 * i, 2 * i + 2), 16);
+        }
+        try {
+            Class<?> bad = MethodHandles.lookup().defineClass(bytes);
+            System.out.println(name + ": " + bad.getDeclaredMethod("run").invoke(null));
+        } catch (VerifyError e) {
+            System.out.println(name + ": " + e.getMessage().split("\n")[0]);
+        } catch (ReflectiveOperationException e) {
+            System.out.println(name + ": " + e);
+        }
+    }
+
+    public static void main(String[] args) {
+        Square square = new Square();
+        System.out.println(square.name + " " + square.side + " " + new Square(11).name);
+        System.out.println(wide(1L << 40, 0.5, 4));
+        System.out.println(pick(false) + " " + (pick(true) != null));
+        System.out.println(retries(1) + " " + retries(5));
+        int[][] cells = grid(4);
+        System.out.println(cells[3][2] + " " + cells.length + " " + cells[1].length);
+        Shape[] shapes = {new Shape("a"), new Square(3), null};
+        int named = 0;
+        for (Shape s : shapes) {
+            named += s == null ? 0 : s.name.length();
+        }
+        System.out.println(named);
+
+        define("Fit", "04ac");
+        // aconst_null ireturn
+        define("Nul", "01ac");
+        // nop ireturn
+        define("Pop", "00ac");
+    }
+}